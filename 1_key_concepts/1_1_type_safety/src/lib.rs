@@ -0,0 +1,470 @@
+extern crate chrono;
+extern crate humantime;
+#[macro_use]
+extern crate lazy_static;
+extern crate regex;
+extern crate regular_expressions;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+extern crate serde_json;
+
+#[macro_use]
+pub mod machine;
+pub mod moderation;
+pub mod stream;
+
+use machine::{Machine, MachineRecord, State};
+use regex::Regex;
+use regular_expressions::user::EMAIL_PATTERN;
+use serde::ser::{Serialize, Serializer};
+use std::convert::TryFrom;
+use std::fmt;
+use std::string::String;
+
+/// Сущности
+#[derive(Debug, Serialize, Deserialize)]
+pub struct User {
+    pub user_id: u64,
+    pub full_name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostContent {
+    post_id: u64,
+    user: User,
+    title: String,
+    body: String,
+}
+
+pub type Post<S> = Machine<PostContent, S>;
+
+// Состояния
+states!(New, Unmoderated, InReview, Published, Deleted);
+
+// Transition table of the `Post` flow.
+// `allow` and `approve` are private: the post is published only through the validation
+// of `try_allow` and `try_approve`.
+transitions! {
+    PostContent {
+        pub publish: New => Unmoderated,
+        allow: Unmoderated => Published,
+        pub deny: Unmoderated => Deleted,
+        pub claim: Unmoderated => InReview,
+        pub release: InReview => Unmoderated,
+        approve: InReview => Published,
+        pub reject: InReview => Deleted,
+        pub delete: Published => Deleted,
+    }
+}
+
+/// States in which the post content may still be changed.
+pub trait Editable: State {}
+impl Editable for New {}
+impl Editable for Unmoderated {}
+
+/// States in which the post content is visible.
+pub trait Readable: State {}
+impl Readable for New {}
+impl Readable for Unmoderated {}
+impl Readable for InReview {}
+impl Readable for Published {}
+
+/// Editing is allowed only before the post is published.
+impl<S: Editable> Post<S> {
+    pub fn set_title(&mut self, title: String) {
+        self.entity.title = title;
+    }
+
+    pub fn set_body(&mut self, body: String) {
+        self.entity.body = body;
+    }
+}
+
+/// Read-only access, deleted posts don't expose their content.
+impl<S: Readable> Post<S> {
+    pub fn post_id(&self) -> u64 {
+        self.entity.post_id
+    }
+
+    pub fn user(&self) -> &User {
+        &self.entity.user
+    }
+
+    pub fn title(&self) -> &str {
+        &self.entity.title
+    }
+
+    pub fn body(&self) -> &str {
+        &self.entity.body
+    }
+}
+
+/// Create new Post
+/// state New
+pub fn new(user: User, title: String, body: String) -> Post<New> {
+    new_with_id(1u64, user, title, body)
+}
+
+/// Create new Post with the given identifier
+/// state New
+pub fn new_with_id(post_id: u64, user: User, title: String, body: String) -> Post<New> {
+    Machine::new(PostContent {
+        post_id,
+        user,
+        title,
+        body,
+    })
+}
+
+// Сохранение и загрузка
+
+/// Post in any state, as it is read from the storage.
+#[derive(Debug)]
+pub enum AnyPost {
+    New(Post<New>),
+    Unmoderated(Post<Unmoderated>),
+    InReview(Post<InReview>),
+    Published(Post<Published>),
+    Deleted(Post<Deleted>),
+}
+
+impl AnyPost {
+    pub fn state(&self) -> &'static str {
+        match self {
+            AnyPost::New(_) => New::NAME,
+            AnyPost::Unmoderated(_) => Unmoderated::NAME,
+            AnyPost::InReview(_) => InReview::NAME,
+            AnyPost::Published(_) => Published::NAME,
+            AnyPost::Deleted(_) => Deleted::NAME,
+        }
+    }
+
+    pub fn post_id(&self) -> u64 {
+        match self {
+            AnyPost::New(post) => post.entity.post_id,
+            AnyPost::Unmoderated(post) => post.entity.post_id,
+            AnyPost::InReview(post) => post.entity.post_id,
+            AnyPost::Published(post) => post.entity.post_id,
+            AnyPost::Deleted(post) => post.entity.post_id,
+        }
+    }
+}
+
+impl Serialize for AnyPost {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        match self {
+            AnyPost::New(post) => post.serialize(serializer),
+            AnyPost::Unmoderated(post) => post.serialize(serializer),
+            AnyPost::InReview(post) => post.serialize(serializer),
+            AnyPost::Published(post) => post.serialize(serializer),
+            AnyPost::Deleted(post) => post.serialize(serializer),
+        }
+    }
+}
+
+/// `Post<S>` can be taken out of `AnyPost` only if the stored state is `S`,
+/// otherwise `AnyPost` is given back unchanged.
+macro_rules! any_post_try_from {
+    ($($state:ident),*) => {
+        $(
+            impl From<Post<$state>> for AnyPost {
+                fn from(post: Post<$state>) -> AnyPost {
+                    AnyPost::$state(post)
+                }
+            }
+
+            impl TryFrom<AnyPost> for Post<$state> {
+                type Error = AnyPost;
+
+                fn try_from(post: AnyPost) -> Result<Post<$state>, AnyPost> {
+                    match post {
+                        AnyPost::$state(post) => Ok(post),
+                        other => Err(other),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+any_post_try_from!(New, Unmoderated, InReview, Published, Deleted);
+
+/// Errors of loading a post.
+#[derive(Debug)]
+pub enum LoadError {
+    Json(serde_json::Error),
+    UnknownState(String),
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(err: serde_json::Error) -> Self {
+        LoadError::Json(err)
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Json(err) => write!(f, "invalid post json: {}", err),
+            LoadError::UnknownState(state) => write!(f, "unknown post state: {}", state),
+        }
+    }
+}
+
+/// Serialize the post with its state tag.
+pub fn save<S: State>(post: &Post<S>) -> serde_json::Result<String> {
+    serde_json::to_string(post)
+}
+
+/// Restore the post saved with `save`, the state tag selects the variant of `AnyPost`.
+pub fn load(json: &str) -> Result<AnyPost, LoadError> {
+    let record: MachineRecord<PostContent> = serde_json::from_str(json)?;
+    let post = match record.state.as_str() {
+        state if state == New::NAME => AnyPost::New(record.into_machine()),
+        state if state == Unmoderated::NAME => AnyPost::Unmoderated(record.into_machine()),
+        state if state == InReview::NAME => AnyPost::InReview(record.into_machine()),
+        state if state == Published::NAME => AnyPost::Published(record.into_machine()),
+        state if state == Deleted::NAME => AnyPost::Deleted(record.into_machine()),
+        _ => return Err(LoadError::UnknownState(record.state)),
+    };
+    Ok(post)
+}
+
+// Модерация
+
+/// Maximum length of the published body, in characters.
+pub const MAX_BODY_LENGTH: usize = 10_000;
+
+/// Reasons for the post to be not allowed.
+#[derive(Debug, PartialEq)]
+pub enum ModerationError {
+    EmptyBody,
+    BodyTooLong { max: usize, actual: usize },
+    BannedWord(String),
+    InvalidAuthorEmail(String),
+}
+
+impl fmt::Display for ModerationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModerationError::EmptyBody => write!(f, "post body is empty"),
+            ModerationError::BodyTooLong { max, actual } => {
+                write!(f, "post body has {} characters, maximum is {}", actual, max)
+            }
+            ModerationError::BannedWord(word) => write!(f, "post contains banned word: {}", word),
+            ModerationError::InvalidAuthorEmail(email) => {
+                write!(f, "author email is invalid: {}", email)
+            }
+        }
+    }
+}
+
+/// Check the post before it may be published.
+fn validate<S: State>(post: &Post<S>) -> Result<(), ModerationError> {
+    lazy_static! {
+        static ref BANNED_WORDS: Regex =
+            Regex::new(r"(?i)\b(spam|scam|casino|viagra)\b").unwrap();
+        // The same email validation as in the `regular_expressions` step.
+        static ref EMAIL: Regex = Regex::new(EMAIL_PATTERN).unwrap();
+    }
+
+    let length = post.entity.body.chars().count();
+    if post.entity.body.trim().is_empty() {
+        return Err(ModerationError::EmptyBody);
+    }
+    if length > MAX_BODY_LENGTH {
+        return Err(ModerationError::BodyTooLong {
+            max: MAX_BODY_LENGTH,
+            actual: length,
+        });
+    }
+    let text = format!("{}\n{}", post.entity.title, post.entity.body);
+    if let Some(word) = BANNED_WORDS.find(&text) {
+        return Err(ModerationError::BannedWord(word.as_str().to_string()));
+    }
+    if !EMAIL.is_match(&post.entity.user.email) {
+        return Err(ModerationError::InvalidAuthorEmail(
+            post.entity.user.email.clone(),
+        ));
+    }
+    Ok(())
+}
+
+/// Publish the post if it passes validation, otherwise give the unchanged post back
+/// together with the reason, so the caller can fix it and retry.
+#[allow(clippy::result_large_err)] // the post is given back by value on purpose
+pub fn try_allow(
+    post: Post<Unmoderated>,
+    actor: &str,
+) -> Result<Post<Published>, (Post<Unmoderated>, ModerationError)> {
+    match validate(&post) {
+        Ok(()) => Ok(post.allow(actor)),
+        Err(err) => Err((post, err)),
+    }
+}
+
+/// The same as `try_allow` for the post claimed by a reviewer.
+#[allow(clippy::result_large_err)] // the post is given back by value on purpose
+pub fn try_approve(
+    post: Post<InReview>,
+    actor: &str,
+) -> Result<Post<Published>, (Post<InReview>, ModerationError)> {
+    match validate(&post) {
+        Ok(()) => Ok(post.approve(actor)),
+        Err(err) => Err((post, err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn user() -> User {
+        User {
+            user_id: 7,
+            full_name: String::from("Egor Egorov"),
+            email: String::from("email@mail.ru"),
+        }
+    }
+
+    #[test]
+    fn test_save_contains_state_tag() {
+        let post = new(user(), String::from("title"), String::from("body")).publish("author");
+        let json: serde_json::Value = serde_json::from_str(&save(&post).unwrap()).unwrap();
+        assert_eq!("Unmoderated", json["state"]);
+        assert_eq!(7, json["user"]["user_id"]);
+    }
+
+    #[test]
+    fn test_round_trip_keeps_state() {
+        let post = try_allow(
+            new(user(), String::from("title"), String::from("body")).publish("author"),
+            "moderator",
+        )
+        .unwrap();
+        let loaded = load(&save(&post).unwrap()).unwrap();
+        assert_eq!("Published", loaded.state());
+
+        let published: Post<Published> = loaded.try_into().unwrap();
+        assert_eq!("title", published.title());
+        assert_eq!("body", published.body());
+        assert_eq!(7, published.user().user_id);
+        assert_eq!(post.history(), published.history());
+    }
+
+    #[test]
+    fn test_history_records_every_transition() {
+        let post = new(user(), String::from("title"), String::from("body"));
+        assert!(post.history().is_empty());
+
+        let post = try_allow(post.publish("author"), "moderator").unwrap();
+        let post = post.delete("admin");
+        let steps: Vec<(&str, &str, &str)> = post
+            .history()
+            .iter()
+            .map(|r| (r.from.as_str(), r.to.as_str(), r.actor.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("New", "Unmoderated", "author"),
+                ("Unmoderated", "Published", "moderator"),
+                ("Published", "Deleted", "admin"),
+            ],
+            steps
+        );
+        assert!(post.history().windows(2).all(|w| w[0].at <= w[1].at));
+        assert_eq!(
+            "New -- \"publish()\" --> Unmoderated by author",
+            post.history()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_edit_before_publication() {
+        let mut post = new(user(), String::from("title"), String::from("body"));
+        post.set_title(String::from("new title"));
+        let mut post = post.publish("author");
+        post.set_body(String::from("new body"));
+        let post = try_allow(post, "moderator").unwrap();
+        assert_eq!("new title", post.title());
+        assert_eq!("new body", post.body());
+    }
+
+    #[test]
+    fn test_try_into_wrong_state_returns_post_back() {
+        let post = new(user(), String::from("title"), String::from("body"));
+        let loaded = load(&save(&post).unwrap()).unwrap();
+        let result: Result<Post<Published>, AnyPost> = loaded.try_into();
+        let back = result.unwrap_err();
+        assert_eq!("New", back.state());
+        assert!(Post::<New>::try_from(back).is_ok());
+    }
+
+    #[test]
+    fn test_load_unknown_state() {
+        let json = r#"{"state":"Archived","post_id":1,"title":"t","body":"b",
+                       "user":{"user_id":1,"full_name":"n","email":"e"}}"#;
+        match load(json) {
+            Err(LoadError::UnknownState(state)) => assert_eq!("Archived", state),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    fn unmoderated(email: &str, title: &str, body: &str) -> Post<Unmoderated> {
+        let mut author = user();
+        author.email = email.to_string();
+        new(author, title.to_string(), body.to_string()).publish("author")
+    }
+
+    #[test]
+    fn test_try_allow_rejects_empty_body() {
+        let (_, err) =
+            try_allow(unmoderated("email@mail.ru", "title", "  "), "moderator").unwrap_err();
+        assert_eq!(ModerationError::EmptyBody, err);
+    }
+
+    #[test]
+    fn test_try_allow_rejects_long_body() {
+        let body = "a".repeat(MAX_BODY_LENGTH + 1);
+        let (_, err) =
+            try_allow(unmoderated("email@mail.ru", "title", &body), "moderator").unwrap_err();
+        assert_eq!(
+            ModerationError::BodyTooLong {
+                max: MAX_BODY_LENGTH,
+                actual: MAX_BODY_LENGTH + 1
+            },
+            err
+        );
+    }
+
+    #[test]
+    fn test_try_allow_rejects_banned_word() {
+        let (_, err) = try_allow(
+            unmoderated("email@mail.ru", "Best CASINO", "body"),
+            "moderator",
+        )
+        .unwrap_err();
+        assert_eq!(ModerationError::BannedWord(String::from("CASINO")), err);
+    }
+
+    #[test]
+    fn test_try_allow_returns_post_for_retry() {
+        let post = unmoderated("not-an-email", "title", "body");
+        let (mut post, err) = try_allow(post, "moderator").unwrap_err();
+        assert_eq!(
+            ModerationError::InvalidAuthorEmail(String::from("not-an-email")),
+            err
+        );
+        assert_eq!(1, post.history().len());
+
+        post.entity.user.email = String::from("email@mail.ru");
+        let post = try_allow(post, "moderator").unwrap();
+        assert_eq!("Published", post.history()[1].to);
+    }
+}
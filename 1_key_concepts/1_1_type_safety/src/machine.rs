@@ -0,0 +1,175 @@
+//! Generic typestate machine.
+//!
+//! `Machine<E, S>` holds an entity `E` in the state `S`. The allowed transitions
+//! are the implementations of `Transition<From, To>` for the entity, which are
+//! declared in one place with the `transitions!` macro.
+
+use chrono::{DateTime, Utc};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// State of a `Machine`.
+pub trait State {
+    const NAME: &'static str;
+}
+
+/// Legal transition `From -- ACTION --> To` of the entity.
+/// Any pair without an implementation can't be expressed: it is a compile error.
+pub trait Transition<From: State, To: State> {
+    const ACTION: &'static str;
+}
+
+/// Audit record of one transition of the `Machine`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionRecord {
+    pub from: String,
+    pub to: String,
+    /// Method of the transition, e.g. `publish()`; empty in the records saved without it.
+    #[serde(default)]
+    pub action: String,
+    pub at: DateTime<Utc>,
+    pub actor: String,
+}
+
+/// `From -- "action()" --> To by actor`.
+impl fmt::Display for TransitionRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} -- \"{}\" --> {} by {}",
+            self.from, self.action, self.to, self.actor
+        )
+    }
+}
+
+/// Entity `E` in the state `S`.
+#[derive(Debug)]
+pub struct Machine<E, S> {
+    pub(crate) entity: E,
+    pub(crate) history: Vec<TransitionRecord>,
+    state: PhantomData<S>,
+}
+
+impl<E, S: State> Machine<E, S> {
+    pub(crate) fn new(entity: E) -> Self {
+        Machine::with_history(entity, Vec::new())
+    }
+
+    pub(crate) fn with_history(entity: E, history: Vec<TransitionRecord>) -> Self {
+        Machine {
+            entity,
+            history,
+            state: PhantomData,
+        }
+    }
+
+    /// Move the entity to the state `T`, if the entity allows `S -- T`.
+    /// The transition is recorded into the history, the caller prints it if needed.
+    pub fn transition<T: State>(mut self, actor: &str) -> Machine<E, T>
+    where
+        E: Transition<S, T>,
+    {
+        self.history.push(TransitionRecord {
+            from: S::NAME.to_string(),
+            to: T::NAME.to_string(),
+            action: <E as Transition<S, T>>::ACTION.to_string(),
+            at: Utc::now(),
+            actor: actor.to_string(),
+        });
+        Machine::with_history(self.entity, self.history)
+    }
+
+    /// Name of the current state.
+    pub fn state(&self) -> &'static str {
+        S::NAME
+    }
+
+    /// All transitions the entity went through, oldest first.
+    pub fn history(&self) -> &[TransitionRecord] {
+        &self.history
+    }
+}
+
+/// Borrowed form of the stored machine: the state name, the entity fields and the history.
+#[derive(Serialize)]
+struct MachineRef<'a, E: 'a> {
+    state: &'static str,
+    #[serde(flatten)]
+    entity: &'a E,
+    history: &'a [TransitionRecord],
+}
+
+/// `Machine<E, S>` is stored together with the name of its state.
+impl<E: Serialize, S: State> Serialize for Machine<E, S> {
+    fn serialize<Ser>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error>
+    where
+        Ser: Serializer,
+    {
+        MachineRef {
+            state: S::NAME,
+            entity: &self.entity,
+            history: &self.history,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Untyped form of the stored machine, the state has to be checked by the caller.
+#[derive(Deserialize)]
+pub(crate) struct MachineRecord<E> {
+    pub(crate) state: String,
+    #[serde(flatten)]
+    entity: E,
+    #[serde(default)]
+    history: Vec<TransitionRecord>,
+}
+
+impl<E> MachineRecord<E> {
+    pub(crate) fn into_machine<S: State>(self) -> Machine<E, S> {
+        Machine::with_history(self.entity, self.history)
+    }
+}
+
+/// Declares the states and the transition table of an entity.
+///
+/// Every `method: From => To` line implements `Transition<From, To>` for the entity
+/// and adds `method(self, actor)` to `Machine<Entity, From>`.
+///
+/// ```rust,ignore
+/// transitions! {
+///     Stream {
+///         pub go_live: Setup => Live,
+///         pub finish: Live => Finished,
+///     }
+/// }
+/// ```
+macro_rules! transitions {
+    ($entity:ty { $($vis:vis $method:ident: $from:ident => $to:ident),* $(,)* }) => {
+        $(
+            impl $crate::machine::Transition<$from, $to> for $entity {
+                const ACTION: &'static str = concat!(stringify!($method), "()");
+            }
+
+            impl $crate::machine::Machine<$entity, $from> {
+                $vis fn $method(self, actor: &str) -> $crate::machine::Machine<$entity, $to> {
+                    self.transition(actor)
+                }
+            }
+        )*
+    };
+}
+
+/// Declares unit structs implementing `State`, named after themselves.
+macro_rules! states {
+    ($($state:ident),*) => {
+        $(
+            #[derive(Debug)]
+            pub struct $state;
+
+            impl $crate::machine::State for $state {
+                const NAME: &'static str = stringify!($state);
+            }
+        )*
+    };
+}
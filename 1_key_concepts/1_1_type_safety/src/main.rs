@@ -1,115 +1,8 @@
-#![allow(dead_code)]
-use std::marker::PhantomData;
-use std::string::String;
+extern crate type_safety;
 
-/// Сущности
-struct User {
-    user_id: u64,
-    full_name: String,
-    email: String,
-}
-
-struct Post<S> {
-    post_id: u64,
-    user: User,
-    title: String,
-    body: String,
-    state: PhantomData<S>,
-}
-
-/// Состояния
-struct New;
-struct Unmoderated;
-struct Published;
-struct Deleted;
-
-///Вариант основан на преобразованим From and PhantomData
-
-
-/// New -- Unmoderated
-impl From<Post<New>> for Post<Unmoderated> {
-    fn from(_val: Post<New>) -> Post<Unmoderated> {
-      Post {
-            post_id: _val.post_id,
-            user: _val.user,
-            title: _val.title,
-            body: _val.body,
-            state: PhantomData,
-        }
-    }
-}
-
-/// Unmoderated -- Published
-impl From<Post<Unmoderated>> for Post<Published> {
-    fn from(_val: Post<Unmoderated>) -> Post<Published> {
-
-        Post {
-            post_id: _val.post_id,
-            user: _val.user,
-            title: _val.title,
-            body: _val.body,
-            state: PhantomData,
-        }
-    }
-}
-
-/// Unmoderated -- Deleted
-impl From<Post<Unmoderated>> for Post<Deleted> {
-    fn from(_val: Post<Unmoderated>) -> Post<Deleted> {
-        Post {
-            post_id: _val.post_id,
-            user: _val.user,
-            title: _val.title,
-            body: _val.body,
-            state: PhantomData,
-        }
-    }
-}
-
-/// Published -- Deleted
-impl From<Post<Published>> for Post<Deleted> {
-    fn from(_val: Post<Published>) -> Post<Deleted> {
-        Post {
-            post_id: _val.post_id,
-            user: _val.user,
-            title: _val.title,
-            body: _val.body,
-            state: PhantomData,
-        }
-    }
-}
-
-/// Create new Post
-/// state New
-fn new(user: User, title: String, body: String) -> Post<New> {
-    let post: Post<New> = Post {
-        post_id: 1u64,
-        user: user,
-        title: title,
-        body: body,
-        state: PhantomData,
-    };
-    post
-}
-
-fn publish(post: Post<New>) -> Post<Unmoderated> {
-    println!("New -- \"publish()\" --> Unmoderated");
-    post.into()
-}
-fn allow(post: Post<Unmoderated>) -> Post<Published> {
-    println!("Unmoderated -- \"allow()\" --> Published");
-    post.into()
-}
-
-fn deny(post: Post<Unmoderated>) -> Post<Deleted> {
-    println!("Unmoderated -- \"deny()\" --> Deleted");
-    post.into()
-}
-
-fn delete(post: Post<Published>) -> Post<Deleted> {
-    println!("Published -- \"delete()\" --> Deleted");
-    post.into()
-}
+use std::convert::TryFrom;
+use type_safety::stream::{new_stream, LiveStreamSettings};
+use type_safety::*;
 
 fn main() {
     let user = User {
@@ -120,9 +13,31 @@ fn main() {
 
     let post_new = new(user, String::from("title"), String::from("body"));
 
-    let post_unmoderated = publish(post_new);
+    let post_unmoderated = post_new.publish("author");
 
-    let post_published = allow(post_unmoderated);
+    let post_published = match try_allow(post_unmoderated, "moderator") {
+        Ok(post) => post,
+        Err((_, err)) => panic!("post is not allowed: {}", err),
+    };
 
-    let _post_delete = delete(post_published);
+    let json = save(&post_published).expect("post is serializable");
+    println!("saved: {}", json);
+
+    let post_published: Post<Published> = match load(&json).map(Post::try_from) {
+        Ok(Ok(post)) => post,
+        Ok(Err(other)) => panic!("expected Published post, loaded {}", other.state()),
+        Err(err) => panic!("{}", err),
+    };
+
+    let post_deleted = post_published.delete("moderator");
+    for record in post_deleted.history() {
+        println!("{}", record);
+    }
+
+    let stream = new_stream(1, LiveStreamSettings::default()).go_live("model");
+    assert!(stream.accepts_message("hello"));
+    let stream_finished = stream.finish("model");
+    for record in stream_finished.history() {
+        println!("{}", record);
+    }
 }
@@ -0,0 +1,520 @@
+//! Moderation queue: unmoderated posts are stored in a repository,
+//! handed out to reviewers and moved to `Published` or `Deleted`.
+//!
+//! `ReviewAssignment` is the persistent variant of the queue: a claimed post is
+//! stored in the `InReview` state and locked for the reviewer until the timeout.
+
+use super::*;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+/// The `Repository` trait (interface) for posts:
+///  - stores the post in any state;
+///  - returns single post by its ID;
+///  - returns IDs of posts in the given state.
+pub trait PostsRepository {
+    /// Store the post, replacing the previous version with the same ID.
+    fn save(&mut self, post: AnyPost);
+
+    /// Post search by ID.
+    fn get_post_by_id(&self, post_id: u64) -> Option<AnyPost>;
+
+    /// IDs of the posts in the state, in ascending order.
+    fn get_ids_by_state(&self, state: &str) -> Vec<u64>;
+}
+
+/// In-memory `PostsRepository`, posts are kept as JSON documents
+/// produced by `save` and read back with `load`.
+#[derive(Debug, Default)]
+pub struct PostsMemory {
+    posts: BTreeMap<u64, String>,
+}
+
+impl PostsMemory {
+    /// Creates a new empty PostsMemory object.
+    pub fn new() -> Self {
+        PostsMemory::default()
+    }
+}
+
+impl PostsRepository for PostsMemory {
+    fn save(&mut self, post: AnyPost) {
+        let json = serde_json::to_string(&post).expect("post is serializable");
+        self.posts.insert(post.post_id(), json);
+    }
+
+    fn get_post_by_id(&self, post_id: u64) -> Option<AnyPost> {
+        self.posts
+            .get(&post_id)
+            .map(|json| load(json).expect("stored post is valid"))
+    }
+
+    fn get_ids_by_state(&self, state: &str) -> Vec<u64> {
+        self.posts
+            .keys()
+            .filter(|id| self.get_post_by_id(**id).map(|post| post.state()) == Some(state))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Queue of posts waiting for moderation.
+///
+/// Every unmoderated post is handed out to one reviewer at a time,
+/// the decision of the reviewer is persisted into the repository.
+pub struct ModerationQueue<R: PostsRepository> {
+    repository: R,
+    /// Post ID -> reviewer it is handed out to.
+    assigned: HashMap<u64, String>,
+}
+
+impl<R: PostsRepository> ModerationQueue<R> {
+    pub fn new(repository: R) -> Self {
+        ModerationQueue {
+            repository,
+            assigned: HashMap::new(),
+        }
+    }
+
+    /// Put the post into the queue.
+    pub fn submit(&mut self, post: Post<Unmoderated>) {
+        self.repository.save(post.into());
+    }
+
+    /// Number of the posts waiting for a reviewer.
+    pub fn pending(&self) -> usize {
+        self.repository
+            .get_ids_by_state(Unmoderated::NAME)
+            .iter()
+            .filter(|id| !self.assigned.contains_key(id))
+            .count()
+    }
+
+    /// Hand out the oldest post which is not assigned to anybody yet.
+    pub fn next_for(&mut self, reviewer: &str) -> Option<Post<Unmoderated>> {
+        let post_id = self
+            .repository
+            .get_ids_by_state(Unmoderated::NAME)
+            .into_iter()
+            .find(|id| !self.assigned.contains_key(id))?;
+        let post = Post::<Unmoderated>::try_from(self.repository.get_post_by_id(post_id)?).ok()?;
+        self.assigned.insert(post_id, reviewer.to_string());
+        Some(post)
+    }
+
+    /// Return the post to the queue without a decision.
+    pub fn release(&mut self, post: Post<Unmoderated>) {
+        self.assigned.remove(&post.entity.post_id);
+    }
+
+    /// Publish the post if it passes validation and persist it.
+    /// On failure the post stays assigned to the reviewer and is given back.
+    #[allow(clippy::result_large_err)]
+    pub fn allow(
+        &mut self,
+        post: Post<Unmoderated>,
+        reviewer: &str,
+    ) -> Result<(), (Post<Unmoderated>, ModerationError)> {
+        let post = try_allow(post, reviewer)?;
+        self.assigned.remove(&post.entity.post_id);
+        self.repository.save(post.into());
+        Ok(())
+    }
+
+    /// Reject the post and persist it as deleted.
+    pub fn deny(&mut self, post: Post<Unmoderated>, reviewer: &str) {
+        let post = post.deny(reviewer);
+        self.assigned.remove(&post.entity.post_id);
+        self.repository.save(post.into());
+    }
+
+    /// The underlying repository.
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+}
+
+/// Actor of the audit record written when an expired lock is taken away from the reviewer.
+pub const LOCK_EXPIRED_ACTOR: &str = "lock-timeout";
+
+/// Parse the lock timeout like `"5m"` or `"30s"`, the same format as the durations
+/// of the configuration.
+pub fn parse_lock_timeout(value: &str) -> Result<Duration, ReviewError> {
+    humantime::parse_duration(value)
+        .map_err(|err| err.to_string())
+        .and_then(|timeout| Duration::from_std(timeout).map_err(|err| err.to_string()))
+        .map_err(|err| ReviewError::InvalidTimeout(format!("{}: {}", value, err)))
+}
+
+/// Post claimed by the reviewer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewLock {
+    pub reviewer: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ReviewLock {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Errors of the review workflow.
+#[derive(Debug, PartialEq)]
+pub enum ReviewError {
+    NotFound(u64),
+    /// The post is not in a state the action applies to.
+    WrongState {
+        post_id: u64,
+        state: &'static str,
+    },
+    /// The post is claimed by another reviewer.
+    Locked {
+        post_id: u64,
+        reviewer: String,
+    },
+    /// The lock of the reviewer has expired, the post has to be claimed again.
+    LockExpired(u64),
+    Moderation(ModerationError),
+    InvalidTimeout(String),
+}
+
+impl fmt::Display for ReviewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReviewError::NotFound(post_id) => write!(f, "post {} is not found", post_id),
+            ReviewError::WrongState { post_id, state } => {
+                write!(f, "post {} is in the {} state", post_id, state)
+            }
+            ReviewError::Locked { post_id, reviewer } => {
+                write!(f, "post {} is claimed by {}", post_id, reviewer)
+            }
+            ReviewError::LockExpired(post_id) => {
+                write!(f, "the lock of the post {} has expired", post_id)
+            }
+            ReviewError::Moderation(err) => write!(f, "{}", err),
+            ReviewError::InvalidTimeout(err) => write!(f, "invalid lock timeout: {}", err),
+        }
+    }
+}
+
+impl From<ModerationError> for ReviewError {
+    fn from(err: ModerationError) -> Self {
+        ReviewError::Moderation(err)
+    }
+}
+
+/// Reviewer assignment on top of the repository.
+///
+/// `claim` moves the post into `InReview` and locks it for the reviewer,
+/// `release`/`approve`/`reject` are accepted only from the owner of a live lock.
+/// Every step is a transition of the post, so it is recorded into its history.
+pub struct ReviewAssignment<R: PostsRepository> {
+    repository: R,
+    lock_timeout: Duration,
+    /// Post ID -> lock of the reviewer.
+    locks: HashMap<u64, ReviewLock>,
+}
+
+impl<R: PostsRepository> ReviewAssignment<R> {
+    pub fn new(repository: R, lock_timeout: Duration) -> Self {
+        ReviewAssignment {
+            repository,
+            lock_timeout,
+            locks: HashMap::new(),
+        }
+    }
+
+    /// Claim the post for the reviewer.
+    ///
+    /// An `InReview` post may be claimed only when its lock has expired
+    /// (or was lost, e.g. after a restart): it is released first.
+    pub fn claim(&mut self, post_id: u64, reviewer: &str) -> Result<ReviewLock, ReviewError> {
+        let now = Utc::now();
+        let post = match self.repository.get_post_by_id(post_id) {
+            Some(AnyPost::Unmoderated(post)) => post,
+            Some(AnyPost::InReview(post)) => {
+                if let Some(lock) = self.locks.get(&post_id) {
+                    if !lock.is_expired(now) {
+                        return Err(ReviewError::Locked {
+                            post_id,
+                            reviewer: lock.reviewer.clone(),
+                        });
+                    }
+                }
+                post.release(LOCK_EXPIRED_ACTOR)
+            }
+            Some(other) => {
+                return Err(ReviewError::WrongState {
+                    post_id,
+                    state: other.state(),
+                })
+            }
+            None => return Err(ReviewError::NotFound(post_id)),
+        };
+        let lock = ReviewLock {
+            reviewer: reviewer.to_string(),
+            expires_at: now + self.lock_timeout,
+        };
+        self.repository.save(post.claim(reviewer).into());
+        self.locks.insert(post_id, lock.clone());
+        Ok(lock)
+    }
+
+    /// Claim the oldest post which is free for review.
+    pub fn claim_next(&mut self, reviewer: &str) -> Option<(u64, ReviewLock)> {
+        let now = Utc::now();
+        let expired = self
+            .repository
+            .get_ids_by_state(InReview::NAME)
+            .into_iter()
+            .filter(|id| self.locks.get(id).is_none_or(|lock| lock.is_expired(now)));
+        let mut ids: Vec<u64> = self
+            .repository
+            .get_ids_by_state(Unmoderated::NAME)
+            .into_iter()
+            .chain(expired)
+            .collect();
+        ids.sort();
+        ids.into_iter()
+            .filter_map(|id| self.claim(id, reviewer).ok().map(|lock| (id, lock)))
+            .next()
+    }
+
+    /// Return the post to the queue without a decision.
+    pub fn release(&mut self, post_id: u64, reviewer: &str) -> Result<(), ReviewError> {
+        let post = self.take(post_id, reviewer)?;
+        self.finish(post_id, post.release(reviewer).into());
+        Ok(())
+    }
+
+    /// Publish the post if it passes validation.
+    /// On failure the post stays claimed by the reviewer.
+    pub fn approve(&mut self, post_id: u64, reviewer: &str) -> Result<(), ReviewError> {
+        let post = self.take(post_id, reviewer)?;
+        let post = try_approve(post, reviewer).map_err(|(_, err)| err)?;
+        self.finish(post_id, post.into());
+        Ok(())
+    }
+
+    /// Reject the post and persist it as deleted.
+    pub fn reject(&mut self, post_id: u64, reviewer: &str) -> Result<(), ReviewError> {
+        let post = self.take(post_id, reviewer)?;
+        self.finish(post_id, post.reject(reviewer).into());
+        Ok(())
+    }
+
+    /// Lock of the post, if it is claimed.
+    pub fn lock(&self, post_id: u64) -> Option<&ReviewLock> {
+        self.locks.get(&post_id)
+    }
+
+    /// The underlying repository.
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Claimed post, if the reviewer owns a live lock on it.
+    fn take(&self, post_id: u64, reviewer: &str) -> Result<Post<InReview>, ReviewError> {
+        let post = match self.repository.get_post_by_id(post_id) {
+            Some(AnyPost::InReview(post)) => post,
+            Some(other) => {
+                return Err(ReviewError::WrongState {
+                    post_id,
+                    state: other.state(),
+                })
+            }
+            None => return Err(ReviewError::NotFound(post_id)),
+        };
+        match self.locks.get(&post_id) {
+            Some(lock) if lock.reviewer != reviewer => Err(ReviewError::Locked {
+                post_id,
+                reviewer: lock.reviewer.clone(),
+            }),
+            Some(lock) if !lock.is_expired(Utc::now()) => Ok(post),
+            _ => Err(ReviewError::LockExpired(post_id)),
+        }
+    }
+
+    fn finish(&mut self, post_id: u64, post: AnyPost) {
+        self.locks.remove(&post_id);
+        self.repository.save(post);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unmoderated(post_id: u64, body: &str) -> Post<Unmoderated> {
+        let user = User {
+            user_id: 7,
+            full_name: String::from("Egor Egorov"),
+            email: String::from("email@mail.ru"),
+        };
+        new_with_id(post_id, user, String::from("title"), body.to_string()).publish("author")
+    }
+
+    #[test]
+    fn test_posts_are_handed_out_once() {
+        let mut queue = ModerationQueue::new(PostsMemory::new());
+        queue.submit(unmoderated(1, "first"));
+        queue.submit(unmoderated(2, "second"));
+        assert_eq!(2, queue.pending());
+
+        let first = queue.next_for("alice").unwrap();
+        let second = queue.next_for("bob").unwrap();
+        assert_eq!(1, first.post_id());
+        assert_eq!(2, second.post_id());
+        assert!(queue.next_for("carol").is_none());
+
+        queue.release(first);
+        assert_eq!(1, queue.next_for("carol").unwrap().post_id());
+    }
+
+    #[test]
+    fn test_decisions_are_persisted() {
+        let mut queue = ModerationQueue::new(PostsMemory::new());
+        queue.submit(unmoderated(1, "good post"));
+        queue.submit(unmoderated(2, "spam"));
+
+        let post = queue.next_for("alice").unwrap();
+        queue.allow(post, "alice").unwrap();
+        let post = queue.next_for("alice").unwrap();
+        queue.deny(post, "alice");
+
+        assert_eq!(0, queue.pending());
+        let repository = queue.repository();
+        assert_eq!(vec![1], repository.get_ids_by_state(Published::NAME));
+        assert_eq!(vec![2], repository.get_ids_by_state(Deleted::NAME));
+
+        let published = Post::<Published>::try_from(repository.get_post_by_id(1).unwrap()).unwrap();
+        assert_eq!("alice", published.history()[1].actor);
+    }
+
+    #[test]
+    fn test_failed_allow_keeps_post_assigned() {
+        let mut queue = ModerationQueue::new(PostsMemory::new());
+        queue.submit(unmoderated(1, "buy spam now"));
+
+        let post = queue.next_for("alice").unwrap();
+        let (post, err) = queue.allow(post, "alice").unwrap_err();
+        assert_eq!(ModerationError::BannedWord(String::from("spam")), err);
+        assert!(queue.next_for("bob").is_none());
+
+        queue.deny(post, "alice");
+        assert_eq!(vec![1], queue.repository().get_ids_by_state(Deleted::NAME));
+    }
+
+    #[test]
+    fn test_parse_lock_timeout() {
+        assert_eq!(Duration::minutes(5), parse_lock_timeout("5m").unwrap());
+        match parse_lock_timeout("soon") {
+            Err(ReviewError::InvalidTimeout(err)) => assert!(err.starts_with("soon")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_review_workflow_is_audited() {
+        let mut repository = PostsMemory::new();
+        repository.save(unmoderated(1, "good post").into());
+        repository.save(unmoderated(2, "second").into());
+        let mut review = ReviewAssignment::new(repository, parse_lock_timeout("5m").unwrap());
+
+        let (post_id, lock) = review.claim_next("alice").unwrap();
+        assert_eq!(1, post_id);
+        assert_eq!("alice", lock.reviewer);
+        assert_eq!(
+            Err(ReviewError::Locked {
+                post_id: 1,
+                reviewer: String::from("alice")
+            }),
+            review.claim(1, "bob")
+        );
+        assert_eq!(2, review.claim_next("bob").unwrap().0);
+        assert!(review.claim_next("carol").is_none());
+
+        review.approve(1, "alice").unwrap();
+        review.release(2, "bob").unwrap();
+        review.claim(2, "carol").unwrap();
+        review.reject(2, "carol").unwrap();
+        assert_eq!(
+            Err(ReviewError::WrongState {
+                post_id: 2,
+                state: Deleted::NAME
+            }),
+            review.claim(2, "alice")
+        );
+
+        let post =
+            Post::<Published>::try_from(review.repository().get_post_by_id(1).unwrap()).unwrap();
+        let steps: Vec<(&str, &str, &str)> = post
+            .history()
+            .iter()
+            .map(|r| (r.from.as_str(), r.to.as_str(), r.actor.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("New", "Unmoderated", "author"),
+                ("Unmoderated", "InReview", "alice"),
+                ("InReview", "Published", "alice"),
+            ],
+            steps
+        );
+        let post =
+            Post::<Deleted>::try_from(review.repository().get_post_by_id(2).unwrap()).unwrap();
+        let actors: Vec<&str> = post.history().iter().map(|r| r.actor.as_str()).collect();
+        assert_eq!(vec!["author", "bob", "bob", "carol", "carol"], actors);
+    }
+
+    #[test]
+    fn test_only_owner_of_live_lock_decides() {
+        let mut repository = PostsMemory::new();
+        repository.save(unmoderated(1, "buy spam now").into());
+        let mut review = ReviewAssignment::new(repository, Duration::minutes(5));
+
+        review.claim(1, "alice").unwrap();
+        assert_eq!(
+            Err(ReviewError::Locked {
+                post_id: 1,
+                reviewer: String::from("alice")
+            }),
+            review.reject(1, "bob")
+        );
+        assert_eq!(
+            Err(ReviewError::Moderation(ModerationError::BannedWord(
+                String::from("spam")
+            ))),
+            review.approve(1, "alice")
+        );
+        assert_eq!("alice", review.lock(1).unwrap().reviewer);
+        assert_eq!(
+            vec![1],
+            review.repository().get_ids_by_state(InReview::NAME)
+        );
+        assert_eq!(Err(ReviewError::NotFound(2)), review.release(2, "alice"));
+    }
+
+    #[test]
+    fn test_expired_lock_is_taken_over() {
+        let mut repository = PostsMemory::new();
+        repository.save(unmoderated(1, "body").into());
+        let mut review = ReviewAssignment::new(repository, Duration::zero());
+
+        review.claim(1, "alice").unwrap();
+        assert_eq!(Err(ReviewError::LockExpired(1)), review.approve(1, "alice"));
+
+        review.lock_timeout = Duration::minutes(5);
+        assert_eq!(1, review.claim_next("bob").unwrap().0);
+        review.approve(1, "bob").unwrap();
+
+        let post =
+            Post::<Published>::try_from(review.repository().get_post_by_id(1).unwrap()).unwrap();
+        let actors: Vec<&str> = post.history().iter().map(|r| r.actor.as_str()).collect();
+        assert_eq!(
+            vec!["author", "alice", LOCK_EXPIRED_ACTOR, "bob", "bob"],
+            actors
+        );
+    }
+}
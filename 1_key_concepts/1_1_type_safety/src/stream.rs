@@ -0,0 +1,100 @@
+//! Live stream lifecycle `Setup -> Live -> Finished` on top of the generic `Machine`.
+
+use machine::Machine;
+use std::time::Duration;
+
+/// Settings of the live stream, the same as `app.live_stream` of the configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveStreamSettings {
+    pub max_message_length: usize,
+    pub idle_timeout: Duration,
+    pub starting_timeout: Duration,
+}
+
+impl Default for LiveStreamSettings {
+    fn default() -> Self {
+        LiveStreamSettings {
+            max_message_length: 1000,
+            idle_timeout: Duration::from_secs(5),
+            starting_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+/// Сущность
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreamContent {
+    pub stream_id: u64,
+    pub settings: LiveStreamSettings,
+}
+
+// Состояния
+states!(Setup, Live, Finished);
+
+transitions! {
+    StreamContent {
+        pub go_live: Setup => Live,
+        pub abort: Setup => Finished,
+        pub finish: Live => Finished,
+    }
+}
+
+pub type Stream<S> = Machine<StreamContent, S>;
+
+/// Create new stream in the state Setup.
+pub fn new_stream(stream_id: u64, settings: LiveStreamSettings) -> Stream<Setup> {
+    Machine::new(StreamContent {
+        stream_id,
+        settings,
+    })
+}
+
+impl<S> Stream<S> {
+    pub fn stream_id(&self) -> u64 {
+        self.entity.stream_id
+    }
+
+    pub fn settings(&self) -> &LiveStreamSettings {
+        &self.entity.settings
+    }
+}
+
+/// Chat is available only while the stream is live.
+impl Stream<Live> {
+    /// Whether the chat message fits `max_message_length`.
+    pub fn accepts_message(&self, message: &str) -> bool {
+        message.chars().count() <= self.entity.settings.max_message_length
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stream_lifecycle() {
+        let stream = new_stream(3, LiveStreamSettings::default());
+        assert_eq!("Setup", stream.state());
+
+        let stream = stream.go_live("model");
+        assert!(stream.accepts_message(&"a".repeat(1000)));
+        assert!(!stream.accepts_message(&"a".repeat(1001)));
+
+        let stream = stream.finish("watchdog");
+        assert_eq!("Finished", stream.state());
+        assert_eq!(3, stream.stream_id());
+        let actions: Vec<(&str, &str)> = stream
+            .history()
+            .iter()
+            .map(|r| (r.to.as_str(), r.actor.as_str()))
+            .collect();
+        assert_eq!(vec![("Live", "model"), ("Finished", "watchdog")], actions);
+    }
+
+    #[test]
+    fn test_stream_abort_before_live() {
+        let stream = new_stream(4, LiveStreamSettings::default()).abort("model");
+        assert_eq!(1, stream.history().len());
+        assert_eq!("Setup", stream.history()[0].from);
+    }
+}
@@ -0,0 +1,8 @@
+extern crate trybuild;
+
+/// Methods which are not available in the current state of `Post` must not compile.
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/compile_fail/*.rs");
+}
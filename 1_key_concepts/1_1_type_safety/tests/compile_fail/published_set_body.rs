@@ -0,0 +1,13 @@
+extern crate type_safety;
+
+use type_safety::*;
+
+fn main() {
+    let user = User {
+        user_id: 1,
+        full_name: String::from("Egor Egorov"),
+        email: String::from("email@mail.ru"),
+    };
+    let mut post = try_allow(new(user, String::from("title"), String::from("body")).publish("author"), "moderator").unwrap();
+    post.set_body(String::from("edited after publication"));
+}
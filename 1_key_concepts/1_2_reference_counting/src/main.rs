@@ -1,17 +1,92 @@
+extern crate crossbeam;
+extern crate memmap;
+
 mod read_file {
 
+    use crossbeam;
+    use memmap::Mmap;
     use std::borrow::Cow;
     use std::fs::File;
     use std::io::Error;
     use std::io::Read;
+    use std::ops::AddAssign;
     use std::path::Path;
     use std::rc::Rc;
     use std::result;
+    use std::str;
+    use std::sync::Arc;
     use std::vec;
 
     type Result<T> = result::Result<T, Error>;
 
-    pub fn read<P: AsRef<Path>>(path: P) -> Result<()> {
+    /// Statistics of the read content, summed over all passes.
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    pub struct ReadStats {
+        pub bytes: usize,
+        /// Bytes which are part of valid UTF-8 sequences.
+        pub valid_utf8_bytes: usize,
+        pub lines: usize,
+        /// Invalid UTF-8 sequences replaced with `U+FFFD` by the lossy conversion.
+        pub lossy_replacements: usize,
+    }
+
+    impl AddAssign for ReadStats {
+        fn add_assign(&mut self, other: ReadStats) {
+            self.bytes += other.bytes;
+            self.valid_utf8_bytes += other.valid_utf8_bytes;
+            self.lines += other.lines;
+            self.lossy_replacements += other.lossy_replacements;
+        }
+    }
+
+    /// Default size of a chunk of `read_chunks`.
+    pub const CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Reusable buffers of the streaming read.
+    /// A buffer can be filled again once the consumer dropped its `Rc`.
+    struct BufferPool {
+        buffers: Vec<Rc<Vec<u8>>>,
+        chunk_size: usize,
+    }
+
+    impl BufferPool {
+        fn new(chunk_size: usize) -> Self {
+            BufferPool {
+                buffers: Vec::new(),
+                chunk_size,
+            }
+        }
+
+        /// Fill a free buffer with the next chunk, `None` at the end of the input.
+        fn fill<R: Read>(&mut self, input: &mut R) -> Result<Option<Rc<Vec<u8>>>> {
+            let free = self
+                .buffers
+                .iter()
+                .position(|buffer| Rc::strong_count(buffer) == 1);
+            let mut buffer = match free {
+                Some(index) => self.buffers.swap_remove(index),
+                None => Rc::new(Vec::with_capacity(self.chunk_size)),
+            };
+            let read = {
+                let content = Rc::get_mut(&mut buffer).expect("the buffer is free");
+                content.clear();
+                input
+                    .by_ref()
+                    .take(self.chunk_size as u64)
+                    .read_to_end(content)?
+            };
+            self.buffers.push(Rc::clone(&buffer));
+            Ok(if read == 0 { None } else { Some(buffer) })
+        }
+    }
+
+    /// Read the file into the heap once and hand the shared content
+    /// to `f` for each of the `passes`. The first error stops the passes.
+    pub fn read_with<P, F>(path: P, passes: usize, mut f: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&Rc<Vec<u8>>) -> Result<()>,
+    {
         let mut file = File::open(path)?;
 
         let mut file_content: vec::Vec<u8> = Vec::new();
@@ -19,26 +94,254 @@ mod read_file {
 
         let rc_file_content = Rc::new(file_content);
 
-        for _i in 0..5 {
-            buffer_read(Rc::clone(&rc_file_content))?;
+        for _i in 0..passes {
+            f(&rc_file_content)?;
         }
 
         Ok(())
     }
 
-    fn buffer_read(buffer: Rc<Vec<u8>>) -> Result<()> {
-        let content: Cow<str> = String::from_utf8_lossy(&buffer);
-        println!("{:?}", content.len());
-        // println!("{:?}",content.into_owned());
-        Ok(())
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<ReadStats> {
+        let mut stats = ReadStats::default();
+        read_with(path, 5, |content| {
+            stats += buffer_read(Rc::clone(content))?;
+            Ok(())
+        })?;
+        Ok(stats)
+    }
+
+    /// Map the file into memory instead of copying it into the heap,
+    /// all passes share the same mapping.
+    /// Mapping of an empty file fails on some platforms.
+    pub fn read_mmap<P: AsRef<Path>>(path: P) -> Result<ReadStats> {
+        let file = File::open(path)?;
+
+        // The file must not be changed while it is mapped.
+        let rc_mmap = Rc::new(unsafe { Mmap::map(&file)? });
+
+        let mut stats = ReadStats::default();
+        for _i in 0..5 {
+            stats += buffer_read(Rc::clone(&rc_mmap))?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Stream the file by chunks of `chunk_size` bytes, so the memory doesn't depend
+    /// on the file size. A chunk buffer is reused as soon as the consumer drops it.
+    /// Returns the number of chunks.
+    pub fn read_chunks<P, F>(path: P, chunk_size: usize, mut consumer: F) -> Result<usize>
+    where
+        P: AsRef<Path>,
+        F: FnMut(Rc<Vec<u8>>) -> Result<()>,
+    {
+        let mut file = File::open(path)?;
+        let mut pool = BufferPool::new(chunk_size.max(1));
+
+        let mut chunks = 0;
+        while let Some(chunk) = pool.fill(&mut file)? {
+            consumer(chunk)?;
+            chunks += 1;
+        }
+
+        Ok(chunks)
+    }
+
+    /// Run `consumers` threads over the same content. `Rc` can't be sent
+    /// to another thread, so the content is shared with `Arc` instead.
+    /// Results are returned in the order of the consumers.
+    pub fn read_parallel<P, F, T>(path: P, consumers: usize, consumer: F) -> Result<Vec<T>>
+    where
+        P: AsRef<Path>,
+        F: Fn(Arc<Vec<u8>>) -> T + Sync,
+        T: Send,
+    {
+        let mut file = File::open(path)?;
+
+        let mut file_content: vec::Vec<u8> = Vec::new();
+        file.read_to_end(&mut file_content)?;
+
+        let arc_file_content = Arc::new(file_content);
+        let consumer = &consumer;
+
+        let results = crossbeam::scope(|scope_| {
+            let handles: Vec<_> = (0..consumers)
+                .map(|_i| {
+                    let content = Arc::clone(&arc_file_content);
+                    scope_.spawn(move || consumer(content))
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join()).collect()
+        });
+
+        Ok(results)
+    }
+
+    fn buffer_read<B: AsRef<[u8]>>(buffer: Rc<B>) -> Result<ReadStats> {
+        let bytes = (*buffer).as_ref();
+        let content: Cow<str> = String::from_utf8_lossy(bytes);
+
+        let mut stats = ReadStats {
+            bytes: bytes.len(),
+            lines: content.lines().count(),
+            ..ReadStats::default()
+        };
+        // Walk the same invalid sequences as `from_utf8_lossy` does.
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            match str::from_utf8(rest) {
+                Ok(valid) => {
+                    stats.valid_utf8_bytes += valid.len();
+                    break;
+                }
+                Err(err) => {
+                    stats.valid_utf8_bytes += err.valid_up_to();
+                    stats.lossy_replacements += 1;
+                    let invalid = err.error_len().unwrap_or(rest.len() - err.valid_up_to());
+                    rest = &rest[err.valid_up_to() + invalid..];
+                }
+            }
+        }
+        Ok(stats)
     }
 }
 
 fn main() {
     let path = std::path::Path::new("war_and_peace.pdf");
 
-    match read_file::read(path) {
+    let result = if std::env::args().any(|arg| arg == "--mmap") {
+        read_file::read_mmap(path).map(|stats| println!("{:?}", stats))
+    } else if std::env::args().any(|arg| arg == "--chunks") {
+        let mut bytes = 0;
+        read_file::read_chunks(path, read_file::CHUNK_SIZE, |chunk| {
+            bytes += chunk.len();
+            Ok(())
+        })
+        .map(|chunks| println!("{} bytes in {} chunks", bytes, chunks))
+    } else if std::env::args().any(|arg| arg == "--parallel") {
+        read_file::read_parallel(path, 5, |content| String::from_utf8_lossy(&content).len())
+            .map(|lengths| println!("{:?}", lengths))
+    } else {
+        read_file::read(path).map(|stats| println!("{:?}", stats))
+    };
+
+    match result {
         Ok(_) => println!("Reading complete"),
         Err(e) => println!("Reading failed:{}", e),
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_stats() {
+        let stats = read_file::read("file.txt").unwrap();
+        assert_eq!(
+            read_file::ReadStats {
+                bytes: 55,
+                valid_utf8_bytes: 55,
+                lines: 10,
+                lossy_replacements: 0,
+            },
+            stats
+        );
+
+        let path = std::env::temp_dir().join(format!("reference_counting_{}", std::process::id()));
+        // Two invalid sequences: a lone continuation byte and a truncated `ж`.
+        std::fs::write(&path, b"ok\x80\nwo\xd0").unwrap();
+        let stats = read_file::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            read_file::ReadStats {
+                bytes: 35,
+                valid_utf8_bytes: 25,
+                lines: 10,
+                lossy_replacements: 10,
+            },
+            stats
+        );
+    }
+
+    #[test]
+    fn test_read_with() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        use std::io::{Error, ErrorKind};
+
+        let mut hashes = Vec::new();
+        read_file::read_with("file.txt", 3, |content| {
+            let mut hasher = DefaultHasher::new();
+            content.hash(&mut hasher);
+            hashes.push(hasher.finish());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(3, hashes.len());
+        assert!(hashes.iter().all(|hash| *hash == hashes[0]));
+
+        let mut passes = 0;
+        let result = read_file::read_with("file.txt", 5, |content| {
+            passes += 1;
+            std::str::from_utf8(content)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?
+                .parse::<u32>()
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+            Ok(())
+        });
+        assert_eq!(ErrorKind::InvalidData, result.unwrap_err().kind());
+        assert_eq!(1, passes);
+    }
+
+    #[test]
+    fn test_read_mmap() {
+        assert_eq!(
+            read_file::read("file.txt").unwrap(),
+            read_file::read_mmap("file.txt").unwrap()
+        );
+        assert!(read_file::read_mmap("not_found.txt").is_err());
+    }
+
+    #[test]
+    fn test_read_chunks() {
+        use std::rc::Rc;
+
+        let mut content = Vec::new();
+        let mut buffers: Vec<Rc<Vec<u8>>> = Vec::new();
+        let chunks = read_file::read_chunks("file.txt", 4, |chunk| {
+            content.extend_from_slice(&chunk);
+            if !buffers.iter().any(|buffer| Rc::ptr_eq(buffer, &chunk)) {
+                buffers.push(chunk);
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(3, chunks);
+        assert_eq!(b"hello\nJeka\n".to_vec(), content);
+        // The retained buffers can't be reused: each chunk got a new one.
+        assert_eq!(3, buffers.len());
+
+        let mut pointers = Vec::new();
+        read_file::read_chunks("file.txt", 4, |chunk| {
+            pointers.push(&*chunk as *const Vec<u8>);
+            Ok(())
+        })
+        .unwrap();
+        // The dropped buffer is reused for every chunk.
+        assert!(pointers.iter().all(|pointer| *pointer == pointers[0]));
+    }
+
+    #[test]
+    fn test_read_parallel() {
+        let lengths = read_file::read_parallel("file.txt", 3, |content| content.len()).unwrap();
+        assert_eq!(vec![11, 11, 11], lengths);
+
+        // Every consumer holds its own reference to the same content.
+        let counts = read_file::read_parallel("file.txt", 2, |content| {
+            std::sync::Arc::strong_count(&content) > 1
+        })
+        .unwrap();
+        assert_eq!(vec![true, true], counts);
+    }
+}
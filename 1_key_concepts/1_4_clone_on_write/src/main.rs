@@ -1,80 +1,345 @@
+#[macro_use(crate_version)]
+extern crate clap;
+
+use clap::{App, Arg, ArgMatches};
 use std::borrow::Cow;
 use std::env;
-//use std::io::{Error, ErrorKind};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 
+#[cfg(not(windows))]
 const CONFIG_PATH_DEFAULT: &str = "/etc/app/app.conf";
+/// Used on Windows only if `%APPDATA%` is not set.
+#[cfg(windows)]
+const CONFIG_PATH_DEFAULT: &str = r"C:\ProgramData\app\app.conf";
 const APP_CONF: &str = "APP_CONF";
+/// Location of the config inside of a config directory: `app/app.conf`.
+const CONFIG_DIR: &str = "app";
+const CONFIG_FILE: &str = "app.conf";
+
+/// Where the path of the config is taken from, in the order of precedence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Source {
+    Args,
+    Env,
+    XdgConfigHome,
+    HomeConfig,
+    /// `%APPDATA%\app\app.conf`, the default on Windows.
+    AppData,
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Args => write!(f, "--conf argument"),
+            Source::Env => write!(f, "{} env", APP_CONF),
+            Source::XdgConfigHome => write!(f, "$XDG_CONFIG_HOME"),
+            Source::HomeConfig => write!(f, "~/.config"),
+            Source::AppData => write!(f, "%APPDATA%"),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Why the config path can't be used.
+#[derive(Debug, PartialEq)]
+enum PathError {
+    NotFound(PathBuf),
+    NotReadable(PathBuf),
+    /// `--conf` is given without a path.
+    EmptyArg,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::NotFound(path) => write!(f, "config {} is not found", path.display()),
+            PathError::NotReadable(path) => write!(f, "config {} is not readable", path.display()),
+            PathError::EmptyArg => write!(f, "arguments --conf can not be empty"),
+        }
+    }
+}
+
+impl Error for PathError {}
 
 fn get_env() -> Option<String> {
     env::var(APP_CONF).ok()
 }
 
-fn get_value_args(var_arg: &str) -> Option<String> {
-    if env::args_os().len() < 2 {
-        return None;
-    }
-    let mut result: Option<String> = None;
-    for argument in env::args_os() {
-        if argument
-            .to_str()
-            .and_then(|v| v.find(var_arg).map_or(None, |_byte| Some(v)))
-            .and_then(|v| {
-                v.find("=")
-                    .map_or(Some(("", "")), |byte| Some(v.split_at(byte + 1)))
-            })
-            .and_then(|(_, last)| {
-                result = Some(last.to_string());
-                Some(())
-            })
-            .is_some()
-        {
-            break;
+/// Values the config path is resolved from.
+#[derive(Debug, Default)]
+struct Vars {
+    args: Option<String>,
+    app_conf: Option<String>,
+    xdg_config_home: Option<String>,
+    /// `$HOME`, or `%USERPROFILE%` on Windows.
+    home: Option<String>,
+    appdata: Option<String>,
+}
+
+impl Vars {
+    fn from_env(matches: &ArgMatches) -> Self {
+        let home = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+        let non_empty = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+        Vars {
+            args: matches.value_of("conf").map(String::from),
+            app_conf: get_env().filter(|v| !v.is_empty()),
+            xdg_config_home: non_empty("XDG_CONFIG_HOME"),
+            home: non_empty(home),
+            appdata: non_empty("APPDATA"),
         }
     }
+}
+
+/// Config file inside of the directory, if the file exists.
+fn config_in_dir(dir: &Path) -> Option<PathBuf> {
+    Some(dir.join(CONFIG_DIR).join(CONFIG_FILE)).filter(|path| path.is_file())
+}
+
+/// Replace the leading `~` of the path with the home directory.
+/// The path is returned as is if it has no `~` or the home is unknown.
+fn expand_tilde<'a>(path: Cow<'a, Path>, home: Option<&str>) -> Cow<'a, Path> {
+    match (path.strip_prefix("~"), home) {
+        (Ok(rest), Some(home)) => Cow::Owned(Path::new(home).join(rest)),
+        _ => path,
+    }
+}
 
-    return result;
+/// Command line interface: `--conf <FILE>` and `--help`.
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("clone_on_write")
+        .version(crate_version!())
+        .about("Prints out the path to the configuration file")
+        .arg(
+            Arg::with_name("conf")
+                .long("conf")
+                .value_name("FILE")
+                .takes_value(true)
+                .help("Path to the configuration file"),
+        )
 }
 
-fn path<'a>() -> Cow<'a, str> {
-    let mut path = Cow::Borrowed(CONFIG_PATH_DEFAULT);
+/// Path of the config and the source it is taken from.
+fn path<'a>(matches: &ArgMatches) -> (Cow<'a, Path>, Source) {
+    resolve(Vars::from_env(matches))
+}
+
+/// The first available of: `--conf`, `APP_CONF`, `$XDG_CONFIG_HOME/app/app.conf`,
+/// `~/.config/app/app.conf` and the default: `/etc/app/app.conf`
+/// or `%APPDATA%\app\app.conf` on Windows.
+/// The config directories are used only if the config exists in them,
+/// `~` of `--conf` and `APP_CONF` is expanded to the home directory.
+fn resolve<'a>(vars: Vars) -> (Cow<'a, Path>, Source) {
+    let home = vars.home.as_deref();
+
+    if let Some(_path) = vars.args {
+        return (expand_tilde(Cow::Owned(_path.into()), home), Source::Args);
+    }
+
+    if let Some(_path) = vars.app_conf {
+        return (expand_tilde(Cow::Owned(_path.into()), home), Source::Env);
+    }
+
+    if let Some(_path) = vars
+        .xdg_config_home
+        .as_ref()
+        .and_then(|dir| config_in_dir(Path::new(dir)))
+    {
+        return (Cow::Owned(_path), Source::XdgConfigHome);
+    }
 
-    if let Some(_path) = get_env() {
-        path = Cow::Owned(_path);
+    if let Some(_path) = home.and_then(|dir| config_in_dir(&Path::new(dir).join(".config"))) {
+        return (Cow::Owned(_path), Source::HomeConfig);
     }
 
-    if let Some(_path) = get_value_args("--conf") {
-        if _path.is_empty() {
-            eprintln!("Error: arguments --conf can not be empty !");
-        } else {
-            path = Cow::Owned(_path);
+    if cfg!(windows) {
+        if let Some(dir) = vars.appdata {
+            let _path = Path::new(&dir).join(CONFIG_DIR).join(CONFIG_FILE);
+            return (Cow::Owned(_path), Source::AppData);
         }
     }
 
-    path
+    (
+        Cow::Borrowed(Path::new(CONFIG_PATH_DEFAULT)),
+        Source::Default,
+    )
+}
+
+/// Resolved path of the config, checked to be an existing readable file.
+/// The compiled-in default stays borrowed.
+fn resolve_config_path<'a>(matches: &ArgMatches) -> Result<(Cow<'a, Path>, Source), PathError> {
+    let (path, source) = path(matches);
+    if source == Source::Args && path.as_os_str().is_empty() {
+        return Err(PathError::EmptyArg);
+    }
+    validate(path).map(|path| (path, source))
+}
+
+/// Stat the file and try to open it for reading.
+fn validate(path: Cow<Path>) -> Result<Cow<Path>, PathError> {
+    match fs::metadata(&path) {
+        Ok(ref metadata) if metadata.is_file() => {}
+        _ => return Err(PathError::NotFound(path.into_owned())),
+    }
+    match File::open(&path) {
+        Ok(_) => Ok(path),
+        Err(ref err) if err.kind() == ErrorKind::PermissionDenied => {
+            Err(PathError::NotReadable(path.into_owned()))
+        }
+        Err(_) => Err(PathError::NotFound(path.into_owned())),
+    }
 }
 
 fn main() {
-    println!("path:{}", path());
+    match resolve_config_path(&app().get_matches()) {
+        Ok((path, source)) => println!("path:{} (from {})", path.display(), source),
+        Err(err) => {
+            eprintln!("Error: {} !", err);
+            std::process::exit(1);
+        }
+    }
 }
 
 #[test]
 fn path_test() {
-    let _path = path();
-
-    get_value_args("--conf")
-        .and_then(|args_path| {
-            if args_path.is_empty() {
-                eprintln!("Error: arguments --conf can not be empty !");
-            } else {
-                assert_eq!(_path.trim(), args_path.trim());
-            }
-            Some(())
-        })
-        .or_else(|| {
-            get_env().and_then(|env_path| {
-                assert_eq!(_path.trim(), env_path.trim());
-                Some(())
-            });
-            Some(())
-        });
+    let matches = app().get_matches_from(vec!["app", "--conf", "/tmp/app.conf"]);
+    assert_eq!(
+        (Cow::Borrowed(Path::new("/tmp/app.conf")), Source::Args),
+        path(&matches)
+    );
+
+    let matches = app().get_matches_from(vec!["app", "--conf=/tmp/app.conf"]);
+    assert_eq!(Path::new("/tmp/app.conf"), path(&matches).0);
+
+    let (_path, source) = path(&app().get_matches_from(vec!["app"]));
+    assert_ne!(Source::Args, source);
+    if let Some(env_path) = get_env().filter(|p| !p.is_empty() && !p.starts_with('~')) {
+        assert_eq!(Path::new(&env_path), _path);
+    }
+}
+
+#[test]
+fn args_error_test() {
+    use clap::ErrorKind;
+
+    let kind = |args: Vec<&str>| app().get_matches_from_safe(args).unwrap_err().kind;
+    assert_eq!(ErrorKind::EmptyValue, kind(vec!["app", "--conf"]));
+    assert_eq!(ErrorKind::UnknownArgument, kind(vec!["app", "--confx=a"]));
+    assert_eq!(ErrorKind::HelpDisplayed, kind(vec!["app", "--help"]));
+}
+
+#[test]
+fn resolve_fallback_test() {
+    let home = env::temp_dir().join(format!("clone_on_write_{}", std::process::id()));
+    let xdg = home.join("xdg");
+    fs::create_dir_all(home.join(".config/app")).unwrap();
+    fs::create_dir_all(xdg.join("app")).unwrap();
+    fs::write(home.join(".config/app/app.conf"), "").unwrap();
+    let vars = |args: Option<&str>, app_conf: Option<&str>| Vars {
+        args: args.map(String::from),
+        app_conf: app_conf.map(String::from),
+        xdg_config_home: Some(xdg.to_str().unwrap().to_string()),
+        home: Some(home.to_str().unwrap().to_string()),
+        appdata: None,
+    };
+
+    // The config is missing in $XDG_CONFIG_HOME: ~/.config is used.
+    let (path, source) = resolve(vars(None, None));
+    assert_eq!(Source::HomeConfig, source);
+    assert_eq!(home.join(".config/app/app.conf"), path);
+
+    fs::write(xdg.join("app/app.conf"), "").unwrap();
+    let (path, source) = resolve(vars(None, None));
+    assert_eq!(Source::XdgConfigHome, source);
+    assert_eq!(xdg.join("app/app.conf"), path);
+
+    let (path, source) = resolve(vars(None, Some("env.conf")));
+    assert_eq!(
+        (Path::new("env.conf"), Source::Env),
+        (path.as_ref(), source)
+    );
+
+    let (path, source) = resolve(vars(Some("args.conf"), Some("env.conf")));
+    assert_eq!(
+        (Path::new("args.conf"), Source::Args),
+        (path.as_ref(), source)
+    );
+
+    let (path, source) = resolve(Vars {
+        home: Some("/nonexistent".into()),
+        ..Vars::default()
+    });
+    assert_eq!(
+        (
+            Cow::Borrowed(Path::new(CONFIG_PATH_DEFAULT)),
+            Source::Default
+        ),
+        (path, source)
+    );
+
+    fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn expand_tilde_test() {
+    let expand = |path: &'static str, home| expand_tilde(Cow::Borrowed(Path::new(path)), home);
+
+    assert_eq!(
+        Path::new("/home/u/app.conf"),
+        expand("~/app.conf", Some("/home/u"))
+    );
+    assert_eq!(Path::new("/home/u"), expand("~", Some("/home/u")));
+    // Only the whole `~` component is expanded.
+    assert_eq!(
+        Path::new("~user/app.conf"),
+        expand("~user/app.conf", Some("/home/u"))
+    );
+    match expand("~/app.conf", None) {
+        Cow::Borrowed(path) => assert_eq!(Path::new("~/app.conf"), path),
+        Cow::Owned(path) => panic!("unexpected {:?}", path),
+    }
+
+    let (path, source) = resolve(Vars {
+        app_conf: Some("~/.app.conf".into()),
+        home: Some("/home/u".into()),
+        ..Vars::default()
+    });
+    assert_eq!(
+        (Path::new("/home/u/.app.conf"), Source::Env),
+        (path.as_ref(), source)
+    );
+}
+
+#[test]
+fn validate_test() {
+    let file = env::temp_dir().join(format!("clone_on_write_{}.conf", std::process::id()));
+    fs::write(&file, "").unwrap();
+
+    match validate(Cow::Borrowed(file.as_path())) {
+        Ok(Cow::Borrowed(path)) => assert_eq!(file, path),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let matches = app().get_matches_from(vec!["app", "--conf", file.to_str().unwrap()]);
+    let (path, source) = resolve_config_path(&matches).unwrap();
+    assert_eq!((file.as_path(), Source::Args), (path.as_ref(), source));
+
+    let matches = app().get_matches_from(vec!["app", "--conf="]);
+    assert_eq!(Err(PathError::EmptyArg), resolve_config_path(&matches));
+
+    let matches = app().get_matches_from(vec!["app", "--conf", "/nonexistent/app.conf"]);
+    assert_eq!(
+        Err(PathError::NotFound(PathBuf::from("/nonexistent/app.conf"))),
+        resolve_config_path(&matches)
+    );
+    assert_eq!(
+        Err(PathError::NotFound(env::temp_dir())),
+        validate(Cow::Borrowed(env::temp_dir().as_path()))
+    );
+
+    fs::remove_file(file).unwrap();
 }
@@ -0,0 +1,156 @@
+//! # Async smart pointer
+//!
+//! `AsyncFile<'a>` is the `SPFile::File` smart pointer over `tokio::fs::File`:
+//! it derefs to the file and removes it when is dropped. The removal is spawned
+//! on the runtime, out of the runtime the file is removed in place.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//!  # extern crate dereferencing;
+//!  # extern crate tokio;
+//!  use dereferencing::async_file::AsyncFile;
+//!  use std::path::Path;
+//!  use tokio::prelude::*;
+//!
+//!  let path = Path::new("async_example.txt");
+//!
+//!  tokio::run(
+//!      AsyncFile::create(path)
+//!          .and_then(|file| tokio::io::write_all(file, "some bytes"))
+//!          .map(|_| ())
+//!          .map_err(|e| println!("{}", e)),
+//!  );
+//! ```
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use tokio;
+use tokio::executor::{DefaultExecutor, Executor};
+use tokio::prelude::*;
+
+/// The smart pointer to `tokio::fs::File`, removes the file on drop.
+#[derive(Debug)]
+pub struct AsyncFile<'a> {
+    pub file: tokio::fs::File,
+    path: &'a Path,
+}
+
+impl<'a> AsyncFile<'a> {
+    /// Opens the existing file for reading.
+    pub fn open(path: &'a Path) -> impl Future<Item = AsyncFile<'a>, Error = io::Error> {
+        tokio::fs::File::open(path.to_path_buf()).map(move |file| AsyncFile::new(file, path))
+    }
+
+    /// Creates the file for writing.
+    pub fn create(path: &'a Path) -> impl Future<Item = AsyncFile<'a>, Error = io::Error> {
+        tokio::fs::File::create(path.to_path_buf()).map(move |file| AsyncFile::new(file, path))
+    }
+
+    fn new(file: tokio::fs::File, path: &'a Path) -> Self {
+        AsyncFile { file, path }
+    }
+}
+
+impl<'a> Deref for AsyncFile<'a> {
+    type Target = tokio::fs::File;
+
+    fn deref(&self) -> &tokio::fs::File {
+        &self.file
+    }
+}
+
+impl<'a> DerefMut for AsyncFile<'a> {
+    fn deref_mut(&mut self) -> &mut tokio::fs::File {
+        &mut self.file
+    }
+}
+
+/// Spawns the removal of the file on the runtime.
+impl<'a> Drop for AsyncFile<'a> {
+    fn drop(&mut self) {
+        let path = self.path.to_path_buf();
+        let in_place = path.clone();
+        // `tokio::fs` needs the threadpool: on the current thread runtime
+        // the file is removed in place.
+        let cleanup = tokio::fs::remove_file(path)
+            .or_else(move |_| fs::remove_file(in_place))
+            .then(|_| Ok(()));
+        if DefaultExecutor::current().spawn(Box::new(cleanup)).is_err() {
+            let _ = fs::remove_file(self.path);
+        }
+        println!("File is being dropped");
+    }
+}
+
+/// `AsyncFile` reads and writes as the target file,
+/// so it is passed to `tokio::io` as is.
+impl<'a> Read for AsyncFile<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl<'a> AsyncRead for AsyncFile<'a> {}
+
+impl<'a> Write for AsyncFile<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl<'a> AsyncWrite for AsyncFile<'a> {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.file.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn test_async_file() {
+        let path = Path::new("async_file.txt");
+        let mut rt = Runtime::new().unwrap();
+
+        let (writer, _) = rt
+            .block_on(
+                AsyncFile::create(path).and_then(|file| tokio::io::write_all(file, "some text")),
+            )
+            .unwrap();
+        let text = rt
+            .block_on(
+                AsyncFile::open(path)
+                    .and_then(|file| tokio::io::read_to_end(file, Vec::new()))
+                    .map(|(_, text)| text),
+            )
+            .unwrap();
+        assert_eq!(b"some text".to_vec(), text);
+
+        // The reader is dropped on the runtime.
+        rt.shutdown_on_idle().wait().unwrap();
+        assert!(!path.exists());
+        drop(writer);
+    }
+
+    #[test]
+    fn test_drop_out_of_runtime() {
+        let path = Path::new("async_file_sync.txt");
+        let mut rt = Runtime::new().unwrap();
+
+        let file = rt.block_on(AsyncFile::create(path)).unwrap();
+        assert!(path.exists());
+        drop(file);
+        assert!(!path.exists());
+    }
+}
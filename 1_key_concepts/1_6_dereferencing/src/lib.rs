@@ -0,0 +1,5 @@
+//! Smart pointers of the step shared with the other steps:
+//! the tokio examples keep their temporary files in `AsyncFile`.
+extern crate tokio;
+
+pub mod async_file;
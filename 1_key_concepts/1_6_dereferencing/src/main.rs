@@ -1,12 +1,15 @@
 use std::fs;
-use std::io::{Read, Write};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::borrow::Cow;
 use std::path::Path;
 
 /// The module implements a smart pointer
 ///
 /// The module implements inefficient and funny user-defined type of smart pointer containing the data File.
-/// Removes data after the end of the lifetime.
-/// For a smart pointer File<'a, T> implemented traits: Deref, DerefMut, Drop
+/// Removes data after the end of the lifetime, unless it is kept by `keep()` or renamed by `persist_to()`.
+/// For a smart pointer File<'a, T> implemented traits: Deref, DerefMut, and Drop of its `Guard`
+/// The buffered variants File<'a, BufReader<fs::File>> and File<'a, BufWriter<fs::File>> are made by `buffered()`.
+/// The file made by `create_atomic()` replaces the target only on `commit()`.
 ///
 /// # Examples
 ///
@@ -30,11 +33,32 @@ mod SPFile {
 
     /// This structure is a smart point.
     /// The target data type is contained in the field `pub file` and has a data type std::fs::File.
-    /// The `path` field  has a data type std::path::Path and contains the path to the file.
+    /// The `guard` field removes the file on drop, it is dropped after `file`,
+    /// so the file is closed before.
     #[derive(Debug)]
     pub struct File<'a, T> {
         pub file: T,
-        path: &'a Path,
+        guard: Guard<'a>,
+    }
+
+    /// The `path` field  has a data type std::path::Path and contains the path to the file.
+    /// The `target` field is the path replaced by the atomic file on `commit()`.
+    #[derive(Debug)]
+    struct Guard<'a> {
+        path: Cow<'a, Path>,
+        target: Option<&'a Path>,
+        on_drop: OnDrop<'a>,
+    }
+
+    /// What happens to the file when File<'a, T> is dropped.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum OnDrop<'a> {
+        /// The file is removed, by default.
+        Remove,
+        /// The file is left as is.
+        Keep,
+        /// The file is renamed to the path.
+        PersistTo(&'a Path),
     }
 
     /// Implements Deref trait for smart pointer struct File<'a, T>.
@@ -56,15 +80,24 @@ mod SPFile {
         }
     }
 
-    /// Implements Drop trait for smart pointer struct File<'a, T>.
+    /// Implements Drop trait for the guard of smart pointer struct File<'a, T>.
     /// If you delete File<'a, T>, the target data will be deleted if the data exists.
-    impl<'a, T> Drop for File<'a, T> {
+    /// The file kept by `keep()` is left, the one persisted by `persist_to()` is renamed.
+    impl<'a> Drop for Guard<'a> {
         /// Realization of the drop function for struct File<'a, T>.
         fn drop(&mut self) {
             if self.path.exists() == true {
-                if let Some(file_name) = &self.path.file_name() {
-                    std::fs::remove_file(file_name);
-                    println!("File is being dropped");
+                match self.on_drop {
+                    OnDrop::Remove => match fs::remove_file(&self.path) {
+                        Ok(()) => println!("File is being dropped"),
+                        Err(err) => println!("File is not removed: {}", err),
+                    },
+                    OnDrop::Keep => {}
+                    OnDrop::PersistTo(path) => {
+                        if fs::rename(&self.path, path).is_ok() {
+                            println!("File is persisted to {}", path.display());
+                        }
+                    }
                 }
             }
         }
@@ -100,7 +133,7 @@ mod SPFile {
         ///      file.write(b);
         ///   }
         /// ```
-        pub fn open(path: &'a Path) -> Option<File<fs::File>> {
+        pub fn open(path: &'a Path) -> Option<File<'a, fs::File>> {
             let file = fs::File::open(path).ok()?;
             Some(File::new(file, path))
         }
@@ -122,10 +155,138 @@ mod SPFile {
         ///      file.read_to_string(&mut buffer);
         ///   }
         /// ```
-        pub fn create(path: &'a Path) -> Option<File<fs::File>> {
+        pub fn create(path: &'a Path) -> Option<File<'a, fs::File>> {
             let file = fs::File::create(path).ok()?;
             Some(File::new(file, path))
         }
+
+        /// Creates a smart-pointer for the atomic writing: the data is written into
+        /// the hidden temporary file `.<name>.tmp` next to `path`, which replaces `path`
+        /// on `commit()` and is removed on drop otherwise.
+        ///
+        /// ## Examples
+        ///
+        /// Basic usage:
+        ///
+        /// ```rust
+        ///  use SPFile::File;
+        ///
+        ///  let path = Path::new("config.toml");
+        ///
+        ///   if let Some(mut file) = File::create_atomic(path){
+        ///      file.write_all_str("debug = true");
+        ///      file.commit();
+        ///   }
+        /// ```
+        pub fn create_atomic(path: &'a Path) -> Option<File<'a, fs::File>> {
+            let name = path.file_name()?.to_string_lossy();
+            let temp = path.with_file_name(format!(".{}.tmp", name));
+            let file = fs::File::create(&temp).ok()?;
+            Some(File {
+                file,
+                guard: Guard {
+                    path: Cow::Owned(temp),
+                    target: Some(path),
+                    on_drop: OnDrop::Remove,
+                },
+            })
+        }
+
+        /// Converts the smart-pointer into the buffered one: `BufReader` or `BufWriter`
+        /// by the target type. The file is not removed by the conversion.
+        ///
+        /// ## Examples
+        ///
+        /// Basic usage:
+        ///
+        /// ```rust
+        ///  use SPFile::File;
+        ///
+        ///  let path = Path::new("file.txt");
+        ///
+        ///   if let Some(file) = File::create(path){
+        ///      let mut file: File<BufWriter<fs::File>> = file.buffered();
+        ///      file.write_all_str("some bytes");
+        ///   }
+        /// ```
+        pub fn buffered<B: Buffered>(self) -> File<'a, B> {
+            // Only the guard implements Drop, so the fields are moved out of `self`.
+            File {
+                file: B::buffer(self.file),
+                guard: self.guard,
+            }
+        }
+    }
+
+    /// The buffered target type of the File<'a, fs::File> made by `buffered()`.
+    pub trait Buffered {
+        /// Wraps the file into the buffer.
+        fn buffer(file: fs::File) -> Self;
+    }
+
+    impl Buffered for BufReader<fs::File> {
+        fn buffer(file: fs::File) -> Self {
+            BufReader::new(file)
+        }
+    }
+
+    impl Buffered for BufWriter<fs::File> {
+        fn buffer(file: fs::File) -> Self {
+            BufWriter::new(file)
+        }
+    }
+
+    /// The target type which data is written to the disk by `commit()`.
+    pub trait SyncAll: Write {
+        /// Flushes the buffers and syncs the data to the disk.
+        fn sync_all(&mut self) -> io::Result<()>;
+    }
+
+    impl SyncAll for fs::File {
+        fn sync_all(&mut self) -> io::Result<()> {
+            fs::File::sync_all(self)
+        }
+    }
+
+    impl SyncAll for BufWriter<fs::File> {
+        fn sync_all(&mut self) -> io::Result<()> {
+            self.flush()?;
+            self.get_ref().sync_all()
+        }
+    }
+
+    /// Commit of the file made by `create_atomic()`.
+    impl<'a, T: SyncAll> File<'a, T> {
+        /// Syncs the written data and renames the temporary file over the target.
+        /// If it fails, the temporary file is removed on drop and the target is left as is.
+        pub fn commit(mut self) -> io::Result<()> {
+            let target = self
+                .guard
+                .target
+                .ok_or_else(|| io::Error::other("the file is not created by create_atomic"))?;
+            self.file.sync_all()?;
+            fs::rename(&self.guard.path, target)?;
+            self.guard.on_drop = OnDrop::Keep;
+            Ok(())
+        }
+    }
+
+    /// Reading of the text from the target data.
+    impl<'a, T: Read> File<'a, T> {
+        /// Reads all the remaining text of the file.
+        pub fn read_to_string(&mut self) -> io::Result<String> {
+            let mut buffer = String::new();
+            self.file.read_to_string(&mut buffer)?;
+            Ok(buffer)
+        }
+    }
+
+    /// Writing of the text into the target data.
+    impl<'a, T: Write> File<'a, T> {
+        /// Writes all the text into the file.
+        pub fn write_all_str(&mut self, text: &str) -> io::Result<()> {
+            self.file.write_all(text.as_bytes())
+        }
     }
 
     /// Implementation of the File for general type.
@@ -134,10 +295,54 @@ mod SPFile {
         /// Creates new `File<T>` smart-pointer.
         fn new(file: T, path: &'a Path) -> Self {
             File {
-                file: file,
-                path: path,
+                file,
+                guard: Guard {
+                    path: Cow::Borrowed(path),
+                    target: None,
+                    on_drop: OnDrop::Remove,
+                },
             }
         }
+
+        /// Disables the removal of the file on drop.
+        ///
+        /// ## Examples
+        ///
+        /// Basic usage:
+        ///
+        /// ```rust
+        ///  use SPFile::File;
+        ///
+        ///  let path = Path::new("file.txt");
+        ///
+        ///   if let Some(mut file) = File::create(path){
+        ///      file.keep();
+        ///   }
+        ///   assert!(path.exists());
+        /// ```
+        pub fn keep(&mut self) {
+            self.guard.on_drop = OnDrop::Keep;
+        }
+
+        /// Renames the file to `path` on drop instead of removing it.
+        ///
+        /// ## Examples
+        ///
+        /// Basic usage:
+        ///
+        /// ```rust
+        ///  use SPFile::File;
+        ///
+        ///  let path = Path::new("file.txt");
+        ///
+        ///   if let Some(mut file) = File::create(path){
+        ///      file.persist_to(Path::new("saved.txt"));
+        ///   }
+        ///   assert!(Path::new("saved.txt").exists());
+        /// ```
+        pub fn persist_to(&mut self, path: &'a Path) {
+            self.guard.on_drop = OnDrop::PersistTo(path);
+        }
     }
 
     #[test]
@@ -169,6 +374,90 @@ mod SPFile {
         }
     }
 
+    #[test]
+    fn buffered_test() {
+        use SPFile::File;
+
+        let path = Path::new("buffered.txt");
+
+        let mut writer: File<BufWriter<fs::File>> = File::create(path).unwrap().buffered();
+        writer.write_all_str("some ").unwrap();
+        writer.write_all_str("text").unwrap();
+        writer.flush().unwrap();
+        assert!(path.exists());
+
+        let mut reader: File<BufReader<fs::File>> = File::open(path).unwrap().buffered();
+        assert_eq!("some text", reader.read_to_string().unwrap());
+        assert_eq!("", reader.read_to_string().unwrap());
+    }
+
+    #[test]
+    fn keep_test() {
+        use SPFile::File;
+
+        let path = Path::new("keep.txt");
+
+        let mut file = File::create(path).unwrap();
+        file.keep();
+        drop(file);
+        assert!(path.exists());
+
+        // Dropped by default.
+        drop(File::open(path).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn persist_test() {
+        use SPFile::File;
+
+        let path = Path::new("persist.txt");
+        let persisted = Path::new("persisted.txt");
+
+        let mut writer: File<BufWriter<fs::File>> = File::create(path).unwrap().buffered();
+        writer.persist_to(persisted);
+        writer.write_all_str("some text").unwrap();
+        drop(writer);
+        assert!(!path.exists());
+
+        let mut reader = File::open(persisted).unwrap();
+        assert_eq!("some text", reader.read_to_string().unwrap());
+        drop(reader);
+        assert!(!persisted.exists());
+    }
+
+    #[test]
+    fn atomic_test() {
+        use SPFile::File;
+
+        let path = Path::new("atomic.txt");
+        let temp = Path::new(".atomic.txt.tmp");
+
+        let mut writer: File<BufWriter<fs::File>> =
+            File::create_atomic(path).unwrap().buffered();
+        writer.write_all_str("some text").unwrap();
+        assert!(temp.exists());
+        assert!(!path.exists());
+        writer.commit().unwrap();
+        assert!(!temp.exists());
+
+        let mut reader = File::open(path).unwrap();
+        assert_eq!("some text", reader.read_to_string().unwrap());
+        reader.keep();
+        drop(reader);
+
+        // Not committed: the target is left as is.
+        let mut writer = File::create_atomic(path).unwrap();
+        writer.write_all_str("other text").unwrap();
+        drop(writer);
+        assert!(!temp.exists());
+        assert_eq!("some text", File::open(path).unwrap().read_to_string().unwrap());
+        assert!(!path.exists());
+
+        // Only the atomic file is committed.
+        assert!(File::create(temp).unwrap().commit().is_err());
+        assert!(!temp.exists());
+    }
 }
 
 fn main() {
@@ -195,4 +484,36 @@ fn main() {
         }
         None => assert!(false),
     }
+
+    let path = Path::new("buffered.txt");
+    let persisted = Path::new("persisted.txt");
+
+    {
+        let mut writer: File<BufWriter<fs::File>> = File::create_atomic(path)
+            .expect("create buffered.txt")
+            .buffered();
+        writer
+            .write_all_str("some text")
+            .expect("write buffered.txt");
+        // .buffered.txt.tmp is renamed to buffered.txt
+        writer.commit().expect("commit buffered.txt");
+    }
+
+    {
+        let mut reader: File<BufReader<fs::File>> =
+            File::open(path).expect("open buffered.txt").buffered();
+        assert_eq!("some text", reader.read_to_string().expect("read buffered.txt"));
+        // buffered.txt is left
+        reader.keep();
+    }
+
+    {
+        let mut file = File::open(path).expect("open buffered.txt");
+        // buffered.txt is renamed to persisted.txt
+        file.persist_to(persisted);
+    }
+
+    // persisted.txt is removed
+    assert!(File::open(persisted).is_some());
+    assert!(!persisted.exists());
 }
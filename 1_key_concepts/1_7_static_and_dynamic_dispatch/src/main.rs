@@ -1,3 +1,6 @@
+extern crate serde;
+extern crate serde_json;
+
 /// # The simple `Queue<T>` collection of fixed size `n`.
 
 /// `Queue<T>` collection  can be used both as
@@ -23,6 +26,8 @@
 ///  }
 /// ```
 mod queue {
+    use serde::de::{Deserialize, Deserializer, Error};
+    use serde::ser::{Serialize, Serializer};
 
     /// The collection Queue works with an array and uses a constant for a fixed size.
     pub const SIZE_ARRAY: usize = 5;
@@ -96,6 +101,70 @@ mod queue {
                 index: Default::default(),
             }
         }
+
+        /// Count of the elements in the queue.
+        pub fn len(&self) -> usize {
+            self.index
+        }
+
+        /// Returns `true` if the queue contains no elements.
+        pub fn is_empty(&self) -> bool {
+            self.index == 0
+        }
+
+        /// Count of the elements the queue can hold, the last cell of the array stays free.
+        pub fn capacity(&self) -> usize {
+            SIZE_ARRAY - 1
+        }
+    }
+
+    /// The queue is serialized as the sequence of its elements in the order they were pushed.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    ///  let mut buffer: Queue<i32> = Queue::new([0; SIZE_ARRAY]);
+    ///  buffer.push(4);
+    ///  buffer.push(5);
+    ///
+    ///  assert_eq!("[4,5]", serde_json::to_string(&buffer).unwrap());
+    /// ```
+    impl<T: Serialize> Serialize for Queue<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(&self.value[..self.index])
+        }
+    }
+
+    /// Restores the queue from the sequence of its elements, the free cells are `T::default()`.
+    /// Fails if there are more elements than the queue can hold.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    ///  let mut buffer: Queue<i32> = serde_json::from_str("[4,5]").unwrap();
+    ///
+    ///  assert_eq!(Some(5), buffer.pop());
+    ///  assert!(serde_json::from_str::<Queue<i32>>("[1,2,3,4,5]").is_err());
+    /// ```
+    impl<'de, T: Deserialize<'de> + Default> Deserialize<'de> for Queue<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let items: Vec<T> = Vec::deserialize(deserializer)?;
+            let mut queue = Queue::new(Default::default());
+            if items.len() > queue.capacity() {
+                return Err(D::Error::invalid_length(
+                    items.len(),
+                    &"at most SIZE_ARRAY - 1 elements",
+                ));
+            }
+            for item in items {
+                queue.push(item);
+            }
+            Ok(queue)
+        }
     }
 
     #[cfg(test)]
@@ -143,6 +212,25 @@ mod queue {
             }
         }
 
+        #[test]
+        fn test_queue_serde() {
+            let mut buffer: Queue<String> = Queue::new(Default::default());
+            buffer.push("first".to_string());
+            buffer.push("second".to_string());
+
+            let json = serde_json::to_string(&buffer).unwrap();
+            assert_eq!(r#"["first","second"]"#, json);
+
+            let mut restored: Queue<String> = serde_json::from_str(&json).unwrap();
+            assert_eq!(2, restored.len());
+            assert_eq!(Some("second".to_string()), restored.pop());
+            assert_eq!(Some("first".to_string()), restored.pop());
+            assert!(restored.is_empty());
+
+            let full: Queue<i32> = serde_json::from_str("[1,2,3,4]").unwrap();
+            assert_eq!(full.capacity(), full.len());
+            assert!(serde_json::from_str::<Queue<i32>>("[1,2,3,4,5]").is_err());
+        }
     }
 
 }
@@ -161,4 +249,11 @@ fn main() {
     } else {
         assert!(false);
     }
+
+    // Example snapshot of the queue
+
+    let snapshot = serde_json::to_string(&buffer).unwrap();
+    let restored: Queue<i32> = serde_json::from_str(&snapshot).unwrap();
+    assert_eq!(buffer.len(), restored.len());
+    assert!(!restored.is_empty());
 }
@@ -0,0 +1,32 @@
+//! # Loader of the environment variables of `.env`
+//!
+//! The variables of `.env` in the current directory fill in the variables
+//! missing in the process, the variables already set are above `.env`.
+//! The downloader of 2_13_futures_and_async_io loads its `.env` with it.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//!  extern crate environment_variables;
+//!
+//!  use environment_variables::load_dotenv;
+//!  use std::env;
+//!
+//!  env::set_var("ENV_VAR_ONE", "from_process");
+//!  load_dotenv();
+//!  assert_eq!("from_process", env::var("ENV_VAR_ONE").unwrap());
+//! ```
+
+extern crate dotenv;
+
+use std::env;
+
+/// Load the variables of `.env` in the current directory, the variables
+/// already set are kept. The file is optional.
+pub fn load_dotenv() {
+    if let Ok(path) = env::current_dir().map(|dir| dir.join(".env")) {
+        let _ = dotenv::from_path(path);
+    }
+}
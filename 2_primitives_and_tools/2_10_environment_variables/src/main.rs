@@ -1,3 +1,4 @@
+extern crate common_errors;
 #[macro_use]
 extern crate serde_derive;
 #[macro_use]
@@ -7,8 +8,10 @@ extern crate dotenv;
 extern crate lazy_static;
 use dotenv::dotenv;
 
+use common_errors::AppError;
 use std::collections::HashMap;
 use std::env;
+use std::ffi::OsString;
 use std::path::Path;
 /// # Example environment variables.
 ///
@@ -98,33 +101,36 @@ mod environment_variables {
     ///
     /// variant_env();
     /// ```
-    pub fn variant_env() {
-        let mut vars: [&str; 3] = ["ENV_VAR_THREE", "ENV_VAR_TWO", "ENV_VAR_ONE"];
+    pub fn variant_env() -> Result<(), AppError> {
+        let vars: [&str; 3] = ["ENV_VAR_THREE", "ENV_VAR_TWO", "ENV_VAR_ONE"];
 
         let vars_map: HashMap<String, String> = vars
-            .into_iter()
+            .iter()
             .map(|&var| {
-                env::var_os(var).map_or((var.to_uppercase(), String::from("<absent>")), |value| {
-                    (
-                        var.to_uppercase(),
-                        value
-                            .into_string()
-                            .and_then(|string| {
-                                if string.is_empty() {
-                                    Ok(String::from("<empty>"))
-                                } else {
-                                    Ok(string)
-                                }
-                            })
-                            .unwrap(),
-                    )
-                })
+                env::var_os(var).map_or(
+                    Ok((var.to_uppercase(), String::from("<absent>"))),
+                    |value| value_to_string(value).map(|string| (var.to_uppercase(), string)),
+                )
             })
-            .collect();
+            .collect::<Result<_, AppError>>()?;
 
         for (key, val) in vars_map.iter() {
             println!("{}: {}", key, val);
         }
+
+        Ok(())
+    }
+
+    /// The value of the variable, `<empty>` if it is empty.
+    fn value_to_string(value: OsString) -> Result<String, AppError> {
+        let string = value
+            .into_string()
+            .map_err(|value| AppError::parse(format!("not unicode value {:?}", value)))?;
+        if string.is_empty() {
+            Ok(String::from("<empty>"))
+        } else {
+            Ok(string)
+        }
     }
 
     /// The function that uses the crate envy.
@@ -146,39 +152,22 @@ mod environment_variables {
     ///
     /// variant_envy();
     /// ```
-    pub fn variant_envy() {
+    pub fn variant_envy() -> Result<(), AppError> {
         let vars_map: HashMap<String, String> = envy::prefixed("ENV_VAR_")
-            .from_env::<Config>()
-            .and_then(|vars_map| {
-                Ok(vars_map
-                    .into_iter()
-                    .map(|(key, value)| {
-                        env::var_os(&key).map_or(
-                            (key.to_uppercase(), String::from("<absent>")),
-                            |value| {
-                                (
-                                    key.to_uppercase(),
-                                    value
-                                        .into_string()
-                                        .and_then(|string| {
-                                            if string.is_empty() {
-                                                Ok(String::from("<empty>"))
-                                            } else {
-                                                Ok(string)
-                                            }
-                                        })
-                                        .unwrap(),
-                                )
-                            },
-                        )
-                    })
-                    .collect())
+            .from_env::<Config>()?
+            .map(|(key, _)| {
+                env::var_os(&key).map_or(
+                    Ok((key.to_uppercase(), String::from("<absent>"))),
+                    |value| value_to_string(value).map(|string| (key.to_uppercase(), string)),
+                )
             })
-            .unwrap();
+            .collect::<Result<_, AppError>>()?;
 
         for (key, val) in vars_map.iter() {
             println!("{}: {}", key, val);
         }
+
+        Ok(())
     }
 
     #[cfg(test)]
@@ -193,8 +182,7 @@ mod environment_variables {
                 assert!(false);
             }
 
-            variant_env();
-            assert!(true);
+            variant_env().unwrap();
         }
 
         #[test]
@@ -205,14 +193,13 @@ mod environment_variables {
                 assert!(false);
             }
 
-            variant_envy();
-            assert!(true);
+            variant_envy().unwrap();
         }
     }
 
 }
 
-fn main() {
+fn main() -> Result<(), AppError> {
     use environment_variables::*;
 
     if let Ok(path) = env::current_dir().and_then(|a| Ok(a.join(".env"))) {
@@ -223,9 +210,9 @@ fn main() {
 
     println!("Variant crate envy:\n");
 
-    variant_envy();
+    variant_envy()?;
 
     println!("\nVariant std::env:\n");
 
-    variant_env();
+    variant_env()
 }
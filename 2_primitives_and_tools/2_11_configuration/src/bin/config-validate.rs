@@ -0,0 +1,49 @@
+//! Validation of the configuration for CI and deploy pipelines:
+//!
+//! ```text
+//! config-validate [FILE]
+//! ```
+//!
+//! Reads `FILE` (`config.toml` by default) with the priorities of
+//! `AppConfig::priority_config`, prints the effective configuration
+//! with the secrets masked, and the errors of the validation pass.
+//! Exits with 1 if `FILE` doesn't exist, the configuration can't be read or is invalid:
+//! unlike the binaries, the validation doesn't fall back to the embedded defaults.
+extern crate common_errors;
+extern crate configuration;
+extern crate toml;
+
+use common_errors::AppError;
+use configuration::configuration::AppConfig;
+use std::env;
+use std::path::Path;
+use std::process;
+
+fn main() {
+    let name = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "config.toml".to_string());
+    if !Path::new(&name).is_file() {
+        fail(&name, &[AppError::config("the file doesn't exist")]);
+    }
+    let config = match AppConfig::load_typed(&name) {
+        Ok(config) => config,
+        Err(err) => fail(&name, &[err]),
+    };
+    let errors = config.validate();
+
+    match toml::Value::try_from(config.masked()).and_then(|value| toml::to_string(&value)) {
+        Ok(effective) => print!("{}", effective),
+        Err(err) => fail(&name, &[AppError::from(err)]),
+    }
+    if !errors.is_empty() {
+        fail(&name, &errors);
+    }
+}
+
+fn fail(name: &str, errors: &[AppError]) -> ! {
+    for err in errors {
+        eprintln!("{}: {}", name, err);
+    }
+    process::exit(1);
+}
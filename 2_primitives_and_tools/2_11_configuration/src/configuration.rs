@@ -0,0 +1,2028 @@
+//! # Hierarchical typed configuration structure for configuration.
+//!
+//! Module based on the structure of the configuration file.
+//!
+//! A priority:
+//! 1. Default value in `Rust` sources;
+//! 2. Value of `default_config.toml` compiled into the binary;
+//! 3. Value read from `TOML`, `YAML` or `JSON` file, if it exists;
+//! 4. Value read from the file of the profile set by `APP_PROFILE`,
+//!    like `config.prod.toml`, if it exists;
+//! 5. Value set by environment variable;
+//! 6. Value set by `--set key=value` of the command line (`Override`).
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//!
+//!    extern crate common_errors;
+//!    extern crate configuration;
+//!
+//!    use common_errors::AppError;
+//!    use configuration::configuration::*;
+//!
+//!    fn main() -> Result<(), AppError> {
+//!        let config: AppConfig = AppConfig::load_typed("config.toml")?;
+//!
+//!        assert_eq!("127.0.0.1", config.db.mysql.host);
+//!
+//!        Ok(())
+//!    }
+//! ```
+
+use common_errors::AppError;
+use config::*;
+use humantime;
+use secrets::Secret;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use toml;
+
+/// `default_config.toml`, the defaults of all the keys in one file.
+pub const DEFAULT_CONFIG: &str = include_str!("../default_config.toml");
+
+const REDIS_PORT: u16 = 6379;
+const REDIS_HOST: &'static str = "127.0.0.1";
+
+/// Implements `Deserialize` for the struct with the listed fields,
+/// the missing fields are taken from the `Default` of the struct.
+///
+/// ```rust,ignore
+/// default_field!(Addr {
+///     host: Cow<'static, str>,
+///     port: u16,
+/// });
+/// ```
+macro_rules! default_field {
+    ($name:ident { $($field:ident: $ty:ty),* $(,)* }) => {
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<$name, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                #[derive(Deserialize)]
+                struct Fields {
+                    $($field: Option<$ty>,)*
+                }
+
+                let fields = Fields::deserialize(deserializer)?;
+                let default = $name::default();
+                Ok($name {
+                    $($field: fields.$field.unwrap_or(default.$field),)*
+                })
+            }
+        }
+    };
+}
+
+/// Configuration parameter `mode`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Mode {
+    pub debug: bool,
+}
+/// Default Value for `Mode`.
+impl Default for Mode {
+    fn default() -> Self {
+        Mode { debug: false }
+    }
+}
+/// Configuration parameter `server`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Server {
+    pub shard_url: Cow<'static, str>,
+    pub http_port: u16,
+    pub grpc_port: u16,
+    pub healthz_port: u16,
+    pub metrics_port: u16,
+}
+/// Default Value for `Server`.
+impl Default for Server {
+    fn default() -> Self {
+        Server {
+            shard_url: "http://127.0.0.1".into(),
+            http_port: 8081,
+            grpc_port: 8082,
+            healthz_port: 10025,
+            metrics_port: 9199,
+        }
+    }
+}
+/// Configuration parameter `db`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Db {
+    pub mysql: MySQL,
+    pub redis: Redis,
+}
+/// Default Value for `Db`.
+impl Default for Db {
+    fn default() -> Self {
+        Db {
+            mysql: Default::default(),
+            redis: Default::default(),
+        }
+    }
+}
+
+/// Configuration parameter `redis`.
+/// Setting for the `db` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Redis {
+    pub addrs: Vec<Addr>,
+}
+/// Default Value for `Redis`.
+impl Default for Redis {
+    fn default() -> Self {
+        Redis {
+            addrs: vec![Default::default()],
+        }
+    }
+}
+/// Configuration parameter `addr`.
+/// Setting for the `redis` parameter.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Addr {
+    pub host: Cow<'static, str>,
+    pub port: u16,
+}
+/// Default Value for `Addr`.
+impl Default for Addr {
+    fn default() -> Self {
+        Addr {
+            host: REDIS_HOST.into(),
+            port: REDIS_PORT,
+        }
+    }
+}
+
+default_field!(Addr {
+    host: Cow<'static, str>,
+    port: u16,
+});
+
+/// Configuration parameter `mysql`.
+/// Setting for the `db` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MySQL {
+    pub host: Cow<'static, str>,
+    pub port: u16,
+    pub user: Cow<'static, str>,
+    pub pass: Secret,
+    pub databases: Databases,
+    pub connections: Connections,
+}
+/// Default Value for `MySQL`.
+impl Default for MySQL {
+    fn default() -> Self {
+        MySQL {
+            host: "127.0.0.1".into(),
+            port: 3306,
+            user: "root".into(),
+            pass: "".into(),
+            databases: Default::default(),
+            connections: Default::default(),
+        }
+    }
+}
+
+/// Configuration parameter `databases`.
+/// Setting for the `mysql` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Databases {
+    pub dating: Cow<'static, str>,
+    pub social: Cow<'static, str>,
+}
+/// Default Value for `Databases`.
+impl Default for Databases {
+    fn default() -> Self {
+        Databases {
+            dating: "dating".into(),
+            social: "social".into(),
+        }
+    }
+}
+
+/// Configuration parameter `connections`.
+/// Setting for the `mysql` parameter.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Connections {
+    pub max_idle: u16,
+    pub max_open: u16,
+}
+/// Default Value for `Connections`.
+impl Default for Connections {
+    fn default() -> Self {
+        Connections {
+            max_idle: 30,
+            max_open: 30,
+        }
+    }
+}
+default_field!(Connections {
+    max_idle: u16,
+    max_open: u16,
+});
+
+/// Configuration parameter `ms`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Ms {
+    pub openvidu: Openvidu,
+}
+/// Default Value for `Ms`.
+impl Default for Ms {
+    fn default() -> Self {
+        Ms {
+            openvidu: Default::default(),
+        }
+    }
+}
+
+/// Configuration parameter `openvidu`.
+/// Setting for the `ms` parameter.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Openvidu {
+    pub host: Cow<'static, str>,
+    pub grpc_port: u16,
+    pub metrics_port: u16,
+}
+/// Default Value for `Openvidu`.
+impl Default for Openvidu {
+    fn default() -> Self {
+        Openvidu {
+            host: "127.0.0.1".into(),
+            grpc_port: 8080,
+            metrics_port: 9321,
+        }
+    }
+}
+default_field!(Openvidu {
+    host: Cow<'static, str>,
+    grpc_port: u16,
+    metrics_port: u16,
+});
+
+/// Configuration parameter `log`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Log {
+    pub app: LogLevel,
+    pub access: LogLevel,
+    pub user: LogLevel,
+}
+/// Default Value for `Log`.
+impl Default for Log {
+    fn default() -> Self {
+        Log {
+            app: LogLevel {
+                level: ErrorLevel::INFO,
+            },
+            access: LogLevel {
+                level: ErrorLevel::INFO,
+            },
+            user: LogLevel {
+                level: ErrorLevel::INFO,
+            },
+        }
+    }
+}
+
+/// Enumeration contains types of possible errors.
+/// Serialized as it is written in the configuration file.
+#[derive(Debug, Serialize, PartialEq)]
+pub enum ErrorLevel {
+    #[serde(rename = "debug")]
+    DEBUG,
+    #[serde(rename = "info")]
+    INFO,
+    #[serde(rename = "warn")]
+    WARN,
+    #[serde(rename = "error")]
+    ERROR,
+    #[serde(rename = "fatal")]
+    FATAL,
+    #[serde(rename = "panic")]
+    PANIC,
+    #[serde(rename = "")]
+    EMPTY,
+}
+/// Names of the `ErrorLevel` variants in the configuration file, in ascending order.
+pub const ERROR_LEVEL_NAMES: &[&str] = &["debug", "info", "warn", "error", "fatal", "panic", ""];
+
+/// Implemented Deserialize for coexistence of error types in the `ErrorLevel` enumeration.
+impl<'de> Deserialize<'de> for ErrorLevel {
+    fn deserialize<D>(deserializer: D) -> Result<ErrorLevel, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FieldVisitor;
+
+        impl<'de> Visitor<'de> for FieldVisitor {
+            type Value = ErrorLevel;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str(
+                    "Possible values (in ascending order):\n\
+                     \"debug\", \"info\", \"warn\", \"error\", \"fatal\", \"panic\", \"\"",
+                )
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<ErrorLevel, E>
+            where
+                E: de::Error,
+            {
+                value
+                    .parse()
+                    .map_err(|_| de::Error::unknown_field(value, ERROR_LEVEL_NAMES))
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+/// Default Value for `ErrorLevel`.
+impl Default for ErrorLevel {
+    fn default() -> Self {
+        ErrorLevel::EMPTY
+    }
+}
+
+impl ErrorLevel {
+    /// The name of the level in the configuration file and in the command line flags.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ErrorLevel::DEBUG => "debug",
+            ErrorLevel::INFO => "info",
+            ErrorLevel::WARN => "warn",
+            ErrorLevel::ERROR => "error",
+            ErrorLevel::FATAL => "fatal",
+            ErrorLevel::PANIC => "panic",
+            ErrorLevel::EMPTY => "",
+        }
+    }
+
+    /// The lowest slog level to log, `None` for the empty level disabling the logging.
+    ///
+    /// slog has no level above `Critical`, so `fatal` and `panic` are both `Critical`.
+    #[cfg(feature = "slog")]
+    pub fn as_slog_level(&self) -> Option<slog::Level> {
+        match *self {
+            ErrorLevel::DEBUG => Some(slog::Level::Debug),
+            ErrorLevel::INFO => Some(slog::Level::Info),
+            ErrorLevel::WARN => Some(slog::Level::Warning),
+            ErrorLevel::ERROR => Some(slog::Level::Error),
+            ErrorLevel::FATAL | ErrorLevel::PANIC => Some(slog::Level::Critical),
+            ErrorLevel::EMPTY => None,
+        }
+    }
+}
+
+/// The name of the level, parsed by `from_str`.
+impl fmt::Display for ErrorLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parse the name of the level, like `"warn"`, as it is written in the configuration file.
+impl FromStr for ErrorLevel {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<ErrorLevel, AppError> {
+        match value {
+            "debug" => Ok(ErrorLevel::DEBUG),
+            "info" => Ok(ErrorLevel::INFO),
+            "warn" => Ok(ErrorLevel::WARN),
+            "error" => Ok(ErrorLevel::ERROR),
+            "fatal" => Ok(ErrorLevel::FATAL),
+            "panic" => Ok(ErrorLevel::PANIC),
+            "" => Ok(ErrorLevel::EMPTY),
+            _ => Err(AppError::config(format!(
+                "{:?} is not a log level, possible values: {:?}",
+                value, ERROR_LEVEL_NAMES
+            ))),
+        }
+    }
+}
+
+/// The same as `from_str`.
+impl<'a> TryFrom<&'a str> for ErrorLevel {
+    type Error = AppError;
+
+    fn try_from(value: &'a str) -> Result<ErrorLevel, AppError> {
+        value.parse()
+    }
+}
+
+/// Configuration parameter `level`.
+/// Setting for the `log` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LogLevel {
+    pub level: ErrorLevel,
+}
+/// Default Value for `LogLevel`.
+impl Default for LogLevel {
+    fn default() -> Self {
+        LogLevel {
+            level: ErrorLevel::EMPTY,
+        }
+    }
+}
+
+/// Configuration parameter `auth`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Auth {
+    pub user_password_salt: Secret,
+    pub renewal_duration: Cow<'static, str>,
+}
+/// Default Value for `Auth`.
+impl Default for Auth {
+    fn default() -> Self {
+        Auth {
+            user_password_salt: "".into(),
+            renewal_duration: "5m".into(),
+        }
+    }
+}
+
+/// Configuration parameter `app`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct App {
+    pub shutdown_timeout: Cow<'static, str>,
+    pub live_stream: LiveStream,
+    pub setup_stream: SetupStream,
+}
+/// Default Value for `App`.
+impl Default for App {
+    fn default() -> Self {
+        App {
+            shutdown_timeout: "30s".into(),
+            live_stream: Default::default(),
+            setup_stream: Default::default(),
+        }
+    }
+}
+/// Configuration parameter `setup_stream`.
+/// Setting for the `app` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct SetupStream {
+    pub idle_timeout: Cow<'static, str>,
+    pub starting_timeout: Cow<'static, str>,
+}
+/// Default Value for `SetupStream`.
+impl Default for SetupStream {
+    fn default() -> Self {
+        SetupStream {
+            idle_timeout: "5s".into(),
+            starting_timeout: "20s".into(),
+        }
+    }
+}
+
+/// Configuration parameter `live_stream`.
+/// Setting for the `app` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LiveStream {
+    pub max_message_length: u16,
+    pub idle_timeout: Cow<'static, str>,
+    pub starting_timeout: Cow<'static, str>,
+    pub visit: Visit,
+    pub preview: Preview,
+}
+/// Default Value for `LiveStream`.
+impl Default for LiveStream {
+    fn default() -> Self {
+        LiveStream {
+            max_message_length: 1000,
+            idle_timeout: "5s".into(),
+            starting_timeout: "20s".into(),
+            visit: Default::default(),
+            preview: Default::default(),
+        }
+    }
+}
+
+/// Configuration parameter `visit`.
+/// Setting for the `live_stream` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Visit {
+    pub idle_timeout: Cow<'static, str>,
+    pub starting_timeout: Cow<'static, str>,
+}
+/// Default Value for `Visit`.
+impl Default for Visit {
+    fn default() -> Self {
+        Visit {
+            idle_timeout: "5s".into(),
+            starting_timeout: "20s".into(),
+        }
+    }
+}
+
+/// Configuration parameter `preview`.
+/// Setting for the `live_stream` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Preview {
+    pub idle_timeout: Cow<'static, str>,
+    pub starting_timeout: Cow<'static, str>,
+}
+/// Default Value for `Preview`.
+impl Default for Preview {
+    fn default() -> Self {
+        Preview {
+            idle_timeout: "5s".into(),
+            starting_timeout: "20s".into(),
+        }
+    }
+}
+
+/// Configuration parameter `background`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Background {
+    pub finalizer: Finalizer,
+    pub recounter: Recounter,
+    pub watchdog: Watchdog,
+}
+/// Default Value for `Background`.
+impl Default for Background {
+    fn default() -> Self {
+        Background {
+            finalizer: Default::default(),
+            recounter: Default::default(),
+            watchdog: Default::default(),
+        }
+    }
+}
+
+/// Configuration parameter `finalizer`.
+/// Setting for the `background` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Finalizer {
+    pub period: Cow<'static, str>,
+    pub limit: Cow<'static, str>,
+}
+/// Default Value for `Finalizer`.
+impl Default for Finalizer {
+    fn default() -> Self {
+        Finalizer {
+            period: "10s".into(),
+            limit: "50".into(),
+        }
+    }
+}
+
+/// Configuration parameter `recounter`.
+/// Setting for the `background` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Recounter {
+    pub period: Cow<'static, str>,
+    pub limit: Cow<'static, str>,
+    pub lock_timeout: Cow<'static, str>,
+}
+/// Default Value for `Recounter`.
+impl Default for Recounter {
+    fn default() -> Self {
+        Recounter {
+            period: "5s".into(),
+            limit: "50".into(),
+            lock_timeout: "4s".into(),
+        }
+    }
+}
+
+/// Configuration parameter `watchdog`.
+/// Setting for the `background` parameter.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Watchdog {
+    pub period: Cow<'static, str>,
+    pub limit: Cow<'static, str>,
+    pub lock_timeout: Cow<'static, str>,
+}
+/// Default Value for `Watchdog`.
+impl Default for Watchdog {
+    fn default() -> Self {
+        Watchdog {
+            period: "5s".into(),
+            limit: "10".into(),
+            lock_timeout: "4s".into(),
+        }
+    }
+}
+
+/// Configuration parameter `actors`.
+/// Topology of the actors of the matrix example (2_14_actors).
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Actors {
+    pub consumers: u16,
+    pub arbiter: ConsumerArbiter,
+    pub producer_interval: Cow<'static, str>,
+}
+/// Default Value for `Actors`.
+impl Default for Actors {
+    fn default() -> Self {
+        Actors {
+            consumers: 2,
+            arbiter: ConsumerArbiter::Same,
+            producer_interval: "110ms".into(),
+        }
+    }
+}
+
+/// Where the consumers of `actors` run.
+/// Serialized as it is written in the configuration file.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum ConsumerArbiter {
+    /// On the arbiter of the producer, in its thread.
+    #[serde(rename = "same")]
+    Same,
+    /// On a `SyncArbiter`, a thread per consumer.
+    #[serde(rename = "sync")]
+    Sync,
+}
+/// Implemented Deserialize for the names of the `ConsumerArbiter` variants.
+impl<'de> Deserialize<'de> for ConsumerArbiter {
+    fn deserialize<D>(deserializer: D) -> Result<ConsumerArbiter, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct FieldVisitor;
+
+        impl<'de> Visitor<'de> for FieldVisitor {
+            type Value = ConsumerArbiter;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("Possible values: \"same\", \"sync\"")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<ConsumerArbiter, E>
+            where
+                E: de::Error,
+            {
+                match value {
+                    "same" => Ok(ConsumerArbiter::Same),
+                    "sync" => Ok(ConsumerArbiter::Sync),
+                    _ => Err(de::Error::unknown_variant(value, &["same", "sync"])),
+                }
+            }
+        }
+
+        deserializer.deserialize_identifier(FieldVisitor)
+    }
+}
+
+/// Configuration parameter `ice`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Ice {
+    pub servers: Vec<IceServer>,
+}
+/// Default Value for `Ice`.
+impl Default for Ice {
+    fn default() -> Self {
+        Ice {
+            servers: vec![IceServer {
+                scheme: IceScheme::Turn,
+                credentials: Some(("access_token".into(), "qwerty".into())),
+                host: "127.0.0.1".into(),
+                port: 3478,
+            }],
+        }
+    }
+}
+
+/// Scheme of the URL of an ICE server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IceScheme {
+    Stun,
+    Stuns,
+    Turn,
+    Turns,
+}
+impl IceScheme {
+    /// Port of the server if the URL has none.
+    pub fn default_port(self) -> u16 {
+        match self {
+            IceScheme::Stun | IceScheme::Turn => 3478,
+            IceScheme::Stuns | IceScheme::Turns => 5349,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            IceScheme::Stun => "stun",
+            IceScheme::Stuns => "stuns",
+            IceScheme::Turn => "turn",
+            IceScheme::Turns => "turns",
+        }
+    }
+}
+
+/// An entry of `ice.servers`: `scheme:[username:credential@]host[:port]`,
+/// the credentials are required by TURN and forbidden for STUN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IceServer {
+    pub scheme: IceScheme,
+    /// Username and credential.
+    pub credentials: Option<(Cow<'static, str>, Cow<'static, str>)>,
+    /// Name, IPv4 or IPv6 address in brackets.
+    pub host: Cow<'static, str>,
+    pub port: u16,
+}
+
+/// Parse an entry of `ice.servers` like `turn:access_token:qwerty@127.0.0.1:3478`.
+impl FromStr for IceServer {
+    type Err = AppError;
+
+    fn from_str(url: &str) -> Result<IceServer, AppError> {
+        let invalid = |reason: &str| AppError::config(format!("ice.servers: {:?}: {}", url, reason));
+
+        let colon = url.find(':').ok_or_else(|| invalid("no scheme"))?;
+        let scheme = match &url[..colon] {
+            "stun" => IceScheme::Stun,
+            "stuns" => IceScheme::Stuns,
+            "turn" => IceScheme::Turn,
+            "turns" => IceScheme::Turns,
+            _ => return Err(invalid("the scheme is not stun, stuns, turn or turns")),
+        };
+        let rest = &url[colon + 1..];
+
+        let (credentials, addr) = match rest.rfind('@') {
+            Some(at) => {
+                let mut parts = rest[..at].splitn(2, ':');
+                let username = parts.next().unwrap_or("");
+                let credential = parts
+                    .next()
+                    .ok_or_else(|| invalid("the credentials are not `username:credential`"))?;
+                if username.is_empty() {
+                    return Err(invalid("empty username"));
+                }
+                let credentials = (username.to_string().into(), credential.to_string().into());
+                (Some(credentials), &rest[at + 1..])
+            }
+            None => (None, rest),
+        };
+        match (scheme, &credentials) {
+            (IceScheme::Turn, &None) | (IceScheme::Turns, &None) => {
+                return Err(invalid("TURN requires credentials"))
+            }
+            (IceScheme::Stun, &Some(_)) | (IceScheme::Stuns, &Some(_)) => {
+                return Err(invalid("STUN has no credentials"))
+            }
+            _ => {}
+        }
+
+        // The port is after the last colon, unless it is inside an IPv6 address.
+        let (host, port) = match addr.rfind(':') {
+            Some(colon) if !addr[colon..].contains(']') => (&addr[..colon], Some(&addr[colon + 1..])),
+            _ => (addr, None),
+        };
+        if host.is_empty() {
+            return Err(invalid("empty host"));
+        }
+        if host.starts_with('[') != host.ends_with(']') {
+            return Err(invalid("unclosed IPv6 address"));
+        }
+        let port = match port {
+            Some(port) => match port.parse() {
+                Ok(0) | Err(_) => return Err(invalid("the port is not in 1..65535")),
+                Ok(port) => port,
+            },
+            None => scheme.default_port(),
+        };
+
+        Ok(IceServer {
+            scheme,
+            credentials,
+            host: host.to_string().into(),
+            port,
+        })
+    }
+}
+
+/// The URL of the server, parsed by `from_str`.
+impl fmt::Display for IceServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.scheme.as_str())?;
+        if let Some((ref username, ref credential)) = self.credentials {
+            write!(f, "{}:{}@", username, credential)?;
+        }
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// Serialized as its URL.
+impl Serialize for IceServer {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Implemented Deserialize for the validation of the URL of `IceServer`.
+impl<'de> Deserialize<'de> for IceServer {
+    fn deserialize<D>(deserializer: D) -> Result<IceServer, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct UrlVisitor;
+
+        impl<'de> Visitor<'de> for UrlVisitor {
+            type Value = IceServer;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("URL like \"turn:username:credential@host:port\"")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<IceServer, E>
+            where
+                E: de::Error,
+            {
+                value.parse().map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(UrlVisitor)
+    }
+}
+
+/// The main structure contains all the configuration settings.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct AppConfig {
+    pub mode: Mode,
+    pub server: Server,
+    pub db: Db,
+    pub ms: Ms,
+    pub log: Log,
+    pub auth: Auth,
+    pub app: App,
+    pub background: Background,
+    pub actors: Actors,
+    pub ice: Ice,
+}
+
+/// A layer of `AppConfig::priority_config`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    /// `AppConfig::default()`.
+    Defaults,
+    /// `DEFAULT_CONFIG` compiled into the binary.
+    Embedded,
+    /// The TOML, YAML or JSON file, skipped if it doesn't exist.
+    File,
+    /// The file of the profile, like `config.prod.toml` for `config.toml`,
+    /// skipped if there is no profile or the file doesn't exist.
+    Profile,
+    /// The environment variables, like `DB__MYSQL__HOST` for `db.mysql.host`.
+    Environment,
+}
+
+impl Source {
+    /// The layers from the lowest priority to the highest.
+    pub const ALL: [Source; 5] = [
+        Source::Defaults,
+        Source::Embedded,
+        Source::File,
+        Source::Profile,
+        Source::Environment,
+    ];
+
+    /// Merge the layer into `config`, `name` is the file in the `format`.
+    fn merge(
+        self,
+        config: &mut Config,
+        name: &str,
+        format: ConfigFormat,
+        profile: Option<&str>,
+    ) -> Result<(), AppError> {
+        match self {
+            Source::Defaults => {
+                let my_conf: AppConfig = Default::default();
+                config.merge(Config::try_from(&my_conf)?)?;
+            }
+            Source::Embedded => {
+                config.merge(config::File::from_str(DEFAULT_CONFIG, FileFormat::Toml))?;
+            }
+            Source::File => {
+                config.merge(config::File::new(name, format.into()).required(false))?;
+            }
+            Source::Profile => {
+                if let Some(profile) = profile {
+                    let name = profile_file(name, profile);
+                    config.merge(config::File::new(&name, format.into()).required(false))?;
+                }
+            }
+            Source::Environment => {
+                config.merge(environment())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Separator of the names of a key in its environment variable. It is `__`,
+/// so the names with `_` are kept: `SERVER__HTTP_PORT` is `server.http_port`.
+pub const ENV_SEPARATOR: &str = "__";
+
+/// The environment variables of `Source::Environment`, like `DB__MYSQL__HOST` for `db.mysql.host`,
+/// for the other configurations following the same convention.
+pub fn environment() -> config::Environment {
+    config::Environment::new().separator(ENV_SEPARATOR)
+}
+
+/// The environment variable of the key, like `DB__MYSQL__HOST` for `db.mysql.host`.
+pub fn env_var(key: &str) -> String {
+    key.replace('.', ENV_SEPARATOR).to_uppercase()
+}
+
+/// Environment variable with the name of the profile, like `prod` or `staging`.
+pub const PROFILE_VAR: &str = "APP_PROFILE";
+
+/// The file of the profile next to the file `name`: `config.prod.toml` for `config.toml`.
+pub fn profile_file(name: &str, profile: &str) -> String {
+    let path = Path::new(name);
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => path
+            .with_extension(format!("{}.{}", profile, ext))
+            .to_string_lossy()
+            .into_owned(),
+        None => format!("{}.{}", name, profile),
+    }
+}
+
+/// The profile set by `PROFILE_VAR`, the empty value is no profile.
+fn env_profile() -> Result<Option<String>, AppError> {
+    match env::var(PROFILE_VAR) {
+        Ok(ref profile) if profile.is_empty() => Ok(None),
+        Ok(profile) => Ok(Some(profile)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(err) => Err(AppError::config(format!("{}: {}", PROFILE_VAR, err))),
+    }
+}
+
+/// Format of the configuration file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Format detected by the extension of the file: `.toml`, `.yaml`, `.yml` or `.json`.
+    /// The file without an extension is TOML.
+    pub fn from_path(name: &str) -> Result<ConfigFormat, AppError> {
+        match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+            None | Some("toml") => Ok(ConfigFormat::Toml),
+            Some("yaml") | Some("yml") => Ok(ConfigFormat::Yaml),
+            Some("json") => Ok(ConfigFormat::Json),
+            Some(ext) => Err(AppError::config(format!(
+                "{}: unknown configuration format: {}",
+                name, ext
+            ))),
+        }
+    }
+}
+
+impl From<ConfigFormat> for FileFormat {
+    fn from(format: ConfigFormat) -> FileFormat {
+        match format {
+            ConfigFormat::Toml => FileFormat::Toml,
+            ConfigFormat::Yaml => FileFormat::Yaml,
+            ConfigFormat::Json => FileFormat::Json,
+        }
+    }
+}
+
+/// `--set key=value` of the command line, merged above all the other layers.
+///
+/// The key is a path like `db.mysql.port` or `db.redis.addrs[0].host`,
+/// the value is coerced to a bool (`true`, `false`), to an integer
+/// or else is a string; a quoted value like `"3307"` is always a string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    pub key: String,
+    pub value: OverrideValue,
+}
+
+/// Coerced value of `Override`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OverrideValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl OverrideValue {
+    fn coerce(value: &str) -> OverrideValue {
+        let value = value.trim();
+        if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            return OverrideValue::Str(value[1..value.len() - 1].to_string());
+        }
+        if let Ok(flag) = value.parse() {
+            return OverrideValue::Bool(flag);
+        }
+        if let Ok(number) = value.parse() {
+            return OverrideValue::Int(number);
+        }
+        OverrideValue::Str(value.to_string())
+    }
+}
+
+impl From<OverrideValue> for Value {
+    fn from(value: OverrideValue) -> Value {
+        match value {
+            OverrideValue::Bool(flag) => flag.into(),
+            OverrideValue::Int(number) => number.into(),
+            OverrideValue::Str(text) => text.into(),
+        }
+    }
+}
+
+/// The key path: the names of `[a-z0-9_]` separated by dots,
+/// each name may be followed by the array indices like `[0]`.
+fn is_key_path(key: &str) -> bool {
+    key.split('.').all(|segment| {
+        let name_end = segment.find('[').unwrap_or(segment.len());
+        let (name, mut indices) = segment.split_at(name_end);
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return false;
+        }
+        while !indices.is_empty() {
+            let close = match indices.find(']') {
+                Some(close) => close,
+                None => return false,
+            };
+            if !indices.starts_with('[')
+                || close == 1
+                || !indices[1..close].chars().all(|c| c.is_ascii_digit())
+            {
+                return false;
+            }
+            indices = &indices[close + 1..];
+        }
+        true
+    })
+}
+
+impl FromStr for Override {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Override, AppError> {
+        let eq = s
+            .find('=')
+            .ok_or_else(|| AppError::config(format!("{}: expected key=value", s)))?;
+        let key = s[..eq].trim();
+        if !is_key_path(key) {
+            return Err(AppError::config(format!("{}: invalid key {:?}", s, key)));
+        }
+        Ok(Override {
+            key: key.to_string(),
+            value: OverrideValue::coerce(&s[eq + 1..]),
+        })
+    }
+}
+
+/// Create a config with priority.
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///
+///    extern crate common_errors;
+///    extern crate config;
+///    extern crate configuration;
+///
+///    use common_errors::AppError;
+///    use config::Config;
+///    use configuration::configuration::*;
+///
+///    fn main() -> Result<(), AppError> {
+///        let config: Config = AppConfig::priority_config("config.toml")?;
+///
+///     Ok(())
+/// }
+/// ```
+impl AppConfig {
+    pub fn priority_config(name: &str) -> Result<Config, AppError> {
+        let profile = env_profile()?;
+        AppConfig::priority_config_profile(name, profile.as_deref())
+    }
+
+    /// `priority_config` with the given profile instead of the one of `PROFILE_VAR`.
+    pub fn priority_config_profile(name: &str, profile: Option<&str>) -> Result<Config, AppError> {
+        AppConfig::layers(name, ConfigFormat::from_path(name)?, profile)
+    }
+
+    /// `priority_config` with the `overrides` of the command line above all the layers.
+    /// The overridden key must exist in the configuration.
+    pub fn priority_config_with(name: &str, overrides: &[Override]) -> Result<Config, AppError> {
+        let mut config = AppConfig::priority_config(name)?;
+        for item in overrides {
+            if config.get::<Value>(&item.key).is_err() {
+                return Err(AppError::config(format!("{}: unknown key", item.key)));
+            }
+            config.set(&item.key, item.value.clone())?;
+        }
+        Ok(config)
+    }
+
+    /// `load_typed` with the `overrides` of the command line.
+    pub fn load_typed_with(name: &str, overrides: &[Override]) -> Result<AppConfig, AppError> {
+        Ok(AppConfig::priority_config_with(name, overrides)?.try_into()?)
+    }
+
+    /// The same as `priority_config`, but the file is read in the given format
+    /// whatever its extension is.
+    pub fn priority_config_as(name: &str, format: ConfigFormat) -> Result<Config, AppError> {
+        let profile = env_profile()?;
+        AppConfig::layers(name, format, profile.as_deref())
+    }
+
+    fn layers(name: &str, format: ConfigFormat, profile: Option<&str>) -> Result<Config, AppError> {
+        if let Some(profile) = profile {
+            let valid = profile
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if profile.is_empty() || !valid {
+                return Err(AppError::config(format!(
+                    "invalid profile name: {:?}",
+                    profile
+                )));
+            }
+        }
+        let mut config = Config::new();
+        for source in &Source::ALL {
+            source.merge(&mut config, name, format, profile)?;
+        }
+        Ok(config)
+    }
+
+    /// Typed configuration read from `name` with the priorities of `priority_config`:
+    /// the fully merged `Config` is deserialized back into `AppConfig`,
+    /// so the values are accessed as fields, like `config.db.mysql.host`.
+    pub fn load_typed(name: &str) -> Result<AppConfig, AppError> {
+        Ok(AppConfig::priority_config(name)?.try_into()?)
+    }
+
+    /// Validation pass over the values which fit their types, but not the application:
+    /// the ports, the durations, the limits and the required names.
+    /// Returns all the found errors, empty if the configuration is valid.
+    pub fn validate(&self) -> Vec<AppError> {
+        Validate::validate(self, "")
+    }
+
+    /// The configuration with the password, the salt and the credentials
+    /// of the ICE servers replaced by `MASK`, to be printed or logged.
+    pub fn masked(mut self) -> AppConfig {
+        if !self.db.mysql.pass.is_empty() {
+            self.db.mysql.pass = MASK.into();
+        }
+        if !self.auth.user_password_salt.is_empty() {
+            self.auth.user_password_salt = MASK.into();
+        }
+        for server in &mut self.ice.servers {
+            if let Some((_, ref mut credential)) = server.credentials {
+                *credential = MASK.into();
+            }
+        }
+        self
+    }
+
+    /// Every key of the configuration with the environment variable overriding it,
+    /// like `("db.mysql.host", "DB__MYSQL__HOST")`, sorted by key.
+    /// The arrays, like `db.redis.addrs`, can't be set from the environment
+    /// and aren't listed.
+    pub fn env_keys() -> Vec<(String, String)> {
+        let mut keys = Vec::new();
+        if let Ok(toml::Value::Table(table)) = toml::Value::try_from(AppConfig::default()) {
+            collect_env_keys("", &table, &mut keys);
+        }
+        keys
+    }
+}
+
+/// Walks the tables of `AppConfig::env_keys` depth-first.
+fn collect_env_keys(prefix: &str, table: &toml::value::Table, keys: &mut Vec<(String, String)>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match *value {
+            toml::Value::Table(ref table) => collect_env_keys(&key, table, keys),
+            toml::Value::Array(_) => {}
+            _ => {
+                let env = env_var(&key);
+                keys.push((key, env));
+            }
+        }
+    }
+}
+
+/// Replacement of the secrets in `AppConfig::masked`.
+pub const MASK: &str = "******";
+
+/// Validation of a section of the configuration.
+///
+/// `path` is the key of the section, like `db.mysql`, the errors are reported
+/// for the full keys of the fields, like `db.mysql.port: the port is 0`.
+pub trait Validate {
+    /// All the invalid values of the section, empty if it is valid.
+    fn validate(&self, path: &str) -> Vec<AppError>;
+}
+
+/// Errors collected by `Validate::validate` for the fields of the section `path`.
+struct Validation<'a> {
+    path: &'a str,
+    errors: Vec<AppError>,
+}
+
+impl<'a> Validation<'a> {
+    fn new(path: &'a str) -> Self {
+        Validation {
+            path,
+            errors: Vec::new(),
+        }
+    }
+
+    /// The full key of the field.
+    fn key(&self, field: &str) -> String {
+        if self.path.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", self.path, field)
+        }
+    }
+
+    fn check(&mut self, valid: bool, field: &str, reason: &str) {
+        if !valid {
+            let key = self.key(field);
+            self.errors.push(AppError::config(format!("{}: {}", key, reason)));
+        }
+    }
+
+    fn not_empty(&mut self, field: &str, value: &str) {
+        self.check(!value.trim().is_empty(), field, "is empty");
+    }
+
+    fn port(&mut self, field: &str, port: u16) {
+        self.check(port != 0, field, "the port is 0");
+    }
+
+    fn duration(&mut self, field: &str, value: &str) {
+        if let Err(err) = humantime::parse_duration(value) {
+            self.check(false, field, &format!("{:?}: {}", value, err));
+        }
+    }
+
+    fn limit(&mut self, field: &str, value: &str) {
+        match value.trim().parse::<usize>() {
+            Ok(0) => self.check(false, field, "must be positive"),
+            Ok(_) => {}
+            Err(err) => self.check(false, field, &format!("{:?}: {}", value, err)),
+        }
+    }
+
+    /// Validate the nested section `field`.
+    fn section<V: Validate>(&mut self, field: &str, section: &V) {
+        let key = self.key(field);
+        self.errors.extend(section.validate(&key));
+    }
+
+    fn finish(self) -> Vec<AppError> {
+        self.errors
+    }
+}
+
+impl Validate for AppConfig {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.section("server", &self.server);
+        errors.section("db", &self.db);
+        errors.section("ms", &self.ms);
+        errors.section("auth", &self.auth);
+        errors.section("app", &self.app);
+        errors.section("background", &self.background);
+        errors.section("actors", &self.actors);
+        errors.finish()
+    }
+}
+
+impl Validate for Server {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.check(
+            self.shard_url.starts_with("http://") || self.shard_url.starts_with("https://"),
+            "shard_url",
+            "is not an HTTP URL",
+        );
+        let ports = [
+            ("http_port", self.http_port),
+            ("grpc_port", self.grpc_port),
+            ("healthz_port", self.healthz_port),
+            ("metrics_port", self.metrics_port),
+        ];
+        for (i, &(field, port)) in ports.iter().enumerate() {
+            errors.port(field, port);
+            if let Some(&(other, _)) = ports[..i].iter().find(|&&(_, p)| p == port) {
+                let other = errors.key(other);
+                errors.check(false, field, &format!("the same as {}", other));
+            }
+        }
+        errors.finish()
+    }
+}
+
+impl Validate for Db {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.section("mysql", &self.mysql);
+        errors.section("redis", &self.redis);
+        errors.finish()
+    }
+}
+
+impl Validate for MySQL {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.not_empty("host", &self.host);
+        errors.port("port", self.port);
+        errors.not_empty("user", &self.user);
+        errors.section("databases", &self.databases);
+        errors.section("connections", &self.connections);
+        errors.finish()
+    }
+}
+
+impl Validate for Databases {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.not_empty("dating", &self.dating);
+        errors.not_empty("social", &self.social);
+        errors.finish()
+    }
+}
+
+impl Validate for Connections {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.check(self.max_open > 0, "max_open", "must be positive");
+        errors.finish()
+    }
+}
+
+impl Validate for Redis {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.check(!self.addrs.is_empty(), "addrs", "is empty");
+        for (i, addr) in self.addrs.iter().enumerate() {
+            errors.section(&format!("addrs[{}]", i), addr);
+        }
+        errors.finish()
+    }
+}
+
+impl Validate for Addr {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.not_empty("host", &self.host);
+        errors.port("port", self.port);
+        errors.finish()
+    }
+}
+
+impl Validate for Ms {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.section("openvidu", &self.openvidu);
+        errors.finish()
+    }
+}
+
+impl Validate for Openvidu {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.not_empty("host", &self.host);
+        errors.port("grpc_port", self.grpc_port);
+        errors.port("metrics_port", self.metrics_port);
+        errors.finish()
+    }
+}
+
+impl Validate for Auth {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.duration("renewal_duration", &self.renewal_duration);
+        errors.finish()
+    }
+}
+
+impl Validate for App {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.duration("shutdown_timeout", &self.shutdown_timeout);
+        errors.section("live_stream", &self.live_stream);
+        errors.section("setup_stream", &self.setup_stream);
+        errors.finish()
+    }
+}
+
+impl Validate for LiveStream {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.check(
+            self.max_message_length > 0,
+            "max_message_length",
+            "must be positive",
+        );
+        errors.duration("idle_timeout", &self.idle_timeout);
+        errors.duration("starting_timeout", &self.starting_timeout);
+        errors.section("visit", &self.visit);
+        errors.section("preview", &self.preview);
+        errors.finish()
+    }
+}
+
+/// The sections with only the timeouts of a stream.
+macro_rules! validate_stream_timeouts {
+    ($($section:ident),*) => {
+        $(
+            impl Validate for $section {
+                fn validate(&self, path: &str) -> Vec<AppError> {
+                    let mut errors = Validation::new(path);
+                    errors.duration("idle_timeout", &self.idle_timeout);
+                    errors.duration("starting_timeout", &self.starting_timeout);
+                    errors.finish()
+                }
+            }
+        )*
+    };
+}
+
+validate_stream_timeouts!(Visit, Preview, SetupStream);
+
+impl Validate for Background {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.section("finalizer", &self.finalizer);
+        errors.section("recounter", &self.recounter);
+        errors.section("watchdog", &self.watchdog);
+        errors.finish()
+    }
+}
+
+impl Validate for Finalizer {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.duration("period", &self.period);
+        errors.limit("limit", &self.limit);
+        errors.finish()
+    }
+}
+
+impl Validate for Recounter {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.duration("period", &self.period);
+        errors.limit("limit", &self.limit);
+        errors.duration("lock_timeout", &self.lock_timeout);
+        errors.finish()
+    }
+}
+
+impl Validate for Watchdog {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.duration("period", &self.period);
+        errors.limit("limit", &self.limit);
+        errors.duration("lock_timeout", &self.lock_timeout);
+        errors.finish()
+    }
+}
+
+impl Validate for Actors {
+    fn validate(&self, path: &str) -> Vec<AppError> {
+        let mut errors = Validation::new(path);
+        errors.check(self.consumers > 0, "consumers", "must be positive");
+        errors.duration("producer_interval", &self.producer_interval);
+        errors.finish()
+    }
+}
+
+/// Default Value for `AppConfig`.
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            mode: Default::default(),
+            server: Default::default(),
+            db: Default::default(),
+            ms: Default::default(),
+            log: Default::default(),
+            auth: Default::default(),
+            app: Default::default(),
+            background: Default::default(),
+            actors: Default::default(),
+            ice: Default::default(),
+        }
+    }
+}
+
+/// The tests reading the environment through `Source::Environment` or changing it
+/// hold the lock: the environment of the process is shared by the tests running in parallel.
+#[cfg(test)]
+pub(crate) fn env_lock() -> ::std::sync::MutexGuard<'static, ()> {
+    static ENV_LOCK: ::std::sync::Mutex<()> = ::std::sync::Mutex::new(());
+    ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use dotenv;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_mode_debug() {
+        let _env = env_lock();
+        let mut config: Config = AppConfig::priority_config("config.toml").unwrap();
+
+        let debug: bool = config.get_bool("mode.debug").unwrap();
+
+        config.set("mode.debug", !debug as bool);
+
+        assert_ne!(debug, config.get_bool("mode.debug").unwrap());
+    }
+
+    #[test]
+    fn test_db_mysql_host() {
+        let _env = env_lock();
+        let mut config: Config = AppConfig::priority_config("config.toml").unwrap();
+
+        config.set("db.mysql.host", "127.0.0.2");
+
+        assert_eq!("127.0.0.2", config.get_str("db.mysql.host").unwrap());
+    }
+
+    #[test]
+    fn test_environment() {
+        let _env = env_lock();
+        // $ MODE__DEBUG=true app
+        let mut config: Config = AppConfig::priority_config("config.toml").unwrap();
+
+        if let Ok(path) = env::current_dir().and_then(|a| Ok(a.join(".env"))) {
+            dotenv::from_path(path);
+
+            config.merge(environment()).unwrap();
+
+            assert_ne!(false, config.get_bool("mode.debug").unwrap());
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_db_redis_addrs() {
+        let _env = env_lock();
+        let mut config: Config = AppConfig::priority_config("config.toml").unwrap();
+
+        config
+            .merge(File::from_str("[[db.redis.addrs]]", FileFormat::Toml))
+            .unwrap();
+
+        config.set("db.redis.addrs[0].host", "1.2.3.4");
+        config.set("db.redis.addrs[1].port", 535);
+
+        let addrs: Vec<Addr> = config.get::<Vec<Addr>>("db.redis.addrs").unwrap();
+
+        assert_eq!(addrs[0].port, 6379);
+        assert_eq!(addrs[1].host, "127.0.0.1");
+    }
+
+    #[test]
+    fn test_default_field() {
+        let openvidu: Openvidu = toml::from_str("grpc_port = 9090").unwrap();
+        assert_eq!(
+            Openvidu {
+                grpc_port: 9090,
+                ..Openvidu::default()
+            },
+            openvidu
+        );
+        let connections: Connections = toml::from_str("").unwrap();
+        assert_eq!(Connections::default(), connections);
+        let addr: Addr = toml::from_str(r#"host = "redis""#).unwrap();
+        assert_eq!(REDIS_PORT, addr.port);
+
+        let err = toml::from_str::<Connections>("max_open = -1").unwrap_err();
+        assert!(err.to_string().contains("max_open"), "{}", err);
+    }
+
+    #[test]
+    fn test_ice_server() {
+        let server: IceServer = "turn:access_token:qwerty@127.0.0.1:3478".parse().unwrap();
+        assert_eq!(Ice::default().servers[0], server);
+        assert_eq!("turn:access_token:qwerty@127.0.0.1:3478", server.to_string());
+
+        let server: IceServer = "stuns:[::1]".parse().unwrap();
+        assert_eq!(IceScheme::Stuns, server.scheme);
+        assert_eq!(None, server.credentials);
+        assert_eq!("[::1]", server.host);
+        assert_eq!(5349, server.port);
+
+        // The credential may contain colons.
+        let server: IceServer = "turns:user:a:b@turn.example.com:443".parse().unwrap();
+        assert_eq!(Some(("user".into(), "a:b".into())), server.credentials);
+        assert_eq!(443, server.port);
+
+        for url in &[
+            "",
+            "127.0.0.1:3478",
+            "http://127.0.0.1",
+            "turn:127.0.0.1:3478",
+            "turn:token@127.0.0.1",
+            "turn::qwerty@127.0.0.1",
+            "stun:user:pass@127.0.0.1",
+            "stun:",
+            "stun:127.0.0.1:0",
+            "stun:127.0.0.1:port",
+            "stun:[::1:3478",
+        ] {
+            assert!(url.parse::<IceServer>().is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_ice_servers() {
+        let _env = env_lock();
+        let mut config: Config = AppConfig::priority_config("config.toml").unwrap();
+
+        config.set("ice.servers", vec!["stun:stun.example.com", "turn:u:p@[::1]:3479"]).unwrap();
+        let servers: Vec<IceServer> = config.get("ice.servers").unwrap();
+        assert_eq!(3478, servers[0].port);
+        assert_eq!(3479, servers[1].port);
+
+        config.set("ice.servers", vec!["turn:127.0.0.1"]).unwrap();
+        let err = config.get::<Vec<IceServer>>("ice.servers").unwrap_err();
+        assert!(err.to_string().contains("TURN requires credentials"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let _env = env_lock();
+        let config = AppConfig::load_typed("config.toml").unwrap();
+        assert_eq!(Vec::<String>::new(), messages(&config.validate()));
+
+        let mut config = AppConfig::default();
+        config.server.metrics_port = config.server.http_port;
+        config.db.mysql.connections.max_open = 0;
+        config.db.redis.addrs[0].host = "".into();
+        config.app.live_stream.idle_timeout = "5 parsecs".into();
+        config.background.watchdog.limit = "ten".into();
+        config.actors.consumers = 0;
+        let errors = messages(&config.validate());
+        assert_eq!(6, errors.len(), "{:?}", errors);
+        assert!(errors[0].contains("server.metrics_port: the same as server.http_port"));
+        assert!(errors[1].contains("db.mysql.connections.max_open"));
+        assert!(errors[2].contains("db.redis.addrs[0].host"));
+        assert!(errors[3].contains("app.live_stream.idle_timeout"));
+        assert!(errors[4].contains("background.watchdog.limit"));
+        assert!(errors[5].contains("actors.consumers"));
+    }
+
+    #[test]
+    fn test_validate_sections() {
+        assert!(Validate::validate(&Server::default(), "server").is_empty());
+
+        let mut mysql = MySQL::default();
+        mysql.port = 0;
+        mysql.user = " ".into();
+        mysql.databases.social = "".into();
+        let errors = messages(&mysql.validate("db.mysql"));
+        assert_eq!(3, errors.len(), "{:?}", errors);
+        assert!(errors[0].contains("db.mysql.port: the port is 0"));
+        assert!(errors[1].contains("db.mysql.user: is empty"));
+        assert!(errors[2].contains("db.mysql.databases.social: is empty"));
+
+        let mut config = AppConfig::default();
+        config.auth.renewal_duration = "5x".into();
+        config.app.setup_stream.starting_timeout = "".into();
+        config.ms.openvidu.grpc_port = 0;
+        let errors = messages(&config.validate());
+        assert_eq!(3, errors.len(), "{:?}", errors);
+        assert!(errors[0].contains("ms.openvidu.grpc_port: the port is 0"));
+        assert!(errors[1].contains("auth.renewal_duration: \"5x\""));
+        assert!(errors[2].contains("app.setup_stream.starting_timeout"));
+    }
+
+    #[test]
+    fn test_override_parse() {
+        let item: Override = "db.mysql.port=3307".parse().unwrap();
+        assert_eq!("db.mysql.port", item.key);
+        assert_eq!(OverrideValue::Int(3307), item.value);
+        let item: Override = "mode.debug = true".parse().unwrap();
+        assert_eq!(
+            ("mode.debug", OverrideValue::Bool(true)),
+            (item.key.as_str(), item.value)
+        );
+        let item: Override = "db.redis.addrs[0].host=10.0.0.1".parse().unwrap();
+        assert_eq!(OverrideValue::Str("10.0.0.1".into()), item.value);
+        let item: Override = r#"db.mysql.pass="1234""#.parse().unwrap();
+        assert_eq!(OverrideValue::Str("1234".into()), item.value);
+        let item: Override = "db.mysql.pass=a=b".parse().unwrap();
+        assert_eq!(OverrideValue::Str("a=b".into()), item.value);
+
+        for bad in &[
+            "db.mysql.port",
+            "=1",
+            "db..port=1",
+            "db.MySQL=1",
+            "addrs[]=1",
+            "addrs[0=1",
+        ] {
+            assert!(bad.parse::<Override>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_overrides_have_highest_priority() {
+        let _env = env_lock();
+        env::set_var("DB__MYSQL__DATABASES__DATING", "from_env");
+        let overrides: Vec<Override> = [
+            "db.mysql.port=3307",
+            "db.mysql.databases.dating=from_cli",
+            "mode.debug=true",
+            "db.redis.addrs[0].host=10.0.0.1",
+        ]
+        .iter()
+        .map(|item| item.parse().unwrap())
+        .collect();
+        let config = AppConfig::load_typed_with("config.toml", &overrides);
+        env::remove_var("DB__MYSQL__DATABASES__DATING");
+        let config = config.unwrap();
+        assert_eq!(3307, config.db.mysql.port);
+        assert_eq!("from_cli", config.db.mysql.databases.dating);
+        assert!(config.mode.debug);
+        assert_eq!("10.0.0.1", config.db.redis.addrs[0].host);
+
+        let unknown = ["db.mysql.socket=/tmp/mysql.sock".parse().unwrap()];
+        let err = AppConfig::load_typed_with("config.toml", &unknown).unwrap_err();
+        assert!(err.to_string().contains("db.mysql.socket: unknown key"));
+        let wrong_type = ["db.mysql.port=port".parse().unwrap()];
+        assert!(AppConfig::load_typed_with("config.toml", &wrong_type).is_err());
+    }
+
+    #[test]
+    fn test_profile_file() {
+        let _env = env_lock();
+        assert_eq!("config.prod.toml", profile_file("config.toml", "prod"));
+        assert_eq!(
+            "a/config.staging.yml",
+            profile_file("a/config.yml", "staging")
+        );
+        assert_eq!("config.prod", profile_file("config", "prod"));
+        assert!(AppConfig::priority_config_profile("config.toml", Some("../prod")).is_err());
+    }
+
+    #[test]
+    fn test_profile_layer() {
+        let _env = env_lock();
+        let dir = env::temp_dir().join(format!("profiles_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("config.toml");
+        fs::write(
+            &base,
+            "[db.mysql]\nhost = \"base\"\nport = 3307\n\n[ms.openvidu]\nhost = \"base\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("config.prod.toml"),
+            "[db.mysql]\nhost = \"prod\"\n\n[ms.openvidu]\nhost = \"prod\"\n",
+        )
+        .unwrap();
+        let base = base.to_str().unwrap();
+
+        let without: AppConfig = AppConfig::priority_config_profile(base, None)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let staging: AppConfig = AppConfig::priority_config_profile(base, Some("staging"))
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!("base", without.db.mysql.host);
+        assert_eq!(without, staging);
+
+        // The profile overrides the base file, the environment overrides the profile.
+        env::set_var("MS__OPENVIDU__HOST", "env");
+        env::set_var(PROFILE_VAR, "prod");
+        let config = AppConfig::load_typed(base);
+        env::remove_var(PROFILE_VAR);
+        env::remove_var("MS__OPENVIDU__HOST");
+        fs::remove_dir_all(&dir).unwrap();
+        let config = config.unwrap();
+        assert_eq!("prod", config.db.mysql.host);
+        assert_eq!(3307, config.db.mysql.port);
+        assert_eq!("env", config.ms.openvidu.host);
+    }
+
+    #[test]
+    fn test_error_level() {
+        for &name in ERROR_LEVEL_NAMES {
+            let level: ErrorLevel = name.parse().unwrap();
+            assert_eq!(name, level.to_string());
+            assert_eq!(level, ErrorLevel::try_from(name).unwrap());
+        }
+        assert_eq!(ErrorLevel::WARN, "warn".parse().unwrap());
+        assert!("WARN".parse::<ErrorLevel>().is_err());
+        assert!(ErrorLevel::try_from("verbose").is_err());
+
+        let log: LogLevel = toml::from_str(r#"level = "error""#).unwrap();
+        assert_eq!(ErrorLevel::ERROR, log.level);
+        assert!(toml::from_str::<LogLevel>(r#"level = "verbose""#).is_err());
+    }
+
+    #[cfg(feature = "slog")]
+    #[test]
+    fn test_as_slog_level() {
+        assert_eq!(Some(slog::Level::Debug), ErrorLevel::DEBUG.as_slog_level());
+        assert_eq!(Some(slog::Level::Warning), ErrorLevel::WARN.as_slog_level());
+        assert_eq!(Some(slog::Level::Critical), ErrorLevel::PANIC.as_slog_level());
+        assert_eq!(None, ErrorLevel::EMPTY.as_slog_level());
+    }
+
+    #[test]
+    fn test_embedded_config() {
+        let _env = env_lock();
+        let embedded: AppConfig = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(AppConfig::default(), embedded);
+
+        // Without the file the binaries run with the embedded defaults.
+        let config = AppConfig::priority_config("missing_config.toml").unwrap();
+        assert_eq!("127.0.0.1", config.get_str("db.mysql.host").unwrap());
+        assert_eq!(8081, config.get_int("server.http_port").unwrap());
+    }
+
+    #[test]
+    fn test_actors() {
+        let _env = env_lock();
+        let mut config: Config = AppConfig::priority_config("config.toml").unwrap();
+        let actors: Actors = config.get("actors").unwrap();
+        assert_eq!(Actors::default(), actors);
+
+        config.set("actors.arbiter", "sync").unwrap();
+        config.set("actors.consumers", 4).unwrap();
+        let actors: Actors = config.get("actors").unwrap();
+        assert_eq!(ConsumerArbiter::Sync, actors.arbiter);
+        assert_eq!(4, actors.consumers);
+
+        config.set("actors.arbiter", "other").unwrap();
+        assert!(config.get::<Actors>("actors").is_err());
+    }
+
+    #[test]
+    fn test_masked() {
+        let mut config = AppConfig::default();
+        config.db.mysql.pass = "secret".into();
+        let config = config.masked();
+        assert_eq!(MASK, config.db.mysql.pass);
+        // Not set, so nothing to hide.
+        assert_eq!("", config.auth.user_password_salt);
+        assert_eq!(
+            "turn:access_token:******@127.0.0.1:3478",
+            config.ice.servers[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_env_keys() {
+        let _env = env_lock();
+        let keys = AppConfig::env_keys();
+        assert!(keys.contains(&("db.mysql.host".to_string(), "DB__MYSQL__HOST".to_string())));
+        assert!(keys
+            .iter()
+            .all(|(key, _)| !key.starts_with("db.redis.addrs")));
+        // The names with `_` are listed too.
+        assert!(keys.contains(&(
+            "server.http_port".to_string(),
+            "SERVER__HTTP_PORT".to_string()
+        )));
+        assert!(keys.contains(&(
+            "db.mysql.connections.max_idle".to_string(),
+            "DB__MYSQL__CONNECTIONS__MAX_IDLE".to_string()
+        )));
+
+        // Every listed variable overrides its key.
+        let previous: Vec<_> = keys.iter().map(|(_, var)| env::var_os(var)).collect();
+        for (_, var) in &keys {
+            env::set_var(var, "from_env");
+        }
+        let config = AppConfig::priority_config("config.toml");
+        for ((_, var), value) in keys.iter().zip(previous) {
+            match value {
+                Some(value) => env::set_var(var, value),
+                None => env::remove_var(var),
+            }
+        }
+        let config = config.unwrap();
+        for (key, _) in &keys {
+            assert_eq!("from_env", config.get_str(key).unwrap(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_load_typed() {
+        let _env = env_lock();
+        let config = AppConfig::load_typed("config.toml").unwrap();
+        let raw = AppConfig::priority_config("config.toml").unwrap();
+        assert_eq!(raw.get_str("db.mysql.host").unwrap(), config.db.mysql.host);
+        assert_eq!(
+            raw.get_int("server.http_port").unwrap(),
+            i64::from(config.server.http_port)
+        );
+
+        // The file overrides the embedded defaults, the environment overrides the file.
+        let path = env::temp_dir().join(format!("load_typed_{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "[db.mysql]\nhost = \"10.0.0.1\"\nport = 3307\n\n[db.mysql.databases]\ndating = \"love\"\n",
+        )
+        .unwrap();
+        env::set_var("DB__MYSQL__DATABASES__SOCIAL", "friends");
+        env::set_var("DB__MYSQL__CONNECTIONS__MAX_IDLE", "5");
+        let config = AppConfig::load_typed(path.to_str().unwrap());
+        env::remove_var("DB__MYSQL__DATABASES__SOCIAL");
+        env::remove_var("DB__MYSQL__CONNECTIONS__MAX_IDLE");
+        fs::remove_file(&path).unwrap();
+        let config = config.unwrap();
+        assert_eq!("10.0.0.1", config.db.mysql.host);
+        assert_eq!(3307, config.db.mysql.port);
+        assert_eq!("love", config.db.mysql.databases.dating);
+        assert_eq!("friends", config.db.mysql.databases.social);
+        assert_eq!(5, config.db.mysql.connections.max_idle);
+        assert_eq!("root", config.db.mysql.user);
+        assert_eq!(AppConfig::default().server, config.server);
+
+        // The typed configuration survives the round trip through `Config`.
+        let again: AppConfig = Config::try_from(&config).unwrap().try_into().unwrap();
+        assert_eq!(config, again);
+    }
+
+    #[test]
+    fn test_config_formats() {
+        let _env = env_lock();
+        assert_eq!(
+            ConfigFormat::Toml,
+            ConfigFormat::from_path("config").unwrap()
+        );
+        assert_eq!(
+            ConfigFormat::Yaml,
+            ConfigFormat::from_path("a/config.yml").unwrap()
+        );
+        assert!(ConfigFormat::from_path("config.ini").is_err());
+
+        let dir = env::temp_dir();
+        let yaml = dir.join(format!("formats_{}.yaml", std::process::id()));
+        fs::write(
+            &yaml,
+            "db:\n  mysql:\n    host: 10.0.0.1\nactors:\n  consumers: 4\n",
+        )
+        .unwrap();
+        let json = dir.join(format!("formats_{}.conf", std::process::id()));
+        fs::write(&json, r#"{"db": {"mysql": {"host": "10.0.0.2"}}}"#).unwrap();
+
+        let from_yaml = AppConfig::load_typed(yaml.to_str().unwrap());
+        let from_json = AppConfig::priority_config_as(json.to_str().unwrap(), ConfigFormat::Json)
+            .and_then(|config| Ok(config.try_into::<AppConfig>()?));
+        fs::remove_file(&yaml).unwrap();
+        fs::remove_file(&json).unwrap();
+
+        let config = from_yaml.unwrap();
+        assert_eq!("10.0.0.1", config.db.mysql.host);
+        assert_eq!(4, config.actors.consumers);
+        // The missing sections and keys are the defaults.
+        assert_eq!(3306, config.db.mysql.port);
+        assert_eq!(AppConfig::default().server, config.server);
+
+        let config = from_json.unwrap();
+        assert_eq!("10.0.0.2", config.db.mysql.host);
+        assert_eq!(AppConfig::default().actors, config.actors);
+    }
+
+    fn messages(errors: &[AppError]) -> Vec<String> {
+        errors.iter().map(|err| err.to_string()).collect()
+    }
+
+}
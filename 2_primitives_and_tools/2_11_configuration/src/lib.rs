@@ -0,0 +1,24 @@
+//! The typed configuration of the application with its priorities,
+//! shared with the other steps through `toolkit`.
+//! The `testing` feature adds the proptest strategies of the configuration,
+//! the `slog` feature adds the conversion of the log levels into the slog ones.
+//! `watch::watch_config` reloads the configuration when the TOML file changes,
+//! `secrets` resolves the passwords from an external backend and redacts them.
+extern crate common_errors;
+extern crate config;
+#[macro_use]
+extern crate serde_derive;
+extern crate dotenv;
+extern crate humantime;
+extern crate serde;
+#[cfg(any(test, feature = "testing"))]
+extern crate proptest;
+#[cfg(feature = "slog")]
+extern crate slog;
+extern crate toml;
+
+pub mod configuration;
+pub mod secrets;
+pub mod watch;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -0,0 +1,299 @@
+//! Secrets of the configuration.
+//!
+//! The password of MySQL and the salt of the passwords are `Secret` values:
+//! they are redacted in the `Debug` and `Serialize` output. Besides the usual
+//! layers of `AppConfig::priority_config` they may be resolved at load time
+//! from a `SecretsSource`, like the files mounted by Docker or Kubernetes.
+//!
+//! ## Examples
+//!
+//! ```rust
+//!
+//!    extern crate common_errors;
+//!    extern crate configuration;
+//!
+//!    use common_errors::AppError;
+//!    use configuration::configuration::AppConfig;
+//!    use configuration::secrets::FileSecrets;
+//!
+//!    fn main() -> Result<(), AppError> {
+//!        // /run/secrets/db.mysql.pass
+//!        let secrets = FileSecrets::new("/run/secrets");
+//!        let config = AppConfig::load_with_secrets("config.toml", &secrets)?;
+//!
+//!        println!("{:?}", config.db.mysql); // pass: "******"
+//!
+//!        Ok(())
+//!    }
+//! ```
+
+use common_errors::AppError;
+use configuration::{env_var, AppConfig, MASK};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::borrow::Cow;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Deref;
+use std::path::PathBuf;
+
+/// Keys of the configuration which are resolved by `AppConfig::resolve_secrets`.
+pub const SECRET_KEYS: &[&str] = &["db.mysql.pass", "auth.user_password_salt"];
+
+/// Value which is never printed: `Debug` and `Serialize` show `MASK` instead,
+/// the empty value is shown as is. `expose` gives the value itself.
+#[derive(Clone, Default, PartialEq)]
+pub struct Secret(Cow<'static, str>);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    fn shown(&self) -> &str {
+        if self.0.is_empty() {
+            ""
+        } else {
+            MASK
+        }
+    }
+}
+
+impl Deref for Secret {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&'static str> for Secret {
+    fn from(value: &'static str) -> Self {
+        Secret(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Secret(Cow::Owned(value))
+    }
+}
+
+impl PartialEq<&str> for Secret {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<Secret> for &str {
+    fn eq(&self, other: &Secret) -> bool {
+        *self == other.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.shown(), f)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.shown())
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(deserializer: D) -> Result<Secret, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Secret::from)
+    }
+}
+
+/// External backend of the secrets.
+pub trait SecretsSource {
+    /// The secret of the configuration key, like `db.mysql.pass`,
+    /// `None` if the backend doesn't have it.
+    fn secret(&self, key: &str) -> Result<Option<String>, AppError>;
+}
+
+/// Secrets stored one per file named after the key, like `/run/secrets/db.mysql.pass`.
+/// The trailing line break of the file is not a part of the secret.
+#[derive(Debug, Clone)]
+pub struct FileSecrets {
+    dir: PathBuf,
+}
+
+impl FileSecrets {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        FileSecrets { dir: dir.into() }
+    }
+}
+
+impl SecretsSource for FileSecrets {
+    fn secret(&self, key: &str) -> Result<Option<String>, AppError> {
+        match fs::read_to_string(self.dir.join(key)) {
+            Ok(value) => Ok(Some(value.trim_end_matches(&['\r', '\n'][..]).to_string())),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Secrets stored in the environment variables named after the key with a prefix,
+/// like `SECRET_DB__MYSQL__PASS` for `db.mysql.pass`, see `env_var`.
+#[derive(Debug, Clone)]
+pub struct EnvSecrets {
+    prefix: String,
+}
+
+impl EnvSecrets {
+    /// Prefix of the variables of `EnvSecrets::default()`.
+    pub const DEFAULT_PREFIX: &'static str = "SECRET_";
+
+    pub fn new(prefix: &str) -> Self {
+        EnvSecrets {
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// The variable of the key.
+    pub fn var(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, env_var(key))
+    }
+}
+
+impl Default for EnvSecrets {
+    fn default() -> Self {
+        EnvSecrets::new(EnvSecrets::DEFAULT_PREFIX)
+    }
+}
+
+impl SecretsSource for EnvSecrets {
+    fn secret(&self, key: &str) -> Result<Option<String>, AppError> {
+        let var = self.var(key);
+        match env::var(&var) {
+            Ok(value) => Ok(Some(value)),
+            Err(env::VarError::NotPresent) => Ok(None),
+            Err(err) => Err(AppError::config(format!("{}: {}", var, err))),
+        }
+    }
+}
+
+impl AppConfig {
+    /// `load_typed` with the `SECRET_KEYS` resolved from `source`.
+    pub fn load_with_secrets(
+        name: &str,
+        source: &dyn SecretsSource,
+    ) -> Result<AppConfig, AppError> {
+        let mut config = AppConfig::load_typed(name)?;
+        config.resolve_secrets(source)?;
+        Ok(config)
+    }
+
+    /// Replace the `SECRET_KEYS` with the values of `source`,
+    /// the keys it doesn't have keep their values.
+    pub fn resolve_secrets(&mut self, source: &dyn SecretsSource) -> Result<(), AppError> {
+        for &key in SECRET_KEYS {
+            if let Some(value) = source.secret(key)? {
+                *self.secret_mut(key) = value.into();
+            }
+        }
+        Ok(())
+    }
+
+    fn secret_mut(&mut self, key: &str) -> &mut Secret {
+        match key {
+            "db.mysql.pass" => &mut self.db.mysql.pass,
+            "auth.user_password_salt" => &mut self.auth.user_password_salt,
+            _ => unreachable!("{} is not in SECRET_KEYS", key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use configuration::{env_lock, Auth};
+    use std::collections::HashMap;
+    use toml;
+
+    impl SecretsSource for HashMap<&'static str, &'static str> {
+        fn secret(&self, key: &str) -> Result<Option<String>, AppError> {
+            Ok(self.get(key).map(|value| value.to_string()))
+        }
+    }
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = Secret::from("hunter2");
+        assert_eq!("hunter2", secret.expose());
+        assert_eq!(format!("{:?}", MASK), format!("{:?}", secret));
+        assert_eq!(format!("{:?}", ""), format!("{:?}", Secret::default()));
+
+        let mut config = AppConfig::default();
+        config.db.mysql.pass = secret;
+        let toml = toml::to_string(&config).unwrap();
+        assert!(!toml.contains("hunter2"));
+        assert!(toml.contains(MASK));
+        assert!(!format!("{:?}", config).contains("hunter2"));
+
+        let auth: Auth =
+            toml::from_str("user_password_salt = \"hunter2\"\nrenewal_duration = \"5m\"").unwrap();
+        assert_eq!("hunter2", auth.user_password_salt);
+    }
+
+    #[test]
+    fn test_resolve_secrets() {
+        let mut config = AppConfig::default();
+        config.auth.user_password_salt = "salt".into();
+        let mut source = HashMap::new();
+        source.insert("db.mysql.pass", "hunter2");
+        config.resolve_secrets(&source).unwrap();
+        assert_eq!("hunter2", config.db.mysql.pass);
+        assert_eq!("salt", config.auth.user_password_salt);
+    }
+
+    #[test]
+    fn test_file_secrets() {
+        let dir = env::temp_dir().join(format!("file_secrets_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("db.mysql.pass"), "hunter2\n").unwrap();
+
+        let secrets = FileSecrets::new(&dir);
+        let pass = secrets.secret("db.mysql.pass");
+        let salt = secrets.secret("auth.user_password_salt");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(Some("hunter2".to_string()), pass.unwrap());
+        assert_eq!(None, salt.unwrap());
+    }
+
+    #[test]
+    fn test_env_secrets() {
+        let _env = env_lock();
+        let secrets = EnvSecrets::new("TEST_ENV_SECRETS_");
+        assert_eq!(
+            "TEST_ENV_SECRETS_DB__MYSQL__PASS",
+            secrets.var("db.mysql.pass")
+        );
+        assert_eq!(
+            "SECRET_AUTH__USER_PASSWORD_SALT",
+            EnvSecrets::default().var("auth.user_password_salt")
+        );
+
+        env::set_var("TEST_ENV_SECRETS_AUTH__USER_PASSWORD_SALT", "salt");
+        let mut config = AppConfig::default();
+        let result = config.resolve_secrets(&secrets);
+        env::remove_var("TEST_ENV_SECRETS_AUTH__USER_PASSWORD_SALT");
+        result.unwrap();
+        assert_eq!("salt", config.auth.user_password_salt);
+        assert_eq!("", config.db.mysql.pass);
+    }
+}
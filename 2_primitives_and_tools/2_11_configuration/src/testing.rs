@@ -0,0 +1,81 @@
+//! # Generators of the configuration for the property-based tests
+//!
+//! [Proptest]:https://docs.rs/proptest strategies of `Addr`,
+//! enabled by the `testing` feature.
+//! The properties check that `Addr` survives the round trip through TOML
+//! and that the missing fields get the default values.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//!  extern crate configuration;
+//!  extern crate proptest;
+//!
+//!  use configuration::testing::addr;
+//!  use proptest::strategy::{Strategy, ValueTree};
+//!  use proptest::test_runner::TestRunner;
+//!
+//!  let mut runner = TestRunner::default();
+//!  let addr = addr().new_tree(&mut runner).unwrap().current();
+//!  assert!(addr.port > 0);
+//! ```
+
+use configuration::Addr;
+use proptest::prelude::*;
+use std::borrow::Cow;
+
+/// Host names like "redis-1.local" and IPv4 addresses.
+pub fn host() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z][a-z0-9-]{0,10}(\\.[a-z][a-z0-9-]{0,10}){0,2}",
+        (any::<u8>(), any::<u8>(), any::<u8>(), any::<u8>())
+            .prop_map(|(a, b, c, d)| format!("{}.{}.{}.{}", a, b, c, d)),
+    ]
+}
+
+pub fn addr() -> impl Strategy<Value = Addr> {
+    (host(), 1..=u16::MAX).prop_map(|(host, port)| Addr {
+        host: Cow::Owned(host),
+        port,
+    })
+}
+
+impl Arbitrary for Addr {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Addr>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        addr().boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use toml;
+
+    proptest! {
+        #[test]
+        fn test_toml_round_trip(addr in any::<Addr>()) {
+            let toml = toml::to_string(&addr).unwrap();
+            prop_assert_eq!(addr, toml::from_str::<Addr>(&toml).unwrap());
+        }
+
+        #[test]
+        fn test_missing_fields(addr in any::<Addr>()) {
+            let default = Addr::default();
+
+            let without_port = format!("host = {:?}", addr.host);
+            let parsed: Addr = toml::from_str(&without_port).unwrap();
+            prop_assert_eq!(&addr.host, &parsed.host);
+            prop_assert_eq!(default.port, parsed.port);
+
+            let without_host = format!("port = {}", addr.port);
+            let parsed: Addr = toml::from_str(&without_host).unwrap();
+            prop_assert_eq!(default.host, parsed.host);
+            prop_assert_eq!(addr.port, parsed.port);
+        }
+    }
+}
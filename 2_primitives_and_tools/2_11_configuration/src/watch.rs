@@ -0,0 +1,207 @@
+//! Hot reload of the configuration.
+//!
+//! `watch_config` reads the configuration with `AppConfig::priority_config`
+//! and starts a background thread polling the file. When the file changes,
+//! the configuration is read again and, if it is still a valid `AppConfig`,
+//! the shared `Arc<Config>` is swapped and sent to the subscribers.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//!
+//!    extern crate common_errors;
+//!    extern crate configuration;
+//!
+//!    use common_errors::AppError;
+//!    use configuration::watch::watch_config;
+//!
+//!    fn main() -> Result<(), AppError> {
+//!        let watcher = watch_config("config.toml")?;
+//!        let changes = watcher.subscribe();
+//!
+//!        for config in changes {
+//!            println!("db.mysql.host = {}", config.get_str("db.mysql.host")?);
+//!        }
+//!
+//!        Ok(())
+//!    }
+//! ```
+
+use common_errors::AppError;
+use config::Config;
+use configuration::AppConfig;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// How often `watch_config` checks the file.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watch `name` with `POLL_INTERVAL`.
+pub fn watch_config(name: &str) -> Result<ConfigWatcher, AppError> {
+    ConfigWatcher::start(name, POLL_INTERVAL)
+}
+
+/// Modification time and length of the file, `None` if it doesn't exist.
+type Stamp = Option<(Option<SystemTime>, u64)>;
+
+fn stamp(name: &str) -> Stamp {
+    fs::metadata(name)
+        .ok()
+        .map(|meta| (meta.modified().ok(), meta.len()))
+}
+
+/// The configuration read by `priority_config`, rejected if it isn't a valid `AppConfig`.
+fn read(name: &str) -> Result<Config, AppError> {
+    let config = AppConfig::priority_config(name)?;
+    let _: AppConfig = config.clone().try_into()?;
+    Ok(config)
+}
+
+/// State shared by the `ConfigWatcher` and its thread.
+struct Shared {
+    current: RwLock<Arc<Config>>,
+    subscribers: Mutex<Vec<Sender<Arc<Config>>>>,
+    stopped: AtomicBool,
+}
+
+impl Shared {
+    /// Swap the configuration and notify the subscribers,
+    /// the disconnected ones are dropped.
+    fn publish(&self, config: Config) {
+        let config = Arc::new(config);
+        *self.current.write().unwrap() = Arc::clone(&config);
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(Arc::clone(&config)).is_ok());
+    }
+}
+
+/// Handle of the watching thread, the thread is stopped when it is dropped.
+pub struct ConfigWatcher {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Read the configuration and check `name` for changes every `interval`.
+    /// A change which can't be read is reported to stderr,
+    /// the previous configuration stays in use.
+    pub fn start(name: &str, interval: Duration) -> Result<ConfigWatcher, AppError> {
+        let mut last = stamp(name);
+        let shared = Arc::new(Shared {
+            current: RwLock::new(Arc::new(read(name)?)),
+            subscribers: Mutex::new(Vec::new()),
+            stopped: AtomicBool::new(false),
+        });
+
+        let name = name.to_string();
+        let watched = Arc::clone(&shared);
+        let thread = thread::Builder::new()
+            .name(format!("watch {}", name))
+            .spawn(move || {
+                while !watched.stopped.load(Ordering::SeqCst) {
+                    thread::sleep(interval);
+                    let current = stamp(&name);
+                    if current == last {
+                        continue;
+                    }
+                    last = current;
+                    match read(&name) {
+                        Ok(config) => watched.publish(config),
+                        Err(err) => {
+                            eprintln!("{}: the configuration isn't reloaded: {}", name, err)
+                        }
+                    }
+                }
+            })?;
+
+        Ok(ConfigWatcher {
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// The latest configuration.
+    pub fn config(&self) -> Arc<Config> {
+        Arc::clone(&self.shared.current.read().unwrap())
+    }
+
+    /// Channel receiving every reloaded configuration.
+    /// It is closed when the watcher is dropped.
+    pub fn subscribe(&self) -> Receiver<Arc<Config>> {
+        let (sender, receiver) = mpsc::channel();
+        self.shared.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+        self.shared.subscribers.lock().unwrap().clear();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use configuration::env_lock;
+    use std::env;
+    use std::io::Write;
+
+    const INTERVAL: Duration = Duration::from_millis(20);
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn write(path: &str, text: &str) {
+        let mut file = fs::File::create(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        file.sync_all().unwrap();
+    }
+
+    #[test]
+    fn test_reload_on_change() {
+        let _env = env_lock();
+        let path = env::temp_dir().join(format!("watch_config_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        write(path, "[db.mysql]\nhost = \"10.0.0.1\"\n");
+
+        let watcher = ConfigWatcher::start(path, INTERVAL).unwrap();
+        let changes = watcher.subscribe();
+        assert_eq!(
+            "10.0.0.1",
+            watcher.config().get_str("db.mysql.host").unwrap()
+        );
+
+        // The invalid port keeps the previous configuration.
+        write(path, "[db.mysql]\nport = \"port\"\nhost = \"10.0.0.1\"\n");
+        thread::sleep(INTERVAL * 5);
+        assert_eq!(
+            "10.0.0.1",
+            watcher.config().get_str("db.mysql.host").unwrap()
+        );
+        assert!(changes.try_recv().is_err());
+
+        write(
+            path,
+            "[db.mysql]\nhost = \"10.0.0.2\"\n\n[[db.redis.addrs]]\nport = 6380\n",
+        );
+        let config = changes.recv_timeout(WAIT).unwrap();
+        assert_eq!("10.0.0.2", config.get_str("db.mysql.host").unwrap());
+        assert_eq!(
+            "10.0.0.2",
+            watcher.config().get_str("db.mysql.host").unwrap()
+        );
+
+        drop(watcher);
+        assert!(changes.recv().is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
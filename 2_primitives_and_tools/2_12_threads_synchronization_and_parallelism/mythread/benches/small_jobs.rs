@@ -0,0 +1,109 @@
+//! Many small jobs: the dispatch dominates the time of the pool.
+//!
+//! Compares the work stealing `ThreadPool` with the previous design,
+//! where all the workers take the jobs from one `Mutex<Receiver>`.
+//! The workers of the latter contend for its lock, so the difference
+//! shows on a machine with at least `WORKERS` cores; on a single core
+//! there is no contention and the extra bookkeeping of the deques dominates.
+//!
+//! ```bash
+//! $ cargo bench
+//! ```
+#[macro_use]
+extern crate criterion;
+extern crate mythread;
+
+use criterion::Criterion;
+use mythread::{SubmitPolicy, ThreadPool};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const WORKERS: usize = 4;
+const JOBS: usize = 10_000;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Pool of the workers sharing one receiver of the jobs.
+struct MutexPool {
+    sender: Option<mpsc::Sender<Job>>,
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+impl MutexPool {
+    fn new(size: usize) -> MutexPool {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let threads = (0..size)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+        MutexPool {
+            sender: Some(sender),
+            threads,
+        }
+    }
+
+    fn execute<F: FnOnce() + Send + 'static>(&self, f: F) {
+        self.sender.as_ref().unwrap().send(Box::new(f)).unwrap();
+    }
+}
+
+impl Drop for MutexPool {
+    fn drop(&mut self) {
+        self.sender.take();
+        for thread in self.threads.drain(..) {
+            thread.join().unwrap();
+        }
+    }
+}
+
+/// A tiny piece of work for a job.
+fn small_job(done: &AtomicUsize, i: usize) {
+    criterion::black_box(i.wrapping_mul(31));
+    done.fetch_add(1, Ordering::SeqCst);
+}
+
+fn wait_all(done: &AtomicUsize) {
+    while done.load(Ordering::SeqCst) < JOBS {
+        thread::yield_now();
+    }
+}
+
+fn bench_small_jobs(c: &mut Criterion) {
+    c.bench_function("work stealing ThreadPool", |b| {
+        let pool = ThreadPool::with_queue(WORKERS, JOBS, SubmitPolicy::Block);
+        b.iter(|| {
+            let done = Arc::new(AtomicUsize::new(0));
+            for i in 0..JOBS {
+                let done = Arc::clone(&done);
+                pool.execute(move || small_job(&done, i)).unwrap();
+            }
+            wait_all(&done);
+        })
+    });
+
+    c.bench_function("Mutex<Receiver> pool", |b| {
+        let pool = MutexPool::new(WORKERS);
+        b.iter(|| {
+            let done = Arc::new(AtomicUsize::new(0));
+            for i in 0..JOBS {
+                let done = Arc::clone(&done);
+                pool.execute(move || small_job(&done, i));
+            }
+            wait_all(&done);
+        })
+    });
+}
+
+criterion_group!(benches, bench_small_jobs);
+criterion_main!(benches);
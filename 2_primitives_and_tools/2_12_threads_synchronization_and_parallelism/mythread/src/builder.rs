@@ -0,0 +1,172 @@
+//! Configuration of the `ThreadPool` before spawning its workers.
+
+use num_cpus;
+use scheduler::Scheduler;
+use stats::Counters;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
+#[cfg(feature = "slog")]
+use std::time::Duration;
+use {SubmitPolicy, ThreadPool, Workers, DEFAULT_QUEUE_CAPACITY};
+
+#[cfg(feature = "metrics")]
+use metrics::Metrics;
+#[cfg(feature = "slog")]
+use slog::Logger;
+#[cfg(feature = "metrics")]
+use stats::register_metrics;
+#[cfg(feature = "slog")]
+use stats::StatsLogger;
+
+/// Builder of the `ThreadPool`.
+///
+/// ## Examples
+///
+/// ```rust
+/// use mythread::ThreadPoolBuilder;
+///
+/// let pool = ThreadPoolBuilder::new()
+///     .name_prefix("downloader-")
+///     .stack_size(256 * 1024)
+///     .num_threads(2)
+///     .build()
+///     .unwrap();
+///
+/// let name = pool.execute(|| thread_name()).unwrap().wait().unwrap();
+/// assert!(name.starts_with("downloader-"));
+///
+/// fn thread_name() -> String {
+///     std::thread::current().name().unwrap().to_string()
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThreadPoolBuilder {
+    name_prefix: String,
+    stack_size: Option<usize>,
+    num_threads: usize,
+    queue_capacity: usize,
+    policy: SubmitPolicy,
+    #[cfg(feature = "slog")]
+    log_stats: Option<(Logger, Duration)>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Metrics>,
+}
+
+impl Default for ThreadPoolBuilder {
+    fn default() -> Self {
+        ThreadPoolBuilder {
+            name_prefix: "mythread-".to_string(),
+            stack_size: None,
+            num_threads: num_cpus::get(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            policy: SubmitPolicy::Block,
+            #[cfg(feature = "slog")]
+            log_stats: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+}
+
+impl ThreadPoolBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// The workers are named `{prefix}{id}`, `mythread-0`, `mythread-1`... by default.
+    pub fn name_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.name_prefix = prefix.into();
+        self
+    }
+
+    /// Stack size of the workers in bytes, the default of `std::thread` if not set.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = Some(size);
+        self
+    }
+
+    /// Number of the workers, the number of the CPU cores by default.
+    pub fn num_threads(mut self, num: usize) -> Self {
+        self.num_threads = num;
+        self
+    }
+
+    /// At most `capacity` jobs waiting in the queue, `DEFAULT_QUEUE_CAPACITY` by default.
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity;
+        self
+    }
+
+    /// What to do with the jobs coming to the full queue, `SubmitPolicy::Block` by default.
+    pub fn submit_policy(mut self, policy: SubmitPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Log `ThreadPool::stats` to `log` every `interval` while the pool lives.
+    #[cfg(feature = "slog")]
+    pub fn log_stats(mut self, log: Logger, interval: Duration) -> Self {
+        self.log_stats = Some((log, interval));
+        self
+    }
+
+    /// Report `ThreadPool::stats` as the `thread_pool_*` metrics of `metrics`,
+    /// one pool per registry: `build` fails if the names are already registered.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Spawn the workers, fails if the OS can't create a thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of threads or the queue capacity is zero.
+    pub fn build(self) -> io::Result<ThreadPool> {
+        assert!(self.num_threads > 0);
+        assert!(self.queue_capacity > 0);
+
+        let scheduler = Arc::new(Scheduler::new(self.queue_capacity));
+        let counters = Arc::new(Counters::default());
+        #[cfg(feature = "slog")]
+        let stats_logger = self.log_stats.clone().map(|(log, interval)| {
+            StatsLogger::spawn(log, interval, Arc::clone(&counters), Arc::clone(&scheduler))
+        });
+        #[cfg(feature = "metrics")]
+        {
+            if let Some(ref metrics) = self.metrics {
+                register_metrics(metrics, Arc::clone(&counters), Arc::clone(&scheduler))
+                    .map_err(io::Error::other)?;
+            }
+        }
+
+        // The workers spawned before a failure are stopped by the drop of the pool.
+        let pool = ThreadPool {
+            workers: Mutex::new(Workers {
+                running: Vec::with_capacity(self.num_threads),
+                retired: Vec::new(),
+                next_id: 0,
+            }),
+            scheduler,
+            policy: self.policy,
+            builder: self.clone(),
+            counters,
+            #[cfg(feature = "slog")]
+            _stats_logger: stats_logger,
+        };
+        pool.resize(self.num_threads)?;
+
+        Ok(pool)
+    }
+
+    /// Builder of the thread of the worker `id`.
+    pub(crate) fn thread(&self, id: usize) -> thread::Builder {
+        let builder = thread::Builder::new().name(format!("{}{}", self.name_prefix, id));
+        match self.stack_size {
+            Some(size) => builder.stack_size(size),
+            None => builder,
+        }
+    }
+}
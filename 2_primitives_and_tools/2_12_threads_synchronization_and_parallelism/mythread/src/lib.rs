@@ -1,20 +1,86 @@
 // Теперь мы реализуем Drop черту, чтобы вызвать joinкаждый из потоков в пуле, чтобы они могли завершить запросы, над которыми они работают, перед закрытием.
 // Затем мы реализуем способ сообщить потокам, что они должны перестать принимать новые запросы и закрывать
 
-use std::thread;
+extern crate crossbeam_deque;
+#[cfg(feature = "metrics")]
+extern crate metrics;
+extern crate num_cpus;
+#[cfg(feature = "slog")]
+#[macro_use]
+extern crate slog;
+
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
-use std::sync::Arc;
-use std::sync::Mutex;
-// Сигнализация к потокам, чтобы остановить прослушивание заданий
+use std::sync::{Arc, Mutex};
+use std::thread;
 
-enum Message {
-    NewJob(Job),
-    Terminate,
-}
+mod builder;
+mod oneshot;
+mod scheduler;
+mod stats;
+
+pub use builder::ThreadPoolBuilder;
+pub use stats::Stats;
+
+use scheduler::Scheduler;
+use stats::Counters;
+
+/// Capacity of the job queue of `ThreadPool::new`.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 1024;
 
 pub struct ThreadPool {
-    workers: Vec<Worker>,
-    sender: mpsc::Sender<Message>,
+    workers: Mutex<Workers>,
+    scheduler: Arc<Scheduler<Job>>,
+    policy: SubmitPolicy,
+    /// Names and stack size of the workers spawned by `resize`.
+    builder: ThreadPoolBuilder,
+    counters: Arc<Counters>,
+    #[cfg(feature = "slog")]
+    _stats_logger: Option<stats::StatsLogger>,
+}
+
+struct Workers {
+    running: Vec<Worker>,
+    /// Told to stop by `resize`, joined by `Drop`.
+    retired: Vec<Worker>,
+    next_id: usize,
+}
+
+/// What `ThreadPool::execute` does when the job queue is full.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubmitPolicy {
+    /// Wait until a worker takes a job from the queue.
+    Block,
+    /// Return the closure back as `Rejected`.
+    Reject,
+    /// Run the closure on the calling thread, which also slows the producer down.
+    CallerRuns,
+}
+
+/// The closure not accepted by the full queue of the pool.
+pub struct Rejected<F>(pub F);
+
+impl<F> Rejected<F> {
+    pub fn into_inner(self) -> F {
+        self.0
+    }
+}
+
+impl<F> fmt::Debug for Rejected<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Rejected(..)")
+    }
+}
+
+impl<F> fmt::Display for Rejected<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("job queue is full")
+    }
 }
 
 trait FnBox {
@@ -36,51 +102,198 @@ impl ThreadPool {
     ///
     /// # Panics
     ///
-    /// The `new` function will panic if the size is zero.
+    /// The `new` function will panic if the size is zero
+    /// or a thread can't be spawned, `ThreadPoolBuilder` returns the error instead.
     pub fn new(size: usize) -> ThreadPool {
-        assert!(size > 0);
+        ThreadPool::with_queue(size, DEFAULT_QUEUE_CAPACITY, SubmitPolicy::Block)
+    }
+
+    /// Create a new ThreadPool with at most `capacity` jobs waiting in the queue,
+    /// `policy` tells what to do with the jobs coming to the full queue.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size or the capacity is zero or a thread can't be spawned.
+    pub fn with_queue(size: usize, capacity: usize, policy: SubmitPolicy) -> ThreadPool {
+        ThreadPoolBuilder::new()
+            .num_threads(size)
+            .queue_capacity(capacity)
+            .submit_policy(policy)
+            .build()
+            .expect("failed to spawn a worker thread")
+    }
 
-        let (sender, receiver) = mpsc::channel();
+    /// Send the closure to a free worker.
+    ///
+    /// The returned handle gives the value of the closure, it may be dropped
+    /// for a fire-and-forget job. A panic of the closure doesn't kill the worker,
+    /// it is returned by the handle as `JobError::Panicked`.
+    ///
+    /// Only `SubmitPolicy::Reject` makes it fail, when the queue is full.
+    pub fn execute<F, T>(&self, f: F) -> Result<JobHandle<T>, Rejected<F>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match self.policy {
+            SubmitPolicy::Block => self.scheduler.reserve(),
+            SubmitPolicy::Reject => {
+                if !self.scheduler.try_reserve() {
+                    return Err(Rejected(f));
+                }
+            }
+            SubmitPolicy::CallerRuns => {
+                if !self.scheduler.try_reserve() {
+                    self.counters.submitted();
+                    let (job, handle) = job(f, &self.counters);
+                    job.call_box();
+                    return Ok(handle);
+                }
+            }
+        }
+        self.counters.submitted();
+        let (job, handle) = job(f, &self.counters);
+        self.scheduler.push(job);
+        Ok(handle)
+    }
 
-        let receiver = Arc::new(Mutex::new(receiver));
+    /// Number of the running workers.
+    pub fn size(&self) -> usize {
+        self.workers.lock().unwrap().running.len()
+    }
 
-        let mut workers = Vec::with_capacity(size);
+    /// Change the number of the workers to `new_size`.
+    ///
+    /// The new workers are spawned at once. The surplus ones finish their
+    /// current job and exit, their queued jobs are left to the others.
+    /// On a spawn error the workers spawned before it are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_size` is zero.
+    pub fn resize(&self, new_size: usize) -> io::Result<()> {
+        assert!(new_size > 0);
 
-        for id in 0..size {
-            workers.push(Worker::new(id, Arc::clone(&receiver)));
+        let mut workers = self.workers.lock().unwrap();
+        while workers.running.len() < new_size {
+            self.spawn_worker(&mut workers)?;
+        }
+        if workers.running.len() > new_size {
+            let surplus = workers.running.split_off(new_size);
+            for worker in &surplus {
+                worker.stop.store(true, Ordering::SeqCst);
+            }
+            self.scheduler.wake_all();
+            workers.retired.extend(surplus);
         }
+        Ok(())
+    }
+
+    fn spawn_worker(&self, workers: &mut Workers) -> io::Result<()> {
+        let id = workers.next_id;
+        let worker = Worker::new(id, Arc::clone(&self.scheduler), self.builder.thread(id))?;
+        workers.next_id += 1;
+        workers.running.push(worker);
+        Ok(())
+    }
+
+    /// Counters of the jobs since the pool was built.
+    pub fn stats(&self) -> Stats {
+        self.counters
+            .stats(self.scheduler.queued(), self.scheduler.capacity())
+    }
+}
 
-        ThreadPool {
-            workers,
-            sender,
+/// Wrap the closure into a job sending its result to the handle.
+fn job<F, T>(f: F, counters: &Arc<Counters>) -> (Job, JobHandle<T>)
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    let counters = Arc::clone(counters);
+    let job = Box::new(move || {
+        counters.started();
+        let result = panic::catch_unwind(AssertUnwindSafe(f)).map_err(JobError::from_panic);
+        counters.finished(result.is_ok());
+        sender.send(result);
+    });
+    (job, JobHandle { receiver })
+}
+
+/// Why a job gave no value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobError {
+    /// The closure panicked with the message.
+    Panicked(String),
+    /// The job was dropped without running, or its result is already taken.
+    Lost,
+}
+
+impl JobError {
+    fn from_panic(payload: Box<dyn Any + Send>) -> JobError {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<Any>".to_string()
+        };
+        JobError::Panicked(message)
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobError::Panicked(message) => write!(f, "job panicked: {}", message),
+            JobError::Lost => write!(f, "job result is lost"),
         }
     }
+}
 
-    pub fn execute<F>(&self, f: F)
-        where
-            F: FnOnce() + Send + 'static
-    {
-        let job = Box::new(f);
+impl Error for JobError {}
 
-        self.sender.send(Message::NewJob(job)).unwrap();
+/// Handle of the job sent by `ThreadPool::execute`, its result is returned once.
+pub struct JobHandle<T> {
+    receiver: oneshot::Receiver<Result<T, JobError>>,
+}
+
+impl<T> fmt::Debug for JobHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("JobHandle { .. }")
     }
 }
 
-impl Drop for ThreadPool {
-    fn drop(&mut self) {
-        println!("Sending terminate message to all workers.");
+impl<T> JobHandle<T> {
+    /// Block until the job is finished.
+    pub fn wait(self) -> Result<T, JobError> {
+        self.receiver.recv().unwrap_or(Err(JobError::Lost))
+    }
 
-        for _ in &mut self.workers {
-            self.sender.send(Message::Terminate).unwrap();
+    /// The result if the job is finished, `None` while it is queued or running.
+    pub fn try_get(&self) -> Option<Result<T, JobError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(JobError::Lost)),
         }
+    }
+}
 
-        println!("Shutting down all workers.");
+/// Graceful shutdown: the jobs sent before the drop are finished,
+/// then the workers exit and their threads are joined.
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.scheduler.shutdown();
 
-        for worker in &mut self.workers {
+        let workers = self.workers.get_mut().unwrap();
+        for worker in workers.running.iter_mut().chain(&mut workers.retired) {
             println!("Shutting down worker {}", worker.id);
 
             if let Some(thread) = worker.thread.take() {
-                thread.join().unwrap();
+                // A panic of the job has already been reported by its thread.
+                let _ = thread.join();
             }
         }
     }
@@ -88,45 +301,245 @@ impl Drop for ThreadPool {
 
 struct Worker {
     id: usize,
+    /// `None` once the thread is joined by `Drop`, `take()` moves the handle out.
     thread: Option<thread::JoinHandle<()>>,
+    /// Set by `ThreadPool::resize` to retire the worker after its current job.
+    stop: Arc<AtomicBool>,
 }
 
 impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>) ->
-    Worker {
-
-        let thread = thread::spawn(move ||{
-            loop {
-                let message = receiver.lock().unwrap().recv().unwrap();
-
-                match message {
-                    Message::NewJob(job) => {
-                        println!("Worker {} got a job; executing.", id);
-
+    fn new(
+        id: usize,
+        scheduler: Arc<Scheduler<Job>>,
+        builder: thread::Builder,
+    ) -> io::Result<Worker> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = Arc::clone(&stop);
+            builder.spawn(move || {
+                let deque = scheduler.register(id);
+                loop {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Some(job) = scheduler.take(&deque) {
                         job.call_box();
-                    },
-                    Message::Terminate => {
-                        println!("Worker {} was told to terminate.", id);
-
+                    } else if !scheduler.wait(&stop) {
                         break;
-                    },
+                    }
                 }
-            }
-        });
+                println!("Worker {} was told to terminate.", id);
+                scheduler.unregister(id, deque);
+            })?
+        };
 
-        Worker {
+        Ok(Worker {
             id,
             thread: Some(thread),
-        }
+            stop,
+        })
     }
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Barrier;
+    use std::time::Duration;
+
+    #[test]
+    fn test_drop_finishes_jobs() {
+        let done = Arc::new(AtomicUsize::new(0));
+        {
+            let pool = ThreadPool::new(2);
+            for _ in 0..8 {
+                let done = Arc::clone(&done);
+                pool.execute(move || {
+                    thread::sleep(Duration::from_millis(10));
+                    done.fetch_add(1, Ordering::SeqCst);
+                })
+                .unwrap();
+            }
+        }
+        assert_eq!(8, done.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_drop_joins_workers() {
+        let pool = ThreadPool::new(3);
+        pool.execute(|| thread::sleep(Duration::from_millis(10)))
+            .unwrap();
+        // Every worker thread holds the scheduler until it exits.
+        let scheduler = Arc::clone(&pool.scheduler);
+        assert!(Arc::strong_count(&scheduler) > 2);
+
+        drop(pool);
+        assert_eq!(1, Arc::strong_count(&scheduler));
+    }
+
+    #[test]
+    fn test_drop_after_panicked_job() {
+        let pool = ThreadPool::new(1);
+        pool.execute(|| panic!("job failed")).unwrap();
+        drop(pool);
+    }
 
+    #[test]
+    fn test_job_handle() {
+        let pool = ThreadPool::new(2);
+        let handles: Vec<JobHandle<usize>> = (0..4)
+            .map(|i| pool.execute(move || i * i).unwrap())
+            .collect();
+        let squares: Vec<usize> = handles.into_iter().map(|h| h.wait().unwrap()).collect();
+        assert_eq!(vec![0, 1, 4, 9], squares);
+
+        let failed = pool.execute(|| -> usize { panic!("job failed") }).unwrap();
+        assert_eq!(
+            Err(JobError::Panicked("job failed".to_string())),
+            failed.wait()
+        );
+        // The worker survived the panic.
+        assert_eq!(Ok(5), pool.execute(|| 5).unwrap().wait());
+    }
+
+    #[test]
+    fn test_job_handle_try_get() {
+        let pool = ThreadPool::new(1);
+        let (start, started) = mpsc::channel();
+        let handle = pool.execute(move || started.recv().unwrap()).unwrap();
+        assert_eq!(None, handle.try_get());
+
+        start.send("done").unwrap();
+        let result = loop {
+            if let Some(result) = handle.try_get() {
+                break result;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(Ok("done"), result);
+        assert_eq!(Some(Err(JobError::Lost)), handle.try_get());
+    }
 
+    /// Pool of one worker blocked by the returned sender, with the full queue of one job.
+    fn busy_pool(policy: SubmitPolicy) -> (ThreadPool, mpsc::Sender<()>) {
+        let pool = ThreadPool::with_queue(1, 1, policy);
+        let (unblock, blocked) = mpsc::channel();
+        let (started, start) = mpsc::channel();
+        pool.execute(move || {
+            started.send(()).unwrap();
+            blocked.recv().unwrap()
+        })
+        .unwrap();
+        start.recv().unwrap();
+        pool.execute(|| ()).unwrap();
+        assert_eq!(1, pool.stats().queued);
+        (pool, unblock)
+    }
 
+    #[test]
+    fn test_submit_policy_reject() {
+        let (pool, unblock) = busy_pool(SubmitPolicy::Reject);
+        let rejected = pool.execute(|| 7).unwrap_err();
+        assert_eq!(7, (rejected.into_inner())());
+        unblock.send(()).unwrap();
+    }
 
+    #[test]
+    fn test_submit_policy_caller_runs() {
+        let (pool, unblock) = busy_pool(SubmitPolicy::CallerRuns);
+        let caller = thread::current().id();
+        let handle = pool
+            .execute(move || thread::current().id() == caller)
+            .unwrap();
+        assert_eq!(Some(Ok(true)), handle.try_get());
+        unblock.send(()).unwrap();
+    }
 
+    #[test]
+    fn test_submit_policy_block() {
+        let (pool, unblock) = busy_pool(SubmitPolicy::Block);
+        let unblocker = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            unblock.send(()).unwrap();
+        });
+        assert_eq!(Ok(3), pool.execute(|| 3).unwrap().wait());
+        unblocker.join().unwrap();
+        assert_eq!(0, pool.stats().queued);
+    }
 
+    #[test]
+    fn test_builder() {
+        let pool = ThreadPoolBuilder::new()
+            .name_prefix("test-pool-")
+            .stack_size(64 * 1024)
+            .num_threads(3)
+            .build()
+            .unwrap();
+        assert_eq!(3, pool.size());
+
+        let workers = pool.workers.lock().unwrap();
+        let names: Vec<&str> = workers
+            .running
+            .iter()
+            .filter_map(|worker| worker.thread.as_ref())
+            .filter_map(|thread| thread.thread().name())
+            .collect();
+        assert_eq!(vec!["test-pool-0", "test-pool-1", "test-pool-2"], names);
+    }
 
+    #[test]
+    fn test_stats() {
+        let pool = ThreadPool::new(1);
+        let handles = vec![
+            pool.execute(|| ()).unwrap(),
+            pool.execute(|| panic!("boom")).unwrap(),
+            pool.execute(|| ()).unwrap(),
+        ];
+        for handle in handles {
+            let _ = handle.wait();
+        }
 
+        let stats = pool.stats();
+        assert_eq!(3, stats.submitted);
+        assert_eq!(3, stats.started);
+        assert_eq!(2, stats.completed);
+        assert_eq!(1, stats.panicked);
+        assert_eq!(0, stats.busy);
+        assert_eq!(0, stats.queued);
+    }
 
+    #[test]
+    fn test_resize() {
+        let pool = ThreadPool::new(1);
+        pool.resize(3).unwrap();
+        assert_eq!(3, pool.size());
+
+        // Only three workers running at once pass the barrier.
+        let barrier = Arc::new(Barrier::new(4));
+        for _ in 0..3 {
+            let barrier = Arc::clone(&barrier);
+            pool.execute(move || barrier.wait()).unwrap();
+        }
+        barrier.wait();
+
+        // A surplus worker finishes its current job before exiting.
+        let (unblock, blocked) = mpsc::channel();
+        let (started, start) = mpsc::channel();
+        let handle = pool
+            .execute(move || {
+                started.send(()).unwrap();
+                blocked.recv().unwrap()
+            })
+            .unwrap();
+        start.recv().unwrap();
+        pool.resize(1).unwrap();
+        assert_eq!(1, pool.size());
+        unblock.send(42).unwrap();
+        assert_eq!(Ok(42), handle.wait());
+
+        let handles: Vec<_> = (0..10).map(|i| pool.execute(move || i).unwrap()).collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.wait().unwrap()).collect();
+        assert_eq!((0..10).collect::<Vec<_>>(), results);
+    }
+}
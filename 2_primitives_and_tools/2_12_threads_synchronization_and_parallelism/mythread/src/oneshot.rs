@@ -0,0 +1,91 @@
+//! Channel for the only value, the result of a job sent to its `JobHandle`.
+//!
+//! Lighter than `mpsc::sync_channel(1)`: one allocation shared by both ends.
+
+use std::sync::mpsc::{RecvError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex};
+
+enum State<T> {
+    Empty,
+    Full(T),
+    /// The value is taken or the sender is dropped without sending it.
+    Closed,
+}
+
+struct Inner<T> {
+    state: Mutex<State<T>>,
+    ready: Condvar,
+}
+
+pub struct Sender<T>(Arc<Inner<T>>);
+
+pub struct Receiver<T>(Arc<Inner<T>>);
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State::Empty),
+        ready: Condvar::new(),
+    });
+    (Sender(Arc::clone(&inner)), Receiver(inner))
+}
+
+impl<T> Sender<T> {
+    pub fn send(self, value: T) {
+        *self.0.state.lock().unwrap() = State::Full(value);
+        self.0.ready.notify_one();
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap();
+        if let State::Empty = *state {
+            *state = State::Closed;
+            self.0.ready.notify_one();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Block until the value is sent.
+    pub fn recv(self) -> Result<T, RecvError> {
+        let mut state = self.0.state.lock().unwrap();
+        while let State::Empty = *state {
+            state = self.0.ready.wait(state).unwrap();
+        }
+        match ::std::mem::replace(&mut *state, State::Closed) {
+            State::Full(value) => Ok(value),
+            _ => Err(RecvError),
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.0.state.lock().unwrap();
+        match ::std::mem::replace(&mut *state, State::Closed) {
+            State::Full(value) => Ok(value),
+            State::Empty => {
+                *state = State::Empty;
+                Err(TryRecvError::Empty)
+            }
+            State::Closed => Err(TryRecvError::Disconnected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_oneshot() {
+        let (sender, receiver) = channel();
+        assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+        sender.send(1);
+        assert_eq!(Ok(1), receiver.try_recv());
+        assert_eq!(Err(TryRecvError::Disconnected), receiver.try_recv());
+
+        let (sender, receiver) = channel::<()>();
+        drop(sender);
+        assert_eq!(Err(RecvError), receiver.recv());
+    }
+}
@@ -0,0 +1,259 @@
+//! Work stealing: the jobs come to the global `Injector`, every worker moves
+//! a batch of them into its own deque and the idle workers steal from the others,
+//! so the workers don't take a shared lock for every job.
+
+use crossbeam_deque::{Injector, Stealer, Worker};
+use std::iter;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, RwLock};
+
+pub struct Scheduler<T> {
+    injector: Injector<T>,
+    /// Stealers of the deques of the running workers by their id,
+    /// a worker is added and removed by `ThreadPool::resize`.
+    stealers: RwLock<Vec<(usize, Stealer<T>)>>,
+    /// Jobs reserved and not taken by a worker yet, at most `capacity`.
+    queued: AtomicUsize,
+    capacity: usize,
+    shutdown: AtomicBool,
+    /// Sleeping workers not notified yet: `sleeping - notified` of `sleepers`,
+    /// read without the lock, so a push locks only when there is a worker to wake.
+    idle: AtomicUsize,
+    /// Guards the sleep of the idle workers and of the producers blocked on the full queue.
+    sleepers: Mutex<Sleepers>,
+    job_pushed: Condvar,
+    job_taken: Condvar,
+}
+
+struct Sleepers {
+    /// Workers waiting on `job_pushed`.
+    sleeping: usize,
+    /// Wakeups sent to them and not received yet.
+    notified: usize,
+}
+
+impl Sleepers {
+    fn idle(&self) -> usize {
+        self.sleeping.saturating_sub(self.notified)
+    }
+}
+
+impl<T> Scheduler<T> {
+    /// Scheduler without workers, they come with `register`.
+    pub fn new(capacity: usize) -> Scheduler<T> {
+        Scheduler {
+            injector: Injector::new(),
+            stealers: RwLock::new(Vec::new()),
+            queued: AtomicUsize::new(0),
+            capacity,
+            shutdown: AtomicBool::new(false),
+            idle: AtomicUsize::new(0),
+            sleepers: Mutex::new(Sleepers {
+                sleeping: 0,
+                notified: 0,
+            }),
+            job_pushed: Condvar::new(),
+            job_taken: Condvar::new(),
+        }
+    }
+
+    /// The deque of the new worker `id`, the others steal from it from now on.
+    pub fn register(&self, id: usize) -> Worker<T> {
+        let deque = Worker::new_fifo();
+        self.stealers.write().unwrap().push((id, deque.stealer()));
+        deque
+    }
+
+    /// Remove the worker `id` leaving the pool, the jobs left in its deque
+    /// go back to the injector for the other workers.
+    pub fn unregister(&self, id: usize, local: Worker<T>) {
+        self.stealers
+            .write()
+            .unwrap()
+            .retain(|&(other, _)| other != id);
+        let mut moved = false;
+        while let Some(job) = local.pop() {
+            self.injector.push(job);
+            moved = true;
+        }
+        if moved {
+            self.wake_one();
+        }
+    }
+
+    /// Take a place in the queue if it isn't full.
+    pub fn try_reserve(&self) -> bool {
+        let mut queued = self.queued.load(Ordering::SeqCst);
+        loop {
+            if queued >= self.capacity {
+                return false;
+            }
+            match self.queued.compare_exchange_weak(
+                queued,
+                queued + 1,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => queued = actual,
+            }
+        }
+    }
+
+    /// Take a place in the queue, waiting for it while the queue is full.
+    pub fn reserve(&self) {
+        if self.try_reserve() {
+            return;
+        }
+        let mut sleepers = self.sleepers.lock().unwrap();
+        while !self.try_reserve() {
+            sleepers = self.job_taken.wait(sleepers).unwrap();
+        }
+    }
+
+    /// Push the job into the place taken by `reserve` or `try_reserve`.
+    pub fn push(&self, job: T) {
+        self.injector.push(job);
+        self.wake_one();
+    }
+
+    /// Wake a sleeping worker unless all of them are already notified.
+    fn wake_one(&self) {
+        if self.idle.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+        let mut sleepers = self.sleepers.lock().unwrap();
+        if sleepers.idle() > 0 {
+            sleepers.notified += 1;
+            self.idle.store(sleepers.idle(), Ordering::SeqCst);
+            self.job_pushed.notify_one();
+        }
+    }
+
+    /// The next job for the worker owning `local`: from its deque,
+    /// a batch from the injector or stolen from the other workers.
+    pub fn take(&self, local: &Worker<T>) -> Option<T> {
+        let job = local.pop().or_else(|| {
+            iter::repeat_with(|| {
+                self.injector.steal_batch_and_pop(local).or_else(|| {
+                    self.stealers
+                        .read()
+                        .unwrap()
+                        .iter()
+                        .map(|(_, stealer)| stealer.steal())
+                        .collect()
+                })
+            })
+            .find(|steal| !steal.is_retry())
+            .and_then(|steal| steal.success())
+        })?;
+        let queued = self.queued.fetch_sub(1, Ordering::SeqCst);
+        if queued >= self.capacity {
+            // The queue was full, a producer may be waiting.
+            let _sleepers = self.sleepers.lock().unwrap();
+            self.job_taken.notify_one();
+        }
+        if queued > 1 {
+            // More jobs are left, another worker may help.
+            self.wake_one();
+        }
+        Some(job)
+    }
+
+    /// Sleep while there are no jobs, `false` once the scheduler is shut down
+    /// and all the jobs are taken or once the worker is told to `stop`.
+    pub fn wait(&self, stop: &AtomicBool) -> bool {
+        let mut sleepers = self.sleepers.lock().unwrap();
+        // Counted before checking `queued`, so a push either is seen here or wakes us.
+        sleepers.sleeping += 1;
+        self.idle.store(sleepers.idle(), Ordering::SeqCst);
+        let running = loop {
+            if stop.load(Ordering::SeqCst) {
+                break false;
+            }
+            if self.queued.load(Ordering::SeqCst) > 0 {
+                break true;
+            }
+            if self.shutdown.load(Ordering::SeqCst) {
+                break false;
+            }
+            sleepers = self.job_pushed.wait(sleepers).unwrap();
+            if sleepers.notified > 0 {
+                sleepers.notified -= 1;
+            }
+            self.idle.store(sleepers.idle(), Ordering::SeqCst);
+        };
+        sleepers.sleeping -= 1;
+        self.idle.store(sleepers.idle(), Ordering::SeqCst);
+        running
+    }
+
+    /// Let the workers exit once the queued jobs are done.
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        self.wake_all();
+    }
+
+    /// Wake all the sleeping workers to check their `stop` flags.
+    pub fn wake_all(&self) {
+        let mut sleepers = self.sleepers.lock().unwrap();
+        sleepers.notified = sleepers.sleeping;
+        self.idle.store(0, Ordering::SeqCst);
+        self.job_pushed.notify_all();
+    }
+
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scheduler_capacity() {
+        let scheduler = Scheduler::new(2);
+        let deques = [scheduler.register(0), scheduler.register(1)];
+        for i in 0..2 {
+            assert!(scheduler.try_reserve());
+            scheduler.push(i);
+        }
+        assert!(!scheduler.try_reserve());
+        assert_eq!(2, scheduler.queued());
+
+        assert_eq!(Some(0), scheduler.take(&deques[0]));
+        // The second job is left in the injector or stolen from the first deque.
+        assert_eq!(Some(1), scheduler.take(&deques[1]));
+        assert_eq!(None, scheduler.take(&deques[1]));
+        assert!(scheduler.try_reserve());
+    }
+
+    #[test]
+    fn test_scheduler_shutdown() {
+        let scheduler = Scheduler::<()>::new(1);
+        scheduler.shutdown();
+        assert!(!scheduler.wait(&AtomicBool::new(false)));
+    }
+
+    #[test]
+    fn test_scheduler_unregister() {
+        let scheduler = Scheduler::new(2);
+        let first = scheduler.register(0);
+        for i in 0..2 {
+            assert!(scheduler.try_reserve());
+            scheduler.push(i);
+        }
+        assert_eq!(Some(0), scheduler.take(&first));
+        // The second job is left in the injector or moved back from the first deque.
+        scheduler.unregister(0, first);
+
+        let second = scheduler.register(1);
+        assert_eq!(Some(1), scheduler.take(&second));
+        assert!(!scheduler.wait(&AtomicBool::new(true)));
+    }
+}
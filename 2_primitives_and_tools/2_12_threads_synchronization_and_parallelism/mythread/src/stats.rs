@@ -0,0 +1,246 @@
+//! Counters of the jobs of the pool, read by `ThreadPool::stats`.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current state of the pool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// Jobs waiting in the queue for a free worker.
+    pub queued: usize,
+    /// Capacity of the queue.
+    pub capacity: usize,
+    /// Jobs accepted by `ThreadPool::execute`, the rejected ones are not counted.
+    pub submitted: usize,
+    /// Jobs started by a worker or by the caller with `SubmitPolicy::CallerRuns`.
+    pub started: usize,
+    /// Jobs returned a value.
+    pub completed: usize,
+    /// Jobs panicked.
+    pub panicked: usize,
+    /// Jobs running right now.
+    pub busy: usize,
+}
+
+#[derive(Default)]
+pub struct Counters {
+    submitted: AtomicUsize,
+    started: AtomicUsize,
+    completed: AtomicUsize,
+    panicked: AtomicUsize,
+    busy: AtomicUsize,
+}
+
+impl Counters {
+    pub fn submitted(&self) {
+        self.submitted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn started(&self) {
+        self.started.fetch_add(1, Ordering::Relaxed);
+        self.busy.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The job is finished, `ok` is false if it panicked.
+    pub fn finished(&self, ok: bool) {
+        if ok {
+            self.completed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.panicked.fetch_add(1, Ordering::Relaxed);
+        }
+        self.busy.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self, queued: usize, capacity: usize) -> Stats {
+        Stats {
+            queued,
+            capacity,
+            submitted: self.submitted.load(Ordering::Relaxed),
+            started: self.started.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            panicked: self.panicked.load(Ordering::Relaxed),
+            busy: self.busy.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub use self::export::register_metrics;
+#[cfg(feature = "slog")]
+pub use self::logger::StatsLogger;
+
+#[cfg(feature = "metrics")]
+mod export {
+    use super::Counters;
+    use metrics::{self, Metrics};
+    use scheduler::Scheduler;
+    use std::sync::Arc;
+    use Job;
+
+    /// Register the `thread_pool_*` metrics, copied from the counters on every scrape.
+    pub fn register_metrics(
+        metrics: &Metrics,
+        counters: Arc<Counters>,
+        scheduler: Arc<Scheduler<Job>>,
+    ) -> metrics::Result<()> {
+        let submitted = metrics.counter(
+            "thread_pool_jobs_submitted_total",
+            "Jobs accepted by the pool.",
+        )?;
+        let started = metrics.counter("thread_pool_jobs_started_total", "Jobs started.")?;
+        let completed =
+            metrics.counter("thread_pool_jobs_completed_total", "Jobs returned a value.")?;
+        let panicked = metrics.counter("thread_pool_jobs_panicked_total", "Jobs panicked.")?;
+        let queued = metrics.gauge(
+            "thread_pool_jobs_queued",
+            "Jobs waiting in the queue for a free worker.",
+        )?;
+        let busy = metrics.gauge("thread_pool_jobs_busy", "Jobs running right now.")?;
+        let capacity = metrics.gauge("thread_pool_queue_capacity", "Capacity of the queue.")?;
+
+        metrics.on_collect(move || {
+            let stats = counters.stats(scheduler.queued(), scheduler.capacity());
+            metrics::set_counter(&submitted, stats.submitted as f64);
+            metrics::set_counter(&started, stats.started as f64);
+            metrics::set_counter(&completed, stats.completed as f64);
+            metrics::set_counter(&panicked, stats.panicked as f64);
+            queued.set(stats.queued as f64);
+            busy.set(stats.busy as f64);
+            capacity.set(stats.capacity as f64);
+        });
+        Ok(())
+    }
+}
+
+#[cfg(feature = "slog")]
+mod logger {
+    use super::Counters;
+    use scheduler::Scheduler;
+    use slog::Logger;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use Job;
+
+    /// Thread logging the stats every interval until it is dropped with the pool.
+    pub struct StatsLogger {
+        stop: Option<mpsc::Sender<()>>,
+        thread: Option<thread::JoinHandle<()>>,
+    }
+
+    impl StatsLogger {
+        pub fn spawn(
+            log: Logger,
+            interval: Duration,
+            counters: Arc<Counters>,
+            scheduler: Arc<Scheduler<Job>>,
+        ) -> StatsLogger {
+            let (stop, stopped) = mpsc::channel::<()>();
+            let thread = thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    let stats = counters.stats(scheduler.queued(), scheduler.capacity());
+                    info!(log, "thread pool";
+                        "queued" => stats.queued,
+                        "capacity" => stats.capacity,
+                        "submitted" => stats.submitted,
+                        "started" => stats.started,
+                        "completed" => stats.completed,
+                        "panicked" => stats.panicked,
+                        "busy" => stats.busy,
+                    );
+                }
+            });
+            StatsLogger {
+                stop: Some(stop),
+                thread: Some(thread),
+            }
+        }
+    }
+
+    impl Drop for StatsLogger {
+        fn drop(&mut self) {
+            // Disconnects the channel, the thread wakes up and exits.
+            self.stop.take();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "slog"))]
+mod test {
+    use slog::{Drain, Logger, OwnedKVList, Record};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+    use ThreadPoolBuilder;
+
+    /// Keeps the messages of the records.
+    struct Messages(Arc<Mutex<Vec<String>>>);
+
+    impl Drain for Messages {
+        type Ok = ();
+        type Err = slog::Never;
+
+        fn log(&self, record: &Record, _: &OwnedKVList) -> Result<(), slog::Never> {
+            self.0.lock().unwrap().push(record.msg().to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_log_stats() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let log = Logger::root(Messages(Arc::clone(&messages)), o!());
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(1)
+            .log_stats(log, Duration::from_millis(10))
+            .build()
+            .unwrap();
+        thread::sleep(Duration::from_millis(50));
+        drop(pool);
+
+        let logged = messages.lock().unwrap().len();
+        assert!(logged > 0);
+        assert!(messages
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|msg| msg == "thread pool"));
+        // The logger is stopped with the pool.
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(logged, messages.lock().unwrap().len());
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod test_metrics {
+    use metrics::Metrics;
+    use ThreadPoolBuilder;
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Metrics::new();
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(1)
+            .metrics(metrics.clone())
+            .build()
+            .unwrap();
+        let _ = pool.execute(|| ()).unwrap().wait();
+        let _ = pool.execute(|| panic!("boom")).unwrap().wait();
+
+        let text = String::from_utf8(metrics.encode().0).unwrap();
+        assert!(text.contains("thread_pool_jobs_submitted_total 2"));
+        assert!(text.contains("thread_pool_jobs_completed_total 1"));
+        assert!(text.contains("thread_pool_jobs_panicked_total 1"));
+        assert!(text.contains("thread_pool_queue_capacity 1024"));
+
+        // One pool per registry.
+        assert!(ThreadPoolBuilder::new()
+            .num_threads(1)
+            .metrics(metrics)
+            .build()
+            .is_err());
+    }
+}
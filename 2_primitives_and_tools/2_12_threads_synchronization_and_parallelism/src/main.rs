@@ -1,13 +1,9 @@
 extern crate crossbeam;
-extern crate rand;
-extern crate rayon;
+extern crate matrix;
 #[macro_use]
 extern crate crossbeam_channel;
 
-use rand::thread_rng;
-use rand::Rng;
-use rayon::prelude::*;
-use std::collections::HashMap;
+use matrix::Matrix;
 use std::io::Write;
 use std::sync::mpsc;
 use std::thread;
@@ -26,8 +22,8 @@ use std::thread;
 ///    use threads_synchronization_and_parallelism::*;
 ///
 ///    let (tx, rx): (
-///        crossbeam_channel::Sender<HashMap<(i32, i32), u8>>,
-///        crossbeam_channel::Receiver<HashMap<(i32, i32), u8>>,
+///        crossbeam_channel::Sender<Matrix>,
+///        crossbeam_channel::Receiver<Matrix>,
 ///    ) = crossbeam_channel::unbounded();
 ///
 ///    let rx_2 = rx.clone();
@@ -59,15 +55,8 @@ mod threads_synchronization_and_parallelism {
     /// Implement Producer.
     impl Producer {
         /// Implement generates square matrixes.
-        pub fn generate_matrix() -> HashMap<(i32, i32), u8> {
-            let mut matrix: HashMap<(i32, i32), u8> = HashMap::with_capacity(4096);
-            let mut rng = thread_rng();
-            for x in (1..65) {
-                for y in (1..65) {
-                    matrix.insert((x, y), rng.gen::<u8>());
-                }
-            }
-            matrix
+        pub fn generate_matrix() -> Matrix {
+            Matrix::random(matrix::SIDE)
         }
     }
 
@@ -77,9 +66,9 @@ mod threads_synchronization_and_parallelism {
     /// Implement Consumer.
     impl Consumer {
         /// Implement the calculation of the sum of a square matrix.
-        /// The matrix is counted in parallel.
-        pub fn sum_matrix(matrix: HashMap<(i32, i32), u8>) {
-            let sum: u32 = matrix.par_iter().map(|(&k, &val)| val as u32).sum();
+        /// The matrix is counted in parallel by `matrix::sum`.
+        pub fn sum_matrix(matrix: Matrix) {
+            let sum: u64 = matrix::sum(&matrix);
             writeln!(std::io::stdout(), "Matrix sum:{}", sum);
         }
     }
@@ -90,8 +79,8 @@ fn main() {
     use threads_synchronization_and_parallelism::*;
 
     let (tx, rx): (
-        crossbeam_channel::Sender<HashMap<(i32, i32), u8>>,
-        crossbeam_channel::Receiver<HashMap<(i32, i32), u8>>,
+        crossbeam_channel::Sender<Matrix>,
+        crossbeam_channel::Receiver<Matrix>,
     ) = crossbeam_channel::unbounded();
 
     let rx_2 = rx.clone();
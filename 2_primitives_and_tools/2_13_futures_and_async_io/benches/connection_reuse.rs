@@ -0,0 +1,81 @@
+//! 100 links of the same host: the connection setup dominates the time of the downloads.
+//!
+//! Compares one shared `http_client` keeping the connection alive
+//! with the previous setup of the thread pool engine, where every link got
+//! its own runtime, TLS connector and client, so a new connection.
+//! Building the TLS connector loads the root certificates, which dominates
+//! the per-link setup. The server is local plain HTTP, so only the TCP handshake
+//! is saved on the connection; on a real HTTPS host the TLS handshake per link
+//! makes the difference larger.
+//!
+//! ```bash
+//! $ cargo bench
+//! ```
+#[macro_use]
+extern crate criterion;
+extern crate futures;
+extern crate futures_and_async_io;
+extern crate hyper;
+extern crate tokio;
+
+use criterion::Criterion;
+use futures::Future;
+use futures_and_async_io::http_client;
+use hyper::service::service_fn_ok;
+use hyper::{Body, Response, Server};
+use std::net::SocketAddr;
+use tokio::runtime::{current_thread, Runtime};
+
+const LINKS: usize = 100;
+const PAGE: &str = "<html><body>page</body></html>";
+
+/// Local server answering every link with `PAGE`, runs until `Runtime` is dropped.
+fn serve() -> (Runtime, SocketAddr) {
+    let mut runtime = Runtime::new().unwrap();
+    let server = Server::bind(&([127, 0, 0, 1], 0).into())
+        .serve(|| service_fn_ok(|_| Response::new(Body::from(PAGE))));
+    let addr = server.local_addr();
+    runtime.spawn(server.map_err(|e| eprintln!("server: {}", e)));
+    (runtime, addr)
+}
+
+fn links(addr: &SocketAddr) -> Vec<String> {
+    (0..LINKS)
+        .map(|i| format!("http://{}/page/{}", addr, i))
+        .collect()
+}
+
+fn bench_downloads(c: &mut Criterion) {
+    let (_server, addr) = serve();
+
+    let urls = links(&addr);
+    c.bench_function("shared client, 100 links", move |b| {
+        let mut runtime = Runtime::new().unwrap();
+        let client = http_client::new(1).unwrap();
+        b.iter(|| {
+            for url in &urls {
+                let page = runtime.block_on(http_client::fetch(&client, url)).unwrap();
+                assert_eq!(PAGE.len(), page.len());
+            }
+        })
+    });
+
+    let urls = links(&addr);
+    c.bench_function("client per link, 100 links", move |b| {
+        b.iter(|| {
+            for url in &urls {
+                let mut runtime = current_thread::Runtime::new().unwrap();
+                let client = http_client::new(1).unwrap();
+                let page = runtime.block_on(http_client::fetch(&client, url)).unwrap();
+                assert_eq!(PAGE.len(), page.len());
+            }
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_downloads
+}
+criterion_main!(benches);
@@ -0,0 +1,123 @@
+//! # The shared HTTP client of the downloads
+//!
+//! One client downloads all the links of the list: hyper keeps the connections
+//! alive in its pool, so the links of the same host reuse a connection
+//! instead of the TCP and TLS handshakes per link. The TLS connector offers HTTP/2
+//! by ALPN, the hosts accepting it serve all the links over one connection.
+//!
+//! The connections are the tasks of the runtime the requests run on,
+//! so the client is used on one runtime for the whole list.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust,no_run
+//!  extern crate futures_and_async_io;
+//!  extern crate tokio;
+//!
+//!  use futures_and_async_io::http_client;
+//!
+//!  let mut runtime = tokio::runtime::Runtime::new().unwrap();
+//!  let client = http_client::new(4).unwrap();
+//!  for url in &["https://www.rust-lang.org/", "https://www.rust-lang.org/learn"] {
+//!      let page = runtime.block_on(http_client::fetch(&client, url)).unwrap();
+//!      println!("{}: {} bytes", url, page.len());
+//!  }
+//! ```
+
+use futures::{future, Future, Stream};
+use hyper::client::connect::{Connect, Connected, Destination, HttpConnector};
+use hyper::{Body, Chunk, Client, Request};
+use hyper_tls::{HttpsConnector, MaybeHttpsStream};
+use native_tls::{self, TlsConnector};
+use std::io;
+
+pub type DownloadError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The client of the downloads.
+pub type HttpClient = Client<AlpnConnector, Body>;
+
+/// The protocols offered by ALPN, HTTP/2 first.
+pub const ALPN_PROTOCOLS: &[&str] = &["h2", "http/1.1"];
+
+type Transport = MaybeHttpsStream<<HttpConnector as Connect>::Transport>;
+
+/// HTTPS connector telling hyper that the connection negotiated HTTP/2 by ALPN,
+/// the plain `HttpsConnector` always speaks HTTP/1.1.
+#[derive(Clone)]
+pub struct AlpnConnector(HttpsConnector<HttpConnector>);
+
+impl AlpnConnector {
+    /// Connector resolving the names on `dns_threads` threads.
+    pub fn new(dns_threads: usize) -> Result<AlpnConnector, native_tls::Error> {
+        let tls = TlsConnector::builder()
+            .request_alpns(ALPN_PROTOCOLS)
+            .build()?;
+        let mut http = HttpConnector::new(dns_threads);
+        http.enforce_http(false);
+        Ok(AlpnConnector(HttpsConnector::from((http, tls))))
+    }
+}
+
+impl Connect for AlpnConnector {
+    type Transport = Transport;
+    type Error = io::Error;
+    type Future = Box<dyn Future<Item = (Transport, Connected), Error = io::Error> + Send>;
+
+    fn connect(&self, dst: Destination) -> Self::Future {
+        Box::new(self.0.connect(dst).map(|(stream, connected)| {
+            if negotiated_h2(&stream) {
+                (stream, connected.negotiated_h2())
+            } else {
+                (stream, connected)
+            }
+        }))
+    }
+}
+
+/// Whether the server chose HTTP/2 of `ALPN_PROTOCOLS`.
+fn negotiated_h2<T: io::Read + io::Write>(stream: &MaybeHttpsStream<T>) -> bool {
+    match *stream {
+        MaybeHttpsStream::Https(ref tls) => match tls.get_ref().negotiated_alpn() {
+            Ok(Some(ref protocol)) => protocol.as_slice() == b"h2",
+            _ => false,
+        },
+        MaybeHttpsStream::Http(_) => false,
+    }
+}
+
+/// Client keeping the connections alive between the downloads.
+pub fn new(dns_threads: usize) -> Result<HttpClient, native_tls::Error> {
+    Ok(Client::builder()
+        .keep_alive(true)
+        .build(AlpnConnector::new(dns_threads)?))
+}
+
+/// The body of the page at `url`, read to the end, so the connection
+/// goes back to the pool of the client.
+pub fn fetch(
+    client: &HttpClient,
+    url: &str,
+) -> impl Future<Item = Chunk, Error = DownloadError> + Send {
+    let client = client.clone();
+    future::result(Request::builder().uri(url).body(Body::empty()))
+        .map_err(DownloadError::from)
+        .and_then(move |request| {
+            client
+                .request(request)
+                .and_then(|response| response.into_body().concat2())
+                .map_err(DownloadError::from)
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fetch_bad_url() {
+        let client = new(1).unwrap();
+        assert!(fetch(&client, "not a url").wait().is_err());
+    }
+}
@@ -0,0 +1,7 @@
+//! The HTTP client of the downloader, shared with its benchmark.
+extern crate futures;
+extern crate hyper;
+extern crate hyper_tls;
+extern crate native_tls;
+
+pub mod http_client;
@@ -1,32 +1,54 @@
+extern crate cryptography;
+extern crate environment_variables;
 extern crate futures;
-extern crate hyper;
-extern crate hyper_tls;
+extern crate futures_and_async_io;
+extern crate healthz;
+extern crate metrics;
+extern crate mythread;
+extern crate num_cpus;
 extern crate tokio;
 
-#[macro_use(values_t, value_t, crate_version, crate_authors)]
+#[macro_use(value_t, crate_version, crate_authors)]
 extern crate clap;
 
 use clap::{App, Arg, ArgMatches};
-use futures::stream::Stream;
-use hyper::Body;
-use hyper::{Client, Request};
+use environment_variables::load_dotenv;
+use futures_and_async_io::http_client::{self, DownloadError};
+use healthz::HealthRegistry;
+use metrics::Metrics;
+use std::env;
 use std::fs::read_to_string;
 use std::fs::File;
-use std::io;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tokio::runtime::Runtime;
 
 /// # Downloading links through asynchronous libraries.
 ///
 /// ## Module implements command line arguments.
 ///
+/// Every argument falls back to an environment variable, so the downloader runs
+/// in a container without a command line. The value is taken from, in priority order:
+/// 1. the command line argument;
+/// 2. the environment variable (`LINKS_FILE`, `MAX_THREADS`, `ENGINE`, `SERVER__METRICS_PORT`,
+///    `SERVER__HEALTHZ_PORT`, `VERIFY_KEY`), also read from `.env` in the current directory
+///    by the loader of 2_10_environment_variables;
+/// 3. the default value, the number of CPUs for `--max-threads`.
+///
+/// The ports have the variables of `server.metrics_port` and `server.healthz_port`
+/// of the configuration (2_11_configuration).
+///
 /// ### Examples
 ///
 /// Basic usage:
 ///
 ///```bash
 ///   $ MyApp download.file.link
+///   $ MyApp --engine threads download.file.link
+///   $ MyApp --metrics-port 9199 download.file.link
+///   $ MyApp --healthz-port 10025 download.file.link
+///   $ MyApp --verify-key <hex of the Ed25519 public key> download.file.link
+///   $ LINKS_FILE=download.file.link MAX_THREADS=8 MyApp
 ///```
 ///
 /// ```rust
@@ -34,8 +56,12 @@ use tokio::runtime::Runtime;
 ///   use settings_args::*;
 ///
 ///   let settings: Settings = settings_args::new();
-///   assert_eq!(4,settings.max_threads);
+///   assert_eq!(default_max_threads(),settings.max_threads);
 ///   assert_eq!("download.file.link",settings.file);
+///   assert_eq!(Engine::Async,settings.engine);
+///   assert_eq!(None,settings.metrics_port);
+///   assert_eq!(None,settings.healthz_port);
+///   assert_eq!(None,settings.verify_key);
 ///
 /// ```
 ///   To call help:
@@ -52,6 +78,22 @@ mod settings_args {
     pub struct Settings {
         pub max_threads: u8,
         pub file: String,
+        pub engine: Engine,
+        /// Serve the metrics of the downloads on this port while they run.
+        pub metrics_port: Option<u16>,
+        /// Serve `/healthz` and `/readyz` on this port while the downloads run.
+        pub healthz_port: Option<u16>,
+        /// Verify the signature of every link by this Ed25519 public key.
+        pub verify_key: Option<Vec<u8>>,
+    }
+
+    /// How the links are downloaded.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Engine {
+        /// One after another on the tokio runtime.
+        Async,
+        /// A blocking download job per link on the `mythread::ThreadPool`.
+        Threads,
     }
 
     /// Function checking the existence of a file.
@@ -62,10 +104,30 @@ mod settings_args {
         Err(String::from("The file notfound"))
     }
 
-    /// Return ArgMatches Object.
-    fn get_matches<'a>() -> ArgMatches<'a> {
+    /// Parse the Ed25519 public key from its 64 hex digits.
+    fn parse_key(key: &str) -> Result<Vec<u8>, String> {
+        if key.len() != 64 || !key.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(String::from("The key is not 64 hex digits"));
+        }
+        Ok((0..key.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&key[i..i + 2], 16).unwrap())
+            .collect())
+    }
+
+    /// Default of `--max-threads`, the number of CPUs.
+    pub fn default_max_threads() -> u8 {
+        num_cpus::get().min(u8::MAX as usize) as u8
+    }
+
+    /// The arguments of the command line.
+    fn app<'a, 'b>() -> App<'a, 'b> {
         App::new("Load files CLI")
-            .usage("MyApp [--max-threads = <number>] <file>")
+            .usage(
+                "MyApp [--max-threads = <number>] [--engine <async|threads>] \
+                 [--metrics-port <port>] [--healthz-port <port>] \
+                 [--verify-key <pubkey>] <file>",
+            )
             .bin_name("MyApp")
             .version(crate_version!())
             .author(crate_authors!())
@@ -74,33 +136,251 @@ mod settings_args {
                 Arg::with_name("file")
                     .validator(has_file)
                     .required(true)
+                    .env("LINKS_FILE")
                     .help("Link file, line break delimiter"),
                 Arg::with_name("max-threads")
                     .long("max-threads")
                     .value_name("number")
                     .required(false)
-                    .help("Number of threads"),
-            ]).get_matches()
+                    .env("MAX_THREADS")
+                    .help("Number of threads, the number of CPUs by default"),
+                Arg::with_name("engine")
+                    .long("engine")
+                    .value_name("engine")
+                    .possible_values(&["async", "threads"])
+                    .default_value("async")
+                    .env("ENGINE")
+                    .help("Download on the tokio runtime or on the thread pool"),
+                Arg::with_name("metrics-port")
+                    .long("metrics-port")
+                    .value_name("port")
+                    .required(false)
+                    .env("SERVER__METRICS_PORT")
+                    .help("Serve the metrics on GET /metrics, server.metrics_port of the config"),
+                Arg::with_name("healthz-port")
+                    .long("healthz-port")
+                    .value_name("port")
+                    .required(false)
+                    .env("SERVER__HEALTHZ_PORT")
+                    .help("Serve GET /healthz and /readyz, server.healthz_port of the config"),
+                Arg::with_name("verify-key")
+                    .long("verify-key")
+                    .value_name("pubkey")
+                    .required(false)
+                    .env("VERIFY_KEY")
+                    .validator(|key| parse_key(&key).map(|_| ()))
+                    .help("Verify the signature <link>.sig of every link, hex of the Ed25519 public key"),
+            ])
     }
 
     /// Create Settings Object.
     pub fn new() -> Settings {
-        let matches = get_matches();
+        load_dotenv();
+        from_matches(&app().get_matches())
+    }
 
+    /// Settings of the parsed arguments, exits with the error of clap
+    /// if a number or a port is invalid.
+    fn from_matches(matches: &ArgMatches) -> Settings {
         let file = matches.value_of("file").unwrap();
 
-        let max_threads: u8 = value_t!(matches, "max-threads", u8).unwrap_or(4);
+        let max_threads: u8 = if matches.is_present("max-threads") {
+            value_t!(matches, "max-threads", u8).unwrap_or_else(|e| e.exit())
+        } else {
+            default_max_threads()
+        };
+
+        let engine = match matches.value_of("engine") {
+            Some("threads") => Engine::Threads,
+            _ => Engine::Async,
+        };
+
+        let port = |name: &str| {
+            if matches.is_present(name) {
+                Some(value_t!(matches, name, u16).unwrap_or_else(|e| e.exit()))
+            } else {
+                None
+            }
+        };
+        let metrics_port = port("metrics-port");
+
+        let healthz_port = port("healthz-port");
+
+        let verify_key = matches
+            .value_of("verify-key")
+            .map(|key| parse_key(key).expect("validated by clap"));
 
         Settings {
             file: file.to_string(),
-            max_threads: max_threads,
+            max_threads,
+            engine,
+            metrics_port,
+            healthz_port,
+            verify_key,
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use std::sync::Mutex;
+
+        /// The tests changing the environment hold the lock: the environment
+        /// of the process is shared by the tests running in parallel.
+        static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+        #[test]
+        fn test_env_fallback() {
+            let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+            let file = env::temp_dir().join("test_env_fallback_links");
+            std::fs::write(&file, "https://www.google.com").unwrap();
+            env::set_var("LINKS_FILE", &file);
+            env::set_var("MAX_THREADS", "8");
+
+            let settings = from_matches(&app().get_matches_from(vec!["MyApp"]));
+            assert_eq!(file.to_str().unwrap(), settings.file);
+            assert_eq!(8, settings.max_threads);
+            assert_eq!(Engine::Async, settings.engine);
+
+            // The command line is above the environment.
+            let matches = app().get_matches_from(vec!["MyApp", "--max-threads", "2"]);
+            assert_eq!(2, from_matches(&matches).max_threads);
+
+            env::set_var("MAX_THREADS", "many");
+            let matches = app().get_matches_from(vec!["MyApp"]);
+            assert!(value_t!(matches, "max-threads", u8).is_err());
+
+            env::remove_var("MAX_THREADS");
+            let settings = from_matches(&app().get_matches_from(vec!["MyApp"]));
+            assert_eq!(
+                num_cpus::get().min(u8::MAX as usize) as u8,
+                settings.max_threads
+            );
+            assert!(settings.max_threads > 0);
+
+            env::remove_var("LINKS_FILE");
+            std::fs::remove_file(&file).unwrap();
+            assert!(app().get_matches_from_safe(vec!["MyApp"]).is_err());
         }
     }
 }
 
+/// ## Metrics of the downloads
+/// Both engines count the downloaded and the failed links and time every download.
+/// The downloader isn't ready while the last download is failed.
+mod download_metrics {
+    use healthz::HealthRegistry;
+    use metrics::{self, Counter, Histogram, Metrics, SECONDS_BUCKETS};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone)]
+    pub struct DownloadMetrics {
+        downloads: Counter,
+        errors: Counter,
+        seconds: Histogram,
+        last_failed: Arc<AtomicBool>,
+    }
+
+    impl DownloadMetrics {
+        /// Register the `download*` metrics in `metrics`.
+        pub fn new(metrics: &Metrics) -> metrics::Result<DownloadMetrics> {
+            Ok(DownloadMetrics {
+                downloads: metrics.counter("downloads_total", "Downloaded links.")?,
+                errors: metrics.counter("download_errors_total", "Links failed to download.")?,
+                seconds: metrics.histogram(
+                    "download_seconds",
+                    "Time of downloading and saving a link.",
+                    SECONDS_BUCKETS,
+                )?,
+                last_failed: Arc::new(AtomicBool::new(false)),
+            })
+        }
+
+        /// Add the `downloads` readiness check to `health`.
+        pub fn register_health(&self, health: &HealthRegistry) {
+            let last_failed = Arc::clone(&self.last_failed);
+            health.readiness("downloads", move || {
+                if last_failed.load(Ordering::SeqCst) {
+                    Err("the last download failed".to_string())
+                } else {
+                    Ok(())
+                }
+            });
+        }
+
+        /// Time `download` and count its result.
+        pub fn observe<T, E, F>(&self, download: F) -> Result<T, E>
+        where
+            F: FnOnce() -> Result<T, E>,
+        {
+            let timer = self.seconds.start_timer();
+            let result = download();
+            timer.observe_duration();
+            match result {
+                Ok(_) => self.downloads.inc(),
+                Err(_) => self.errors.inc(),
+            }
+            self.last_failed.store(result.is_err(), Ordering::SeqCst);
+            result
+        }
+    }
+}
+
+/// ## Signatures of the downloads
+/// With `--verify-key` every link `url` has the detached Ed25519 signature at `url.sig`,
+/// checked by the `cryptography` step. A page failed the check is deleted
+/// together with its signature and counted as a failed download.
+mod signatures {
+    use cryptography::encrypt_file::{signature_path, verify_detached, AppError};
+    use std::fs;
+    use std::path::Path;
+
+    /// URL of the signature of the link.
+    pub fn signature_url(url: &str) -> String {
+        format!("{}.sig", url)
+    }
+
+    /// Save the downloaded signature of the page at `path`.
+    pub fn save(path: &Path, sig: &[u8]) -> std::io::Result<()> {
+        fs::write(signature_path(path), sig)
+    }
+
+    /// Verify the page at `path` by its saved signature, delete both if it fails.
+    pub fn verify(path: &Path, key: &[u8]) -> Result<(), AppError> {
+        verify_detached(path, key).map_err(|e| {
+            let _ = fs::remove_file(path);
+            let _ = fs::remove_file(signature_path(path));
+            AppError::crypto(format!("{}: {}", path.display(), e))
+        })
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use cryptography::encrypt_file::{gen_fingerprint, SignatureAlgorithm};
+
+        #[test]
+        fn test_verify() {
+            let path = Path::new("test_signatures.html");
+            fs::write(path, "<html></html>").unwrap();
+            let (key, sig) =
+                gen_fingerprint(b"<html></html>", SignatureAlgorithm::Ed25519).unwrap();
+            save(path, &sig).unwrap();
+            assert!(verify(path, &key).is_ok());
+
+            let (other_key, _) =
+                gen_fingerprint(b"<html></html>", SignatureAlgorithm::Ed25519).unwrap();
+            assert!(verify(path, &other_key).is_err());
+            assert!(!path.exists());
+            assert!(!signature_path(path).exists());
+        }
+    }
+}
 
 /// ## Load link
 /// Read the list of links from `<file>` and concurrently load the contents of each link into a separate .html file (by reference)
+/// One `http_client` downloads all the links, reusing the connections to the same host.
 /// ### Examples
 ///
 /// Basic usage:
@@ -112,61 +392,226 @@ mod settings_args {
 ///
 ///   let settings: Settings = settings_args::new();
 ///
-///   load_html(settings.max_threads as usize, &settings.file);
+///   load_html(settings.max_threads as usize, &settings.file, None, &Metrics::new(), &HealthRegistry::new());
 ///
 mod load_html {
     use super::*;
+    use download_metrics::DownloadMetrics;
+
     /// Function a list of links and loads them in concurrently.
+    /// With `verify_key` the signatures of the links are downloaded and verified too.
     pub fn load_html(
         max_threads: usize,
         file_list: &str,
-    ) -> Result<(), Box<std::error::Error + 'static>> {
-        let mut runtime = Runtime::new().unwrap();
+        verify_key: Option<&[u8]>,
+        metrics: &Metrics,
+        health: &HealthRegistry,
+    ) -> Result<(), DownloadError> {
+        let metrics = DownloadMetrics::new(metrics)?;
+        metrics.register_health(health);
 
-        let mut https = hyper_tls::HttpsConnector::new(max_threads)?;
+        let mut runtime = Runtime::new().unwrap();
 
-        let client = Client::builder().build::<_, hyper::Body>(https);
+        let client = http_client::new(max_threads)?;
 
         let source: String = read_to_string(file_list)?;
 
         for (i, url) in source.lines().enumerate() {
-            let req = Request::builder().uri(url).body(Body::empty())?;
+            let result = metrics.observe(|| -> Result<(), DownloadError> {
+                let mut fetch = |url: &str| runtime.block_on(http_client::fetch(&client, url));
 
-            let response = runtime.block_on(client.request(req))?;
+                let path = save(i, &fetch(url)?)?;
 
-            let body = runtime.block_on(response.into_body().concat2())?;
+                if let Some(key) = verify_key {
+                    signatures::save(&path, &fetch(&signatures::signature_url(url))?)?;
+                    signatures::verify(&path, key)?;
+                }
 
-            if let Ok(mut file) = File::create(format!("file_{}.html", i)) {
-                file.write_all(&body);
-            }
+                Ok(())
+            });
+            result?;
         }
 
         Ok(())
     }
 
+    /// Write the page of the link number `i` into `file_{i}.html`.
+    fn save(i: usize, body: &[u8]) -> std::io::Result<PathBuf> {
+        let path = PathBuf::from(format!("file_{}.html", i));
+        File::create(&path)?.write_all(body)?;
+        Ok(path)
+    }
+
     #[cfg(test)]
     mod test {
         use super::*;
         #[test]
         fn test_load_html() {
-            std::fs::write("test_load_html", "https://www.google.com");
-            match load_html(4, "test_load_html") {
-                Ok(_) => {
-                    std::fs::remove_file("test_load_html");
-                    std::fs::remove_file("file_0.html");
-                    assert!(true);
-                }
-                Err(_) => assert!(false),
+            std::fs::write("test_load_html", "https://www.google.com").unwrap();
+            load_html(
+                4,
+                "test_load_html",
+                None,
+                &Metrics::new(),
+                &HealthRegistry::new(),
+            )
+            .unwrap();
+            std::fs::remove_file("test_load_html").unwrap();
+            std::fs::remove_file("file_0.html").unwrap();
+        }
+    }
+}
+
+/// ## Load links on the thread pool
+/// The same as `load_html`, but every link is a blocking job of the `mythread::ThreadPool`
+/// with `max_threads` workers, so up to `max_threads` pages are downloaded at once.
+/// The jobs share one `http_client` on one runtime, the connections outlive the jobs.
+///
+/// ### Examples
+///
+/// ```rust
+///
+///   use load_threads::load_threads;
+///
+///   load_threads(settings.max_threads as usize, &settings.file, None, &Metrics::new(), &HealthRegistry::new());
+/// ```
+mod load_threads {
+    use super::*;
+    use download_metrics::DownloadMetrics;
+    use futures::sync::oneshot;
+    use futures::Future;
+    use futures_and_async_io::http_client::HttpClient;
+    use mythread::ThreadPoolBuilder;
+    use tokio::runtime::TaskExecutor;
+
+    /// Function a list of links and loads them on the thread pool.
+    /// With `verify_key` the signatures of the links are downloaded and verified too.
+    pub fn load_threads(
+        max_threads: usize,
+        file_list: &str,
+        verify_key: Option<&[u8]>,
+        metrics: &Metrics,
+        health: &HealthRegistry,
+    ) -> Result<(), DownloadError> {
+        let source: String = read_to_string(file_list)?;
+
+        let download_metrics = DownloadMetrics::new(metrics)?;
+        download_metrics.register_health(health);
+
+        // Declared before the pool: the pool is dropped first and finishes the jobs
+        // while the runtime of their requests still runs.
+        let runtime = Runtime::new()?;
+        let client = http_client::new(max_threads.max(1))?;
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(max_threads.max(1))
+            .metrics(metrics.clone())
+            .build()?;
+
+        let handles: Vec<_> = source
+            .lines()
+            .map(str::to_string)
+            .enumerate()
+            .map(|(i, url)| {
+                let metrics = download_metrics.clone();
+                let verify_key = verify_key.map(<[u8]>::to_vec);
+                let client = client.clone();
+                let executor = runtime.executor();
+                pool.execute(move || {
+                    metrics.observe(|| download(&client, &executor, i, &url, verify_key.as_deref()))
+                })
+                .expect("the pool blocks on the full queue instead of rejecting")
+            })
+            .collect();
+
+        // On the first error the drop of the pool still finishes the other downloads.
+        for handle in handles {
+            match handle.wait() {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => return Err(e),
+                Err(e) => return Err(Box::new(e)),
             }
         }
+
+        Ok(())
+    }
+
+    /// Blocking download of the link number `i`: the current thread waits
+    /// for the request running on the shared runtime of `executor`.
+    fn download(
+        client: &HttpClient,
+        executor: &TaskExecutor,
+        i: usize,
+        url: &str,
+        verify_key: Option<&[u8]>,
+    ) -> Result<(), DownloadError> {
+        let fetch = |url: &str| oneshot::spawn(http_client::fetch(client, url), executor).wait();
+
+        let path = PathBuf::from(format!("file_{}.html", i));
+        File::create(&path)?.write_all(&fetch(url)?)?;
+
+        if let Some(key) = verify_key {
+            signatures::save(&path, &fetch(&signatures::signature_url(url))?)?;
+            signatures::verify(&path, key)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        #[test]
+        fn test_load_threads_bad_url() {
+            std::fs::write("test_load_threads", "not a url").unwrap();
+            let metrics = Metrics::new();
+            let health = HealthRegistry::new();
+            let result = load_threads(2, "test_load_threads", None, &metrics, &health);
+            std::fs::remove_file("test_load_threads").unwrap();
+            assert!(result.is_err());
+            assert!(!health.check(healthz::Probe::Readiness).is_ok());
+
+            let text = String::from_utf8(metrics.encode().0).unwrap();
+            assert!(text.contains("download_errors_total 1"));
+            assert!(text.contains("thread_pool_jobs_completed_total 1"));
+        }
     }
 }
 
 fn main() {
     use load_html::load_html;
+    use load_threads::load_threads;
     use settings_args::*;
 
     let settings: Settings = settings_args::new();
 
-    load_html(settings.max_threads as usize, &settings.file);
+    let metrics = Metrics::new();
+    if let Some(port) = settings.metrics_port {
+        let addr = ([0, 0, 0, 0], port).into();
+        if let Err(e) = metrics::spawn_exporter(&addr, metrics.clone()) {
+            eprintln!("Metrics on port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    }
+
+    let health = HealthRegistry::new();
+    if let Some(port) = settings.healthz_port {
+        let addr = ([0, 0, 0, 0], port).into();
+        if let Err(e) = healthz::spawn_server(&addr, health.clone()) {
+            eprintln!("Health checks on port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    }
+
+    let max_threads = settings.max_threads as usize;
+    let verify_key = settings.verify_key.as_deref();
+    let result = match settings.engine {
+        Engine::Async => load_html(max_threads, &settings.file, verify_key, &metrics, &health),
+        Engine::Threads => load_threads(max_threads, &settings.file, verify_key, &metrics, &health),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,198 @@
+use std::collections::{BTreeSet, HashMap};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use crate::TaskEntry;
+
+/// Приоритет задачи: задачи с высоким приоритетом опрашиваются первыми
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    fn index(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+/// Сколько раз подряд непустую корзину можно обойти ради более приоритетных задач,
+/// после этого из нее берется задача вне очереди (защита от голодания)
+pub const STARVATION_LIMIT: usize = 8;
+
+/// Готовые к выполнению задачи, разложенные по корзинам приоритетов
+#[derive(Debug, Default)]
+pub struct Ready {
+    buckets: [BTreeSet<usize>; 3], // id задач, в корзине сначала старые задачи
+    passed: [usize; 3],            // сколько раз подряд корзину обошли
+}
+
+impl Ready {
+    /// Добавляет задачу в корзину ее приоритета, повторное добавление ничего не делает
+    pub fn push(&mut self, id: usize, priority: Priority) {
+        self.buckets[priority.index()].insert(id);
+    }
+
+    /// Следующая задача для опроса: из самой приоритетной непустой корзины,
+    /// или из корзины, которую обошли `STARVATION_LIMIT` раз
+    pub fn pop(&mut self) -> Option<usize> {
+        let priority = Priority::ALL
+            .iter()
+            .rev()
+            .find(|p| {
+                self.passed[p.index()] >= STARVATION_LIMIT && !self.buckets[p.index()].is_empty()
+            })
+            .or_else(|| {
+                Priority::ALL
+                    .iter()
+                    .find(|p| !self.buckets[p.index()].is_empty())
+            })?
+            .index();
+
+        for lower in priority + 1..self.buckets.len() {
+            if !self.buckets[lower].is_empty() {
+                self.passed[lower] += 1;
+            }
+        }
+        self.passed[priority] = 0;
+
+        let id = *self.buckets[priority].iter().next()?;
+        self.buckets[priority].remove(&id);
+        Some(id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(|bucket| bucket.is_empty())
+    }
+}
+
+/// Опрос дольше этого считается блокирующим исполнитель, если порог не задан
+pub const SLOW_POLL: Duration = Duration::from_millis(10);
+
+/// Статистика опросов задачи
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PollStats {
+    pub polls: u64,      // сколько раз задачу опрашивали
+    pub total: Duration, // суммарное время опросов
+    pub max: Duration,   // самый долгий опрос
+}
+
+impl PollStats {
+    fn record(&mut self, elapsed: Duration) {
+        self.polls += 1;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// Состояние исполнителя
+pub struct ExecState {
+    pub next_id: usize,                       // id для следующей задачи
+    pub tasks: HashMap<usize, TaskEntry>,     // все незавершенные задачи
+    pub priorities: HashMap<usize, Priority>, // приоритеты незавершенных задач
+    pub ready: Ready,                         // готовые к выполнению задачи
+    pub thread: Thread,                       // поток исполнителя, который будим
+    pub stats: HashMap<usize, PollStats>,     // опросы незавершенных задач
+    pub slow_poll: Duration,                  // порог долгого опроса
+}
+
+impl Default for ExecState {
+    fn default() -> Self {
+        ExecState {
+            next_id: 0,
+            tasks: HashMap::new(),
+            priorities: HashMap::new(),
+            ready: Ready::default(),
+            thread: thread::current(),
+            stats: HashMap::new(),
+            slow_poll: SLOW_POLL,
+        }
+    }
+}
+
+impl ExecState {
+    /// Пробуждение задачи: она попадает в корзину своего приоритета,
+    /// а поток исполнителя выходит из `park`
+    pub fn wake_task(&mut self, id: usize) {
+        let priority = self.priorities.get(&id).cloned().unwrap_or_default();
+        self.ready.push(id, priority);
+        self.thread.unpark();
+    }
+
+    /// Учитывает опрос задачи длительностью `elapsed`; опрос дольше `slow_poll`
+    /// пишется в лог: такая задача блокирует исполнитель и задерживает остальные
+    pub fn record_poll(&mut self, id: usize, elapsed: Duration) {
+        self.stats.entry(id).or_default().record(elapsed);
+        if elapsed > self.slow_poll {
+            eprintln!(
+                "задача {} опрашивалась {:?} (порог {:?}): она блокирует исполнитель",
+                id, elapsed, self.slow_poll
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_priority_order() {
+        let mut ready = Ready::default();
+        ready.push(3, Priority::Low);
+        ready.push(2, Priority::Normal);
+        ready.push(1, Priority::High);
+        ready.push(0, Priority::High);
+        ready.push(1, Priority::High);
+
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            (0..4).filter_map(|_| ready.pop()).collect::<Vec<_>>()
+        );
+        assert!(ready.is_empty());
+        assert_eq!(None, ready.pop());
+    }
+
+    #[test]
+    fn test_starvation() {
+        let mut ready = Ready::default();
+        ready.push(100, Priority::Low);
+
+        // Задача с высоким приоритетом все время просыпается снова.
+        let mut polled = Vec::new();
+        for _ in 0..STARVATION_LIMIT + 1 {
+            ready.push(1, Priority::High);
+            polled.push(ready.pop().unwrap());
+        }
+        assert_eq!(Some(&100), polled.last());
+        assert_eq!(Some(1), ready.pop());
+    }
+
+    #[test]
+    fn test_record_poll() {
+        let mut state = ExecState::default();
+        state.record_poll(1, Duration::from_millis(3));
+        state.record_poll(1, Duration::from_millis(5));
+        state.record_poll(2, Duration::from_millis(20));
+
+        assert_eq!(
+            PollStats {
+                polls: 2,
+                total: Duration::from_millis(8),
+                max: Duration::from_millis(5),
+            },
+            state.stats[&1]
+        );
+        assert_eq!(1, state.stats[&2].polls);
+        assert!(state.stats.get(&3).is_none());
+    }
+}
@@ -1,7 +1,6 @@
-use std::collections::{HashMap, HashSet,BTreeMap};
-use std::mem;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::{mpsc,Arc, Mutex, MutexGuard};
-use std::thread::{self, Thread};
+use std::thread;
 use std::time::{Duration, Instant};
 // Что делает синтаксис async / await
 // С обычными фьючерсами вы должны написать функции обратного вызова для обработки результатов, когда они станут доступны.
@@ -17,6 +16,7 @@ use std::time::{Duration, Instant};
 // enum Async<T>           - Тип ответа задачи
 // trait Wake: Send + Sync - Типаж Пробудитель задачи в поток
 // struct ExecState        - Состояние задачи
+// enum Priority           - Приоритет задачи
 // struct ToyExec          - Исполнитель
 // struct TaskEntry        - Задача
 
@@ -25,7 +25,7 @@ pub mod exec;
 pub mod toy;
 pub mod wake;
 
-use exec::ExecState;
+use exec::{ExecState, PollStats, Priority};
 use wake::{Waker,ToyWake};
 use toy::ToyTask;
 
@@ -42,16 +42,14 @@ pub struct ToyExec {
     pub state: Arc<Mutex<ExecState>>,
 }
 
+/// Самый долгий сон исполнителя без пробуждений
+pub const MAX_PARK: Duration = Duration::from_millis(100);
+
 // несколько шаблонов для создания и работы с исполнителем
 impl ToyExec {
     pub fn new() -> Self {
         ToyExec {
-            state: Arc::new(Mutex::new(ExecState {
-                next_id: 0,
-                tasks: HashMap::new(),
-                ready: HashSet::new(),
-                thread: thread::current(),
-            })),
+            state: Arc::new(Mutex::new(ExecState::default())),
         }
     }
 
@@ -62,35 +60,75 @@ impl ToyExec {
 
     /// Основной цикл задачи в исполнителе
     /// Для простоты никогда не выходит, он просто постоянно запускает все нерешенные задачи до завершения
-    pub fn run(&self) {
+    pub fn run(&self, timer: &ToyTimer) {
         loop {
-            //Каждый раз мы собираем полный набор готовых к выполнению идентификаторов задач:
-            let mut ready = mem::replace(&mut self.state_mut().ready, HashSet::new());
-            //  mem::replace(dest: &mut T, src: T) -> T  Перемещается src в ссылку dest, возвращая предыдущее dest значение.
-            // replace позволяет потреблять поле структуры, заменяя его другим значением.
-
-            // Теперь попробуйте «выполнить» каждую изначально готовых задач:
-            for id in ready.drain() {
-                // drain() - Очищает набор, возвращая все элементы в итераторе
-                // Мы берем  полное право собственности на эту задачу; если он будет завершен, он будет опущен.
-                let entry = self.state_mut().tasks.remove(&id);
-                if let Some(mut entry) = entry {
-                    if let Async::Pending = entry.task.poll(&entry.wake) {
-                        // Задача не завершена, поэтому верните ее в таблицу.
-                        self.state_mut().tasks.insert(id, entry);
-                    }
+            self.run_once(timer);
+        }
+    }
+
+    /// Один проход цикла: опрашивает все готовые задачи и засыпает до пробуждения,
+    /// но не дольше, чем до ближайшего срока таймера `timer`
+    pub fn run_once(&self, timer: &ToyTimer) {
+        // Будить нужно поток, который выполняет задачи, а не тот, который создал исполнителя.
+        self.state_mut().thread = thread::current();
+        // Каждый раз берем следующую готовую задачу: сначала с высоким приоритетом,
+        // но задачи из обойденных много раз корзин берутся вне очереди (см. `Ready::pop`).
+        // Задачи, проснувшиеся во время опроса, учитываются сразу по своему приоритету.
+        // Не `while let`: блокировка `state_mut()` держалась бы до конца тела цикла.
+        loop {
+            let id = match self.state_mut().ready.pop() {
+                Some(id) => id,
+                None => break,
+            };
+            // Мы берем  полное право собственности на эту задачу; если он будет завершен, он будет опущен.
+            let entry = self.state_mut().tasks.remove(&id);
+            if let Some(mut entry) = entry {
+                // Время каждого опроса учитывается в `ExecState::stats`
+                let started = Instant::now();
+                let result = entry.task.poll(&entry.wake);
+                self.state_mut().record_poll(id, started.elapsed());
+                if let Async::Pending = result {
+                    // Задача не завершена, поэтому верните ее в таблицу.
+                    self.state_mut().tasks.insert(id, entry);
+                } else {
+                    // Задача завершена: ее приоритет и статистика больше не нужны.
+                    let mut state = self.state_mut();
+                    state.priorities.remove(&id);
+                    state.stats.remove(&id);
                 }
             }
-
-            // Мы обработали всю работу, которую мы приобрели при входе; блокировать до тех пор, пока не будет доступна дополнительная работа
-            // Если новая работа стала доступна после моментального снимка `ready`, это будет no-op.
-            thread::park(); // Блокирует, если или пока токен текущего потока не будет доступен.
         }
+
+        // Мы обработали всю работу, которую мы приобрели при входе; блокировать до тех пор, пока не будет доступна дополнительная работа
+        // Если пробуждение разминулось с `park`, поток все равно проснется к ближайшему сроку таймера
+        // или через `MAX_PARK`.
+        let timeout = timer
+            .next_deadline()
+            .map(|at| at.saturating_duration_since(Instant::now()))
+            .map_or(MAX_PARK, |left| left.min(MAX_PARK));
+        thread::park_timeout(timeout); // Блокирует, пока токен текущего потока не будет доступен или не пройдет timeout.
+    }
+
+    /// Порог долгого опроса задачи, по умолчанию `exec::SLOW_POLL`
+    pub fn set_slow_poll(&self, threshold: Duration) {
+        self.state_mut().slow_poll = threshold;
+    }
+
+    /// Статистика опросов задачи `id`, пока она не завершена
+    pub fn poll_stats(&self, id: usize) -> Option<PollStats> {
+        self.state_mut().stats.get(&id).cloned()
     }
 
     // Остальные части являются простыми. spawn Метод отвечает за пакаджа задачу в TaskEntry и установить его:
     // И с этим мы создали планировщик задач!
     fn spawn<T>(&self, task: T) where T: ToyTask + Send + 'static,
+    {
+        self.spawn_with_priority(task, Priority::Normal)
+    }
+
+    /// Задача с приоритетом `priority`: готовые задачи с высоким приоритетом
+    /// опрашиваются раньше остальных
+    fn spawn_with_priority<T>(&self, task: T, priority: Priority) where T: ToyTask + Send + 'static,
     {
         // Заполняем ExecState
 
@@ -108,6 +146,7 @@ impl ToyExec {
             task: Box::new(task),
         };
         state.tasks.insert(id, entry);
+        state.priorities.insert(id, priority);
 
         // Недавно добавленная задача считается сразу готовой к запуску,
         // которая вызовет последующий вызов `park`, чтобы сразу
@@ -129,6 +168,7 @@ pub struct Registration {
 pub struct Worker {
     pub rx: mpsc::Receiver<Registration>,
     pub active: BTreeMap<Instant, Waker>,
+    pub deadlines: Arc<Mutex<BTreeSet<Instant>>>, // сроки, которые еще не наступили
 }
 
 
@@ -136,25 +176,34 @@ pub struct Worker {
 #[derive(Clone)]
 pub struct ToyTimer {
     pub tx: mpsc::Sender<Registration>,
+    pub deadlines: Arc<Mutex<BTreeSet<Instant>>>, // общие с Worker
 }
 
 impl ToyTimer {
     fn new() -> ToyTimer {
         // создаем связанные каналом два объекта, отправителя и получателя
         let (tx, rx) = mpsc::channel();// создание канала
+        let deadlines = Arc::new(Mutex::new(BTreeSet::new()));
         //создание получателя(любого)
         let worker = Worker {
             rx,
             active: BTreeMap::new(),
+            deadlines: deadlines.clone(),
         };
         thread::spawn(|| worker.work());//получателя запускаем в отдельном потоке
-        ToyTimer { tx }// отдаем отправителя
+        ToyTimer { tx, deadlines }// отдаем отправителя
     }
 
     // Register a new wakeup with this timer
     fn register(&self, at: Instant, wake: Waker) {
+        self.deadlines.lock().unwrap().insert(at);
         self.tx.send(Registration { at, wake }).unwrap();
     }
+
+    /// Ближайший срок, на который зарегистрировано пробуждение
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.lock().unwrap().iter().next().cloned()
+    }
 }
 
 impl Worker {
@@ -167,6 +216,7 @@ impl Worker {
     }
 
     fn fire(&mut self, key: Instant) {
+        self.deadlines.lock().unwrap().remove(&key);
         self.active.remove(&key).unwrap().wake();
     }
 
@@ -207,6 +257,165 @@ fn main() {
             timer.clone(),
         ));
     }
+    // самая частая задача опрашивается раньше остальных
+    exec.spawn_with_priority(
+        Periodic::new(10, Duration::from_millis(100), timer.clone()),
+        Priority::High,
+    );
+
+    exec.run(&timer)
+}
 
-    exec.run()
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Задача, которая завершается в момент `at`
+    struct Sleep {
+        at: Instant,
+        timer: ToyTimer,
+        registered: bool,
+    }
+
+    impl ToyTask for Sleep {
+        fn poll(&mut self, wake: &Waker) -> Async<()> {
+            if Instant::now() >= self.at {
+                return Async::Ready(());
+            }
+            if !self.registered {
+                self.timer.register(self.at, wake.clone());
+                self.registered = true;
+            }
+            Async::Pending
+        }
+    }
+
+    #[test]
+    fn test_next_deadline() {
+        let timer = ToyTimer::new();
+        assert_eq!(None, timer.next_deadline());
+        let exec = ToyExec::new();
+        let at = Instant::now() + Duration::from_millis(20);
+        exec.spawn(Sleep { at, timer: timer.clone(), registered: false });
+        exec.run_once(&timer);
+        assert_eq!(Some(at), timer.next_deadline());
+    }
+
+    #[test]
+    fn test_run_in_other_thread() {
+        // Исполнитель создан в одном потоке, а выполняется в другом:
+        // раньше таймер будил поток, создавший исполнителя, и `park` не возвращался.
+        let timer = ToyTimer::new();
+        let exec = ToyExec::new();
+        exec.spawn(Sleep {
+            at: Instant::now() + Duration::from_millis(50),
+            timer: timer.clone(),
+            registered: false,
+        });
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            while !exec.state_mut().tasks.is_empty() {
+                exec.run_once(&timer);
+            }
+            tx.send(()).unwrap();
+        });
+        assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+
+    /// Задача, которая блокирует поток при каждом опросе и завершается на втором
+    struct Blocking {
+        sleep: Duration,
+        polled: bool,
+    }
+
+    impl ToyTask for Blocking {
+        fn poll(&mut self, _wake: &Waker) -> Async<()> {
+            thread::sleep(self.sleep);
+            if self.polled {
+                return Async::Ready(());
+            }
+            self.polled = true;
+            Async::Pending
+        }
+    }
+
+    #[test]
+    fn test_poll_stats() {
+        let timer = ToyTimer::new();
+        let exec = ToyExec::new();
+        exec.set_slow_poll(Duration::from_millis(5));
+        exec.spawn(Blocking {
+            sleep: Duration::from_millis(20),
+            polled: false,
+        });
+        assert_eq!(None, exec.poll_stats(0));
+
+        exec.run_once(&timer);
+        let stats = exec.poll_stats(0).unwrap();
+        assert_eq!(1, stats.polls);
+        assert!(stats.max >= Duration::from_millis(20));
+        assert_eq!(stats.max, stats.total);
+
+        // Статистика завершенной задачи удаляется вместе с ней.
+        exec.state_mut().wake_task(0);
+        exec.run_once(&timer);
+        assert!(exec.state_mut().tasks.is_empty());
+        assert_eq!(None, exec.poll_stats(0));
+    }
+
+    /// Задача, которая при первом опросе отдает свой `Waker` в канал `wakers`,
+    /// а при втором завершается и сообщает об этом в канал `done`
+    struct WakeFromOtherThread {
+        wakers: mpsc::Sender<Waker>,
+        done: mpsc::Sender<()>,
+        polled: bool,
+    }
+
+    impl ToyTask for WakeFromOtherThread {
+        fn poll(&mut self, wake: &Waker) -> Async<()> {
+            if self.polled {
+                self.done.send(()).unwrap();
+                return Async::Ready(());
+            }
+            self.polled = true;
+            self.wakers.send(wake.clone()).unwrap();
+            Async::Pending
+        }
+    }
+
+    #[test]
+    fn test_wake_before_park() {
+        // Пробуждение из другого потока приходит после опроса задач, но до `park`:
+        // исполнитель не должен его потерять и спать до срока таймера или `MAX_PARK`.
+        let timer = ToyTimer::new();
+        let exec = ToyExec::new();
+        let (wakers, waker) = mpsc::channel();
+        let (done, finished) = mpsc::channel();
+        exec.spawn(WakeFromOtherThread {
+            wakers,
+            done,
+            polled: false,
+        });
+
+        // Пока сроки таймера заблокированы, исполнитель стоит в `next_deadline`,
+        // то есть уже опросил задачи, но еще не вызвал `park`.
+        let deadlines = timer.deadlines.clone();
+        let window = deadlines.lock().unwrap();
+        thread::spawn(move || {
+            while !exec.state_mut().tasks.is_empty() {
+                exec.run_once(&timer);
+            }
+        });
+
+        let waker = waker.recv_timeout(Duration::from_secs(5)).unwrap();
+        // Исполнитель доходит до `next_deadline` после опроса задачи.
+        thread::sleep(Duration::from_millis(20));
+        waker.wake();
+        drop(window);
+
+        // Потерянное пробуждение задержало бы второй опрос на `MAX_PARK`,
+        // а без `MAX_PARK` тест повис бы: ждем с таймаутом.
+        assert!(finished.recv_timeout(MAX_PARK / 2).is_ok());
+    }
 }
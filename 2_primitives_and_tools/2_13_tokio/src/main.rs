@@ -1,38 +1,38 @@
-
-
-#[macro_use(values_t,value_t,crate_version,crate_authors)]
+#[macro_use(value_t, crate_version, crate_authors)]
 extern crate clap;
-extern crate handlebars;
+extern crate futures;
+extern crate hyper;
+extern crate hyper_tls;
+extern crate indicatif;
+extern crate native_tls;
+extern crate serde;
 #[macro_use]
+extern crate serde_derive;
 extern crate serde_json;
-extern crate env_logger;
-extern crate serde;
-extern crate crossbeam;
-
-use clap::{App, Arg,ArgMatches};
-use handlebars::Handlebars;
-use std::collections::btree_map::BTreeMap;
+extern crate tokio;
+extern crate tokio_sync;
+
+use clap::{App, Arg, ArgMatches};
+use futures::future::{self, Either};
+use futures::{Async, Future, Stream};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Uri};
+use hyper_tls::HttpsConnector;
 use std::error::Error;
 use std::path::Path;
-
-use std::{env, io};
-//use std::borrow::Cow;
-
-use std::fs::File;
-use std::io::Read;
-use std::thread;
+use tokio::runtime::Runtime;
 
 use load_files::*;
 
-
-
-mod load_files{
+mod load_files {
     use super::*;
 
     #[derive(Debug)]
     pub struct Settings {
         pub max_threads: u8,
         pub file: String,
+        pub progress: bool,
+        pub report: Option<String>,
     }
 
     fn has_file(file: String) -> Result<(), String> {
@@ -42,9 +42,8 @@ mod load_files{
         Err(String::from("The file notfound"))
     }
     fn get_matches<'a>() -> ArgMatches<'a> {
-
         App::new("Load files CLI")
-            .usage("MyApp [--max-threads = <number>] <file>")
+            .usage("MyApp [--max-threads = <number>] [--progress] [--report <file>] <file>")
             .bin_name("MyApp")
             .version(crate_version!())
             .author(crate_authors!())
@@ -59,77 +58,673 @@ mod load_files{
                     .value_name("number")
                     .required(false)
                     .help("thread number"),
+                Arg::with_name("progress")
+                    .long("progress")
+                    .required(false)
+                    .help("Show progress bars of the active downloads"),
+                Arg::with_name("report")
+                    .long("report")
+                    .value_name("file")
+                    .required(false)
+                    .help("Write the JSON report of the downloads into the file"),
             ])
             .get_matches()
     }
 
-
-    pub fn new() ->   Settings {
-
+    pub fn new() -> Settings {
         let matches = get_matches();
 
         let file = matches.value_of("file").unwrap_or("download");
 
-        let max_threads:u8 = value_t!(matches, "max-threads", u8).unwrap_or(4);
-
-        Settings{file:file.to_string() ,max_threads:max_threads}
+        let max_threads: u8 = value_t!(matches, "max-threads", u8).unwrap_or(4);
 
+        Settings {
+            file: file.to_string(),
+            max_threads,
+            progress: matches.is_present("progress"),
+            report: matches.value_of("report").map(String::from),
+        }
     }
 }
 
-#[derive(Debug)]
-struct Task{
-    url:String
-}
-impl Task{
-    fn new(url:String)->Self{
-        Task{url}
+/// # Progress of the downloads
+///
+/// The async tasks send `Event`s through a channel, a separate thread
+/// renders one bar per active task and the aggregate bar of finished tasks.
+mod progress {
+    use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+    use std::collections::HashMap;
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::thread::{self, JoinHandle};
+
+    #[derive(Debug)]
+    pub enum Event {
+        /// The response is received, `total` is its Content-Length if known.
+        Started { index: usize, total: Option<u64> },
+        /// Another chunk of the body is received.
+        Advanced { index: usize, bytes: u64 },
+        /// The task is finished, successfully or not.
+        Finished { index: usize },
+    }
+
+    /// Sending side of the progress channel, does nothing when progress is disabled.
+    #[derive(Clone)]
+    pub struct Progress(Option<Sender<Event>>);
+
+    impl Progress {
+        pub fn disabled() -> Self {
+            Progress(None)
+        }
+
+        /// Start the rendering thread for the tasks with given labels.
+        /// The thread finishes when every `Progress` clone is dropped.
+        pub fn spawn(labels: Vec<String>) -> (Self, JoinHandle<()>) {
+            let (sender, receiver) = mpsc::channel();
+            let handle = thread::spawn(move || render(&labels, receiver));
+            (Progress(Some(sender)), handle)
+        }
+
+        pub fn send(&self, event: Event) {
+            if let Some(ref sender) = self.0 {
+                // The renderer may be gone already, progress is best effort.
+                sender.send(event).ok();
+            }
+        }
+    }
+
+    fn render(labels: &[String], events: Receiver<Event>) {
+        let multi = MultiProgress::new();
+        let aggregate = multi.add(ProgressBar::new(labels.len() as u64));
+        aggregate.set_style(
+            ProgressStyle::default_bar()
+                .template("{prefix:>8} [{bar:40}] {pos}/{len} files")
+                .unwrap(),
+        );
+        aggregate.set_prefix("total");
+
+        let mut bars: HashMap<usize, ProgressBar> = HashMap::new();
+        for event in events {
+            match event {
+                Event::Started { index, total } => {
+                    let bar = multi.insert_before(&aggregate, ProgressBar::new(total.unwrap_or(0)));
+                    let style = match total {
+                        Some(_) => ProgressStyle::default_bar()
+                            .template("{prefix:>8} [{bar:40}] {bytes}/{total_bytes} {msg}"),
+                        None => ProgressStyle::default_spinner()
+                            .template("{prefix:>8} {spinner} {bytes} {msg}"),
+                    };
+                    bar.set_style(style.unwrap());
+                    bar.set_prefix(format!("#{}", index));
+                    bar.set_message(labels[index].clone());
+                    bars.insert(index, bar);
+                }
+                Event::Advanced { index, bytes } => {
+                    if let Some(bar) = bars.get(&index) {
+                        bar.inc(bytes);
+                    }
+                }
+                Event::Finished { index } => {
+                    if let Some(bar) = bars.remove(&index) {
+                        bar.finish_and_clear();
+                        multi.remove(&bar);
+                    }
+                    aggregate.inc(1);
+                }
+            }
+        }
+        aggregate.finish();
     }
 }
 
+/// # Download pipeline
+///
+/// Every link of the file becomes a `Task`. Tasks are downloaded on the tokio runtime,
+/// a semaphore lets no more than `max_threads` requests be in flight at the same time,
+/// the body of each link is written into its own `.html` file prefixed with its index.
+/// The status of every task is updated as the pipeline progresses
+/// and the status table is printed whenever it changes,
+/// or the progress bars are shown instead when `--progress` is set.
+mod download {
+    use super::*;
+    use hyper::header::CONTENT_LENGTH;
+    use hyper::http::uri::InvalidUri;
+    use progress::{Event, Progress};
+    use std::fmt;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use tokio::fs::File;
+    use tokio::timer::Interval;
+    use tokio_sync::semaphore::{Permit, Semaphore};
+
+    pub type HttpsClient = Client<HttpsConnector<HttpConnector>>;
+
+    /// Tasks shared between the pipeline and the status reporter.
+    type Tasks = Arc<Mutex<Vec<Task>>>;
+
+    /// How often the status table is checked for changes.
+    const REPORT_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Reason of a failed `Task`.
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    #[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+    pub enum DownloadError {
+        InvalidUrl(String),
+        /// The host name could not be resolved.
+        Dns(String),
+        /// The TLS handshake failed.
+        Tls(String),
+        /// The TCP connection could not be established.
+        Connect(String),
+        /// The server answered with the non-success status code.
+        HttpStatus(u16),
+        /// The request or the response body failed.
+        Http(String),
+        /// The file could not be written.
+        Io(String),
+    }
+
+    impl fmt::Display for DownloadError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                DownloadError::InvalidUrl(err) => write!(f, "invalid url: {}", err),
+                DownloadError::Dns(err) => write!(f, "dns: {}", err),
+                DownloadError::Tls(err) => write!(f, "tls: {}", err),
+                DownloadError::Connect(err) => write!(f, "connect: {}", err),
+                DownloadError::HttpStatus(code) => write!(f, "HTTP status {}", code),
+                DownloadError::Http(err) => write!(f, "http: {}", err),
+                DownloadError::Io(err) => write!(f, "io: {}", err),
+            }
+        }
+    }
+
+    impl From<InvalidUri> for DownloadError {
+        fn from(err: InvalidUri) -> Self {
+            DownloadError::InvalidUrl(err.to_string())
+        }
+    }
+
+    impl From<io::Error> for DownloadError {
+        fn from(err: io::Error) -> Self {
+            DownloadError::Io(err.to_string())
+        }
+    }
+
+    /// The connector reports every failure as `io::Error`:
+    /// TLS errors are wrapped into it, DNS errors come from `to_socket_addrs`.
+    impl From<hyper::Error> for DownloadError {
+        fn from(err: hyper::Error) -> Self {
+            let cause = err
+                .source()
+                .and_then(|cause| cause.downcast_ref::<io::Error>());
+            match cause {
+                Some(cause) => {
+                    let inner = cause.get_ref();
+                    if let Some(tls) = inner.and_then(|e| e.downcast_ref::<native_tls::Error>()) {
+                        DownloadError::Tls(tls.to_string())
+                    } else if cause.to_string().contains("lookup address") {
+                        DownloadError::Dns(cause.to_string())
+                    } else if err.is_connect() {
+                        DownloadError::Connect(cause.to_string())
+                    } else {
+                        DownloadError::Http(err.to_string())
+                    }
+                }
+                None => DownloadError::Http(err.to_string()),
+            }
+        }
+    }
+
+    /// Progress of a `Task`.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Status {
+        Pending,
+        Running,
+        /// Number of the written bytes.
+        Done(usize),
+        Failed(DownloadError),
+    }
 
-fn main() -> Result<(), Box<std::error::Error + 'static>>{
+    impl fmt::Display for Status {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Status::Pending => write!(f, "pending"),
+                Status::Running => write!(f, "running"),
+                Status::Done(bytes) => write!(f, "done ({} bytes)", bytes),
+                Status::Failed(err) => write!(f, "failed: {}", err),
+            }
+        }
+    }
 
-    let settings:Settings = load_files::new();
+    #[derive(Debug, Clone)]
+    pub struct Task {
+        pub url: String,
+        pub file: String,
+        pub status: Status,
+        /// Time from the start of the download to its end.
+        pub duration: Option<Duration>,
+    }
 
-    println!("{:?} {:?}",
-             settings.file,
-             settings.max_threads);
+    impl Task {
+        /// Task of the link on the line `index`, its file is `{index}_{file_name(url)}`,
+        /// so the links with the same name don't overwrite each other.
+        pub fn new(index: usize, url: String) -> Self {
+            let file = format!("{}_{}", index, file_name(&url));
+            Task {
+                url,
+                file,
+                status: Status::Pending,
+                duration: None,
+            }
+        }
 
+        /// Whether the task has finished, successfully or not.
+        pub fn is_finished(&self) -> bool {
+            match self.status {
+                Status::Done(_) | Status::Failed(_) => true,
+                Status::Pending | Status::Running => false,
+            }
+        }
+    }
 
+    /// Name of the file for the link: `https://habr.com/post/1/` -> `habr_com_post_1.html`.
+    pub fn file_name(url: &str) -> String {
+        let name: String = url
+            .splitn(2, "://")
+            .last()
+            .unwrap_or("")
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!("{}.html", name.trim_matches('_'))
+    }
 
-    let s:String = std::fs::read_to_string(settings.file)?;
-    let mut v:Vec<Task> = vec![];
-    for url in s.lines(){
-        v.push(Task::new(url.to_string()));
-        let url_ = url.clone();
+    /// Read the list of links, one per line, empty lines are skipped.
+    pub fn read_tasks(file: &str) -> std::io::Result<Vec<Task>> {
+        let source = std::fs::read_to_string(file)?;
+        Ok(source
+            .lines()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .enumerate()
+            .map(|(index, url)| Task::new(index, url.to_string()))
+            .collect())
+    }
 
-        crossbeam::scope(|scope_| {
-                  scope_.spawn(move ||{
-                    // load url and create file number thread
-                    println!("{}",url_);
+    /// Render the status table of the tasks.
+    pub fn status_table(tasks: &[Task]) -> String {
+        let finished = tasks.iter().filter(|task| task.is_finished()).count();
+        let mut table = format!("{:>3} | {:<40} | {}\n", "#", "url", "status");
+        for (i, task) in tasks.iter().enumerate() {
+            table.push_str(&format!("{:>3} | {:<40} | {}\n", i, task.url, task.status));
+        }
+        table.push_str(&format!("{}/{} finished", finished, tasks.len()));
+        table
+    }
 
+    /// Outcome of one task in the JSON report.
+    #[derive(Serialize)]
+    struct Outcome<'a> {
+        url: &'a str,
+        file: &'a str,
+        ok: bool,
+        error: Option<&'a DownloadError>,
+        duration_ms: Option<u64>,
+        bytes: Option<usize>,
+    }
+
+    /// Final report of the downloads.
+    #[derive(Serialize)]
+    struct Report<'a> {
+        total: usize,
+        failed: usize,
+        tasks: Vec<Outcome<'a>>,
+    }
 
+    /// Whether any of the tasks has failed.
+    pub fn has_failures(tasks: &[Task]) -> bool {
+        tasks
+            .iter()
+            .any(|task| matches!(task.status, Status::Failed(_)))
+    }
+
+    /// Render the JSON report with the outcome, duration and size of every task.
+    pub fn json_report(tasks: &[Task]) -> serde_json::Result<String> {
+        let outcomes: Vec<Outcome> = tasks
+            .iter()
+            .map(|task| {
+                let (bytes, error) = match task.status {
+                    Status::Done(bytes) => (Some(bytes), None),
+                    Status::Failed(ref err) => (None, Some(err)),
+                    Status::Pending | Status::Running => (None, None),
+                };
+                Outcome {
+                    url: &task.url,
+                    file: &task.file,
+                    ok: bytes.is_some(),
+                    error,
+                    duration_ms: task
+                        .duration
+                        .map(|d| d.as_secs() * 1000 + u64::from(d.subsec_millis())),
+                    bytes,
+                }
+            })
+            .collect();
+        serde_json::to_string_pretty(&Report {
+            total: outcomes.len(),
+            failed: outcomes.iter().filter(|o| o.error.is_some()).count(),
+            tasks: outcomes,
+        })
+    }
+
+    /// Permit of the semaphore limiting the concurrent requests,
+    /// it is released when the slot is dropped.
+    struct Slot {
+        semaphore: Arc<Semaphore>,
+        permit: Permit,
+    }
+
+    impl Drop for Slot {
+        fn drop(&mut self) {
+            self.permit.release(&self.semaphore);
+        }
+    }
 
-              });
+    /// Wait until `semaphore` has a free permit.
+    fn acquire(semaphore: Arc<Semaphore>) -> impl Future<Item = Slot, Error = ()> {
+        let mut slot = Some(Slot {
+            semaphore,
+            permit: Permit::new(),
         });
+        future::poll_fn(move || {
+            {
+                let slot = slot
+                    .as_mut()
+                    .expect("the slot is polled after it is acquired");
+                let acquired = slot.permit.poll_acquire(&slot.semaphore);
+                if acquired.map_err(|_| ())?.is_not_ready() {
+                    return Ok(Async::NotReady);
+                }
+            }
+            Ok(Async::Ready(slot.take().unwrap()))
+        })
+    }
 
+    fn finish(tasks: &Tasks, index: usize, status: Status, duration: Duration) {
+        let task = &mut tasks.lock().unwrap()[index];
+        task.status = status;
+        task.duration = Some(duration);
     }
 
-    for url in v{
+    /// Download the task with `index` into its file. The future never fails:
+    /// errors are reported in the status of the task.
+    fn fetch(
+        client: &HttpsClient,
+        tasks: Tasks,
+        progress: Progress,
+        index: usize,
+    ) -> impl Future<Item = (), Error = ()> {
+        let (url, path) = {
+            let mut tasks = tasks.lock().unwrap();
+            tasks[index].status = Status::Running;
+            (tasks[index].url.clone(), tasks[index].file.clone())
+        };
+        let client = client.clone();
+        let finished = progress.clone();
+        let started = Instant::now();
+
+        future::result(url.parse::<Uri>().map_err(DownloadError::from))
+            .and_then(move |uri| client.get(uri).map_err(DownloadError::from))
+            .and_then(move |response| {
+                let status = response.status();
+                if !status.is_success() {
+                    return Either::A(future::err(DownloadError::HttpStatus(status.as_u16())));
+                }
+                let total = response
+                    .headers()
+                    .get(CONTENT_LENGTH)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok());
+                progress.send(Event::Started { index, total });
+                Either::B(response.into_body().map_err(DownloadError::from).fold(
+                    Vec::new(),
+                    move |mut body, chunk| {
+                        progress.send(Event::Advanced {
+                            index,
+                            bytes: chunk.len() as u64,
+                        });
+                        body.extend_from_slice(&chunk);
+                        Ok::<_, DownloadError>(body)
+                    },
+                ))
+            })
+            .and_then(move |body| {
+                File::create(path)
+                    .and_then(|file| tokio::io::write_all(file, body))
+                    .map(|(_, body)| body.len())
+                    .map_err(DownloadError::from)
+            })
+            .then(move |result| {
+                let status = match result {
+                    Ok(bytes) => Status::Done(bytes),
+                    Err(err) => Status::Failed(err),
+                };
+                finish(&tasks, index, status, started.elapsed());
+                finished.send(Event::Finished { index });
+                Ok(())
+            })
+    }
 
+    /// Run `fetch` of every index in `0..count` with at most `limit` of them at once,
+    /// `fetch` is called when its index gets a permit of the semaphore.
+    fn limited<F, R>(count: usize, limit: usize, fetch: F) -> impl Future<Item = (), Error = ()>
+    where
+        F: Fn(usize) -> R + Clone,
+        R: Future<Item = (), Error = ()>,
+    {
+        let semaphore = Arc::new(Semaphore::new(limit.max(1)));
+        let downloads = (0..count).map(|index| {
+            let fetch = fetch.clone();
+            acquire(semaphore.clone()).and_then(move |slot| {
+                fetch(index).then(move |result| {
+                    drop(slot);
+                    result
+                })
+            })
+        });
+        future::join_all(downloads.collect::<Vec<_>>()).map(|_| ())
+    }
 
+    /// Print the status table every time it changes, until the reporter is dropped.
+    fn report(tasks: Tasks) -> impl Future<Item = (), Error = ()> {
+        let mut last = String::new();
+        Interval::new(Instant::now(), REPORT_INTERVAL)
+            .for_each(move |_| {
+                let table = status_table(&tasks.lock().unwrap());
+                if table != last {
+                    println!("{}\n", table);
+                    last = table;
+                }
+                Ok(())
+            })
+            .map_err(|_| ())
+    }
 
+    /// Download all tasks with at most `max_threads` concurrent requests,
+    /// the tasks are returned with their final statuses.
+    pub fn run(
+        tasks: Vec<Task>,
+        max_threads: usize,
+        show_progress: bool,
+    ) -> Result<Vec<Task>, Box<dyn Error>> {
+        let https = HttpsConnector::new(4)?;
+        let client: HttpsClient = Client::builder().build::<_, Body>(https);
+        let mut runtime = Runtime::new()?;
+
+        let (progress, renderer) = if show_progress {
+            let labels = tasks.iter().map(|task| task.url.clone()).collect();
+            let (progress, renderer) = Progress::spawn(labels);
+            (progress, Some(renderer))
+        } else {
+            (Progress::disabled(), None)
+        };
+
+        let count = tasks.len();
+        let tasks: Tasks = Arc::new(Mutex::new(tasks));
+
+        let pipeline = {
+            let tasks = tasks.clone();
+            let progress = progress.clone();
+            limited(count, max_threads, move |index| {
+                fetch(&client, tasks.clone(), progress.clone(), index)
+            })
+        };
+        // Only the downloads keep a sender, so the renderer sees the end of events with them.
+        drop(progress);
+
+        if show_progress {
+            runtime
+                .block_on(pipeline)
+                .map_err(|_| "download pipeline failed")?;
+        } else {
+            // The reporter never finishes by itself: it is dropped with the pipeline.
+            runtime
+                .block_on(pipeline.select(report(tasks.clone())).map(|_| ()))
+                .map_err(|_| "download pipeline failed")?;
+        }
+        runtime.shutdown_on_idle().wait().ok();
+        // The pipeline with every sender is gone, so the renderer sees the end of events.
+        if let Some(renderer) = renderer {
+            renderer.join().map_err(|_| "progress renderer panicked")?;
+        }
+
+        let tasks = tasks.lock().unwrap().clone();
+        Ok(tasks)
     }
 
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::timer::Delay;
+
+        #[test]
+        fn test_file_name() {
+            assert_eq!(
+                "habr_com_post_347198.html",
+                file_name("https://habr.com/post/347198/")
+            );
+        }
+
+        #[test]
+        fn test_invalid_url_is_reported() {
+            let tasks = run(vec![Task::new(0, String::from("not a url"))], 2, false).unwrap();
+            assert_eq!(1, tasks.len());
+            match tasks[0].status {
+                Status::Failed(DownloadError::InvalidUrl(_)) => {}
+                ref status => panic!("unexpected status {:?}", status),
+            }
+            assert!(tasks[0].duration.is_some());
+            assert!(has_failures(&tasks));
+        }
+
+        #[test]
+        fn test_file_names_are_unique() {
+            let path = std::env::temp_dir().join(format!("tz_links_{}", std::process::id()));
+            std::fs::write(
+                &path,
+                "https://a.com/x\n\nhttps://a.com/x\nhttps://a.com/x/\n",
+            )
+            .unwrap();
+            let tasks = read_tasks(path.to_str().unwrap());
+            std::fs::remove_file(&path).unwrap();
+
+            let files: Vec<String> = tasks.unwrap().into_iter().map(|task| task.file).collect();
+            assert_eq!(
+                vec!["0_a_com_x.html", "1_a_com_x.html", "2_a_com_x.html"],
+                files
+            );
+        }
 
+        #[test]
+        fn test_concurrency_limit() {
+            let limit = 3;
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            // The stub fetcher holds its request for a while and counts the requests in flight.
+            let fetch = {
+                let in_flight = in_flight.clone();
+                let peak = peak.clone();
+                move |_| {
+                    let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(running, Ordering::SeqCst);
+                    let in_flight = in_flight.clone();
+                    Delay::new(Instant::now() + Duration::from_millis(20))
+                        .map(move |_| {
+                            in_flight.fetch_sub(1, Ordering::SeqCst);
+                        })
+                        .map_err(|_| ())
+                }
+            };
+
+            let mut runtime = Runtime::new().unwrap();
+            runtime.block_on(limited(10, limit, fetch)).unwrap();
+
+            assert_eq!(0, in_flight.load(Ordering::SeqCst));
+            assert!(peak.load(Ordering::SeqCst) <= limit);
+            assert_eq!(limit, peak.load(Ordering::SeqCst));
+        }
 
-    println!("{:?}",v);
+        #[test]
+        fn test_json_report() {
+            let mut tasks = vec![
+                Task::new(0, String::from("https://a.com")),
+                Task::new(1, String::from("https://b.com")),
+            ];
+            tasks[0].status = Status::Done(10);
+            tasks[0].duration = Some(Duration::from_millis(1500));
+            tasks[1].status = Status::Failed(DownloadError::HttpStatus(404));
+
+            let report: serde_json::Value =
+                serde_json::from_str(&json_report(&tasks).unwrap()).unwrap();
+            assert_eq!(1, report["failed"]);
+            assert_eq!(1500, report["tasks"][0]["duration_ms"]);
+            assert_eq!(10, report["tasks"][0]["bytes"]);
+            assert_eq!(
+                serde_json::json!({"kind": "http_status", "detail": 404}),
+                report["tasks"][1]["error"]
+            );
+        }
 
+        #[test]
+        fn test_status_table() {
+            let mut tasks = vec![
+                Task::new(0, String::from("https://a.com")),
+                Task::new(1, String::from("https://b.com")),
+            ];
+            tasks[0].status = Status::Done(10);
+            let table = status_table(&tasks);
+            assert!(table.contains("done (10 bytes)"));
+            assert!(table.contains("pending"));
+            assert!(table.ends_with("1/2 finished"));
+        }
+    }
+}
 
+fn main() -> Result<(), Box<dyn Error + 'static>> {
+    let settings: Settings = load_files::new();
 
-    Ok(())
+    let tasks = download::read_tasks(&settings.file)?;
+    let tasks = download::run(tasks, settings.max_threads as usize, settings.progress)?;
 
+    println!("{}", download::status_table(&tasks));
 
+    if let Some(path) = settings.report {
+        std::fs::write(path, download::json_report(&tasks)?)?;
+    }
+    if download::has_failures(&tasks) {
+        std::process::exit(1);
+    }
 
+    Ok(())
 }
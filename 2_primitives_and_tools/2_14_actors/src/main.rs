@@ -1,21 +1,31 @@
 extern crate actix;
+extern crate common_errors;
+extern crate configuration;
 extern crate futures;
+extern crate humantime;
+extern crate matrix;
+extern crate metrics;
 extern crate tokio;
-extern crate rand;
-extern crate rayon;
+
+mod settings;
 
 use actix::prelude::*;
-use std::collections::HashMap;
-use rand::thread_rng;
-use rand::Rng;
-use rayon::prelude::*;
+use common_errors::AppError;
+use configuration::configuration::{AppConfig, ConsumerArbiter};
+use matrix::Matrix;
+use metrics::{Counter, Histogram, Metrics, SECONDS_BUCKETS};
+use settings::ActorSettings;
+use std::env;
 use std::io::Write;
 use std::time::Duration;
 
 /// # Parallel matrix counting.
 ///
 /// The life cycle consists of the generation of square matrices by a single `Producer`
-/// and the calculation of these matrices by two `Consumer`.
+/// and the calculation of these matrices by the `Consumer` actors.
+/// The number of the consumers, their arbiter and the interval of the producer
+/// are read from the `actors` section of the configuration.
+/// Both report their work to `Metrics`, served on `metrics::DEFAULT_PORT`.
 ///
 /// ## Examples
 ///
@@ -26,12 +36,14 @@ use std::time::Duration;
 ///    fn main() {
 ///    use actor_matrix::*;
 ///
-///    System::run(|| {
-///            let addr_1: actix::Addr<Consumer> = Consumer.start();
-///            let addr_2: actix::Addr<Consumer> = addr_1.clone();
-///            Producer {
-///                subscribers: vec![addr_1.recipient(), addr_2.recipient()],
-///            }.start();
+///    let settings = ActorSettings::from_config(&Actors::default()).unwrap();
+///    let metrics = Metrics::new();
+///    let consumer_metrics = ConsumerMetrics::new(&metrics).unwrap();
+///    System::run(move || {
+///            let consumers = start_consumers(&settings, consumer_metrics);
+///            Producer::new(consumers, settings.producer_interval, &metrics)
+///                .unwrap()
+///                .start();
 ///        });
 ///    }
 /// ```
@@ -41,11 +53,53 @@ mod actor_matrix {
 
     /// Message type for subscribers.
     #[derive(Message)]
-    pub struct Signal(HashMap<(i32, i32), u8>);
+    pub struct Signal(Matrix);
+
+    /// Metrics of the consumers, registered once and shared by all of them.
+    #[derive(Clone)]
+    pub struct ConsumerMetrics {
+        consumed: Counter,
+        sum_seconds: Histogram,
+    }
+    /// Create ConsumerMetrics.
+    impl ConsumerMetrics {
+        /// Register the `matrices_consumed_total` and `matrix_sum_seconds` metrics.
+        pub fn new(metrics: &Metrics) -> metrics::Result<ConsumerMetrics> {
+            Ok(ConsumerMetrics {
+                consumed: metrics.counter(
+                    "matrices_consumed_total",
+                    "Matrices summed by the consumers.",
+                )?,
+                sum_seconds: metrics.histogram(
+                    "matrix_sum_seconds",
+                    "Time of summing a matrix.",
+                    SECONDS_BUCKETS,
+                )?,
+            })
+        }
+    }
 
     /// Actor `Consumer`.
     /// `Consumer` takes generated matrix, counts sum of all its elements and prints the sum to STDOUT.
-    pub struct Consumer;
+    pub struct Consumer {
+        metrics: ConsumerMetrics,
+    }
+    /// Create Consumer.
+    impl Consumer {
+        pub fn new(metrics: ConsumerMetrics) -> Consumer {
+            Consumer { metrics }
+        }
+
+        /// Implement the calculation of the sum of a square matrix.
+        /// The matrix is counted in parallel by `matrix::sum`.
+        fn consume(&mut self, msg: Signal) {
+            let timer = self.metrics.sum_seconds.start_timer();
+            let sum: u64 = matrix::sum(&msg.0);
+            timer.observe_duration();
+            self.metrics.consumed.inc();
+            writeln!(std::io::stdout(), "Matrix sum:{}", sum);
+        }
+    }
     /// Implement Consumer.
     impl Actor for Consumer {
         type Context = Context<Self>;
@@ -53,30 +107,74 @@ mod actor_matrix {
     /// Receiving and processing messages like `Signal`.
     impl Handler<Signal> for Consumer {
         type Result = ();
-        /// Implement the calculation of the sum of a square matrix.
-        /// The matrix is counted in parallel.
         fn handle(&mut self, msg: Signal, _: &mut Self::Context) {
-            let sum: u32 = msg.0.par_iter().map(|(&_k, &val)| val as u32).sum();
-            writeln!(std::io::stdout(), "Matrix sum:{}", sum);
+            self.consume(msg);
+        }
+    }
+
+    /// `Consumer` running on a `SyncArbiter`, in a thread of its own.
+    pub struct SyncConsumer(pub Consumer);
+    /// Implement SyncConsumer.
+    impl Actor for SyncConsumer {
+        type Context = SyncContext<Self>;
+    }
+    /// Receiving and processing messages like `Signal`.
+    impl Handler<Signal> for SyncConsumer {
+        type Result = ();
+        fn handle(&mut self, msg: Signal, _: &mut Self::Context) {
+            self.0.consume(msg);
+        }
+    }
+
+    /// Start `settings.consumers` consumers on the arbiter of `settings.arbiter`,
+    /// returns a subscriber for every consumer.
+    /// Must be called from a running `System`.
+    pub fn start_consumers(
+        settings: &ActorSettings,
+        metrics: ConsumerMetrics,
+    ) -> Vec<actix::Recipient<Signal>> {
+        match settings.arbiter {
+            ConsumerArbiter::Same => (0..settings.consumers)
+                .map(|_| Consumer::new(metrics.clone()).start().recipient())
+                .collect(),
+            ConsumerArbiter::Sync => {
+                let addr = SyncArbiter::start(settings.consumers, move || {
+                    SyncConsumer(Consumer::new(metrics.clone()))
+                });
+                // The consumers share the address: a matrix goes to a free thread.
+                (0..settings.consumers)
+                    .map(|_| addr.clone().recipient())
+                    .collect()
+            }
         }
     }
 
     /// Actor `Producer` continuously generates square matrixes of random `u8` elements and size `4096`.
     pub struct Producer {
         pub subscribers: Vec<actix::Recipient<Signal>>,
+        /// Delay between the matrices.
+        pub interval: Duration,
+        produced: Counter,
     }
     /// Implement Producer.
     impl Producer {
+        /// Register the `matrices_produced_total` metric.
+        pub fn new(
+            subscribers: Vec<actix::Recipient<Signal>>,
+            interval: Duration,
+            metrics: &Metrics,
+        ) -> metrics::Result<Producer> {
+            Ok(Producer {
+                subscribers,
+                interval,
+                produced: metrics
+                    .counter("matrices_produced_total", "Matrices sent to the consumers.")?,
+            })
+        }
+
         /// Implement generates square matrixes.
-        pub fn generate_matrix() -> HashMap<(i32, i32), u8> {
-            let mut matrix: HashMap<(i32, i32), u8> = HashMap::with_capacity(4096);
-            let mut rng = thread_rng();
-            for x in 1..65 {
-                for y in 1..65 {
-                    matrix.insert((x, y), rng.gen::<u8>());
-                }
-            }
-            matrix
+        pub fn generate_matrix() -> Matrix {
+            Matrix::random(matrix::SIDE)
         }
 
         /// Sending Signal Type Messages.
@@ -84,6 +182,7 @@ mod actor_matrix {
         fn send_signal(&mut self) {
             for subscr in &self.subscribers {
                 subscr.do_send(Signal(Producer::generate_matrix()));
+                self.produced.inc();
             }
         }
     }
@@ -93,7 +192,7 @@ mod actor_matrix {
         type Context = actix::Context<Self>;
         /// Interval alert subscribers.
         fn started(&mut self, ctx: &mut Self::Context) {
-            ctx.run_interval(Duration::from_millis(110), |actor, _ctx| {
+            ctx.run_interval(self.interval, |actor, _ctx| {
                 actor.send_signal();
             });
         }
@@ -101,14 +200,26 @@ mod actor_matrix {
 
 }
 
-fn main() {
+/// `$ actors [config.toml]`, the config of 2_11_configuration by default.
+fn main() -> Result<(), AppError> {
     use actor_matrix::*;
 
-    System::run(|| {
-        let addr_1: actix::Addr<Consumer> = Consumer.start();
-        let addr_2: actix::Addr<Consumer> = addr_1.clone();
-        Producer {
-            subscribers: vec![addr_1.recipient(), addr_2.recipient()],
-        }.start();
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "../2_11_configuration/config.toml".to_string());
+    let settings = ActorSettings::from_config(&AppConfig::load_typed(&path)?.actors)?;
+
+    let metrics = Metrics::new();
+    let addr = ([0, 0, 0, 0], metrics::DEFAULT_PORT).into();
+    metrics::spawn_exporter(&addr, metrics.clone()).expect("the metrics port is busy");
+    let consumer_metrics = ConsumerMetrics::new(&metrics).unwrap();
+
+    System::run(move || {
+        let consumers = start_consumers(&settings, consumer_metrics);
+        Producer::new(consumers, settings.producer_interval, &metrics)
+            .unwrap()
+            .start();
     });
+
+    Ok(())
 }
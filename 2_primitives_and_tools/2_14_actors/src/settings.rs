@@ -0,0 +1,64 @@
+//! Topology of the actors, parsed from the `actors` section of the configuration.
+
+use common_errors::{AppError, Result};
+use configuration::configuration::{Actors, ConsumerArbiter};
+use humantime;
+use std::time::Duration;
+
+/// Topology of the actors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActorSettings {
+    /// Number of the `Consumer` actors.
+    pub consumers: usize,
+    /// Where the consumers run.
+    pub arbiter: ConsumerArbiter,
+    /// Delay between the matrices sent by the `Producer`.
+    pub producer_interval: Duration,
+}
+
+impl ActorSettings {
+    /// Parse the `actors` section: the interval is like `"110ms"` or `"1s"`.
+    pub fn from_config(config: &Actors) -> Result<ActorSettings> {
+        if config.consumers == 0 {
+            return Err(AppError::config("actors.consumers: must be positive"));
+        }
+        Ok(ActorSettings {
+            consumers: usize::from(config.consumers),
+            arbiter: config.arbiter,
+            producer_interval: humantime::parse_duration(&config.producer_interval)
+                .map_err(|err| AppError::config(format!("actors.producer_interval: {}", err)))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_config() {
+        let settings = ActorSettings::from_config(&Actors::default()).unwrap();
+        assert_eq!(
+            ActorSettings {
+                consumers: 2,
+                arbiter: ConsumerArbiter::Same,
+                producer_interval: Duration::from_millis(110),
+            },
+            settings
+        );
+
+        let config = Actors {
+            consumers: 0,
+            ..Actors::default()
+        };
+        let err = ActorSettings::from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("actors.consumers"));
+
+        let config = Actors {
+            producer_interval: "often".into(),
+            ..Actors::default()
+        };
+        let err = ActorSettings::from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("actors.producer_interval"));
+    }
+}
@@ -0,0 +1,481 @@
+//! Database executor: a sync actor running queries through the r2d2 pool.
+
+use actix::prelude::*;
+use metrics;
+use postgres::error::SqlState;
+use postgres::transaction::{self, IsolationLevel, Transaction};
+use postgres::{self, rows::Row, Connection};
+use r2d2::{self, ManageConnection, PooledConnection};
+use r2d2_postgres::PostgresConnectionManager;
+use repository::{find_user, search_users, PgUsers};
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub type Pool = r2d2::Pool<PostgresConnectionManager>;
+
+/// How long one attempt of `checkout` waits for a free connection.
+pub const CHECKOUT_TIMEOUT: Duration = Duration::from_millis(500);
+/// Attempts of `checkout` after the first one.
+pub const CHECKOUT_RETRIES: u32 = 3;
+/// Pause before the first retry, doubled for every next one.
+const CHECKOUT_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Runs of `with_transaction` after the first one, when it fails to serialize.
+pub const TRANSACTION_RETRIES: u32 = 3;
+
+/// Page size of `GET /users` when the `limit` is not given.
+pub const DEFAULT_LIMIT: i64 = 20;
+/// The biggest allowed page size.
+pub const MAX_LIMIT: i64 = 100;
+
+/// Errors of the database requests.
+#[derive(Debug)]
+pub enum DbError {
+    /// All connections of the pool stayed busy (or couldn't be opened)
+    /// during every attempt, holds the error of the last attempt.
+    PoolExhausted(r2d2::Error),
+    Query(postgres::Error),
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DbError::PoolExhausted(err) => write!(f, "pool exhausted: {}", err),
+            DbError::Query(err) => write!(f, "query: {}", err),
+        }
+    }
+}
+
+impl From<postgres::Error> for DbError {
+    fn from(err: postgres::Error) -> Self {
+        DbError::Query(err)
+    }
+}
+
+/// Take a connection from the pool, retrying with a backoff while it is exhausted.
+pub fn checkout<M: ManageConnection>(pool: &r2d2::Pool<M>) -> Result<PooledConnection<M>, DbError> {
+    metrics::observe_wait(pool.state().idle_connections);
+    let start = Instant::now();
+    let conn = checkout_with(pool, CHECKOUT_TIMEOUT, CHECKOUT_RETRIES);
+    metrics::observe_checkout(start.elapsed());
+    conn
+}
+
+fn checkout_with<M: ManageConnection>(
+    pool: &r2d2::Pool<M>,
+    timeout: Duration,
+    retries: u32,
+) -> Result<PooledConnection<M>, DbError> {
+    let mut backoff = CHECKOUT_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        match pool.get_timeout(timeout) {
+            Ok(conn) => return Ok(conn),
+            Err(err) => {
+                if attempt == retries {
+                    return Err(DbError::PoolExhausted(err));
+                }
+            }
+        }
+        attempt += 1;
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+}
+
+/// Run `f` in a `SERIALIZABLE` transaction on a connection of the pool.
+///
+/// The transaction is committed when `f` succeeds and rolled back otherwise.
+/// If it conflicts with a concurrent one (SQLSTATE 40001), `f` runs again
+/// in a new transaction, up to `TRANSACTION_RETRIES` times.
+pub fn with_transaction<T, F>(pool: &Pool, mut f: F) -> Result<T, DbError>
+where
+    F: FnMut(&Transaction) -> Result<T, DbError>,
+{
+    let conn = checkout(pool)?;
+    let mut config = transaction::Config::new();
+    config.isolation_level(IsolationLevel::Serializable);
+
+    let mut attempt = 0;
+    loop {
+        let result = conn
+            .transaction_with(&config)
+            .map_err(DbError::from)
+            .and_then(|tx| {
+                let value = f(&tx)?;
+                tx.commit()?;
+                Ok(value)
+            });
+        match result {
+            Err(ref err) if is_serialization_failure(err) && attempt < TRANSACTION_RETRIES => {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_serialization_failure(err: &DbError) -> bool {
+    match err {
+        DbError::Query(err) => err.code() == Some(&SqlState::T_R_SERIALIZATION_FAILURE),
+        DbError::PoolExhausted(_) => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Person {
+    pub id: i32,
+    pub username: String,
+}
+
+impl Person {
+    fn from_row(row: &Row) -> Person {
+        Person {
+            id: row.get(0),
+            username: row.get(1),
+        }
+    }
+}
+
+/// Column the users are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderBy {
+    Id,
+    Username,
+}
+
+impl Default for OrderBy {
+    fn default() -> Self {
+        OrderBy::Id
+    }
+}
+
+impl OrderBy {
+    /// `ORDER BY` can't be a bound parameter, so only these names get into the query.
+    fn column(self) -> &'static str {
+        match self {
+            OrderBy::Id => "id",
+            OrderBy::Username => "username",
+        }
+    }
+}
+
+/// Page of the users sorted by `order_by`.
+pub fn list_users(
+    conn: &Connection,
+    limit: i64,
+    offset: i64,
+    order_by: OrderBy,
+) -> Result<Vec<Person>, DbError> {
+    let query = format!(
+        "SELECT id, username FROM users ORDER BY {} LIMIT $1 OFFSET $2",
+        order_by.column()
+    );
+    let rows = conn.query(&query, &[&limit, &offset])?;
+    Ok(rows.iter().map(|row| Person::from_row(&row)).collect())
+}
+
+/// Page of the users whose name contains `pattern`, sorted by id.
+/// The `%` and `_` of `pattern` are matched literally.
+pub fn find_by_username(
+    conn: &Connection,
+    pattern: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Person>, DbError> {
+    let rows = conn.query(
+        "SELECT id, username FROM users WHERE username LIKE $1 ESCAPE '\\' \
+         ORDER BY id LIMIT $2 OFFSET $3",
+        &[&format!("%{}%", escape_like(pattern)), &limit, &offset],
+    )?;
+    Ok(rows.iter().map(|row| Person::from_row(&row)).collect())
+}
+
+/// `pattern` with its `%` and `_` matched literally by `LIKE ... ESCAPE '\'`.
+pub fn escape_like(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if c == '%' || c == '_' || c == '\\' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Executes the queries on the threads of a `SyncArbiter`,
+/// so the blocking driver doesn't stall the http workers.
+pub struct DbExecutor(pub Pool);
+
+impl Actor for DbExecutor {
+    type Context = SyncContext<Self>;
+}
+
+/// `SELECT 1` on a connection taken from the pool within `timeout`.
+pub struct Ping {
+    pub timeout: Duration,
+}
+
+impl Message for Ping {
+    type Result = Result<(), DbError>;
+}
+
+impl Handler<Ping> for DbExecutor {
+    type Result = Result<(), DbError>;
+
+    fn handle(&mut self, msg: Ping, _: &mut Self::Context) -> Self::Result {
+        let conn = self
+            .0
+            .get_timeout(msg.timeout)
+            .map_err(DbError::PoolExhausted)?;
+        conn.query("SELECT 1", &[])?;
+        Ok(())
+    }
+}
+
+/// Query string of `GET /users`: `?limit=10&offset=20&order_by=username`,
+/// `?username=jek` to search by the name or `?search=JEK` to search by it ignoring the case.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct ListUsers {
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default)]
+    pub order_by: OrderBy,
+    pub username: Option<String>,
+    pub search: Option<String>,
+}
+
+fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+impl Message for ListUsers {
+    type Result = Result<Vec<Person>, DbError>;
+}
+
+impl Handler<ListUsers> for DbExecutor {
+    type Result = Result<Vec<Person>, DbError>;
+
+    fn handle(&mut self, msg: ListUsers, _: &mut Self::Context) -> Self::Result {
+        let conn = checkout(&self.0)?;
+        let limit = msg.limit.max(0).min(MAX_LIMIT);
+        let offset = msg.offset.max(0);
+        match (&msg.username, &msg.search) {
+            (Some(pattern), _) => find_by_username(&conn, pattern, limit, offset),
+            (None, Some(pattern)) => {
+                let users = PgUsers::new(&conn);
+                let found = search_users(&users, pattern, limit as usize, offset as usize);
+                users.check()?;
+                Ok(found)
+            }
+            (None, None) => list_users(&conn, limit, offset, msg.order_by),
+        }
+    }
+}
+
+pub struct GetUser {
+    pub id: i32,
+}
+
+impl Message for GetUser {
+    type Result = Result<Option<Person>, DbError>;
+}
+
+impl Handler<GetUser> for DbExecutor {
+    type Result = Result<Option<Person>, DbError>;
+
+    fn handle(&mut self, msg: GetUser, _: &mut Self::Context) -> Self::Result {
+        let conn = checkout(&self.0)?;
+        let users = PgUsers::new(&conn);
+        let user = find_user(&users, msg.id);
+        users.check()?;
+        Ok(user)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUser {
+    pub username: String,
+}
+
+impl Message for CreateUser {
+    type Result = Result<Person, DbError>;
+}
+
+impl Handler<CreateUser> for DbExecutor {
+    type Result = Result<Person, DbError>;
+
+    fn handle(&mut self, msg: CreateUser, _: &mut Self::Context) -> Self::Result {
+        with_transaction(&self.0, |tx| {
+            let rows = tx.query(
+                "INSERT INTO users (username) VALUES ($1) RETURNING id, username",
+                &[&msg.username],
+            )?;
+            Ok(Person::from_row(&rows.get(0)))
+        })
+    }
+}
+
+pub struct DeleteUser {
+    pub id: i32,
+}
+
+impl Message for DeleteUser {
+    /// Whether the user existed.
+    type Result = Result<bool, DbError>;
+}
+
+impl Handler<DeleteUser> for DbExecutor {
+    type Result = Result<bool, DbError>;
+
+    fn handle(&mut self, msg: DeleteUser, _: &mut Self::Context) -> Self::Result {
+        let conn = checkout(&self.0)?;
+        let deleted = conn.execute("DELETE FROM users WHERE id = $1", &[&msg.id])?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use r2d2_postgres::TlsMode;
+    use serde_json;
+    use settings::{connect_params, Settings};
+    use std::io;
+    use std::process;
+
+    /// Manager of fake connections, which are always opened successfully.
+    struct FakeManager;
+
+    impl ManageConnection for FakeManager {
+        type Connection = ();
+        type Error = io::Error;
+
+        fn connect(&self) -> Result<(), io::Error> {
+            Ok(())
+        }
+
+        fn is_valid(&self, _: &mut ()) -> Result<(), io::Error> {
+            Ok(())
+        }
+
+        fn has_broken(&self, _: &mut ()) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_checkout_pool_exhausted() {
+        let pool = r2d2::Pool::builder()
+            .max_size(1)
+            .build(FakeManager)
+            .unwrap();
+
+        let busy = checkout_with(&pool, Duration::from_millis(10), 2).unwrap();
+        match checkout_with(&pool, Duration::from_millis(10), 2) {
+            Err(DbError::PoolExhausted(_)) => {}
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+
+        drop(busy);
+        assert!(checkout_with(&pool, Duration::from_millis(10), 0).is_ok());
+    }
+
+    /// Needs the database of `config.toml`: `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_with_transaction_concurrent_inserts() {
+        let settings = Settings::priority_config("config.toml").unwrap();
+        let manager =
+            PostgresConnectionManager::new(connect_params(&settings.db.mysql), TlsMode::None)
+                .unwrap();
+        let pool = r2d2::Pool::builder().max_size(10).build(manager).unwrap();
+        let prefix = format!("tx-{}-", process::id());
+
+        let threads: Vec<_> = (0..10)
+            .map(|i| {
+                let pool = pool.clone();
+                let username = format!("{}{}", prefix, i);
+                thread::spawn(move || {
+                    with_transaction(&pool, |tx| {
+                        tx.execute("INSERT INTO users (username) VALUES ($1)", &[&username])?;
+                        Ok(())
+                    })
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap().unwrap();
+        }
+
+        let conn = checkout(&pool).unwrap();
+        let deleted = conn
+            .execute(
+                "DELETE FROM users WHERE username LIKE $1",
+                &[&format!("{}%", prefix)],
+            )
+            .unwrap();
+        assert_eq!(10, deleted);
+    }
+
+    /// Needs the database of `config.toml`: `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_find_by_username_page() {
+        let settings = Settings::priority_config("config.toml").unwrap();
+        let conn = Connection::connect(connect_params(&settings.db.mysql), postgres::TlsMode::None)
+            .unwrap();
+        let prefix = format!("page-{}-", process::id());
+        for i in 0..5 {
+            conn.execute(
+                "INSERT INTO users (username) VALUES ($1)",
+                &[&format!("{}{}", prefix, i)],
+            )
+            .unwrap();
+        }
+
+        let page = find_by_username(&conn, &prefix, 2, 1);
+        let rest = find_by_username(&conn, &prefix, 10, 4);
+        conn.execute(
+            "DELETE FROM users WHERE username LIKE $1",
+            &[&format!("{}%", prefix)],
+        )
+        .unwrap();
+
+        let names: Vec<String> = page
+            .unwrap()
+            .into_iter()
+            .map(|user| user.username)
+            .collect();
+        assert_eq!(vec![format!("{}1", prefix), format!("{}2", prefix)], names);
+        assert_eq!(1, rest.unwrap().len());
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!("jeka", escape_like("jeka"));
+        assert_eq!("100\\%\\_a\\\\", escape_like("100%_a\\"));
+    }
+
+    #[test]
+    fn test_list_users_query() {
+        let query: ListUsers = serde_json::from_str("{}").unwrap();
+        assert_eq!(DEFAULT_LIMIT, query.limit);
+        assert_eq!(0, query.offset);
+        assert_eq!(OrderBy::Id, query.order_by);
+
+        let query: ListUsers =
+            serde_json::from_str(r#"{"limit": 5, "order_by": "username", "username": "je"}"#)
+                .unwrap();
+        assert_eq!(5, query.limit);
+        assert_eq!("username", query.order_by.column());
+        assert_eq!(Some("je".to_string()), query.username);
+        assert_eq!(None, query.search);
+
+        assert!(serde_json::from_str::<ListUsers>(r#"{"order_by": "pass"}"#).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! Statistics of the connection pool, exposed as prometheus metrics
+//! on `GET /metrics` and logged every `LOG_INTERVAL`.
+//!
+//! They show whether `connections.max_open` and `connections.max_idle`
+//! of the config fit the load: waits grow when the pool is too small.
+
+use configuration::configuration::Connections;
+use db::Pool;
+use prometheus::core::Metric;
+use prometheus::{self, Counter, Encoder, Gauge, Histogram, TextEncoder};
+use slog::Logger;
+use std::thread;
+use std::time::Duration;
+
+/// How often the statistics of the pool are logged.
+pub const LOG_INTERVAL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref POOL_CONNECTIONS: Gauge = register_gauge!(
+        "db_pool_connections",
+        "Connections opened by the pool, busy and idle."
+    )
+    .unwrap();
+    static ref POOL_IDLE_CONNECTIONS: Gauge =
+        register_gauge!("db_pool_idle_connections", "Idle connections of the pool.").unwrap();
+    static ref POOL_WAITS: Counter = register_counter!(
+        "db_pool_waits_total",
+        "Checkouts which found no idle connection and had to wait for one."
+    )
+    .unwrap();
+    static ref CHECKOUT_SECONDS: Histogram = register_histogram!(
+        "db_pool_checkout_seconds",
+        "Time of taking a connection from the pool, retries included.",
+        vec![0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 2.5]
+    )
+    .unwrap();
+}
+
+/// Count the checkout as a wait if there is no idle connection in `idle`.
+pub fn observe_wait(idle: u32) {
+    if idle == 0 {
+        POOL_WAITS.inc();
+    }
+}
+
+/// Record how long the checkout took.
+pub fn observe_checkout(elapsed: Duration) {
+    CHECKOUT_SECONDS.observe(seconds(elapsed));
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+/// Update the gauges with the current state of the pool.
+pub fn observe_pool(pool: &Pool) {
+    let state = pool.state();
+    POOL_CONNECTIONS.set(f64::from(state.connections));
+    POOL_IDLE_CONNECTIONS.set(f64::from(state.idle_connections));
+}
+
+/// All the metrics in the prometheus text format and its content type.
+pub fn encode(pool: &Pool) -> (Vec<u8>, String) {
+    observe_pool(pool);
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder
+        .encode(&prometheus::gather(), &mut buffer)
+        .expect("metrics are encoded into memory");
+    (buffer, encoder.format_type().to_string())
+}
+
+/// Log the statistics of the pool every `LOG_INTERVAL` in a background thread.
+pub fn spawn_logger(log: Logger, pool: Pool, connections: &Connections) -> thread::JoinHandle<()> {
+    let max_open = connections.max_open;
+    let max_idle = connections.max_idle;
+    thread::spawn(move || loop {
+        thread::sleep(LOG_INTERVAL);
+        observe_pool(&pool);
+        let checkouts = CHECKOUT_SECONDS.metric();
+        info!(log, "db pool";
+            "connections" => POOL_CONNECTIONS.get(),
+            "idle" => POOL_IDLE_CONNECTIONS.get(),
+            "waits" => POOL_WAITS.get(),
+            "checkouts" => checkouts.get_histogram().get_sample_count(),
+            "checkout_seconds" => checkouts.get_histogram().get_sample_sum(),
+            "max_open" => max_open,
+            "max_idle" => max_idle,
+        );
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_observe_checkout() {
+        let before = CHECKOUT_SECONDS.metric().get_histogram().get_sample_count();
+        observe_checkout(Duration::from_millis(1500));
+        let histogram = CHECKOUT_SECONDS.metric();
+        assert_eq!(before + 1, histogram.get_histogram().get_sample_count());
+
+        let waits = POOL_WAITS.get();
+        observe_wait(2);
+        observe_wait(0);
+        assert_eq!(waits + 1.0, POOL_WAITS.get());
+    }
+
+    #[test]
+    fn test_seconds() {
+        assert_eq!(1.5, seconds(Duration::from_millis(1500)));
+    }
+}
@@ -0,0 +1,86 @@
+//! Migrations of the database schema, embedded into the binary.
+//!
+//! Applied versions are tracked in the `schema_migrations` table,
+//! so running the migrations again applies only the new ones.
+
+use db::DbError;
+use postgres::Connection;
+
+/// Version and SQL of every migration, in the order of applying.
+pub const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "0001_create_users",
+        include_str!("../migrations/0001_create_users.sql"),
+    ),
+    (
+        "0002_users_username_unique",
+        include_str!("../migrations/0002_users_username_unique.sql"),
+    ),
+];
+
+/// Apply the pending migrations, each one in its own transaction.
+/// Returns the versions applied by this call.
+pub fn run(conn: &Connection) -> Result<Vec<&'static str>, DbError> {
+    conn.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version VARCHAR PRIMARY KEY,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )",
+    )?;
+
+    let mut applied = Vec::new();
+    for &(version, sql) in MIGRATIONS {
+        let tx = conn.transaction()?;
+        // Another instance of the service may be migrating at the same time.
+        tx.batch_execute("LOCK TABLE schema_migrations IN EXCLUSIVE MODE")?;
+        let done = tx.query(
+            "SELECT 1 FROM schema_migrations WHERE version = $1",
+            &[&version],
+        )?;
+        if !done.is_empty() {
+            continue;
+        }
+        tx.batch_execute(sql)?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version) VALUES ($1)",
+            &[&version],
+        )?;
+        tx.commit()?;
+        applied.push(version);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use postgres::TlsMode;
+    use settings::Settings;
+
+    #[test]
+    fn test_versions_are_ordered() {
+        let versions: Vec<&str> = MIGRATIONS.iter().map(|&(version, _)| version).collect();
+        let mut sorted = versions.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted, versions);
+    }
+
+    /// Needs the database of `config.toml`: `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_run_is_idempotent() {
+        let settings = Settings::priority_config("config.toml").unwrap();
+        let conn = Connection::connect(settings.db.mysql.connect_params(), TlsMode::None).unwrap();
+
+        run(&conn).unwrap();
+        assert!(run(&conn).unwrap().is_empty());
+
+        let count: i64 = conn
+            .query("SELECT count(*) FROM schema_migrations", &[])
+            .unwrap()
+            .get(0)
+            .get(0);
+        assert_eq!(MIGRATIONS.len() as i64, count);
+    }
+}
@@ -0,0 +1,194 @@
+//! `UsersRepository` of 2_4_collections over the `users` table.
+//!
+//! The lookups of the handlers are written against the trait,
+//! so they are tested with the in-memory `DBMemory` instead of the database.
+
+use collections::users::{
+    get_ids_user_by_nickname, get_user_by_id, get_users_by_ids, SortBy, User, UserId,
+    UsersRepository,
+};
+use db::{escape_like, DbError, Person};
+use im::hashmap::HashMap;
+use postgres::rows::Row;
+use postgres::types::ToSql;
+use postgres::{self, Connection};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+
+/// Users stored in postgres.
+///
+/// The methods of `UsersRepository` can't fail, so the error of a query is kept
+/// (the next lookups return nothing) and reported by `check`.
+pub struct PgUsers<'a> {
+    conn: &'a Connection,
+    error: RefCell<Option<postgres::Error>>,
+}
+
+impl<'a> PgUsers<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        PgUsers {
+            conn,
+            error: RefCell::new(None),
+        }
+    }
+
+    /// The error of the failed query, if any.
+    pub fn check(self) -> Result<(), DbError> {
+        match self.error.into_inner() {
+            Some(err) => Err(DbError::Query(err)),
+            None => Ok(()),
+        }
+    }
+
+    fn query(&self, query: &str, params: &[&dyn ToSql]) -> Vec<User> {
+        if self.error.borrow().is_some() {
+            return Vec::new();
+        }
+        match self.conn.query(query, params) {
+            Ok(rows) => rows.iter().map(|row| user_from_row(&row)).collect(),
+            Err(err) => {
+                *self.error.borrow_mut() = Some(err);
+                Vec::new()
+            }
+        }
+    }
+}
+
+fn user_from_row(row: &Row) -> User {
+    let id: i32 = row.get(0);
+    let username: String = row.get(1);
+    User::new(UserId(id as usize), Cow::Owned(username))
+}
+
+impl<'a> UsersRepository for PgUsers<'a> {
+    fn get_user_by_id(&self, id: UserId) -> Option<User> {
+        let id = match i32::try_from(id.0) {
+            Ok(id) => id,
+            Err(_) => return None,
+        };
+        self.query("SELECT id, username FROM users WHERE id = $1", &[&id])
+            .into_iter()
+            .next()
+    }
+
+    fn get_users_by_ids(&self, vec: Vec<UserId>) -> HashMap<UserId, User> {
+        let ids: Vec<i32> = vec
+            .iter()
+            .filter_map(|id| i32::try_from(id.0).ok())
+            .collect();
+        self.query("SELECT id, username FROM users WHERE id = ANY($1)", &[&ids])
+            .into_iter()
+            .map(|user| (user.get_id().clone(), user))
+            .collect()
+    }
+
+    fn get_ids_user_by_nickname(&self, nickname: &str) -> Vec<UserId> {
+        self.query(
+            "SELECT id, username FROM users WHERE username ILIKE $1 ESCAPE '\\' ORDER BY id",
+            &[&format!("%{}%", escape_like(nickname))],
+        )
+        .into_iter()
+        .map(|user| user.get_id().clone())
+        .collect()
+    }
+
+    fn get_users_page(&self, offset: usize, limit: usize, sort: SortBy) -> Vec<User> {
+        let query = match sort {
+            SortBy::Id => "SELECT id, username FROM users ORDER BY id LIMIT $1 OFFSET $2",
+            // The same key as `normalize_nickname()`.
+            SortBy::Nickname => {
+                "SELECT id, username FROM users \
+                 ORDER BY lower(regexp_replace(btrim(username), '\\s+', ' ', 'g')), id \
+                 LIMIT $1 OFFSET $2"
+            }
+        };
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let offset = i64::try_from(offset).unwrap_or(i64::MAX);
+        self.query(query, &[&limit, &offset])
+    }
+}
+
+impl<'a> From<&'a User> for Person {
+    fn from(user: &'a User) -> Self {
+        Person {
+            id: user.get_id().0 as i32,
+            username: user.get_nickname().to_string(),
+        }
+    }
+}
+
+/// The user `id`, `None` if there is no such user.
+pub fn find_user(repository: &dyn UsersRepository, id: i32) -> Option<Person> {
+    if id < 0 {
+        return None;
+    }
+    get_user_by_id(repository, UserId(id as usize)).map(|user| Person::from(&user))
+}
+
+/// Page of the users whose name contains `pattern` ignoring the case, sorted by id.
+pub fn search_users(
+    repository: &dyn UsersRepository,
+    pattern: &str,
+    limit: usize,
+    offset: usize,
+) -> Vec<Person> {
+    let ids = get_ids_user_by_nickname(repository, pattern);
+    let mut users: Vec<Person> = get_users_by_ids(repository, ids)
+        .iter()
+        .map(|(_, user)| Person::from(user))
+        .collect();
+    users.sort_by_key(|user| user.id);
+    users.into_iter().skip(offset).take(limit).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use collections::users::DBMemory;
+
+    fn memory() -> DBMemory {
+        let mut map_users: HashMap<UserId, User> = HashMap::new();
+        for (id, nickname) in &[
+            (4, "Sara Delafon"),
+            (2, "Jacob Delafon"),
+            (5, "Sara Daniel"),
+        ] {
+            let user = User::new(UserId(*id), Cow::Borrowed(*nickname));
+            map_users.insert(user.get_id().clone(), user);
+        }
+        DBMemory::new(map_users)
+    }
+
+    #[test]
+    fn test_find_user() {
+        let users = memory();
+        assert_eq!(
+            Some(Person {
+                id: 2,
+                username: "Jacob Delafon".to_string(),
+            }),
+            find_user(&users, 2)
+        );
+        assert_eq!(None, find_user(&users, 8));
+        assert_eq!(None, find_user(&users, -1));
+    }
+
+    #[test]
+    fn test_search_users() {
+        let users = memory();
+        let found: Vec<i32> = search_users(&users, "delafon", 10, 0)
+            .iter()
+            .map(|user| user.id)
+            .collect();
+        assert_eq!(vec![2, 4], found);
+        assert!(search_users(&users, "Jeka", 10, 0).is_empty());
+
+        let page: Vec<i32> = search_users(&users, "sara", 1, 1)
+            .iter()
+            .map(|user| user.id)
+            .collect();
+        assert_eq!(vec![5], page);
+        assert!(search_users(&users, "sara", 1, 2).is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+//! Settings of the service: the `server` and `db` sections of 2_11_configuration,
+//! the `db.mysql` section describes the postgres server here.
+//!
+//! A priority:
+//! 1. Default value in `Rust` sources;
+//! 2. Value read from `TOML` file;
+//! 3. Value set by environment variable of 2_11_configuration,
+//!    e.g. `DB__MYSQL__USER` and `DB__MYSQL__CONNECTIONS__MAX_OPEN`.
+
+use config::{self, Config};
+use configuration::configuration::{environment, Db, MySQL, Server};
+use postgres::params::{ConnectParams, Host};
+use std::cmp;
+use std::error::Error;
+
+/// Port of the postgres server, the default of `db.mysql.port`.
+pub const POSTGRES_PORT: u16 = 5432;
+
+/// The settings of the service.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Settings {
+    pub server: Server,
+    pub db: Db,
+}
+
+/// Default Value for `Settings`: the defaults of 2_11_configuration with `POSTGRES_PORT`.
+impl Default for Settings {
+    fn default() -> Self {
+        let mut settings = Settings {
+            server: Default::default(),
+            db: Default::default(),
+        };
+        settings.db.mysql.port = POSTGRES_PORT;
+        settings
+    }
+}
+
+impl Settings {
+    /// Read the settings from the file `name` with the environment overrides.
+    pub fn priority_config(name: &str) -> Result<Settings, Box<dyn Error>> {
+        let mut config = Config::new();
+        config.merge(Config::try_from(&Settings::default())?)?;
+        config.merge(config::File::with_name(name).required(false))?;
+        config.merge(environment())?;
+        Ok(config.try_into()?)
+    }
+}
+
+/// Connection parameters of the `dating` database.
+pub fn connect_params(mysql: &MySQL) -> ConnectParams {
+    let pass = if mysql.pass.is_empty() {
+        None
+    } else {
+        Some(mysql.pass.expose())
+    };
+    ConnectParams::builder()
+        .port(mysql.port)
+        .user(&mysql.user, pass)
+        .database(&mysql.databases.dating)
+        .build(Host::Tcp(mysql.host.to_string()))
+}
+
+/// Maximum size of the pool: `max_open`, at least one connection.
+pub fn pool_max_size(mysql: &MySQL) -> u32 {
+    cmp::max(1, u32::from(mysql.connections.max_open))
+}
+
+/// Idle connections kept by the pool: `max_idle` reduced to `max_open`.
+pub fn pool_min_idle(mysql: &MySQL) -> u32 {
+    cmp::min(u32::from(mysql.connections.max_idle), pool_max_size(mysql))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_priority_config() {
+        let settings = Settings::priority_config("config.toml").unwrap();
+        assert_eq!("diesel_demo", settings.db.mysql.databases.dating);
+        assert_eq!(5432, settings.db.mysql.port);
+        assert_eq!(8080, settings.server.http_port);
+        assert_eq!(10025, settings.server.healthz_port);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        env::set_var("DB__MYSQL__USER", "from_env");
+        env::set_var("DB__MYSQL__CONNECTIONS__MAX_OPEN", "7");
+        let settings = Settings::priority_config("config.toml");
+        env::remove_var("DB__MYSQL__USER");
+        env::remove_var("DB__MYSQL__CONNECTIONS__MAX_OPEN");
+
+        let settings = settings.unwrap();
+        assert_eq!("from_env", settings.db.mysql.user);
+        assert_eq!(7, settings.db.mysql.connections.max_open);
+    }
+
+    #[test]
+    fn test_pool_size() {
+        let mut mysql = MySQL::default();
+        mysql.connections.max_idle = 50;
+        mysql.connections.max_open = 10;
+        assert_eq!(10, pool_max_size(&mysql));
+        assert_eq!(10, pool_min_idle(&mysql));
+
+        mysql.connections.max_open = 0;
+        assert_eq!(1, pool_max_size(&mysql));
+    }
+
+    #[test]
+    fn test_connect_params() {
+        let mysql = Settings::default().db.mysql;
+        let params = connect_params(&mysql);
+        assert_eq!(Some("dating"), params.database());
+        assert_eq!(None, params.user().and_then(|user| user.password()));
+    }
+}
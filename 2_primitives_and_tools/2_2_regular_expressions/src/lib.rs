@@ -0,0 +1,7 @@
+//! The `User` with the validated email, shared with the other steps through `toolkit`.
+extern crate regex;
+
+#[macro_use]
+extern crate lazy_static;
+
+pub mod user;
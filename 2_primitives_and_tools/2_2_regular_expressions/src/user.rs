@@ -0,0 +1,117 @@
+//! #User module with validation email
+//!
+//! Regular expression is taken from [source]: https://habr.com/post/55820/
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! extern crate regular_expressions;
+//!
+//! use regular_expressions::user::User;
+//!
+//! let user = User::validate_and_set_email("mail@mail.ru").unwrap();
+//! assert_eq!(Some("mail.ru"), user.email_domain());
+//! ```
+
+use regex::Regex;
+
+/// Regular expression of the email «username@hostname» in the verbose `(?x)` syntax,
+/// the other steps validate the emails with it too.
+pub const EMAIL_PATTERN: &str = r"(?x)
+    ^[-a-z0-9!\#$%&'*+/=?^_`{|}~]+(\.[-a-z0-9!\#$%&'*+/=?^_`{|}~]+)*  # the username
+    @([a-z0-9]([-a-z0-9]{0,61}[a-z0-9])?\.)*  # components separated by a period and not exceeding 63 characters
+    ([a-z]{2,5}) # suffixes (limited list of first level domains)
+    \.[a-z][a-z]$                             # country domains
+    ";
+
+/// Structure containing the user's email.
+pub struct User<'a> {
+    pub email: &'a str,
+}
+/// Methods for verifying the user's email.
+impl<'a> User<'a> {
+    /// Creates the `User` object after successfully checking its email.
+    /// Validation email «username@hostname»,
+    /// username : latin characters, numbers, signs !#$%&'*+—/=?^_`{|}~
+    /// hostname : contains components and suffixes (first-level domains) and domains of countries.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// extern crate regular_expressions;
+    ///
+    /// use regular_expressions::user::User;
+    ///
+    /// assert!(User::validate_and_set_email("mail@mail.ru").is_some());
+    /// assert!(User::validate_and_set_email("mail.ru").is_none());
+    /// ```
+    pub fn validate_and_set_email(email: &'a str) -> Option<Self> {
+        lazy_static! {
+            static ref EMAIL: Regex = Regex::new(EMAIL_PATTERN).unwrap();
+        }
+
+        if EMAIL.is_match(email) {
+            return Some(User { email });
+        }
+        None
+    }
+
+    /// Analyzes a portion of the domain with the user's email address with a regular expression and returns it.
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// extern crate regular_expressions;
+    ///
+    /// use regular_expressions::user::User;
+    ///
+    /// let user = User::validate_and_set_email("mail@mail.ru").unwrap();
+    /// assert_eq!(Some("mail.ru"), user.email_domain());
+    /// ```
+    pub fn email_domain(&self) -> Option<&'a str> {
+        lazy_static! {
+            static ref EMAIL_DOMAIN: Regex = Regex::new(r"@").unwrap();
+        }
+
+        EMAIL_DOMAIN.split(self.email).last()
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn none_validation_email() {
+        assert!(User::validate_and_set_email("mailmail.ru").is_none());
+    }
+
+    #[test]
+    fn none_validation_domain() {
+        assert!(User::validate_and_set_email("mail@mailru").is_none());
+    }
+
+    #[test]
+    fn none_validation_username() {
+        assert!(User::validate_and_set_email("().@mail.ru").is_none());
+    }
+
+    #[test]
+    fn some_validation_username() {
+        assert!(User::validate_and_set_email("user.user@mail.ru").is_some());
+    }
+
+    #[test]
+    fn some_validation_domain() {
+        let user = User::validate_and_set_email("user.user@mail.ru").unwrap();
+        if let Some(domain) = user.email_domain() {
+            assert_eq!(domain, "mail.ru");
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! # The users persisted in a file
+//!
+//! `DBFile` implements the Repository pattern over a JSON file, so the same
+//! functions `get_user_by_id()`, `get_users_by_ids()` and `get_ids_user_by_nickname()`
+//! work with the users surviving the restart of the application.
+//! The users are read by `open()` and kept in memory, every change rewrites the file:
+//! a temporary file is written next to it and renamed over it,
+//! so a crash leaves either the old or the new version.
+//! The change which fails to be written is not applied to the users in memory either.
+//! The file is the JSON array of `DBMemory::export_json()`.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust,no_run
+//! extern crate collections;
+//!
+//! use collections::file::DBFile;
+//! use collections::users::*;
+//! use std::borrow::Cow;
+//! use std::io;
+//!
+//! fn main() -> io::Result<()> {
+//!     let mut users_source = DBFile::open("users.json")?;
+//!     users_source.insert(User::new(UserId(4), Cow::Borrowed("Sara Delafon")))?;
+//!
+//!     let users_source = DBFile::open("users.json")?;
+//!     assert!(get_user_by_id(&users_source, UserId(4)).is_some());
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use im::hashmap::HashMap;
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use users::{normalize_nickname, SortBy, User, UserId, UsersRepository};
+
+/// The type implements the Repository pattern over the users stored in a JSON file.
+#[derive(Debug)]
+pub struct DBFile {
+    path: PathBuf,
+    users: BTreeMap<UserId, User>,
+}
+
+/// Methods of type DBFile.
+impl DBFile {
+    /// Reads the users of the file, there are no users if it doesn't exist yet.
+    pub fn open<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
+        let path = path.into();
+        let users: Vec<User> = match fs::File::open(&path) {
+            Ok(file) => serde_json::from_reader(io::BufReader::new(file))?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        let users = users
+            .into_iter()
+            .map(|user| (user.get_id().clone(), user))
+            .collect();
+        Ok(DBFile { path, users })
+    }
+
+    /// The file of the users.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds the user or replaces the user with the same id,
+    /// returns the replaced one. The file is rewritten.
+    pub fn insert(&mut self, user: User) -> io::Result<Option<User>> {
+        let mut users = self.users.clone();
+        let previous = users.insert(user.get_id().clone(), user);
+        self.save(users)?;
+        Ok(previous)
+    }
+
+    /// Removes the user by id, returns the removed one. The file is rewritten.
+    pub fn remove(&mut self, id: &UserId) -> io::Result<Option<User>> {
+        let mut users = self.users.clone();
+        let previous = users.remove(id);
+        if previous.is_some() {
+            self.save(users)?;
+        }
+        Ok(previous)
+    }
+
+    /// Number of the users.
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    /// Whether there are no users.
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// Writes the users, sorted by id, to the temporary file and renames it over the file,
+    /// then they replace the users in memory.
+    fn save(&mut self, users: BTreeMap<UserId, User>) -> io::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        {
+            let file = fs::File::create(&tmp)?;
+            let sorted: Vec<&User> = users.values().collect();
+            serde_json::to_writer_pretty(io::BufWriter::new(&file), &sorted)?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, &self.path)?;
+        self.users = users;
+        Ok(())
+    }
+}
+
+/// Implementing the template Repocators for type `DBFile`.
+impl UsersRepository for DBFile {
+    /// User search by ID.
+    fn get_user_by_id(&self, id: UserId) -> Option<User> {
+        self.users.get(&id).cloned()
+    }
+
+    /// Search for all users that match the identifiers.
+    fn get_users_by_ids(&self, vec: Vec<UserId>) -> HashMap<UserId, User> {
+        vec.into_iter()
+            .filter_map(|id| self.users.get(&id).cloned().map(|user| (id, user)))
+            .collect()
+    }
+
+    /// Search for users by nickname, the ids are in ascending order.
+    fn get_ids_user_by_nickname(&self, nickname: &str) -> Vec<UserId> {
+        let nickname = nickname.to_lowercase();
+        self.users
+            .values()
+            .filter(|user| user.get_nickname().to_lowercase().contains(&nickname))
+            .map(|user| user.get_id().clone())
+            .collect()
+    }
+
+    /// Page of the users, the users are sorted by nickname on every call.
+    fn get_users_page(&self, offset: usize, limit: usize, sort: SortBy) -> Vec<User> {
+        let mut users: Vec<&User> = self.users.values().collect();
+        if sort == SortBy::Nickname {
+            users.sort_by_key(|user| (normalize_nickname(user.get_nickname()), user.get_id()));
+        }
+        users
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::borrow::Cow;
+    use std::env;
+    use users::get_ids_user_by_nickname;
+
+    fn path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("db_file_{}_{}.json", name, ::std::process::id()))
+    }
+
+    #[test]
+    fn test_reopen() {
+        let path = path("reopen");
+        let mut users_source = DBFile::open(&path).unwrap();
+        assert!(users_source.is_empty());
+        users_source
+            .insert(User::new(UserId(4), Cow::Borrowed("Sara Delafon")))
+            .unwrap();
+        users_source
+            .insert(User::new(UserId(2), Cow::Borrowed("Jacob Delafon")))
+            .unwrap();
+        let previous = users_source
+            .insert(User::new(UserId(4), Cow::Borrowed("Sara Daniel")))
+            .unwrap();
+        assert_eq!("Sara Delafon", previous.unwrap().get_nickname());
+
+        let mut users_source = DBFile::open(&path).unwrap();
+        assert_eq!(2, users_source.len());
+        assert_eq!(
+            "Sara Daniel",
+            users_source
+                .get_user_by_id(UserId(4))
+                .unwrap()
+                .get_nickname()
+        );
+        assert_eq!(
+            vec![UserId(2)],
+            get_ids_user_by_nickname(&users_source, "delafon")
+        );
+
+        assert!(users_source.remove(&UserId(2)).unwrap().is_some());
+        assert!(users_source.remove(&UserId(2)).unwrap().is_none());
+        assert_eq!(1, DBFile::open(&path).unwrap().len());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_write() {
+        let dir = env::temp_dir().join(format!("db_file_failed_{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut users_source = DBFile::open(dir.join("users.json")).unwrap();
+        users_source
+            .insert(User::new(UserId(4), Cow::Borrowed("Sara Delafon")))
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The changes which can't be written leave the users as they are.
+        assert!(users_source
+            .insert(User::new(UserId(2), Cow::Borrowed("Jacob Delafon")))
+            .is_err());
+        assert!(users_source
+            .insert(User::new(UserId(4), Cow::Borrowed("Sara Daniel")))
+            .is_err());
+        assert!(users_source.remove(&UserId(4)).is_err());
+        assert_eq!(1, users_source.len());
+        assert_eq!(
+            "Sara Delafon",
+            users_source
+                .get_user_by_id(UserId(4))
+                .unwrap()
+                .get_nickname()
+        );
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let path = path("invalid");
+        fs::write(&path, "[{\"id\": \"four\"}]").unwrap();
+        let err = DBFile::open(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+}
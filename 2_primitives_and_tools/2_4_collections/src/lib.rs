@@ -0,0 +1,21 @@
+//! The `UsersRepository` trait with its in-memory implementations: `DBMemory`
+//! and `OrderedDBMemory` sorted by nickname for listing the users,
+//! shared with the other steps (2_15_actix_web_r2d2 implements it over postgres),
+//! and `DBFile` persisting the users in a JSON file.
+//! The `repository` module generalizes the pattern to any entity by its id,
+//! `UsersRepository` is implemented for every `Repository<UserId, User>`.
+//! The `testing` feature adds the proptest strategies of the users.
+extern crate im;
+#[cfg(any(test, feature = "testing"))]
+extern crate proptest;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+pub mod file;
+pub mod ordered;
+pub mod repository;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod users;
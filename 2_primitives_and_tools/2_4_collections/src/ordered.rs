@@ -0,0 +1,239 @@
+//! # The users sorted by nickname
+//!
+//! `OrderedDBMemory` keeps the users in an [OrdMap]:https://docs.rs/im/11.0.1/im/ordmap/struct.OrdMap.html
+//! by `UserId` with an [OrdSet]:https://docs.rs/im/11.0.1/im/ordset/struct.OrdSet.html
+//! index of the nicknames, so the users are listed in the order of their nicknames,
+//! as the UI shows them. The nicknames are compared normalized by `normalize_nickname()`,
+//! the users with the same normalized nickname are ordered by `UserId`.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! extern crate collections;
+//! extern crate im;
+//!
+//! use collections::ordered::OrderedDBMemory;
+//! use collections::users::*;
+//! use im::ordmap::OrdMap;
+//! use std::borrow::Cow;
+//!
+//! fn main() {
+//!     let mut users_source = OrderedDBMemory::new(OrdMap::new());
+//!     users_source.insert(User::new(UserId(4), Cow::Borrowed("Sara Delafon")));
+//!     users_source.insert(User::new(UserId(2), Cow::Borrowed("jacob Delafon")));
+//!     users_source.insert(User::new(UserId(5), Cow::Borrowed("Adam Daniel")));
+//!
+//!     let nicknames: Vec<String> = users_source
+//!         .iter_sorted_by_nickname()
+//!         .map(|user| user.get_nickname().to_string())
+//!         .collect();
+//!     assert_eq!(vec!["Adam Daniel", "jacob Delafon", "Sara Delafon"], nicknames);
+//!
+//!     assert_eq!(1, users_source.users_between("b", "s").len());
+//! }
+//! ```
+
+use im::hashmap::HashMap;
+use im::ordmap::OrdMap;
+use im::ordset::OrdSet;
+use users::{normalize_nickname, SortBy, User, UserId, UsersRepository};
+
+/// Sort key of a user: the normalized nickname and the id.
+type NicknameKey = (String, UserId);
+
+/// The type implements the Repository pattern over the users sorted by nickname.
+/// The index of the nicknames is updated by `insert()` and `remove()`.
+#[derive(Debug, Clone)]
+pub struct OrderedDBMemory {
+    users: OrdMap<UserId, User>,
+    nicknames: OrdSet<NicknameKey>,
+}
+
+/// Methods of type OrderedDBMemory.
+impl OrderedDBMemory {
+    /// Creates a new OrderedDBMemory object.
+    pub fn new(users: OrdMap<UserId, User>) -> Self {
+        let nicknames = users.iter().map(|(_, user)| nickname_key(user)).collect();
+        OrderedDBMemory { users, nicknames }
+    }
+
+    /// Adds the user or replaces the user with the same id,
+    /// returns the replaced one.
+    pub fn insert(&mut self, user: User) -> Option<User> {
+        let previous = self.remove(user.get_id());
+        self.nicknames.insert(nickname_key(&user));
+        self.users.insert(user.get_id().clone(), user);
+        previous
+    }
+
+    /// Removes the user by ID, returns the removed one.
+    pub fn remove(&mut self, id: &UserId) -> Option<User> {
+        let user = self.users.get(id).cloned()?;
+        self.users.remove(id);
+        self.nicknames.remove(&nickname_key(&user));
+        Some(user)
+    }
+
+    /// Number of the users.
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    /// Whether there are no users.
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    /// All the users in the order of their nicknames.
+    pub fn iter_sorted_by_nickname<'a>(&'a self) -> impl Iterator<Item = &'a User> + 'a {
+        self.nicknames
+            .iter()
+            .filter_map(move |key| self.users.get(&key.1))
+    }
+
+    /// The users with the nickname from `from` inclusive to `to` exclusive,
+    /// in the order of their nicknames: `users_between("a", "c")` are the nicknames
+    /// starting with "a" or "b".
+    pub fn users_between(&self, from: &str, to: &str) -> Vec<User> {
+        let from = normalize_nickname(from);
+        let to = normalize_nickname(to);
+        if from >= to {
+            return Vec::new();
+        }
+        // `UserId(0)` is the least key of a nickname.
+        self.nicknames
+            .range((from, UserId(0))..(to, UserId(0)))
+            .filter_map(|key| self.users.get(&key.1).cloned())
+            .collect()
+    }
+}
+
+/// Implementing the template Repocators for type `OrderedDBMemory`.
+impl UsersRepository for OrderedDBMemory {
+    /// User search by ID.
+    fn get_user_by_id(&self, id: UserId) -> Option<User> {
+        self.users.get(&id).cloned()
+    }
+
+    /// Search for all users that match the identifiers.
+    fn get_users_by_ids(&self, vec: Vec<UserId>) -> HashMap<UserId, User> {
+        vec.into_iter()
+            .filter_map(|id| self.users.get(&id).cloned().map(|user| (id, user)))
+            .collect()
+    }
+
+    /// Search for users by nickname, the ids are in the order of the nicknames.
+    fn get_ids_user_by_nickname(&self, nickname: &str) -> Vec<UserId> {
+        let nickname = nickname.to_lowercase();
+        self.iter_sorted_by_nickname()
+            .filter(|user| {
+                user.get_nickname()
+                    .to_lowercase()
+                    .contains(nickname.as_str())
+            })
+            .map(|user| user.get_id().clone())
+            .collect()
+    }
+
+    /// Page of the users, both orders are read from the maps without sorting.
+    fn get_users_page(&self, offset: usize, limit: usize, sort: SortBy) -> Vec<User> {
+        match sort {
+            SortBy::Id => self
+                .users
+                .values()
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect(),
+            SortBy::Nickname => self
+                .iter_sorted_by_nickname()
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+fn nickname_key(user: &User) -> NicknameKey {
+    (
+        normalize_nickname(user.get_nickname()),
+        user.get_id().clone(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::borrow::Cow;
+    use users::{get_ids_user_by_nickname, get_user_by_id};
+
+    fn users_source() -> OrderedDBMemory {
+        let mut users_source = OrderedDBMemory::new(OrdMap::new());
+        users_source.insert(User::new(UserId(4), Cow::Borrowed("Sara Delafon")));
+        users_source.insert(User::new(UserId(2), Cow::Borrowed("jacob Delafon")));
+        users_source.insert(User::new(UserId(5), Cow::Borrowed("Adam Daniel")));
+        users_source.insert(User::new(UserId(1), Cow::Borrowed("Sara Delafon")));
+        users_source
+    }
+
+    fn ids<'a, I: Iterator<Item = &'a User>>(users: I) -> Vec<usize> {
+        users.map(|user| user.get_id().0).collect()
+    }
+
+    #[test]
+    fn test_iter_sorted_by_nickname() {
+        let mut users_source = users_source();
+        assert_eq!(
+            vec![5, 2, 1, 4],
+            ids(users_source.iter_sorted_by_nickname())
+        );
+
+        // Renamed user moves to its new place.
+        let previous = users_source.insert(User::new(UserId(4), Cow::Borrowed("Bob")));
+        assert_eq!("Sara Delafon", previous.unwrap().get_nickname());
+        assert_eq!(
+            vec![5, 4, 2, 1],
+            ids(users_source.iter_sorted_by_nickname())
+        );
+
+        assert!(users_source.remove(&UserId(2)).is_some());
+        assert!(users_source.remove(&UserId(2)).is_none());
+        assert_eq!(vec![5, 4, 1], ids(users_source.iter_sorted_by_nickname()));
+        assert_eq!(3, users_source.len());
+    }
+
+    #[test]
+    fn test_users_between() {
+        let users_source = users_source();
+        assert_eq!(vec![2], ids(users_source.users_between("B", "s").iter()));
+        assert_eq!(
+            vec![2, 1, 4],
+            ids(users_source.users_between("j", "t").iter())
+        );
+        assert_eq!(
+            vec![5, 2, 1, 4],
+            ids(users_source.users_between("", "z").iter())
+        );
+        assert!(users_source.users_between("t", "a").is_empty());
+    }
+
+    #[test]
+    fn test_repository() {
+        let users_source = users_source();
+        assert!(get_user_by_id(&users_source, UserId(2)).is_some());
+        assert!(get_user_by_id(&users_source, UserId(8)).is_none());
+        assert_eq!(
+            vec![UserId(2), UserId(1), UserId(4)],
+            get_ids_user_by_nickname(&users_source, "delafon")
+        );
+        assert_eq!(
+            2,
+            users_source
+                .get_users_by_ids(vec![UserId(2), UserId(4), UserId(8)])
+                .len()
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! # The generic Repository pattern
+//!
+//! `Repository<Id, E>` is the storage of any entity by its id,
+//! `MemoryRepository` keeps the entities in an [OrdMap]:https://docs.rs/im/11.0.1/im/ordmap/struct.OrdMap.html,
+//! so they are listed in the order of their ids and the clone is a cheap snapshot.
+//! Every `Repository<UserId, User>` is a `UsersRepository`, see `UsersMemory`,
+//! and the same storage works for the posts of 1_1_type_safety
+//! once they implement `Entity<PostId>`.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! extern crate collections;
+//!
+//! use collections::repository::{MemoryRepository, Repository, UsersMemory};
+//! use collections::users::*;
+//! use std::borrow::Cow;
+//!
+//! fn main() {
+//!     let mut users_source: UsersMemory = MemoryRepository::new();
+//!     users_source.insert(User::new(UserId(4), Cow::Borrowed("Sara Delafon")));
+//!     users_source.insert(User::new(UserId(2), Cow::Borrowed("Jacob Delafon")));
+//!
+//!     assert_eq!(2, get_ids_user_by_nickname(&users_source, "delafon").len());
+//! }
+//! ```
+
+use im::hashmap::HashMap;
+use im::ordmap::OrdMap;
+use users::{normalize_nickname, SortBy, User, UserId, UsersRepository};
+
+/// Entity stored in a `Repository`, identified by `Id`.
+pub trait Entity<Id> {
+    /// Returns the id of the entity.
+    fn id(&self) -> &Id;
+}
+
+impl Entity<UserId> for User {
+    fn id(&self) -> &UserId {
+        self.get_id()
+    }
+}
+
+/// The generic `Repository` trait (interface) of the entities `E` by their ids `Id`.
+/// The entities are listed in the ascending order of their ids.
+pub trait Repository<Id, E> {
+    /// Entity by its id.
+    fn get(&self, id: &Id) -> Option<E>;
+
+    /// Entities with the ids, the missing ones are skipped.
+    fn get_many(&self, ids: &[Id]) -> Vec<E>;
+
+    /// Entities matching the predicate.
+    fn filter(&self, predicate: &dyn Fn(&E) -> bool) -> Vec<E>;
+
+    /// At most `limit` entities after the first `offset` ones.
+    fn page(&self, offset: usize, limit: usize) -> Vec<E>;
+
+    /// Adds the entity or replaces the entity with the same id, returns the replaced one.
+    fn insert(&mut self, entity: E) -> Option<E>;
+
+    /// Removes the entity by its id, returns the removed one.
+    fn remove(&mut self, id: &Id) -> Option<E>;
+
+    /// Number of the entities.
+    fn len(&self) -> usize;
+
+    /// Whether there are no entities.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The type implements `Repository` in memory.
+#[derive(Debug, Clone)]
+pub struct MemoryRepository<Id: Ord + Clone, E: Clone> {
+    entities: OrdMap<Id, E>,
+}
+
+/// The users in memory, it implements `UsersRepository`.
+pub type UsersMemory = MemoryRepository<UserId, User>;
+
+/// Methods of type MemoryRepository.
+impl<Id: Ord + Clone, E: Clone> MemoryRepository<Id, E> {
+    /// Creates an empty MemoryRepository object.
+    pub fn new() -> Self {
+        MemoryRepository {
+            entities: OrdMap::new(),
+        }
+    }
+}
+
+impl<Id: Ord + Clone, E: Clone> Default for MemoryRepository<Id, E> {
+    fn default() -> Self {
+        MemoryRepository::new()
+    }
+}
+
+impl<Id: Ord + Clone, E: Clone + Entity<Id>> Repository<Id, E> for MemoryRepository<Id, E> {
+    fn get(&self, id: &Id) -> Option<E> {
+        self.entities.get(id).cloned()
+    }
+
+    fn get_many(&self, ids: &[Id]) -> Vec<E> {
+        ids.iter()
+            .filter_map(|id| self.entities.get(id).cloned())
+            .collect()
+    }
+
+    fn filter(&self, predicate: &dyn Fn(&E) -> bool) -> Vec<E> {
+        self.entities
+            .values()
+            .filter(|entity| predicate(entity))
+            .cloned()
+            .collect()
+    }
+
+    fn page(&self, offset: usize, limit: usize) -> Vec<E> {
+        self.entities
+            .values()
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn insert(&mut self, entity: E) -> Option<E> {
+        let previous = self.entities.get(entity.id()).cloned();
+        self.entities.insert(entity.id().clone(), entity);
+        previous
+    }
+
+    fn remove(&mut self, id: &Id) -> Option<E> {
+        let previous = self.entities.get(id).cloned();
+        self.entities.remove(id);
+        previous
+    }
+
+    fn len(&self) -> usize {
+        self.entities.len()
+    }
+}
+
+/// `UsersRepository` is the `Repository` of the users:
+/// the search by nickname is the filter ignoring the case.
+impl<R: Repository<UserId, User>> UsersRepository for R {
+    /// User search by ID.
+    fn get_user_by_id(&self, id: UserId) -> Option<User> {
+        self.get(&id)
+    }
+
+    /// Search for all users that match the identifiers.
+    fn get_users_by_ids(&self, vec: Vec<UserId>) -> HashMap<UserId, User> {
+        self.get_many(&vec)
+            .into_iter()
+            .map(|user| (user.get_id().clone(), user))
+            .collect()
+    }
+
+    /// Search for users by nickname, the ids are in ascending order.
+    fn get_ids_user_by_nickname(&self, nickname: &str) -> Vec<UserId> {
+        let nickname = nickname.to_lowercase();
+        self.filter(&|user: &User| user.get_nickname().to_lowercase().contains(&nickname))
+            .iter()
+            .map(|user| user.get_id().clone())
+            .collect()
+    }
+
+    /// Page of the users, the users are sorted by nickname on every call.
+    fn get_users_page(&self, offset: usize, limit: usize, sort: SortBy) -> Vec<User> {
+        match sort {
+            SortBy::Id => self.page(offset, limit),
+            SortBy::Nickname => {
+                let mut users = self.filter(&|_| true);
+                users.sort_by_key(|user| {
+                    (
+                        normalize_nickname(user.get_nickname()),
+                        user.get_id().clone(),
+                    )
+                });
+                users.into_iter().skip(offset).take(limit).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::borrow::Cow;
+    use users::{get_ids_user_by_nickname, get_user_by_id, get_users_page};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Post {
+        id: u64,
+        title: &'static str,
+    }
+
+    impl Entity<u64> for Post {
+        fn id(&self) -> &u64 {
+            &self.id
+        }
+    }
+
+    #[test]
+    fn test_memory_repository() {
+        let mut posts: MemoryRepository<u64, Post> = MemoryRepository::new();
+        assert!(posts.is_empty());
+        posts.insert(Post {
+            id: 3,
+            title: "Immutable collections",
+        });
+        posts.insert(Post {
+            id: 1,
+            title: "Type safety",
+        });
+        let snapshot = posts.clone();
+
+        let previous = posts.insert(Post {
+            id: 3,
+            title: "Persistent collections",
+        });
+        assert_eq!("Immutable collections", previous.unwrap().title);
+        assert_eq!("Immutable collections", snapshot.get(&3).unwrap().title);
+        assert_eq!(2, posts.len());
+
+        assert_eq!(vec![1], ids(&posts.page(0, 1)));
+        assert_eq!(vec![3], ids(&posts.get_many(&[3, 7])));
+        assert_eq!(
+            vec![3],
+            ids(&posts.filter(&|post: &Post| post.title.contains("Persistent")))
+        );
+
+        assert!(posts.remove(&1).is_some());
+        assert!(posts.remove(&1).is_none());
+        assert_eq!(vec![3], ids(&posts.page(0, 10)));
+        assert_eq!(vec![1, 3], ids(&snapshot.page(0, 10)));
+    }
+
+    fn ids(posts: &[Post]) -> Vec<u64> {
+        posts.iter().map(|post| post.id).collect()
+    }
+
+    #[test]
+    fn test_users_memory() {
+        let mut users_source: UsersMemory = MemoryRepository::new();
+        users_source.insert(User::new(UserId(4), Cow::Borrowed("Sara Delafon")));
+        users_source.insert(User::new(UserId(2), Cow::Borrowed("jacob Delafon")));
+        users_source.insert(User::new(UserId(5), Cow::Borrowed("Adam Daniel")));
+
+        assert!(get_user_by_id(&users_source, UserId(2)).is_some());
+        assert_eq!(
+            vec![UserId(2), UserId(4)],
+            get_ids_user_by_nickname(&users_source, "DELAFON")
+        );
+        let nicknames: Vec<String> = get_users_page(&users_source, 1, 2, SortBy::Nickname)
+            .iter()
+            .map(|user| user.get_nickname().to_string())
+            .collect();
+        assert_eq!(vec!["jacob Delafon", "Sara Delafon"], nicknames);
+    }
+}
@@ -0,0 +1,274 @@
+//! # Generators of the users for the property-based tests
+//!
+//! [Proptest]:https://docs.rs/proptest strategies of `UserId` and `User`,
+//! enabled by the `testing` feature for the steps implementing `UsersRepository`.
+//! The properties check that the repositories find every inserted user,
+//! `check_repository()` is the contract shared by all the implementations.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! extern crate collections;
+//! extern crate proptest;
+//!
+//! use collections::testing::user;
+//! use proptest::strategy::{Strategy, ValueTree};
+//! use proptest::test_runner::TestRunner;
+//! use std::error::Error;
+//!
+//! fn main() -> Result<(), Box<dyn Error>> {
+//!     let mut runner = TestRunner::default();
+//!     let user = user()
+//!         .new_tree(&mut runner)
+//!         .map_err(|reason| reason.to_string())?
+//!         .current();
+//!     assert!(!user.get_nickname().is_empty());
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use im::ordmap::OrdMap;
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+use std::borrow::Cow;
+use users::normalize_nickname;
+use users::{get_ids_user_by_nickname, get_user_by_id, get_users_by_ids, get_users_page};
+use users::{SortBy, User, UserId, UsersRepository};
+
+/// Identifiers from a small range, so the generated users share them.
+pub fn user_id() -> impl Strategy<Value = UserId> {
+    (0..64usize).prop_map(UserId)
+}
+
+/// Nicknames of the letters in both cases and spaces, like "jacob Delafon".
+pub fn nickname() -> impl Strategy<Value = String> {
+    "[A-Za-z][A-Za-z ]{0,15}"
+}
+
+pub fn user() -> impl Strategy<Value = User> {
+    (user_id(), nickname()).prop_map(|(id, nickname)| User::new(id, Cow::Owned(nickname)))
+}
+
+/// Up to `max` users, the same id can appear more than once.
+pub fn users(max: usize) -> impl Strategy<Value = Vec<User>> {
+    prop::collection::vec(user(), 0..max)
+}
+
+/// Checks that `repository` holds exactly `users`, the last user with the same id wins:
+/// every user is found by its id and its nickname, `missing` is found only if it is one of them,
+/// the pages of the users make up all of them in the same order,
+/// and it is the order of `SortBy` shared by all the implementations.
+pub fn check_repository<R: UsersRepository>(
+    repository: &R,
+    users: &[User],
+    missing: UserId,
+) -> Result<(), TestCaseError> {
+    let mut last: OrdMap<UserId, User> = OrdMap::new();
+    for user in users {
+        last.insert(user.get_id().clone(), user.clone());
+    }
+    let ids: Vec<UserId> = last.keys().cloned().collect();
+    for id in &ids {
+        let user = &last[id];
+        let found = get_user_by_id(repository, id.clone());
+        prop_assert_eq!(
+            Some(user.get_nickname()),
+            found.as_ref().map(User::get_nickname)
+        );
+        prop_assert!(get_ids_user_by_nickname(repository, user.get_nickname()).contains(id));
+    }
+    prop_assert_eq!(ids.len(), get_users_by_ids(repository, ids.clone()).len());
+    prop_assert_eq!(
+        last.contains_key(&missing),
+        get_user_by_id(repository, missing).is_some()
+    );
+
+    let page_ids = |offset, limit, sort| -> Vec<UserId> {
+        get_users_page(repository, offset, limit, sort)
+            .iter()
+            .map(|user| user.get_id().clone())
+            .collect()
+    };
+    prop_assert_eq!(&ids, &page_ids(0, ids.len() + 1, SortBy::Id));
+    let mut by_nickname: Vec<(String, UserId)> = last
+        .values()
+        .map(|user| {
+            (
+                normalize_nickname(user.get_nickname()),
+                user.get_id().clone(),
+            )
+        })
+        .collect();
+    by_nickname.sort();
+    let by_nickname: Vec<UserId> = by_nickname.into_iter().map(|(_, id)| id).collect();
+    prop_assert_eq!(&by_nickname, &page_ids(0, ids.len(), SortBy::Nickname));
+    for &sort in &[SortBy::Id, SortBy::Nickname] {
+        let all = page_ids(0, ids.len(), sort);
+        let pages: Vec<UserId> = (0..ids.len())
+            .step_by(3)
+            .flat_map(|offset| page_ids(offset, 3, sort))
+            .collect();
+        prop_assert_eq!(ids.len(), all.len());
+        prop_assert_eq!(all, pages);
+    }
+    Ok(())
+}
+
+impl Arbitrary for UserId {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<UserId>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        user_id().boxed()
+    }
+}
+
+impl Arbitrary for User {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<User>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        user().boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use file::DBFile;
+    use im::hashmap::HashMap;
+    use ordered::OrderedDBMemory;
+    use repository::{MemoryRepository, Repository, UsersMemory};
+    use std::env;
+    use std::fs;
+    use users::{get_ids_user_by_nickname_mock, get_user_by_id_mock};
+    use users::{DBMemory, UsersRepositoryMut};
+
+    proptest! {
+        #[test]
+        fn test_db_memory_lookup(users in users(32)) {
+            let map: HashMap<UserId, User> = users
+                .iter()
+                .map(|user| (user.get_id().clone(), user.clone()))
+                .collect();
+            let users_source = DBMemory::new(map.clone());
+            for (id, user) in map.iter() {
+                let found = get_user_by_id(&users_source, id.clone()).unwrap();
+                prop_assert_eq!(user.get_nickname(), found.get_nickname());
+            }
+        }
+
+        #[test]
+        fn test_ordered_insert_lookup(users in users(32), missing in any::<UserId>()) {
+            let mut users_source = OrderedDBMemory::new(OrdMap::new());
+            let mut last: OrdMap<UserId, User> = OrdMap::new();
+            for user in users {
+                users_source.insert(user.clone());
+                last.insert(user.get_id().clone(), user);
+            }
+            prop_assert_eq!(last.len(), users_source.len());
+            for (id, user) in last.iter() {
+                let found = get_user_by_id(&users_source, id.clone()).unwrap();
+                prop_assert_eq!(user.get_nickname(), found.get_nickname());
+                prop_assert!(get_ids_user_by_nickname(&users_source, user.get_nickname())
+                    .contains(id));
+            }
+            prop_assert_eq!(
+                last.contains_key(&missing),
+                get_user_by_id(&users_source, missing).is_some()
+            );
+        }
+
+        #[test]
+        fn test_ordered_sorted(users in users(32)) {
+            let users_source = OrderedDBMemory::new(
+                users
+                    .into_iter()
+                    .map(|user| (user.get_id().clone(), user))
+                    .collect(),
+            );
+            let nicknames: Vec<String> = users_source
+                .iter_sorted_by_nickname()
+                .map(|user| normalize_nickname(user.get_nickname()))
+                .collect();
+            prop_assert_eq!(users_source.len(), nicknames.len());
+            prop_assert!(nicknames.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+
+        #[test]
+        fn test_snapshot_unaffected(
+            users in users(32),
+            changes in users(32),
+            removed in prop::collection::vec(any::<UserId>(), 0..8),
+            missing in any::<UserId>(),
+        ) {
+            let mut users_source = DBMemory::new(HashMap::new());
+            for user in &users {
+                users_source.add_user(user.clone()).unwrap();
+            }
+            let snapshot = users_source.snapshot();
+
+            for user in changes {
+                get_user_by_id_mock(&mut users_source, user.get_id().clone());
+                let nickname = Cow::Owned(user.get_nickname().to_string());
+                let _ = users_source.update_nickname(user.get_id(), nickname);
+                get_ids_user_by_nickname_mock(&mut users_source, "Delafon");
+            }
+            for id in &removed {
+                let _ = users_source.delete_user(id);
+            }
+
+            // The restored snapshot holds exactly the users before the changes.
+            users_source.restore(snapshot);
+            check_repository(&users_source, &users, missing)?;
+        }
+
+        #[test]
+        fn test_contract_db_memory(users in users(32), missing in any::<UserId>()) {
+            let mut users_source = DBMemory::new(HashMap::new());
+            for user in &users {
+                users_source.add_user(user.clone()).unwrap();
+            }
+            check_repository(&users_source, &users, missing)?;
+        }
+
+        #[test]
+        fn test_contract_ordered(users in users(32), missing in any::<UserId>()) {
+            let mut users_source = OrderedDBMemory::new(OrdMap::new());
+            for user in &users {
+                users_source.insert(user.clone());
+            }
+            check_repository(&users_source, &users, missing)?;
+        }
+
+        #[test]
+        fn test_contract_memory_repository(users in users(32), missing in any::<UserId>()) {
+            let mut users_source: UsersMemory = MemoryRepository::new();
+            for user in &users {
+                users_source.insert(user.clone());
+            }
+            check_repository(&users_source, &users, missing)?;
+        }
+    }
+
+    proptest! {
+        // Every insert rewrites the file.
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_contract_db_file(users in users(32), missing in any::<UserId>()) {
+            let path = env::temp_dir().join(format!("contract_{}.json", ::std::process::id()));
+            let _ = fs::remove_file(&path);
+            let mut users_source = DBFile::open(&path).unwrap();
+            for user in &users {
+                users_source.insert(user.clone()).unwrap();
+            }
+            let reopened = DBFile::open(&path).unwrap();
+            let _ = fs::remove_file(&path);
+            check_repository(&reopened, &users, missing)?;
+        }
+    }
+}
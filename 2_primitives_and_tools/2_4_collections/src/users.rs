@@ -0,0 +1,1642 @@
+//! # Applying an immutable collection with pattern Repository
+//!
+//! The module implements simple methods of applying [immutable collections]:
+//! https://docs.rs/im/11.0.1/im.
+//! Used as a [HashMap]:https://docs.rs/im/11.0.1/im/hashmap/struct.HashMap.html
+//! of users, a search is performed for id and nickname.
+//! An example of a `HashMap` change in place was implemented.
+//! `DBMemory::add_user()` can require the nicknames to be unique,
+//! it is checked by the index of the normalized nicknames.
+//! `UsersRepositoryMut` changes the users: the maps of `DBMemory` are persistent,
+//! so a clone taken before the change is a snapshot which keeps the old users
+//! and shares the unchanged ones with the new version.
+//! `DBMemory::snapshot()` captures the users, `DBMemory::restore()` rolls back to them.
+//! `DBMemory::export_json()` and `DBMemory::import_json()` exchange the users
+//! as a JSON array of `{"id": 4, "nickname": "Sara Delafon"}` objects.
+//! The `RepositoryObserver`s subscribed by `DBMemory::subscribe()` are notified
+//! of every added, replaced and deleted user, the mock methods included.
+//!
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! extern crate collections;
+//! extern crate im;
+//!
+//! use collections::users::*;
+//! use im::hashmap::HashMap;
+//! use std::borrow::Cow;
+//!
+//! fn main() {
+//!     let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+//!
+//!     let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+//!     map_users.insert(user.get_id().clone(), user);
+//!     let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+//!     map_users.insert(user.get_id().clone(), user);
+//!
+//!     let users_source: DBMemory = DBMemory::new(map_users);
+//!
+//!     assert!(get_user_by_id(&users_source, UserId(2)).is_some());
+//! }
+//! ```
+
+use im::hashmap::HashMap;
+use im::ordset::OrdSet;
+use serde_json;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+/// The simple `Repository` trait (interface) which supports 4 operations:
+///  - returns single `User` by its ID;
+///  - returns multiple `User`s by their IDs;
+///  - return IDs of `User`s which `nickname` contains given string (search function),
+///    or matches it by `MatchMode`;
+///  - returns a page of `User`s sorted by ID or nickname;
+pub trait UsersRepository {
+    /// User search by ID.
+    fn get_user_by_id(&self, id: UserId) -> Option<User>;
+
+    /// Search for all users that match the identifiers.
+    fn get_users_by_ids(&self, vec: Vec<UserId>) -> HashMap<UserId, User>;
+
+    /// Search for users by nickname.
+    fn get_ids_user_by_nickname(&self, nickname: &str) -> Vec<UserId>;
+
+    /// At most `limit` users after the first `offset` ones in the order of `sort`.
+    fn get_users_page(&self, offset: usize, limit: usize, sort: SortBy) -> Vec<User>;
+
+    /// Search for users by nickname with the `mode`, the ids are in ascending order.
+    /// All the users are read by `get_users_page()`.
+    fn get_ids_user_by_nickname_with(&self, nickname: &str, mode: MatchMode) -> Vec<UserId> {
+        self.get_users_page(0, usize::MAX, SortBy::Id)
+            .into_iter()
+            .filter(|user| mode.matches(user.get_nickname(), nickname))
+            .map(|user| user.get_id().clone())
+            .collect()
+    }
+}
+
+/// How `get_ids_user_by_nickname_with()` compares the nicknames, ignoring the case.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// The nickname is the search string.
+    Exact,
+    /// The nickname contains the search string, as `get_ids_user_by_nickname()`.
+    #[default]
+    Contains,
+    /// The `levenshtein()` distance between the nickname and the search string
+    /// is at most the given number of edits.
+    Levenshtein(usize),
+}
+
+impl MatchMode {
+    /// Whether the nickname matches the search string.
+    pub fn matches(&self, nickname: &str, search: &str) -> bool {
+        let nickname = nickname.to_lowercase();
+        let search = search.to_lowercase();
+        match *self {
+            MatchMode::Exact => nickname == search,
+            MatchMode::Contains => nickname.contains(&search),
+            MatchMode::Levenshtein(max_distance) => levenshtein(&nickname, &search) <= max_distance,
+        }
+    }
+}
+
+/// The number of the inserted, deleted and substituted characters
+/// turning `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // The distances from the prefix of `a` to every prefix of `b`.
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + if a_char == *b_char { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Order of the users of `get_users_page()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    /// Ascending `UserId`.
+    Id,
+    /// Nickname normalized by `normalize_nickname()`,
+    /// the users with the same normalized nickname by `UserId`.
+    Nickname,
+}
+
+/// The `Repository` trait (interface) of the changes of the users:
+///  - adds a new `User`;
+///  - changes the `nickname` of a `User`;
+///  - deletes a `User` by its ID;
+pub trait UsersRepositoryMut {
+    /// Adds the user, fails if its ID is taken.
+    fn insert_user(&mut self, user: User) -> Result<(), WriteError>;
+
+    /// Changes the nickname of the user, returns the user before the change.
+    fn update_nickname(
+        &mut self,
+        id: &UserId,
+        nickname: Cow<'static, str>,
+    ) -> Result<User, WriteError>;
+
+    /// Deletes the user, returns the deleted one.
+    fn delete_user(&mut self, id: &UserId) -> Result<User, WriteError>;
+}
+
+/// Observer of the changes of the users of `DBMemory`, see `DBMemory::subscribe()`.
+/// The methods are called after the change, the default ones do nothing.
+pub trait RepositoryObserver: Send + Sync {
+    /// The user is added.
+    fn on_insert(&self, _user: &User) {}
+
+    /// The user with the same id is replaced.
+    fn on_update(&self, _previous: &User, _user: &User) {}
+
+    /// The user is deleted.
+    fn on_delete(&self, _user: &User) {}
+}
+
+/// Mock implementation of `UsersRepository` trait which allows in-place setup of returned values.
+pub trait UsersRepositoryMock {
+    /// Search for a user by ID or create a user with this ID.
+    fn get_user_by_id_mock(&mut self, id: UserId) -> Option<User>;
+
+    /// Search for all users who match IDs or create users.
+    /// In the absence of users, they are created with these identifiers.
+    fn get_users_by_ids_mock(&mut self, vec: Vec<UserId>) -> HashMap<UserId, User>;
+
+    /// Search for users by nickname.
+    /// If there is no result, a user with nickname.
+    fn get_ids_user_by_nickname_mock(&mut self, nickname: &'static str) -> Vec<UserId>;
+}
+
+/// Simple user type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    id: UserId,
+    nickname: Cow<'static, str>,
+}
+
+/// Simple identifier type for `User` type.
+/// will be used as a key in hashmap for this we implement a crunchy tarit:
+/// Eq,Ord,PartialOrd,PartialEq.
+#[derive(Eq, Debug, Clone, Hash, Serialize, Deserialize)]
+pub struct UserId(pub usize);
+
+/// ## Implementation of tarit for comparing identifiers.
+
+/// Implementation of Ord for UserId.
+impl Ord for UserId {
+    fn cmp(&self, other: &UserId) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+/// Implementation of PartialOrd for UserId.
+impl PartialOrd for UserId {
+    fn partial_cmp(&self, other: &UserId) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+/// Implementation of PartialEq for UserId.
+impl PartialEq for UserId {
+    fn eq(&self, other: &UserId) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// Error of `DBMemory::add_user()`: the nickname is taken by the user with the id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateNickname(pub UserId);
+
+impl fmt::Display for DuplicateNickname {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the nickname is taken by the user {}", (self.0).0)
+    }
+}
+
+impl Error for DuplicateNickname {}
+
+/// Error of `UsersRepositoryMut`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteError {
+    /// The id is taken by another user.
+    DuplicateId(UserId),
+    /// The nickname is taken by the user with the id.
+    DuplicateNickname(UserId),
+    /// There is no user with the id.
+    NotFound(UserId),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WriteError::DuplicateId(ref id) => write!(f, "the user {} already exists", id.0),
+            WriteError::DuplicateNickname(ref id) => DuplicateNickname(id.clone()).fmt(f),
+            WriteError::NotFound(ref id) => write!(f, "there is no user {}", id.0),
+        }
+    }
+}
+
+impl Error for WriteError {}
+
+impl From<DuplicateNickname> for WriteError {
+    fn from(err: DuplicateNickname) -> Self {
+        WriteError::DuplicateNickname(err.0)
+    }
+}
+
+/// What `DBMemory::import_json()` does with a user whose id is taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnCollision {
+    /// Keeps the present user.
+    Skip,
+    /// Replaces the present user with the imported one.
+    Overwrite,
+    /// Fails the import.
+    Error,
+}
+
+/// Error of `DBMemory::import_json()`.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The input is not a JSON array of users.
+    Json(serde_json::Error),
+    /// The imported user can't be added.
+    Write(WriteError),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImportError::Json(ref err) => write!(f, "invalid users: {}", err),
+            ImportError::Write(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ImportError {}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(err: serde_json::Error) -> Self {
+        ImportError::Json(err)
+    }
+}
+
+impl From<WriteError> for ImportError {
+    fn from(err: WriteError) -> Self {
+        ImportError::Write(err)
+    }
+}
+
+/// The nickname compared by the unique constraint:
+/// lowercase, with the whitespaces trimmed and collapsed.
+pub fn normalize_nickname(nickname: &str) -> String {
+    nickname
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Key of the index of the nicknames: the normalized nickname and the id.
+type NicknameKey = (String, UserId);
+
+/// The type implements the Repository pattern.
+/// The users field is private to hide direct access to methods such as DBMemory.
+/// To work with methods like `DBMemory` implements the trait `UsersRepository`:
+/// `get_user_by_id()`,`get_users_by_ids()`,`get_ids_user_by_nickname()`.
+/// To work with methods like `DBMemory` implements the trait `UsersRepositoryMock`:
+/// `get_user_by_id_mock()`,`get_users_by_ids_mock()`,`get_ids_user_by_nickname_mock()`.
+/// The index of the nicknames is updated with the users.
+/// The clone is cheap: the maps share their nodes until one of them is changed.
+/// The clone keeps the observers.
+#[derive(Debug, Clone)]
+pub struct DBMemory {
+    users: HashMap<UserId, User>,
+    nicknames: OrdSet<NicknameKey>,
+    unique_nicknames: bool,
+    observers: Observers,
+}
+
+/// The observers of `DBMemory`.
+#[derive(Clone, Default)]
+struct Observers(Vec<Arc<dyn RepositoryObserver>>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+impl Observers {
+    /// Reports the user put in place of `previous`.
+    fn put(&self, previous: Option<&User>, user: &User) {
+        for observer in &self.0 {
+            match previous {
+                Some(previous) => observer.on_update(previous, user),
+                None => observer.on_insert(user),
+            }
+        }
+    }
+
+    fn delete(&self, user: &User) {
+        for observer in &self.0 {
+            observer.on_delete(user);
+        }
+    }
+}
+
+/// Methods of type DBMemory.
+impl DBMemory {
+    /// Creates a new DBMemory object.
+    /// The nicknames are not required to be unique, see `set_unique_nicknames()`.
+    pub fn new(users: HashMap<UserId, User>) -> Self {
+        let nicknames = users.iter().map(|(_, user)| nickname_key(user)).collect();
+        DBMemory {
+            users,
+            nicknames,
+            unique_nicknames: false,
+            observers: Observers::default(),
+        }
+    }
+
+    /// Adds the observer of the changes of the users.
+    /// `restore()` replaces the users without notifying the observers.
+    pub fn subscribe(&mut self, observer: Arc<dyn RepositoryObserver>) {
+        self.observers.0.push(observer);
+    }
+
+    /// Enables or disables the unique constraint of the nicknames of `add_user()`.
+    /// The users added before are not checked.
+    pub fn set_unique_nicknames(&mut self, unique: bool) {
+        self.unique_nicknames = unique;
+    }
+
+    /// Adds the user or replaces the user with the same id, returns the replaced one.
+    /// With the unique constraint it fails if the normalized nickname is taken
+    /// by another user.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+    /// users_source.set_unique_nicknames(true);
+    ///
+    /// assert!(users_source.add_user(User::new(UserId(4), Cow::Borrowed("Sara Delafon"))).is_ok());
+    /// assert_eq!(
+    ///     Some(DuplicateNickname(UserId(4))),
+    ///     users_source
+    ///         .add_user(User::new(UserId(2), Cow::Borrowed("sara  delafon")))
+    ///         .err()
+    /// );
+    /// # }
+    /// ```
+    pub fn add_user(&mut self, user: User) -> Result<Option<User>, DuplicateNickname> {
+        self.check_nickname(user.get_nickname(), user.get_id())?;
+        Ok(self.put(user))
+    }
+
+    /// Captures the users, the later changes of the `DBMemory` don't affect the snapshot.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+    /// let snapshot = users_source.snapshot();
+    ///
+    /// get_user_by_id_mock(&mut users_source, UserId(4));
+    /// assert_eq!(0, snapshot.len());
+    ///
+    /// users_source.restore(snapshot);
+    /// assert!(get_user_by_id(&users_source, UserId(4)).is_none());
+    /// # }
+    /// ```
+    pub fn snapshot(&self) -> DBMemorySnapshot {
+        DBMemorySnapshot {
+            users: self.users.clone(),
+            nicknames: self.nicknames.clone(),
+        }
+    }
+
+    /// Replaces the users with the snapshot,
+    /// the unique constraint of the nicknames is kept as it is.
+    pub fn restore(&mut self, snapshot: DBMemorySnapshot) {
+        self.users = snapshot.users;
+        self.nicknames = snapshot.nicknames;
+    }
+
+    /// Writes the users sorted by id as a JSON array.
+    pub fn export_json<W: io::Write>(&self, writer: W) -> serde_json::Result<()> {
+        let mut users: Vec<&User> = self.users.values().collect();
+        users.sort_by_key(|user| user.get_id());
+        serde_json::to_writer_pretty(writer, &users)
+    }
+
+    /// Adds the users of the JSON array, `on_collision` decides what happens
+    /// to the users whose id is taken, also by a user imported before.
+    /// Returns the number of the added and replaced users.
+    /// The import is all or nothing: on error the users are left as they were.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+    /// let json = r#"[{"id": 4, "nickname": "Sara Delafon"}]"#;
+    ///
+    /// assert_eq!(1, users_source.import_json(json.as_bytes(), OnCollision::Error)?);
+    /// assert_eq!(0, users_source.import_json(json.as_bytes(), OnCollision::Skip)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn import_json<R: io::Read>(
+        &mut self,
+        reader: R,
+        on_collision: OnCollision,
+    ) -> Result<usize, ImportError> {
+        let users: Vec<User> = serde_json::from_reader(reader)?;
+        // The observers are notified after the import succeeds.
+        let mut imported = self.clone();
+        imported.observers = Observers::default();
+        let mut changes = Vec::new();
+        for user in users {
+            if imported.users.contains_key(user.get_id()) {
+                match on_collision {
+                    OnCollision::Skip => continue,
+                    OnCollision::Overwrite => {}
+                    OnCollision::Error => {
+                        return Err(WriteError::DuplicateId(user.get_id().clone()).into())
+                    }
+                }
+            }
+            let previous = imported.add_user(user.clone()).map_err(WriteError::from)?;
+            changes.push((previous, user));
+        }
+        self.restore(imported.snapshot());
+        for (previous, user) in &changes {
+            self.observers.put(previous.as_ref(), user);
+        }
+        Ok(changes.len())
+    }
+
+    /// `DuplicateNickname` if the constraint is enabled and the nickname is taken
+    /// by a user other than `id`.
+    fn check_nickname(&self, nickname: &str, id: &UserId) -> Result<(), DuplicateNickname> {
+        if self.unique_nicknames {
+            if let Some(owner) = self.nickname_owner(nickname, id) {
+                return Err(DuplicateNickname(owner));
+            }
+        }
+        Ok(())
+    }
+
+    /// Id of another user with the normalized nickname,
+    /// the users of the nickname are the range of the index from `UserId(0)`.
+    fn nickname_owner(&self, nickname: &str, id: &UserId) -> Option<UserId> {
+        let nickname = normalize_nickname(nickname);
+        self.nicknames
+            .range((nickname.clone(), UserId(0))..)
+            .take_while(|key| key.0 == nickname)
+            .find(|key| &key.1 != id)
+            .map(|key| key.1.clone())
+    }
+
+    /// Inserts the user with the index of its nickname, returns the replaced one.
+    /// The observers are notified once the user is stored.
+    fn put(&mut self, user: User) -> Option<User> {
+        let previous = self.users.remove(user.get_id());
+        if let Some(ref previous) = previous {
+            self.nicknames.remove(&nickname_key(previous));
+        }
+        self.nicknames.insert(nickname_key(&user));
+        self.users.insert(user.get_id().clone(), user.clone());
+        self.observers.put(previous.as_ref(), &user);
+        previous
+    }
+}
+
+/// The users of `DBMemory` captured by `DBMemory::snapshot()`.
+/// It shares the nodes of the maps with the `DBMemory`, so it is cheap to take.
+#[derive(Debug, Clone)]
+pub struct DBMemorySnapshot {
+    users: HashMap<UserId, User>,
+    nicknames: OrdSet<NicknameKey>,
+}
+
+/// Methods of type DBMemorySnapshot.
+impl DBMemorySnapshot {
+    /// The captured users.
+    pub fn users(&self) -> &HashMap<UserId, User> {
+        &self.users
+    }
+
+    /// Number of the users.
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    /// Whether there are no users.
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+}
+
+fn nickname_key(user: &User) -> NicknameKey {
+    (normalize_nickname(user.get_nickname()), user.get_id().clone())
+}
+
+/// Methods of type User.
+/// Implemented set and get methods for private fields.
+impl User {
+    /// Creates a new User object.
+    pub fn new(id: UserId, nickname: Cow<'static, str>) -> Self {
+        User { id, nickname }
+    }
+
+    /// Returns the private field `id`.
+    pub fn get_id(&self) -> &UserId {
+        &self.id
+    }
+
+    /// Returns the private field `nickname`.
+    pub fn get_nickname(&self) -> &str {
+        &self.nickname
+    }
+
+    /// Sets the value for the `nickname` field.
+    pub fn set_nickname(&mut self, nickname: Cow<'static, str>) {
+        self.nickname = nickname;
+    }
+}
+
+/// Implementing the template Repocators for type `DBMemory`
+impl UsersRepository for DBMemory {
+    /// User search by ID.
+    /// Access to the method via the function `get_user_by_id()`.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+    ///
+    /// let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    /// let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    ///
+    /// let users_source: DBMemory = DBMemory::new(map_users);
+    ///
+    /// assert!(get_user_by_id(&users_source, UserId(2)).is_some());
+    /// # }
+    /// ```
+    fn get_user_by_id(&self, id: UserId) -> Option<User> {
+        if let Some(user) = self.users.get(&id) {
+            return Some(user.clone());
+        };
+        None
+    }
+
+    /// Search for all users that match the identifiers.
+    /// Access to the method via the function `get_users_by_ids()`.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+    ///
+    /// let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    /// let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    /// let user = User::new(UserId(5usize), Cow::Borrowed("Sara Daniel"));
+    /// map_users.insert(user.get_id().clone(), user);
+    ///
+    /// let users_source: DBMemory = DBMemory::new(map_users);
+    ///
+    /// let v = get_users_by_ids(&users_source, vec![UserId(2), UserId(4)]);
+    ///
+    /// assert_eq!(2, v.len());
+    /// # }
+    /// ```
+    fn get_users_by_ids(&self, vec: Vec<UserId>) -> HashMap<UserId, User> {
+        self.users
+            .iter()
+            .filter(|(_, ref value)| vec.contains(&value.get_id()))
+            .cloned()
+            .collect::<HashMap<UserId, User>>()
+    }
+
+    /// Search for users by nickname.
+    /// Access to the method via the function `get_ids_user_by_nickname()`.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+    ///
+    /// let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    /// let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    ///
+    /// let users_source: DBMemory = DBMemory::new(map_users);
+    /// let ids: Vec<UserId> = get_ids_user_by_nickname(&users_source, "Delafon");
+    ///
+    /// assert_eq!(2, ids.len());
+    /// # }
+    /// ```
+    fn get_ids_user_by_nickname(&self, nickname: &str) -> Vec<UserId> {
+        let nickname = nickname.to_lowercase();
+        let nickname: &str = nickname.as_str();
+        let map: HashMap<UserId, User> = self.users
+            .iter()
+            .filter(|(_, ref value)| value.get_nickname().to_lowercase().contains(nickname))
+            .cloned()
+            .collect::<HashMap<UserId, User>>();
+        map.keys().cloned().collect::<Vec<UserId>>()
+    }
+
+    /// Page of the users.
+    /// Access to the method via the function `get_users_page()`.
+    /// The users sorted by nickname are read from the index of the nicknames,
+    /// the ids of the `HashMap` are sorted on every call.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+    ///
+    /// let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    /// let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+    /// map_users.insert(user.get_id().clone(), user);
+    ///
+    /// let users_source: DBMemory = DBMemory::new(map_users);
+    /// let users: Vec<User> = get_users_page(&users_source, 1, 10, SortBy::Id);
+    ///
+    /// assert_eq!(&UserId(4), users[0].get_id());
+    /// # }
+    /// ```
+    fn get_users_page(&self, offset: usize, limit: usize, sort: SortBy) -> Vec<User> {
+        match sort {
+            SortBy::Id => {
+                let mut ids: Vec<&UserId> = self.users.keys().collect();
+                ids.sort();
+                ids.into_iter()
+                    .skip(offset)
+                    .take(limit)
+                    .filter_map(|id| self.users.get(id).cloned())
+                    .collect()
+            }
+            SortBy::Nickname => self
+                .nicknames
+                .iter()
+                .skip(offset)
+                .take(limit)
+                .filter_map(|key| self.users.get(&key.1).cloned())
+                .collect(),
+        }
+    }
+}
+/// Implementing the changes of the users for type `DBMemory`.
+/// The unique constraint of the nicknames of `add_user()` is applied as well.
+impl UsersRepositoryMut for DBMemory {
+    /// Adds the user.
+    /// Access to the method via the function `insert_user()`.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+    /// let snapshot = users_source.clone();
+    ///
+    /// insert_user(&mut users_source, User::new(UserId(4), Cow::Borrowed("Sara Delafon")))?;
+    ///
+    /// assert!(get_user_by_id(&users_source, UserId(4)).is_some());
+    /// assert!(get_user_by_id(&snapshot, UserId(4)).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn insert_user(&mut self, user: User) -> Result<(), WriteError> {
+        if self.users.contains_key(user.get_id()) {
+            return Err(WriteError::DuplicateId(user.get_id().clone()));
+        }
+        self.check_nickname(user.get_nickname(), user.get_id())?;
+        self.put(user);
+        Ok(())
+    }
+
+    /// Changes the nickname of the user.
+    /// Access to the method via the function `update_nickname()`.
+    fn update_nickname(
+        &mut self,
+        id: &UserId,
+        nickname: Cow<'static, str>,
+    ) -> Result<User, WriteError> {
+        let mut user = match self.users.get(id) {
+            Some(user) => user.clone(),
+            None => return Err(WriteError::NotFound(id.clone())),
+        };
+        self.check_nickname(&nickname, id)?;
+        user.set_nickname(nickname);
+        Ok(self.put(user).expect("the user is checked above"))
+    }
+
+    /// Deletes the user with its nickname from the index.
+    /// Access to the method via the function `delete_user()`.
+    fn delete_user(&mut self, id: &UserId) -> Result<User, WriteError> {
+        let user = match self.users.remove(id) {
+            Some(user) => user,
+            None => return Err(WriteError::NotFound(id.clone())),
+        };
+        self.nicknames.remove(&nickname_key(&user));
+        self.observers.delete(&user);
+        Ok(user)
+    }
+}
+
+/// Mock implementing the template Repocators for type `DBMemory`.
+impl UsersRepositoryMock for DBMemory {
+    /// Search for a user by ID or create a user with this ID.
+    /// Access to the method via the function `get_user_by_id_mock()`.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+    /// let mut users_source: DBMemory = DBMemory::new(map_users);
+    ///
+    /// assert!(get_user_by_id_mock(&mut users_source, UserId(2)).is_some());
+    /// assert!(get_user_by_id(&users_source, UserId(2)).is_some());
+    /// # }
+    /// ```
+    fn get_user_by_id_mock(&mut self, id: UserId) -> Option<User> {
+        if self.users.get(&id).is_none() {
+            self.put(User::new(id.clone(), Default::default()));
+        }
+        self.users.get(&id).cloned()
+    }
+
+    /// Search for all users that match the identifiers.
+    /// In the absence of users, they are created with these identifiers.
+    /// Access to the method via the function `get_users_by_ids_mock()`.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+    /// let mut users_source: DBMemory = DBMemory::new(map_users);
+    ///
+    /// let v = get_users_by_ids_mock(&mut users_source, vec![UserId(2), UserId(4)]);
+    ///
+    /// assert_eq!(2, v.len());
+    /// # }
+    /// ```
+    fn get_users_by_ids_mock(&mut self, vec: Vec<UserId>) -> HashMap<UserId, User> {
+        for key in &vec {
+            if self.users.get(key).is_none() {
+                self.put(User::new(key.clone(), Default::default()));
+            }
+        }
+        self.users
+            .iter()
+            .filter(|(_, ref value)| vec.contains(&value.get_id()))
+            .cloned()
+            .collect::<HashMap<UserId, User>>()
+    }
+
+    /// Search for users by nickname.
+    /// If there is no result, a user with nickname.
+    /// Access to the method via the function `get_ids_user_by_nickname_mock()`.
+    ///
+    /// ### Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    /// # extern crate collections;
+    /// # extern crate im;
+    /// # use collections::users::*;
+    /// # use im::hashmap::HashMap;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+    ///
+    /// let mut users_source: DBMemory = DBMemory::new(map_users);
+    /// let ids: Vec<UserId> = get_ids_user_by_nickname_mock(&mut users_source, "Delafon");
+    ///
+    /// assert_eq!(1, ids.len());
+    /// # }
+    /// ```
+    fn get_ids_user_by_nickname_mock(&mut self, nickname: &'static str) -> Vec<UserId> {
+        let nickname_lower = nickname.to_lowercase();
+        let nickname_lower: &str = nickname_lower.as_str();
+
+        let map: HashMap<UserId, User> = self.users
+            .iter()
+            .filter(|(_, ref value)| {
+                value.get_nickname().to_lowercase().contains(nickname_lower)
+            })
+            .cloned()
+            .collect::<HashMap<UserId, User>>();
+        let id: UserId = UserId(0);
+        let mut ids: Vec<UserId> = map.keys().cloned().collect::<Vec<UserId>>();
+        if ids.is_empty() {
+            self.put(User::new(id.clone(), Cow::Borrowed(nickname)));
+
+            ids.push(id);
+        }
+        ids
+    }
+}
+
+/// ## These functions provide an interface for any type of Implementing `UsersRepository` tarit.
+
+/// Provides access to the `get_user_by_id` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+///
+/// let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+/// map_users.insert(user.get_id().clone(), user);
+///
+/// let users_source: DBMemory = DBMemory::new(map_users);
+///
+/// assert!(get_user_by_id(&users_source, UserId(4)).is_some());
+/// assert!(get_user_by_id(&users_source, UserId(2)).is_none());
+/// # }
+/// ```
+pub fn get_user_by_id(repository: &UsersRepository, id: UserId) -> Option<User> {
+    repository.get_user_by_id(id)
+}
+
+/// Provides access to the `get_users_by_ids` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+///
+/// let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+/// map_users.insert(user.get_id().clone(), user);
+/// let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+/// map_users.insert(user.get_id().clone(), user);
+/// let user = User::new(UserId(5usize), Cow::Borrowed("Sara Daniel"));
+/// map_users.insert(user.get_id().clone(), user);
+///
+/// let users_source: DBMemory = DBMemory::new(map_users);
+///
+/// let users: HashMap<UserId, User> = get_users_by_ids(&users_source, vec![UserId(2), UserId(4)]);
+///
+/// assert_eq!(2, users.len());
+/// # }
+/// ```
+pub fn get_users_by_ids(
+    repository: &UsersRepository,
+    vec: Vec<UserId>,
+) -> HashMap<UserId, User> {
+    repository.get_users_by_ids(vec)
+}
+
+/// Provides access to the `get_ids_user_by_nickname` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+///
+/// let user = User::new(UserId(5usize), Cow::Borrowed("Sara Delafon"));
+/// map_users.insert(user.get_id().clone(), user);
+///
+/// let users_source: DBMemory = DBMemory::new(map_users);
+/// let ids: Vec<UserId> = get_ids_user_by_nickname(&users_source, "Delafon");
+///
+/// assert_eq!(1, ids.len());
+/// # }
+/// ```
+pub fn get_ids_user_by_nickname(
+    repository: &UsersRepository,
+    nickname: &str,
+) -> Vec<UserId> {
+    repository.get_ids_user_by_nickname(nickname)
+}
+
+/// Provides access to the `get_ids_user_by_nickname_with` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+///
+/// let user = User::new(UserId(5usize), Cow::Borrowed("Sara Delafon"));
+/// map_users.insert(user.get_id().clone(), user);
+///
+/// let users_source: DBMemory = DBMemory::new(map_users);
+/// let ids: Vec<UserId> =
+///     get_ids_user_by_nickname_with(&users_source, "Sarah Delafon", MatchMode::Levenshtein(1));
+///
+/// assert_eq!(1, ids.len());
+/// # }
+/// ```
+pub fn get_ids_user_by_nickname_with(
+    repository: &dyn UsersRepository,
+    nickname: &str,
+    mode: MatchMode,
+) -> Vec<UserId> {
+    repository.get_ids_user_by_nickname_with(nickname, mode)
+}
+
+/// Provides access to the `get_users_page` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+///
+/// let user = User::new(UserId(5usize), Cow::Borrowed("Sara Delafon"));
+/// map_users.insert(user.get_id().clone(), user);
+///
+/// let users_source: DBMemory = DBMemory::new(map_users);
+/// let users: Vec<User> = get_users_page(&users_source, 0, 10, SortBy::Nickname);
+///
+/// assert_eq!(1, users.len());
+/// # }
+/// ```
+pub fn get_users_page(
+    repository: &dyn UsersRepository,
+    offset: usize,
+    limit: usize,
+    sort: SortBy,
+) -> Vec<User> {
+    repository.get_users_page(offset, limit, sort)
+}
+
+// ## These functions provide an interface for any type of Implementing `UsersRepositoryMut` tarit.
+
+/// Provides access to the `insert_user` method.
+pub fn insert_user(repository: &mut dyn UsersRepositoryMut, user: User) -> Result<(), WriteError> {
+    repository.insert_user(user)
+}
+
+/// Provides access to the `update_nickname` method.
+pub fn update_nickname(
+    repository: &mut dyn UsersRepositoryMut,
+    id: &UserId,
+    nickname: Cow<'static, str>,
+) -> Result<User, WriteError> {
+    repository.update_nickname(id, nickname)
+}
+
+/// Provides access to the `delete_user` method.
+pub fn delete_user(
+    repository: &mut dyn UsersRepositoryMut,
+    id: &UserId,
+) -> Result<User, WriteError> {
+    repository.delete_user(id)
+}
+
+/// ## These functions provide an interface for any type of Implementing `UsersRepositoryMock` tarit.
+
+/// Provides access to the `get_user_by_id_mock` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let map_users: HashMap<UserId, User> = HashMap::new();
+/// let mut users_source: DBMemory = DBMemory::new(map_users);
+///
+/// assert!(get_user_by_id_mock(&mut users_source, UserId(2)).is_some());
+/// # }
+/// ```
+pub fn get_user_by_id_mock(
+    repository: &mut UsersRepositoryMock,
+    id: UserId,
+) -> Option<User> {
+    repository.get_user_by_id_mock(id)
+}
+
+/// Provides access to the `get_users_by_ids_mock` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+/// let mut users_source: DBMemory = DBMemory::new(map_users);
+///
+/// let users: HashMap<UserId, User> =
+///     get_users_by_ids_mock(&mut users_source, vec![UserId(2), UserId(4)]);
+///
+/// assert_eq!(2, users.len());
+/// # }
+/// ```
+pub fn get_users_by_ids_mock(
+    repository: &mut UsersRepositoryMock,
+    vec: Vec<UserId>,
+) -> HashMap<UserId, User> {
+    repository.get_users_by_ids_mock(vec)
+}
+
+/// Provides access to the `get_ids_user_by_nickname_mock` method.
+///
+/// ### Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// # extern crate collections;
+/// # extern crate im;
+/// # use collections::users::*;
+/// # use im::hashmap::HashMap;
+/// # use std::borrow::Cow;
+/// # fn main() {
+/// let map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+///
+/// let mut users_source: DBMemory = DBMemory::new(map_users);
+/// let ids: Vec<UserId> = get_ids_user_by_nickname_mock(&mut users_source, "Delafon");
+///
+/// assert_eq!(1, ids.len());
+/// # }
+/// ```
+pub fn get_ids_user_by_nickname_mock(
+    repository: &mut UsersRepositoryMock,
+    nickname: &'static str,
+) -> Vec<UserId> {
+    repository.get_ids_user_by_nickname_mock(nickname)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_users_by_ids() {
+        let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+
+        let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let user = User::new(UserId(5usize), Cow::Borrowed("Sara Daniel"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let users_source: DBMemory = DBMemory::new(map_users);
+
+        let v = get_users_by_ids(&users_source, vec![UserId(2), UserId(4)]);
+
+        assert_eq!(2, v.len());
+    }
+
+    #[test]
+    fn test_get_user_by_id() {
+        let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+
+        let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let users_source: DBMemory = DBMemory::new(map_users);
+
+        assert!(get_user_by_id(&users_source, UserId(2)).is_some());
+        assert!(get_user_by_id(&users_source, UserId(8)).is_none());
+    }
+
+    #[test]
+    fn test_get_ids_user_by_nickname() {
+        let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+
+        let user = User::new(UserId(4usize), Cow::Borrowed("Sara Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let user = User::new(UserId(2usize), Cow::Borrowed("Jacob Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let user = User::new(UserId(5usize), Cow::Borrowed("Sara Daniel"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let users_source: DBMemory = DBMemory::new(map_users);
+
+        let ids: Vec<UserId> = get_ids_user_by_nickname(&users_source, "Delafon");
+
+        if !ids.is_empty() {
+            assert_eq!(2, ids.len());
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(0, levenshtein("", ""));
+        assert_eq!(3, levenshtein("", "bob"));
+        assert_eq!(3, levenshtein("bob", ""));
+        assert_eq!(3, levenshtein("kitten", "sitting"));
+        assert_eq!(1, levenshtein("sara", "sarah"));
+        assert_eq!(1, levenshtein("жека", "жэка"));
+    }
+
+    #[test]
+    fn test_get_ids_user_by_nickname_with() {
+        let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+        for (id, nickname) in &[
+            (4, "Sara Delafon"),
+            (2, "Jacob Delafon"),
+            (5, "Sara Daniel"),
+        ] {
+            let user = User::new(UserId(*id), Cow::Borrowed(*nickname));
+            map_users.insert(user.get_id().clone(), user);
+        }
+        let users_source: DBMemory = DBMemory::new(map_users);
+        let search = |nickname, mode| get_ids_user_by_nickname_with(&users_source, nickname, mode);
+
+        assert_eq!(vec![UserId(4)], search("sara delafon", MatchMode::Exact));
+        assert!(search("Sara", MatchMode::Exact).is_empty());
+        assert_eq!(
+            vec![UserId(2), UserId(4)],
+            search("Delafon", MatchMode::default())
+        );
+        assert!(search("Sarah Delafon", MatchMode::Levenshtein(0)).is_empty());
+        assert_eq!(
+            vec![UserId(4)],
+            search("Sarah Delafon", MatchMode::Levenshtein(1))
+        );
+        assert_eq!(
+            vec![UserId(2), UserId(4)],
+            search("Sarah Delafon", MatchMode::Levenshtein(4))
+        );
+    }
+
+    #[test]
+    fn test_get_users_page() {
+        let mut map_users: HashMap<UserId, User> = <HashMap<UserId, User>>::new();
+        for (id, nickname) in &[
+            (4, "Sara Delafon"),
+            (2, "jacob Delafon"),
+            (5, "Sara Daniel"),
+            (3, "Adam"),
+        ] {
+            let user = User::new(UserId(*id), Cow::Borrowed(*nickname));
+            map_users.insert(user.get_id().clone(), user);
+        }
+        let users_source: DBMemory = DBMemory::new(map_users);
+        let page = |offset, limit, sort| -> Vec<usize> {
+            get_users_page(&users_source, offset, limit, sort)
+                .iter()
+                .map(|user| user.get_id().0)
+                .collect()
+        };
+
+        assert_eq!(vec![2, 3, 4, 5], page(0, 10, SortBy::Id));
+        assert_eq!(vec![4, 5], page(2, 2, SortBy::Id));
+        assert_eq!(vec![3, 2], page(0, 2, SortBy::Nickname));
+        assert_eq!(vec![5, 4], page(2, 2, SortBy::Nickname));
+        assert!(page(4, 10, SortBy::Id).is_empty());
+        assert!(page(0, 0, SortBy::Nickname).is_empty());
+    }
+
+    #[test]
+    fn test_get_user_by_id_mock() {
+        let users: HashMap<UserId, User> = HashMap::new();
+
+        let mut users_source: DBMemory = DBMemory::new(users);
+
+        if let Some(user) = get_user_by_id_mock(&mut users_source, UserId(4)) {
+            assert_eq!(&UserId(4), user.get_id());
+        }
+    }
+
+    #[test]
+    fn test_get_users_by_ids_mock() {
+        let users: HashMap<UserId, User> = HashMap::new();
+
+        let mut users_source: DBMemory = DBMemory::new(users);
+        let value = get_users_by_ids_mock(&mut users_source, vec![UserId(2), UserId(4)]);
+
+        assert_eq!(2, value.len());
+    }
+
+    #[test]
+    fn test_get_ids_user_by_nickname_mock() {
+        let users: HashMap<UserId, User> = HashMap::new();
+
+        let mut users_source: DBMemory = DBMemory::new(users);
+
+        let ids: Vec<UserId> = get_ids_user_by_nickname_mock(&mut users_source, "Delafon");
+
+        if !ids.is_empty() {
+            assert_eq!(1, ids.len());
+        } else {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn test_add_user() {
+        let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+        let sara = User::new(UserId(4), Cow::Borrowed("Sara Delafon"));
+        let same_nickname = User::new(UserId(2), Cow::Borrowed(" sara   DELAFON"));
+
+        // Duplicates are allowed by default.
+        assert!(users_source.add_user(sara.clone()).unwrap().is_none());
+        assert!(users_source.add_user(same_nickname.clone()).is_ok());
+
+        let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+        users_source.set_unique_nicknames(true);
+        assert!(users_source.add_user(sara.clone()).is_ok());
+        assert_eq!(
+            Err(DuplicateNickname(UserId(4))),
+            users_source.add_user(same_nickname.clone()).map(|_| ())
+        );
+        assert!(get_user_by_id(&users_source, UserId(2)).is_none());
+
+        // The user keeps its own nickname.
+        let replaced = users_source.add_user(sara).unwrap().unwrap();
+        assert_eq!(&UserId(4), replaced.get_id());
+
+        // The renamed user frees its nickname.
+        let bob = User::new(UserId(4), Cow::Borrowed("Bob"));
+        assert!(users_source.add_user(bob).is_ok());
+        assert!(users_source.add_user(same_nickname).is_ok());
+        assert_eq!(
+            "the nickname is taken by the user 4",
+            users_source
+                .add_user(User::new(UserId(7), Cow::Borrowed("bob")))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_write_operations() {
+        let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+        users_source.set_unique_nicknames(true);
+        let sara = User::new(UserId(4), Cow::Borrowed("Sara Delafon"));
+        assert_eq!(Ok(()), insert_user(&mut users_source, sara.clone()));
+        assert_eq!(
+            Err(WriteError::DuplicateId(UserId(4))),
+            insert_user(&mut users_source, sara)
+        );
+        let jacob = User::new(UserId(2), Cow::Borrowed("sara delafon"));
+        assert_eq!(
+            Err(WriteError::DuplicateNickname(UserId(4))),
+            insert_user(&mut users_source, jacob)
+        );
+        insert_user(
+            &mut users_source,
+            User::new(UserId(2), Cow::Borrowed("Jacob Delafon")),
+        )
+        .unwrap();
+
+        // The snapshot keeps the users before the changes.
+        let snapshot = users_source.clone();
+
+        let previous =
+            update_nickname(&mut users_source, &UserId(4), Cow::Borrowed("Sara Daniel")).unwrap();
+        assert_eq!("Sara Delafon", previous.get_nickname());
+        assert_eq!(
+            WriteError::DuplicateNickname(UserId(4)),
+            update_nickname(&mut users_source, &UserId(2), Cow::Borrowed("SARA DANIEL"))
+                .unwrap_err()
+        );
+        assert_eq!(
+            WriteError::NotFound(UserId(8)),
+            update_nickname(&mut users_source, &UserId(8), Cow::Borrowed("Bob")).unwrap_err()
+        );
+        assert_eq!(
+            "Jacob Delafon",
+            delete_user(&mut users_source, &UserId(2))
+                .unwrap()
+                .get_nickname()
+        );
+        assert_eq!(
+            "there is no user 2",
+            delete_user(&mut users_source, &UserId(2))
+                .unwrap_err()
+                .to_string()
+        );
+
+        assert_eq!(
+            vec![UserId(4)],
+            get_ids_user_by_nickname(&users_source, "daniel")
+        );
+        assert!(get_ids_user_by_nickname(&users_source, "delafon").is_empty());
+        let mut ids = get_ids_user_by_nickname(&snapshot, "delafon");
+        ids.sort();
+        assert_eq!(vec![UserId(2), UserId(4)], ids);
+        assert!(get_ids_user_by_nickname(&snapshot, "daniel").is_empty());
+
+        // The freed nickname can be taken again.
+        insert_user(
+            &mut users_source,
+            User::new(UserId(7), Cow::Borrowed("jacob delafon")),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_export_import_json() {
+        let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+        users_source
+            .add_user(User::new(UserId(4), Cow::Borrowed("Sara Delafon")))
+            .unwrap();
+        users_source
+            .add_user(User::new(UserId(2), Cow::Borrowed("Jacob Delafon")))
+            .unwrap();
+        let mut json = Vec::new();
+        users_source.export_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.find("Jacob").unwrap() < json.find("Sara").unwrap());
+
+        let mut imported: DBMemory = DBMemory::new(HashMap::new());
+        assert_eq!(
+            2,
+            imported
+                .import_json(json.as_bytes(), OnCollision::Error)
+                .unwrap()
+        );
+        assert_eq!(
+            "Sara Delafon",
+            get_user_by_id(&imported, UserId(4)).unwrap().get_nickname()
+        );
+
+        let update = r#"[{"id": 7, "nickname": "Bob"}, {"id": 4, "nickname": "Sara Daniel"}]"#;
+        match imported.import_json(update.as_bytes(), OnCollision::Error) {
+            Err(ImportError::Write(WriteError::DuplicateId(UserId(4)))) => {}
+            result => panic!("{:?}", result),
+        }
+        // Nothing is imported on error.
+        assert!(get_user_by_id(&imported, UserId(7)).is_none());
+
+        assert_eq!(
+            1,
+            imported
+                .import_json(update.as_bytes(), OnCollision::Skip)
+                .unwrap()
+        );
+        assert_eq!(
+            "Sara Delafon",
+            get_user_by_id(&imported, UserId(4)).unwrap().get_nickname()
+        );
+        assert_eq!(
+            2,
+            imported
+                .import_json(update.as_bytes(), OnCollision::Overwrite)
+                .unwrap()
+        );
+        assert_eq!(
+            vec![UserId(4)],
+            get_ids_user_by_nickname(&imported, "daniel")
+        );
+
+        imported.set_unique_nicknames(true);
+        let duplicate = r#"[{"id": 8, "nickname": "bob"}]"#;
+        assert_eq!(
+            "the nickname is taken by the user 7",
+            imported
+                .import_json(duplicate.as_bytes(), OnCollision::Overwrite)
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(imported
+            .import_json("{}".as_bytes(), OnCollision::Skip)
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid users"));
+    }
+
+    /// Records the events as strings.
+    #[derive(Default)]
+    struct Recorder(::std::sync::Mutex<Vec<String>>);
+
+    impl RepositoryObserver for Recorder {
+        fn on_insert(&self, user: &User) {
+            let event = format!("insert {}", user.get_id().0);
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_update(&self, previous: &User, user: &User) {
+            let event = format!(
+                "update {}: {} -> {}",
+                user.get_id().0,
+                previous.get_nickname(),
+                user.get_nickname()
+            );
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_delete(&self, user: &User) {
+            let event = format!("delete {}", user.get_id().0);
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    impl Recorder {
+        fn take(&self) -> Vec<String> {
+            self.0.lock().unwrap().drain(..).collect()
+        }
+    }
+
+    #[test]
+    fn test_observers() {
+        let recorder = Arc::new(Recorder::default());
+        let mut users_source: DBMemory = DBMemory::new(HashMap::new());
+        users_source.subscribe(recorder.clone());
+
+        users_source
+            .add_user(User::new(UserId(4), Cow::Borrowed("Sara Delafon")))
+            .unwrap();
+        update_nickname(&mut users_source, &UserId(4), Cow::Borrowed("Sara Daniel")).unwrap();
+        delete_user(&mut users_source, &UserId(4)).unwrap();
+        assert!(delete_user(&mut users_source, &UserId(4)).is_err());
+        assert_eq!(
+            vec![
+                "insert 4",
+                "update 4: Sara Delafon -> Sara Daniel",
+                "delete 4",
+            ],
+            recorder.take()
+        );
+
+        // The mock methods.
+        get_user_by_id_mock(&mut users_source, UserId(2));
+        get_user_by_id_mock(&mut users_source, UserId(2));
+        get_users_by_ids_mock(&mut users_source, vec![UserId(2), UserId(3)]);
+        get_ids_user_by_nickname_mock(&mut users_source, "Bob");
+        assert_eq!(
+            vec!["insert 2", "insert 3", "insert 0"],
+            recorder.take()
+        );
+
+        // The failed import notifies nothing.
+        let json = r#"[{"id": 7, "nickname": "Jacob"}, {"id": 2, "nickname": "Adam"}]"#;
+        assert!(users_source
+            .import_json(json.as_bytes(), OnCollision::Error)
+            .is_err());
+        assert!(recorder.take().is_empty());
+        users_source
+            .import_json(json.as_bytes(), OnCollision::Overwrite)
+            .unwrap();
+        assert_eq!(vec!["insert 7", "update 2:  -> Adam"], recorder.take());
+    }
+
+    #[test]
+    fn test_normalize_nickname() {
+        assert_eq!("sara delafon", normalize_nickname("  Sara \t DELAFON "));
+        assert_eq!("", normalize_nickname(" "));
+    }
+}
@@ -1,3 +1,6 @@
+extern crate common_errors;
+#[cfg(any(test, feature = "testing"))]
+extern crate proptest;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde;
@@ -7,7 +10,7 @@ extern crate toml;
 
 /// # Deserialization of the JSON in the readable TOML and YAML formats
 ///
-/// The module deserializes the json file into the `Request` object.
+/// The module deserializes the JSON, TOML or YAML file into the `Request` object.
 /// The `Request` object can be printed in YAML and TOML [formats]:https://serde.rs/index.html#data-formats
 ///
 ///
@@ -30,18 +33,15 @@ extern crate toml;
 mod request {
     use super::*;
 
-    use std::error;
-    use std::fmt;
-    use std::io;
     use std::result;
 
     use serde::ser::{Serialize, SerializeStruct, Serializer};
-    use std::fs::File;
+    use std::fs::{self, File};
     use std::path::Path;
 
     /// The structures representing the object `Request`.
     /// The reserved name `type` will be deserialized in the field `req_type`.
-    #[derive(Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
     pub struct Request {
         #[serde(rename(deserialize = "type"))]
         pub req_type: String,
@@ -49,7 +49,7 @@ mod request {
         pub gifts: Vec<Block>,
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct Stream {
         pub model_id: i32,
         pub is_private: bool,
@@ -60,21 +60,21 @@ mod request {
         pub private_tariff: PrivateTariff,
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct PublicTariff {
         #[serde(flatten)]
         pub block: Block,
         pub duration: i32,
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct PrivateTariff {
         #[serde(flatten)]
         pub block: Block,
         pub duration: i32,
     }
     /// Used for repetitive data structures.
-    #[derive(Serialize, Deserialize)]
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
     pub struct Block {
         pub id: i32,
         pub model_price: i32,
@@ -82,75 +82,9 @@ mod request {
         pub description: String,
     }
 
-    /// Alias for result::Result with FormatError ,
-    /// combining types of format errors.
-    type Result<T> = result::Result<T, FormatError>;
-
-    /// The `FormatError` contains all types of errors necessary
-    /// for the operation of the module with formats.
-    #[derive(Debug)]
-    pub enum FormatError {
-        Io(io::Error),
-        Json(serde_json::Error),
-        Yaml(serde_yaml::Error),
-        Toml(toml::ser::Error),
-    }
-
-    /// Implementation trait std::fmt::Display for FormatError
-    impl fmt::Display for FormatError {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            match *self {
-                FormatError::Io(ref err) => write!(f, "IO error: {}", err),
-                FormatError::Json(ref err) => write!(f, "JSON error: {};", err),
-                FormatError::Yaml(ref err) => write!(f, "YAML error: {}", err),
-                FormatError::Toml(ref err) => write!(f, "TOML error: {}", err),
-            }
-        }
-    }
-
-    /// Implementation trait std::error::Error for FormatError
-    impl error::Error for FormatError {
-        fn description(&self) -> &str {
-            match *self {
-                FormatError::Io(ref err) => err.description(),
-                FormatError::Json(ref err) => err.description(),
-                FormatError::Yaml(ref err) => err.description(),
-                FormatError::Toml(ref err) => err.description(),
-            }
-        }
-        fn cause(&self) -> Option<&error::Error> {
-            match *self {
-                FormatError::Io(ref err) => Some(err),
-                FormatError::Json(ref err) => Some(err),
-                FormatError::Yaml(ref err) => Some(err),
-                FormatError::Toml(ref err) => Some(err),
-            }
-        }
-    }
-    /// Type conversion io::Error in FormatError.
-    impl From<io::Error> for FormatError {
-        fn from(err: io::Error) -> FormatError {
-            FormatError::Io(err)
-        }
-    }
-    /// Type conversion serde_json::Error in FormatError.
-    impl From<serde_json::Error> for FormatError {
-        fn from(err: serde_json::Error) -> FormatError {
-            FormatError::Json(err)
-        }
-    }
-    /// Type conversion serde_yaml::Error in FormatError.
-    impl From<serde_yaml::Error> for FormatError {
-        fn from(err: serde_yaml::Error) -> FormatError {
-            FormatError::Yaml(err)
-        }
-    }
-    /// Type conversion toml::ser::Error in FormatError.
-    impl From<toml::ser::Error> for FormatError {
-        fn from(err: toml::ser::Error) -> FormatError {
-            FormatError::Toml(err)
-        }
-    }
+    /// The io, JSON, YAML and TOML errors of the module
+    /// are converted into the shared `common_errors::AppError` by `?`.
+    use common_errors::{AppError, Result};
 
     /// Implementation trait Serialize
     /// to replace the reserved name `req_type` with` type`
@@ -187,6 +121,47 @@ mod request {
         Ok(deserialized)
     }
 
+    /// Deserializes the TOML file into the object of the `Request`,
+    /// as it is printed by `print_toml`.
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Request> {
+        let content = fs::read_to_string(path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    /// Deserializes the YAML file into the object of the `Request`,
+    /// as it is printed by `print_yaml`.
+    pub fn from_yaml_file<P: AsRef<Path>>(path: P) -> Result<Request> {
+        let file = File::open(path)?;
+        Ok(serde_yaml::from_reader(file)?)
+    }
+
+    /// Deserializes the file into the object of the `Request`, the format is detected
+    /// by the extension: `.json`, `.toml`, `.yaml` or `.yml`.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust
+    ///  use json::*;
+    ///
+    ///  use request::*;
+    ///
+    ///  let request: Request = from_file("request.toml")?;
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Request> {
+        let path = path.as_ref();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => deserialized_to_request(path),
+            Some("toml") => from_toml_file(path),
+            Some("yaml") | Some("yml") => from_yaml_file(path),
+            _ => Err(AppError::parse(format!(
+                "{}: unknown format, expected .json, .toml, .yaml or .yml",
+                path.display()
+            ))),
+        }
+    }
+
     /// Prints a `Request` object in the YAML format.
     ///
     /// ## Examples
@@ -230,43 +205,191 @@ mod request {
     ///  }
     /// ```
     pub fn print_toml(request: &Request) -> Result<()> {
-        let s: String = toml::to_string(&request)?;
-        println!("{}", s);
+        println!("{}", to_toml(request)?);
         Ok(())
     }
 
+    /// Serializes a `Request` object in the TOML format.
+    /// It goes through `toml::Value`, which puts the values before the tables:
+    /// the empty `gifts` are an array after the `stream` table otherwise.
+    pub fn to_toml(request: &Request) -> Result<String> {
+        let value = toml::Value::try_from(request)?;
+        Ok(toml::to_string(&value)?)
+    }
+
     #[cfg(test)]
     mod test {
         #[test]
         fn test_yaml() {
             use request::*;
-            if let Ok(request) = deserialized_to_request("request.json") {
-                assert!(print_yaml(&request).is_ok());
-            } else {
-                assert!(false);
-            }
+            let request = deserialized_to_request("request.json").unwrap();
+            assert!(print_yaml(&request).is_ok());
         }
 
         #[test]
         fn test_toml() {
             use request::*;
-            if let Ok(request) = deserialized_to_request("request.json") {
-                assert!(print_toml(&request).is_ok());
-            } else {
-                assert!(false);
-            }
+            let request = deserialized_to_request("request.json").unwrap();
+            assert!(print_toml(&request).is_ok());
+        }
+
+        #[test]
+        fn test_from_file() {
+            use request::*;
+            use std::env;
+            use std::fs;
+
+            let request = deserialized_to_request("request.json").unwrap();
+            assert_eq!(request, from_file("request.json").unwrap());
+
+            let path = env::temp_dir().join("test_from_file_request.toml");
+            fs::write(&path, to_toml(&request).unwrap()).unwrap();
+            assert_eq!(request, from_file(&path).unwrap());
+            fs::remove_file(&path).unwrap();
+
+            let path = env::temp_dir().join("test_from_file_request.yml");
+            fs::write(&path, serde_yaml::to_string(&request).unwrap()).unwrap();
+            assert_eq!(request, from_file(&path).unwrap());
+            fs::remove_file(&path).unwrap();
+
+            let err = from_file("request.xml").unwrap_err();
+            assert!(err.to_string().contains("unknown format"));
         }
     }
 }
 
-fn main() {
+/// # Generators of the `Request` for the property-based tests
+///
+/// [Proptest]:https://docs.rs/proptest strategies of the `Request` and its parts,
+/// enabled by the `testing` feature.
+/// The properties check that the `Request` survives the round trip
+/// through JSON, YAML and TOML.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  use proptest::strategy::{Strategy, ValueTree};
+///  use proptest::test_runner::TestRunner;
+///
+///  use testing::*;
+///
+///  let mut runner = TestRunner::default();
+///  let request = request().new_tree(&mut runner).unwrap().current();
+///  assert!(request.gifts.len() < 4);
+/// ```
+#[cfg(any(test, feature = "testing"))]
+mod testing {
+    use proptest::prelude::*;
     use request::*;
 
-    if let Ok(request) = deserialized_to_request("request.json") {
-        println!("Format YAML:");
-        print_yaml(&request);
+    /// Printable ASCII text: every format keeps it as is.
+    pub fn text() -> impl Strategy<Value = String> {
+        "[ -~]{0,16}"
+    }
+
+    pub fn block() -> impl Strategy<Value = Block> {
+        (any::<i32>(), any::<i32>(), any::<i32>(), text()).prop_map(
+            |(id, model_price, client_price, description)| Block {
+                id,
+                model_price,
+                client_price,
+                description,
+            },
+        )
+    }
+
+    pub fn stream() -> impl Strategy<Value = Stream> {
+        (
+            any::<i32>(),
+            any::<bool>(),
+            any::<i32>(),
+            any::<i32>(),
+            text(),
+            (block(), any::<i32>()),
+            (block(), any::<i32>()),
+        )
+            .prop_map(
+                |(model_id, is_private, erotic, places, shard_url, public, private)| Stream {
+                    model_id,
+                    is_private,
+                    erotic,
+                    places,
+                    shard_url,
+                    public_tariff: PublicTariff {
+                        block: public.0,
+                        duration: public.1,
+                    },
+                    private_tariff: PrivateTariff {
+                        block: private.0,
+                        duration: private.1,
+                    },
+                },
+            )
+    }
+
+    pub fn request() -> impl Strategy<Value = Request> {
+        (text(), stream(), prop::collection::vec(block(), 0..4)).prop_map(
+            |(req_type, stream, gifts)| Request {
+                req_type,
+                stream,
+                gifts,
+            },
+        )
+    }
+
+    impl Arbitrary for Request {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Request>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            request().boxed()
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+        use serde_json;
+        use serde_yaml;
+
+        proptest! {
+            #[test]
+            fn test_json_round_trip(request in any::<Request>()) {
+                let json = serde_json::to_string(&request).unwrap();
+                prop_assert_eq!(request, serde_json::from_str::<Request>(&json).unwrap());
+            }
+
+            #[test]
+            fn test_yaml_round_trip(request in any::<Request>()) {
+                let yaml = serde_yaml::to_string(&request).unwrap();
+                prop_assert_eq!(request, serde_yaml::from_str::<Request>(&yaml).unwrap());
+            }
 
-        println!("Format TOML:");
-        print_toml(&request);
+            #[test]
+            fn test_toml_round_trip(request in any::<Request>()) {
+                let toml = to_toml(&request).unwrap();
+                prop_assert_eq!(request, toml::from_str::<Request>(&toml).unwrap());
+            }
+        }
     }
 }
+
+/// `$ encoding_and_serialization [request.json|request.toml|request.yaml]`
+fn main() -> Result<(), common_errors::AppError> {
+    use request::*;
+
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "request.json".to_string());
+    let request = from_file(path)?;
+
+    println!("Format YAML:");
+    print_yaml(&request)?;
+
+    println!("Format TOML:");
+    print_toml(&request)?;
+
+    Ok(())
+}
@@ -0,0 +1,5 @@
+//! Random tokens, passwords, passphrases and uuids, shared with the other steps through `toolkit`.
+extern crate rand;
+extern crate uuid;
+
+pub mod rand_mod;
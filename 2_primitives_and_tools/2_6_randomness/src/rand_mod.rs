@@ -0,0 +1,663 @@
+//! # Functions of working with random numbers
+//! The module contains a set of functions (`new_access_token`, `generate_password`, `select_rand_val`,
+//! `generate_otp`, `generate_code`, `new_uuid_v4`, `new_uuid_v7`) that work with random number generators.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! extern crate randomness;
+//!
+//! use randomness::rand_mod::generate_password;
+//!
+//! let password_ten:String = generate_password(10);
+//!
+//! assert_eq!(10, password_ten.len());
+//! ```
+
+use rand::distributions::{Alphanumeric, Distribution};
+use rand::prng::isaac64::Isaac64Rng;
+use rand::rngs::EntropyRng;
+use rand::rngs::SmallRng;
+use rand::{FromEntropy, Rng, RngCore, SeedableRng};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Separator placed between words by `generate_passphrase`.
+pub const DEFAULT_SEPARATOR: &str = "-";
+
+/// Short diceware list embedded into the binary: `<dice rolls>\t<word>` per line.
+const SHORT_WORDLIST: &str = include_str!("wordlist_short.txt");
+
+/// Source of words for passphrase generation.
+#[derive(Debug, Clone)]
+pub enum Wordlist {
+    /// The embedded short list (216 words, three dice rolls per word).
+    Short,
+    /// A list loaded by the caller with `Wordlist::custom` or `Wordlist::from_file`.
+    Custom(Words),
+}
+
+/// Words of `Wordlist::Custom`, never empty.
+#[derive(Debug, Clone)]
+pub struct Words(Vec<String>);
+
+impl Wordlist {
+    /// The list of given words, an empty list is rejected
+    /// since no passphrase can be drawn from it.
+    pub fn custom(words: Vec<String>) -> io::Result<Wordlist> {
+        if words.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "wordlist does not contain any words",
+            ));
+        }
+        Ok(Wordlist::Custom(Words(words)))
+    }
+
+    /// Load a wordlist from the file.
+    ///
+    /// Both the EFF format (`11111\tword`) and plain one-word-per-line files
+    /// are accepted: the last whitespace separated token of each non-empty line is taken.
+    ///
+    /// ## Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```rust,no_run
+    /// extern crate randomness;
+    ///
+    /// use randomness::rand_mod::Wordlist;
+    /// use std::io;
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     // The EFF list is downloaded separately, it isn't shipped with the crate.
+    ///     let wordlist = Wordlist::from_file("eff_large_wordlist.txt")?;
+    ///     assert!(!wordlist.words().is_empty());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Wordlist> {
+        let content = fs::read_to_string(path)?;
+        let words: Vec<String> = parse_wordlist(&content)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        Wordlist::custom(words)
+    }
+
+    /// Words of the list.
+    pub fn words(&self) -> Vec<&str> {
+        match self {
+            Wordlist::Short => parse_wordlist(SHORT_WORDLIST),
+            Wordlist::Custom(Words(words)) => words.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+fn parse_wordlist(content: &str) -> Vec<&str> {
+    content
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .collect()
+}
+
+fn secure_rng() -> Isaac64Rng {
+    Isaac64Rng::from_entropy()
+}
+
+/// Generate unique cryptographically secure random value in `a-zA-Z0-9`
+/// symbols set and has exactly `64` symbols.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::new_access_token;
+///
+/// let password_64:String = new_access_token();
+///
+/// assert_eq!(64, password_64.len());
+/// ```
+pub fn new_access_token() -> String {
+    let mut rng = Isaac64Rng::seed_from_u64(EntropyRng::new().next_u64());
+    Alphanumeric.sample_iter(&mut rng).take(64).collect()
+}
+
+/// Generate random password of given length and symbols set.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::generate_password;
+///
+/// let password_ten:String = generate_password(10);
+///
+/// assert_eq!(10, password_ten.len());
+/// ```
+pub fn generate_password(length: usize) -> String {
+    let mut rng = Isaac64Rng::seed_from_u64(EntropyRng::new().next_u64());
+    rng.sample_iter(&Alphanumeric).take(length).collect()
+}
+
+/// Retrieve random element of given slice.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::select_rand_val;
+///
+/// let vector: Vec<i32> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+///
+/// assert!(vector.contains(&select_rand_val(vector.as_slice())));
+/// ```
+pub fn select_rand_val(slice: &[i32]) -> i32 {
+    let mut small_rng = SmallRng::from_entropy();
+    let index: usize = small_rng.gen_range(0, slice.len());
+    slice[index]
+}
+
+/// Generate cryptographically random numeric one-time code of given digits count.
+///
+/// Leading zeros are kept, so the result always has exactly `digits` symbols.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::generate_otp;
+///
+/// let otp: String = generate_otp(6);
+///
+/// assert_eq!(6, otp.len());
+/// ```
+pub fn generate_otp(digits: usize) -> String {
+    let mut rng = secure_rng();
+    (0..digits)
+        .map(|_| (b'0' + rng.gen_range(0u8, 10)) as char)
+        .collect()
+}
+
+/// Alphabet of `CodeSpec::default()`: digits and upper case letters
+/// without `I`, `L`, `O` and `U`, which are easily confused when read aloud or typed.
+pub const CODE_ALPHABET: &str = "0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Format of the codes produced by `generate_code`, e.g. `A7F2-99KD-M2XQ`
+/// is 3 groups of 4 symbols joined with `DEFAULT_SEPARATOR`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeSpec {
+    /// Count of the groups.
+    pub groups: usize,
+    /// Count of the symbols in each group.
+    pub group_len: usize,
+    /// Symbols of the code, at least two different ones.
+    pub alphabet: String,
+    /// The last symbol of the code is the Luhn mod N check symbol of the others,
+    /// so `verify_code` catches a mistyped symbol and most swaps of two neighbours.
+    pub checksum: bool,
+}
+
+impl Default for CodeSpec {
+    fn default() -> Self {
+        CodeSpec {
+            groups: 3,
+            group_len: 4,
+            alphabet: CODE_ALPHABET.to_string(),
+            checksum: false,
+        }
+    }
+}
+
+impl CodeSpec {
+    fn symbols(&self) -> Vec<char> {
+        let mut symbols: Vec<char> = Vec::new();
+        for c in self.alphabet.chars() {
+            if !symbols.contains(&c) {
+                symbols.push(c);
+            }
+        }
+        assert!(
+            symbols.len() >= 2,
+            "code alphabet needs at least two symbols"
+        );
+        symbols
+    }
+
+    /// Count of different codes of the format, `None` if it doesn't fit into `u64`.
+    pub fn capacity(&self) -> Option<u64> {
+        let len = (self.groups * self.group_len).saturating_sub(self.checksum as usize);
+        (self.symbols().len() as u64).checked_pow(len as u32)
+    }
+}
+
+/// Luhn mod N check symbol index of `payload` (the indices of the symbols in the alphabet of `n` symbols).
+/// With the `0-9` alphabet it is the usual Luhn check digit.
+fn luhn_check(payload: &[usize], n: usize) -> usize {
+    let sum: usize = payload
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &index)| {
+            let addend = if i % 2 == 0 { index * 2 } else { index };
+            addend / n + addend % n
+        })
+        .sum();
+    (n - sum % n) % n
+}
+
+/// Generate cryptographically random code of given format, e.g. `A7F2-99KD-M2XQ`
+/// for license keys or recovery codes.
+///
+/// ## Panics
+///
+/// If the alphabet of `spec` has less than two different symbols.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::{generate_code, verify_code, CodeSpec};
+///
+/// let spec = CodeSpec { checksum: true, ..CodeSpec::default() };
+/// let code: String = generate_code(&spec);
+///
+/// assert_eq!(14, code.len());
+/// assert!(verify_code(&code, &spec));
+/// ```
+pub fn generate_code(spec: &CodeSpec) -> String {
+    let symbols = spec.symbols();
+    let len = spec.groups * spec.group_len;
+    let mut rng = secure_rng();
+    let mut indices: Vec<usize> = (0..len.saturating_sub(spec.checksum as usize))
+        .map(|_| rng.gen_range(0, symbols.len()))
+        .collect();
+    if spec.checksum && len > 0 {
+        let check = luhn_check(&indices, symbols.len());
+        indices.push(check);
+    }
+    indices
+        .chunks(spec.group_len.max(1))
+        .map(|group| group.iter().map(|&i| symbols[i]).collect::<String>())
+        .collect::<Vec<String>>()
+        .join(DEFAULT_SEPARATOR)
+}
+
+/// Check that `code` has the format of `spec` and, if `spec.checksum` is set, a valid check symbol.
+///
+/// Separators are optional, so a code typed without them is accepted too.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::{verify_code, CodeSpec};
+///
+/// let spec = CodeSpec { groups: 1, group_len: 11, alphabet: "0123456789".to_string(), checksum: true };
+///
+/// assert!(verify_code("79927398713", &spec));
+/// assert!(!verify_code("79927398710", &spec));
+/// ```
+pub fn verify_code(code: &str, spec: &CodeSpec) -> bool {
+    let symbols = spec.symbols();
+    let indices: Option<Vec<usize>> = code
+        .split(DEFAULT_SEPARATOR)
+        .flat_map(str::chars)
+        .map(|c| symbols.iter().position(|&s| s == c))
+        .collect();
+    match indices {
+        Some(ref indices) if indices.len() == spec.groups * spec.group_len => match indices
+            .split_last()
+        {
+            Some((&check, payload)) if spec.checksum => luhn_check(payload, symbols.len()) == check,
+            _ => true,
+        },
+        _ => false,
+    }
+}
+
+/// Generate `count` different codes of given format.
+///
+/// ## Panics
+///
+/// If the format has less than `count` different codes.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::{generate_codes, CodeSpec};
+///
+/// let recovery_codes: Vec<String> = generate_codes(10, &CodeSpec::default());
+///
+/// assert_eq!(10, recovery_codes.len());
+/// ```
+pub fn generate_codes(count: usize, spec: &CodeSpec) -> Vec<String> {
+    if let Some(capacity) = spec.capacity() {
+        assert!(
+            count as u64 <= capacity,
+            "code format has only {} different codes",
+            capacity
+        );
+    }
+    let mut seen = HashSet::with_capacity(count);
+    let mut codes = Vec::with_capacity(count);
+    while codes.len() < count {
+        let code = generate_code(spec);
+        if seen.insert(code.clone()) {
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+/// Generate code of given format which is not taken yet, e.g. not found in the database.
+/// Gives up after `attempts` taken codes.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::{generate_unique_code, CodeSpec};
+/// use std::collections::HashSet;
+///
+/// let issued: HashSet<String> = HashSet::new();
+/// let code: Option<String> = generate_unique_code(&CodeSpec::default(), 5, |code| issued.contains(code));
+///
+/// assert!(code.is_some());
+/// ```
+pub fn generate_unique_code<F>(spec: &CodeSpec, attempts: usize, is_taken: F) -> Option<String>
+where
+    F: Fn(&str) -> bool,
+{
+    (0..attempts)
+        .map(|_| generate_code(spec))
+        .find(|code| !is_taken(code))
+}
+
+/// Generate random (version 4) UUID.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::new_uuid_v4;
+///
+/// assert_eq!(4, new_uuid_v4().get_version_num());
+/// ```
+pub fn new_uuid_v4() -> Uuid {
+    let mut bytes = [0u8; 16];
+    secure_rng().fill_bytes(&mut bytes);
+    Uuid::from_random_bytes(bytes)
+}
+
+/// Generate time-ordered (version 7) UUID: 48 bits of Unix time in milliseconds
+/// followed by random bits, so identifiers sort by creation time.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::new_uuid_v7;
+///
+/// assert_eq!(7, new_uuid_v7().get_version_num());
+/// ```
+pub fn new_uuid_v7() -> Uuid {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time is before Unix epoch");
+    let millis = since_epoch.as_secs() * 1000 + u64::from(since_epoch.subsec_millis());
+
+    let mut bytes = [0u8; 16];
+    secure_rng().fill_bytes(&mut bytes[6..]);
+    bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x70; // version 7
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+    Uuid::from_uuid_bytes(bytes)
+}
+
+/// Generate diceware passphrase of `words` words separated by `DEFAULT_SEPARATOR`.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::{generate_passphrase, Wordlist};
+///
+/// let passphrase: String = generate_passphrase(6, Wordlist::Short);
+///
+/// assert_eq!(6, passphrase.split('-').count());
+/// ```
+pub fn generate_passphrase(words: usize, wordlist: Wordlist) -> String {
+    generate_passphrase_with_separator(words, wordlist, DEFAULT_SEPARATOR)
+}
+
+/// Generate diceware passphrase of `words` words joined with given separator.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// extern crate randomness;
+///
+/// use randomness::rand_mod::{generate_passphrase_with_separator, Wordlist};
+///
+/// let passphrase: String = generate_passphrase_with_separator(4, Wordlist::Short, " ");
+///
+/// assert_eq!(4, passphrase.split(' ').count());
+/// ```
+pub fn generate_passphrase_with_separator(
+    words: usize,
+    wordlist: Wordlist,
+    separator: &str,
+) -> String {
+    let list = wordlist.words();
+    let mut rng = secure_rng();
+    (0..words)
+        .map(|_| list[rng.gen_range(0, list.len())])
+        .collect::<Vec<&str>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod test {
+    use rand_mod::*;
+    #[test]
+    fn test_new_access_token() {
+        assert_eq!(64, new_access_token().len());
+    }
+    #[test]
+    fn test_generate_password() {
+        assert_eq!(10, generate_password(10).len());
+    }
+    #[test]
+    fn test_select_rand_val() {
+        let vector: Vec<i32> = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert!(vector.contains(&select_rand_val(vector.as_slice())));
+    }
+    #[test]
+    fn test_generate_otp() {
+        let otp = generate_otp(8);
+        assert_eq!(8, otp.len());
+        assert!(otp.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!("", generate_otp(0));
+    }
+    #[test]
+    fn test_generate_code() {
+        let code = generate_code(&CodeSpec::default());
+        let groups: Vec<&str> = code.split(DEFAULT_SEPARATOR).collect();
+        assert_eq!(3, groups.len());
+        assert!(groups.iter().all(|group| group.len() == 4));
+        assert!(groups.concat().chars().all(|c| CODE_ALPHABET.contains(c)));
+        assert!(verify_code(&code, &CodeSpec::default()));
+        assert!(!verify_code(&code[1..], &CodeSpec::default()));
+        // `I` is not in the alphabet.
+        assert!(!verify_code("A7F2-99KD-M2XI", &CodeSpec::default()));
+    }
+    #[test]
+    fn test_code_checksum() {
+        let luhn = CodeSpec {
+            groups: 1,
+            group_len: 11,
+            alphabet: "0123456789".to_string(),
+            checksum: true,
+        };
+        assert!(verify_code("79927398713", &luhn));
+        assert!(!verify_code("79927398710", &luhn));
+        // A mistyped symbol.
+        assert!(!verify_code("79927398813", &luhn));
+
+        let spec = CodeSpec {
+            checksum: true,
+            ..CodeSpec::default()
+        };
+        for _ in 0..100 {
+            let code = generate_code(&spec);
+            assert!(verify_code(&code, &spec));
+            assert!(verify_code(&code.replace(DEFAULT_SEPARATOR, ""), &spec));
+        }
+    }
+    #[test]
+    fn test_generate_codes() {
+        let spec = CodeSpec {
+            groups: 1,
+            group_len: 2,
+            alphabet: "01".to_string(),
+            checksum: false,
+        };
+        assert_eq!(Some(4), spec.capacity());
+        let mut codes = generate_codes(4, &spec);
+        codes.sort();
+        assert_eq!(vec!["00", "01", "10", "11"], codes);
+        assert_eq!(
+            None,
+            CodeSpec {
+                groups: 20,
+                ..CodeSpec::default()
+            }
+            .capacity()
+        );
+    }
+    #[test]
+    #[should_panic]
+    fn test_generate_codes_over_capacity() {
+        let spec = CodeSpec {
+            groups: 1,
+            group_len: 1,
+            alphabet: "01".to_string(),
+            checksum: false,
+        };
+        generate_codes(3, &spec);
+    }
+    #[test]
+    fn test_generate_unique_code() {
+        let spec = CodeSpec {
+            groups: 1,
+            group_len: 1,
+            alphabet: "01".to_string(),
+            checksum: false,
+        };
+        assert_eq!(
+            Some("1".to_string()),
+            generate_unique_code(&spec, 100, |code| code == "0")
+        );
+        assert_eq!(None, generate_unique_code(&spec, 100, |_| true));
+    }
+    #[test]
+    fn test_new_uuid_v4() {
+        let uuid = new_uuid_v4();
+        assert_eq!(4, uuid.get_version_num());
+        assert_ne!(uuid, new_uuid_v4());
+    }
+    #[test]
+    fn test_new_uuid_v7() {
+        let first = new_uuid_v7();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = new_uuid_v7();
+        assert_eq!(7, first.get_version_num());
+        assert_eq!(0x80, first.as_bytes()[8] & 0xc0);
+        assert!(first.as_bytes()[..6] < second.as_bytes()[..6]);
+    }
+    #[test]
+    fn test_short_wordlist() {
+        let words = Wordlist::Short.words();
+        assert_eq!(216, words.len());
+        assert_eq!("acid", words[0]);
+    }
+    #[test]
+    fn test_generate_passphrase() {
+        let list = Wordlist::Short.words();
+        let passphrase = generate_passphrase(6, Wordlist::Short);
+        let words: Vec<&str> = passphrase.split(DEFAULT_SEPARATOR).collect();
+        assert_eq!(6, words.len());
+        assert!(words.iter().all(|w| list.contains(w)));
+    }
+    #[test]
+    fn test_generate_passphrase_with_separator() {
+        let wordlist = Wordlist::custom(vec!["one".to_string(), "two".to_string()]).unwrap();
+        let passphrase = generate_passphrase_with_separator(3, wordlist, " ");
+        assert_eq!(3, passphrase.split(' ').count());
+        assert!(passphrase.split(' ').all(|w| w == "one" || w == "two"));
+    }
+    #[test]
+    fn test_empty_custom_wordlist() {
+        let result = Wordlist::custom(Vec::new());
+        assert_eq!(io::ErrorKind::InvalidData, result.unwrap_err().kind());
+    }
+    #[test]
+    fn test_wordlist_from_file() {
+        let path = std::env::temp_dir().join("randomness_test_wordlist.txt");
+        std::fs::write(&path, "11111\tabacus\n\nzebra\n").unwrap();
+        let wordlist = Wordlist::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vec!["abacus", "zebra"], wordlist.words());
+    }
+    #[test]
+    fn test_wordlist_from_empty_file() {
+        let path = std::env::temp_dir().join("randomness_test_wordlist_empty.txt");
+        std::fs::write(&path, "\n").unwrap();
+        let result = Wordlist::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
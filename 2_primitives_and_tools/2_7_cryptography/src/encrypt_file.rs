@@ -0,0 +1,1227 @@
+//! # File Encryption Module
+//!
+//! The module creates the encrypted file using the crate ring and the ring и алгоритма `ring::aead::CHACHA20_POLY1305` algorithm.
+//! The key is a `KeyMaterial`: random, derived from a passphrase or loaded from a file,
+//! every encrypted file is an `EncryptedEnvelope` with its own random nonce,
+//! it describes itself, so the files of the older versions are told apart from the newer ones.
+//! The hash received from the encrypted file is signed using crate `ring::signature::Ed25519KeyPair`
+//! or, for the systems that can't verify Ed25519, with RSA-PSS (`SignatureAlgorithm`).
+//! After checking the hash signature of the encrypted file, you can decrypt the file to its original state.
+//! `verify_file_integrity` checks the file against its hash, the mismatch is `AppError::TamperDetected`.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//!  extern crate common_errors;
+//!  extern crate cryptography;
+//!
+//!  use common_errors::AppError;
+//!  use cryptography::encrypt_file::*;
+//!
+//!  fn test()->Result<(),AppError>{
+//!    let path = std::path::Path::new("pic.jpg");
+//!    let (uuid_name,hash_file) = get_file_name_and_hash(path)?;
+//!    let key = KeyMaterial::generate()?;
+//!
+//!    // create an encrypted version of the file
+//!    let hash_file_encrypt:Vec<u8> = encrypt_file_content(path,&uuid_name,&key)?;
+//!
+//!    // sign a hash
+//!    let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&hash_file_encrypt,SignatureAlgorithm::Ed25519)?;
+//!
+//!    // check hash
+//!    if check_key_is_correct(SignatureAlgorithm::Ed25519,&hash_file_encrypt,&peer_public_key_bytes,&sig_bytes).is_ok(){
+//!
+//!      // verify signature
+//!      deciphering_file_content( std::path::Path::new(&uuid_name) ,std::path::Path::new("pic_deciphering.jpg"),&key)?;
+//!    }   
+//!  Ok(())
+//!  }
+//! ```
+
+use blake2::{Blake2b, Digest};
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use ring::rand::SecureRandom;
+use ring::{aead, constant_time, digest, hkdf, hmac, pbkdf2, rand, signature};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use untrusted;
+use uuid::{self, Uuid};
+
+/// The ring, io and uuid errors of the module are converted into
+/// the shared `AppError`, `AppError::Crypto` for the ring ones.
+pub use common_errors::AppError;
+
+/// Algorithm of the signatures.
+/// RSA-PSS uses SHA-256, its public key is the DER `RSAPublicKey` (PKCS#1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignatureAlgorithm {
+    Ed25519,
+    RsaPss2048,
+    RsaPss4096,
+}
+
+impl Default for SignatureAlgorithm {
+    fn default() -> SignatureAlgorithm {
+        SignatureAlgorithm::Ed25519
+    }
+}
+
+impl SignatureAlgorithm {
+    /// Size of the RSA key, `None` for Ed25519.
+    pub fn rsa_bits(self) -> Option<u32> {
+        match self {
+            SignatureAlgorithm::Ed25519 => None,
+            SignatureAlgorithm::RsaPss2048 => Some(2048),
+            SignatureAlgorithm::RsaPss4096 => Some(4096),
+        }
+    }
+
+    fn verification(self) -> &'static dyn signature::VerificationAlgorithm {
+        match self {
+            SignatureAlgorithm::Ed25519 => &signature::ED25519,
+            SignatureAlgorithm::RsaPss2048 | SignatureAlgorithm::RsaPss4096 => {
+                &signature::RSA_PSS_2048_8192_SHA256
+            }
+        }
+    }
+}
+
+/// Length of the `KeyMaterial`, the keys of the `AeadAlgorithm`s are derived from it.
+pub const KEY_LEN: usize = 32;
+
+/// Length of the salt of `KeyMaterial::generate_salt`.
+pub const SALT_LEN: usize = 16;
+
+/// Iterations of PBKDF2-HMAC-SHA256 of `KeyMaterial::from_passphrase`.
+pub const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// Key of the encryption of the files.
+/// It is never printed: `Debug` shows only that it is a key.
+#[derive(Clone)]
+pub struct KeyMaterial([u8; KEY_LEN]);
+
+impl fmt::Debug for KeyMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("KeyMaterial(..)")
+    }
+}
+
+impl KeyMaterial {
+    /// Random key of `ring::rand::SystemRandom`.
+    pub fn generate() -> Result<KeyMaterial, AppError> {
+        let mut key = [0u8; KEY_LEN];
+        rand::SystemRandom::new().fill(&mut key)?;
+        Ok(KeyMaterial(key))
+    }
+
+    /// Key derived from the passphrase with PBKDF2-HMAC-SHA256,
+    /// the same passphrase and salt give the same key.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> KeyMaterial {
+        let mut key = [0u8; KEY_LEN];
+        pbkdf2::derive(
+            &digest::SHA256,
+            PBKDF2_ITERATIONS,
+            salt,
+            passphrase.as_bytes(),
+            &mut key,
+        );
+        KeyMaterial(key)
+    }
+
+    /// Random salt of `from_passphrase`, it is kept along with the encrypted files.
+    pub fn generate_salt() -> Result<[u8; SALT_LEN], AppError> {
+        let mut salt = [0u8; SALT_LEN];
+        rand::SystemRandom::new().fill(&mut salt)?;
+        Ok(salt)
+    }
+
+    /// Load the key saved by `store`, a file of another length is an error.
+    pub fn load(path: &Path) -> Result<KeyMaterial, AppError> {
+        let bytes: Vec<u8> = fs::read(path)?;
+        if bytes.len() != KEY_LEN {
+            return Err(AppError::crypto(format!(
+                "key of {} bytes, expected {}",
+                bytes.len(),
+                KEY_LEN
+            )));
+        }
+        let mut key = [0u8; KEY_LEN];
+        key.copy_from_slice(&bytes);
+        Ok(KeyMaterial(key))
+    }
+
+    /// Save the key to the file, on Unix only its owner can read it.
+    pub fn store(&self, path: &Path) -> Result<(), AppError> {
+        write_private(path, &self.0)
+    }
+}
+
+/// Write the secret to the file, on Unix only its owner can read it.
+/// The mode of an existing file is changed as well.
+fn write_private(path: &Path, secret: &[u8]) -> Result<(), AppError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(secret)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Magic bytes at the start of the encrypted files.
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"ENCF";
+
+/// Version of the `EncryptedEnvelope` written by `encrypt_file_content`.
+/// Since version 3 the key of every algorithm is derived with HKDF.
+pub const ENVELOPE_VERSION: u8 = 3;
+
+/// Salt of the HKDF deriving the keys of the `AeadAlgorithm`s from the `KeyMaterial`.
+const AEAD_KEY_SALT: &[u8] = b"encrypt_file aead key";
+
+/// Extension of the encrypted files named by `get_file_name_and_hash`.
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+
+/// AEAD algorithm of the encrypted files, it is recorded in the `EncryptedEnvelope`,
+/// so the files are decrypted with their own algorithm.
+/// Every algorithm has its own key derived from the `KeyMaterial`.
+/// The envelope keeps the index of the variant: the new ones are added at the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AeadAlgorithm {
+    #[default]
+    ChaCha20Poly1305,
+    Aes256Gcm,
+    Aes128Gcm,
+}
+
+impl AeadAlgorithm {
+    fn aead(self) -> &'static aead::Algorithm {
+        match self {
+            AeadAlgorithm::ChaCha20Poly1305 => &aead::CHACHA20_POLY1305,
+            AeadAlgorithm::Aes256Gcm => &aead::AES_256_GCM,
+            AeadAlgorithm::Aes128Gcm => &aead::AES_128_GCM,
+        }
+    }
+
+    /// Name of the algorithm in the info of its HKDF.
+    fn id(self) -> &'static [u8] {
+        match self {
+            AeadAlgorithm::ChaCha20Poly1305 => b"CHACHA20-POLY1305",
+            AeadAlgorithm::Aes256Gcm => b"AES-256-GCM",
+            AeadAlgorithm::Aes128Gcm => b"AES-128-GCM",
+        }
+    }
+
+    /// The key of the algorithm derived from the key material with HKDF-SHA256,
+    /// so the algorithms never share a key or a part of it.
+    fn key(self, key: &KeyMaterial) -> Vec<u8> {
+        let salt = hmac::SigningKey::new(&digest::SHA256, AEAD_KEY_SALT);
+        let mut derived = vec![0u8; self.aead().key_len()];
+        hkdf::extract_and_expand(&salt, &key.0, self.id(), &mut derived);
+        derived
+    }
+}
+
+/// Signature of the `EncryptedEnvelope` made by `EncryptedEnvelope::sign`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    pub algorithm: SignatureAlgorithm,
+    pub bytes: Vec<u8>,
+}
+
+/// The encrypted file serialized with bincode.
+/// The magic bytes and the version come first, so `from_bytes` rejects
+/// the other files and the unsupported versions before reading the rest.
+/// The magic bytes, the version, the algorithm and the nonce are authenticated
+/// as the associated data of the payload, the signature covers everything but itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedEnvelope {
+    pub magic: [u8; 4],
+    pub version: u8,
+    pub algorithm: AeadAlgorithm,
+    pub nonce: Vec<u8>,
+    pub tag: Vec<u8>,
+    pub payload: Vec<u8>,
+    pub signature: Option<EnvelopeSignature>,
+}
+
+impl EncryptedEnvelope {
+    /// Encrypt the contents with the key and a new random nonce.
+    pub fn seal(
+        algorithm: AeadAlgorithm,
+        key: &KeyMaterial,
+        contents: &[u8],
+    ) -> Result<EncryptedEnvelope, AppError> {
+        let aead_alg: &'static aead::Algorithm = algorithm.aead();
+        let s_key: ring::aead::SealingKey = aead::SealingKey::new(aead_alg, &algorithm.key(key))?;
+
+        let mut nonce = vec![0u8; aead_alg.nonce_len()];
+        rand::SystemRandom::new().fill(&mut nonce)?;
+        let mut envelope = EncryptedEnvelope {
+            magic: ENVELOPE_MAGIC,
+            version: ENVELOPE_VERSION,
+            algorithm,
+            nonce,
+            tag: Vec::new(),
+            payload: Vec::new(),
+            signature: None,
+        };
+        let ad = envelope.associated_data()?;
+
+        let tag_len = aead_alg.tag_len();
+        let mut in_out: Vec<u8> = Vec::with_capacity(contents.len() + tag_len);
+        in_out.extend_from_slice(contents);
+        in_out.resize(contents.len() + tag_len, 0);
+        let sealed_len = aead::seal_in_place(&s_key, &envelope.nonce, &ad, &mut in_out, tag_len)?;
+        in_out.truncate(sealed_len);
+
+        envelope.tag = in_out.split_off(sealed_len - tag_len);
+        envelope.payload = in_out;
+        Ok(envelope)
+    }
+
+    /// Decrypt the payload with the key it was sealed with.
+    /// The payload which doesn't match its tag, or the wrong key, is `AppError::TamperDetected`.
+    pub fn open(&self, key: &KeyMaterial) -> Result<Vec<u8>, AppError> {
+        let aead_alg: &'static aead::Algorithm = self.algorithm.aead();
+        if self.nonce.len() != aead_alg.nonce_len() || self.tag.len() != aead_alg.tag_len() {
+            return Err(AppError::crypto("invalid length of the nonce or the tag"));
+        }
+        let o_key: ring::aead::OpeningKey =
+            aead::OpeningKey::new(aead_alg, &self.algorithm.key(key))?;
+        let ad = self.associated_data()?;
+        let prefix_len = 0;
+
+        let mut in_out: Vec<u8> = self.payload.clone();
+        in_out.extend_from_slice(&self.tag);
+        let opened_len = aead::open_in_place(&o_key, &self.nonce, &ad, prefix_len, &mut in_out)
+            .map_err(|_| AppError::TamperDetected("the encrypted payload".to_string()))?
+            .len();
+        in_out.truncate(opened_len);
+        Ok(in_out)
+    }
+
+    /// Sign the envelope with the key, the previous signature is replaced.
+    pub fn sign(&mut self, key: &SigningKey) -> Result<(), AppError> {
+        let bytes = key.sign(&self.signed_bytes()?)?;
+        self.signature = Some(EnvelopeSignature {
+            algorithm: key.algorithm(),
+            bytes,
+        });
+        Ok(())
+    }
+
+    /// Verification of the signature of the envelope made with the algorithm,
+    /// an envelope without a signature is an error.
+    pub fn verify(
+        &self,
+        algorithm: SignatureAlgorithm,
+        peer_public_key_bytes: &[u8],
+    ) -> Result<(), AppError> {
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| AppError::crypto("the encrypted file is not signed"))?;
+        if signature.algorithm != algorithm {
+            return Err(AppError::crypto(format!(
+                "the encrypted file is signed with {:?}, expected {:?}",
+                signature.algorithm, algorithm
+            )));
+        }
+        check_key_is_correct(
+            algorithm,
+            &self.signed_bytes()?,
+            peer_public_key_bytes,
+            &signature.bytes,
+        )
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, AppError> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<EncryptedEnvelope, AppError> {
+        if !bytes.starts_with(&ENVELOPE_MAGIC) {
+            return Err(AppError::crypto("not an encrypted file"));
+        }
+        match bytes.get(ENVELOPE_MAGIC.len()) {
+            Some(&ENVELOPE_VERSION) => Ok(bincode::deserialize(bytes)?),
+            Some(version) => Err(AppError::crypto(format!(
+                "unsupported version {} of the encrypted file",
+                version
+            ))),
+            None => Err(AppError::crypto("the encrypted file is truncated")),
+        }
+    }
+
+    fn associated_data(&self) -> Result<Vec<u8>, AppError> {
+        Ok(bincode::serialize(&(
+            self.magic,
+            self.version,
+            self.algorithm,
+            &self.nonce,
+        ))?)
+    }
+
+    fn signed_bytes(&self) -> Result<Vec<u8>, AppError> {
+        Ok(bincode::serialize(&(
+            self.magic,
+            self.version,
+            self.algorithm,
+            &self.nonce,
+            &self.tag,
+            &self.payload,
+        ))?)
+    }
+}
+
+/// Create a new encrypted version of this file and
+/// return the hash of the encrypted file.
+/// The encrypted file is the `EncryptedEnvelope` of `AeadAlgorithm::default()`.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///    let path = std::path::Path::new("pic.jpg");
+///    let (uuid_name,hash_file) = get_file_name_and_hash(path)?;
+///
+///    let key = KeyMaterial::load(std::path::Path::new("pic.key"))?;
+///
+///    // create an encrypted version of the file  
+///    let hash_file_encrypt:Vec<u8> = encrypt_file_content(path,&uuid_name,&key)?;
+///  Ok(())
+///  }
+/// ```
+pub fn encrypt_file_content(
+    path: &std::path::Path,
+    uuid_name: &str,
+    key: &KeyMaterial,
+) -> Result<(Vec<u8>), AppError> {
+    encrypt_file_content_with(path, uuid_name, key, AeadAlgorithm::default())
+}
+
+/// `encrypt_file_content` with the algorithm.
+pub fn encrypt_file_content_with(
+    path: &Path,
+    uuid_name: &str,
+    key: &KeyMaterial,
+    algorithm: AeadAlgorithm,
+) -> Result<Vec<u8>, AppError> {
+    let contents: Vec<u8> = std::fs::read(path)?;
+    let envelope = EncryptedEnvelope::seal(algorithm, key, &contents)?;
+    std::fs::write(uuid_name, envelope.to_bytes()?)?;
+
+    let (_, hash_file_encrypt) = get_file_name_and_hash(std::path::Path::new(&uuid_name))?;
+    Ok(hash_file_encrypt)
+}
+
+/// Return the signature of the received data.
+/// It is better to sign a hash file than the file itself.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///    let path = std::path::Path::new("pic.jpg");
+///    let (uuid_name,hash_file) = get_file_name_and_hash(path)?;
+///    let key = KeyMaterial::generate()?;
+///
+///    // создать шифрованную версия файла  
+///    let hash_file_encrypt:Vec<u8> = encrypt_file_content(path,&uuid_name,&key)?;
+///    // подписать хеш
+///    // let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&hash_file_encrypt).unwrap_or((vec![1u8;0],vec![1u8;0]));
+///
+///    let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&hash_file_encrypt,SignatureAlgorithm::Ed25519)?;
+///  
+///  Ok(())
+///  }
+/// ```
+pub fn gen_fingerprint(
+    message: &[u8],
+    algorithm: SignatureAlgorithm,
+) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    let pkcs8_bytes = generate_pkcs8(algorithm)?;
+    sign_pkcs8(algorithm, &pkcs8_bytes, message)
+}
+
+/// Generate a new private key of the algorithm in PKCS#8 (DER).
+/// ring can't generate the RSA keys, they are generated by openssl.
+pub fn generate_pkcs8(algorithm: SignatureAlgorithm) -> Result<Vec<u8>, AppError> {
+    match algorithm.rsa_bits() {
+        None => {
+            let rng = rand::SystemRandom::new();
+            let pkcs8_bytes = signature::Ed25519KeyPair::generate_pkcs8(&rng)?;
+            Ok(pkcs8_bytes.to_vec())
+        }
+        Some(bits) => {
+            let rsa = Rsa::generate(bits).map_err(AppError::crypto)?;
+            PKey::from_rsa(rsa)
+                .and_then(|key| key.private_key_to_pkcs8())
+                .map_err(AppError::crypto)
+        }
+    }
+}
+
+/// Save the PKCS#8 private key to the file, on Unix only its owner can read it.
+pub fn store_pkcs8(path: &Path, pkcs8_bytes: &[u8]) -> Result<(), AppError> {
+    write_private(path, pkcs8_bytes)
+}
+
+/// Load the PKCS#8 private key of the algorithm from the file,
+/// a key of another algorithm or size is an error.
+pub fn load_pkcs8(path: &Path, algorithm: SignatureAlgorithm) -> Result<Vec<u8>, AppError> {
+    let pkcs8_bytes: Vec<u8> = fs::read(path)?;
+    // Signing checks the algorithm and the size of the key.
+    sign_pkcs8(algorithm, &pkcs8_bytes, b"")?;
+    Ok(pkcs8_bytes)
+}
+
+/// Private key of the signatures kept in a PKCS#8 file between the runs,
+/// unlike the throwaway key of `gen_fingerprint`.
+/// It is never printed: `Debug` shows only its algorithm.
+#[derive(Clone)]
+pub struct SigningKey {
+    algorithm: SignatureAlgorithm,
+    pkcs8_bytes: Vec<u8>,
+    public_key_bytes: Vec<u8>,
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SigningKey({:?})", self.algorithm)
+    }
+}
+
+impl SigningKey {
+    /// Generate a new `SignatureAlgorithm::default()` key and save it to the file,
+    /// on Unix only its owner can read it.
+    pub fn generate_to_file(path: &Path) -> Result<SigningKey, AppError> {
+        SigningKey::generate_to_file_with(path, SignatureAlgorithm::default())
+    }
+
+    /// `generate_to_file` with the algorithm.
+    pub fn generate_to_file_with(
+        path: &Path,
+        algorithm: SignatureAlgorithm,
+    ) -> Result<SigningKey, AppError> {
+        let key = SigningKey::from_pkcs8(algorithm, generate_pkcs8(algorithm)?)?;
+        store_pkcs8(path, &key.pkcs8_bytes)?;
+        Ok(key)
+    }
+
+    /// Load the key saved by `generate_to_file`, its algorithm is found from the key.
+    pub fn load(path: &Path) -> Result<SigningKey, AppError> {
+        let pkcs8_bytes: Vec<u8> = fs::read(path)?;
+        [
+            SignatureAlgorithm::Ed25519,
+            SignatureAlgorithm::RsaPss2048,
+            SignatureAlgorithm::RsaPss4096,
+        ]
+        .iter()
+        .filter_map(|&algorithm| SigningKey::from_pkcs8(algorithm, pkcs8_bytes.clone()).ok())
+        .next()
+        .ok_or_else(|| AppError::crypto("the file is not a PKCS#8 signing key"))
+    }
+
+    fn from_pkcs8(
+        algorithm: SignatureAlgorithm,
+        pkcs8_bytes: Vec<u8>,
+    ) -> Result<SigningKey, AppError> {
+        // Signing checks the algorithm and the size of the key.
+        let (public_key_bytes, _) = sign_pkcs8(algorithm, &pkcs8_bytes, b"")?;
+        Ok(SigningKey {
+            algorithm,
+            pkcs8_bytes,
+            public_key_bytes,
+        })
+    }
+
+    pub fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+
+    /// Public key of `check_key_is_correct` and `verify_file`.
+    pub fn public_key_bytes(&self) -> &[u8] {
+        &self.public_key_bytes
+    }
+
+    /// Signature of the message.
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, AppError> {
+        let (_, sig_bytes) = sign_pkcs8(self.algorithm, &self.pkcs8_bytes, message)?;
+        Ok(sig_bytes)
+    }
+}
+
+/// Sign the message with the PKCS#8 private key,
+/// return the public key and the signature like `gen_fingerprint`.
+pub fn sign_pkcs8(
+    algorithm: SignatureAlgorithm,
+    pkcs8_bytes: &[u8],
+    message: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    match algorithm.rsa_bits() {
+        None => sign_ed25519(pkcs8_bytes, message),
+        Some(bits) => sign_rsa_pss(bits as usize, pkcs8_bytes, message),
+    }
+}
+
+fn sign_ed25519(pkcs8_bytes: &[u8], message: &[u8]) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    let key_pair: ring::signature::Ed25519KeyPair =
+        signature::Ed25519KeyPair::from_pkcs8(untrusted::Input::from(pkcs8_bytes))?;
+
+    //Подпишите сообщение.
+    let sig: ring::signature::Signature = key_pair.sign(message);
+
+    let peer_public_key_bytes: &[u8] = key_pair.public_key_bytes();
+    let sig_bytes: &[u8] = sig.as_ref();
+
+    Ok((peer_public_key_bytes.to_vec(), sig_bytes.to_vec()))
+}
+
+fn sign_rsa_pss(
+    bits: usize,
+    pkcs8_bytes: &[u8],
+    message: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), AppError> {
+    let key_pair = signature::RSAKeyPair::from_pkcs8(untrusted::Input::from(pkcs8_bytes))?;
+    if key_pair.public_modulus_len() * 8 != bits {
+        return Err(AppError::crypto(format!(
+            "RSA key of {} bits, expected {}",
+            key_pair.public_modulus_len() * 8,
+            bits
+        )));
+    }
+    let mut sig_bytes = vec![0u8; key_pair.public_modulus_len()];
+    let mut signing_state = signature::RSASigningState::new(Arc::new(key_pair))?;
+    let rng = rand::SystemRandom::new();
+    signing_state.sign(&signature::RSA_PSS_SHA256, &rng, message, &mut sig_bytes)?;
+
+    // ring doesn't export the public key of the RSA key pair.
+    let peer_public_key_bytes = PKey::private_key_from_pkcs8(pkcs8_bytes)
+        .and_then(|key| key.rsa())
+        .and_then(|rsa| rsa.public_key_to_der_pkcs1())
+        .map_err(AppError::crypto)?;
+
+    Ok((peer_public_key_bytes, sig_bytes))
+}
+
+/// Verification of a signature.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///    let path = std::path::Path::new("pic.jpg");
+///    let (uuid_name,hash_file) = get_file_name_and_hash(path)?;
+///    let key = KeyMaterial::generate()?;
+///
+///    // создать шифрованную версия файла  
+///    let hash_file_encrypt:Vec<u8> = encrypt_file_content(path,&uuid_name,&key)?;
+///    // подписать хеш
+///    // let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&hash_file_encrypt).unwrap_or((vec![1u8;0],vec![1u8;0]));
+///
+///    let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&hash_file_encrypt,SignatureAlgorithm::Ed25519)?;
+///
+///    // проверить хеш
+///
+///    if check_key_is_correct(SignatureAlgorithm::Ed25519,&hash_file_encrypt,&peer_public_key_bytes,&sig_bytes).is_ok(){
+///
+///      println!("Можно расшифровывать в исходную картинку");
+///
+///      deciphering_file_content( std::path::Path::new(&uuid_name) ,std::path::Path::new("pic_deciphering.jpg"),&key)?;
+///    }   
+///  Ok(())
+///  }
+/// ```
+pub fn check_key_is_correct(
+    algorithm: SignatureAlgorithm,
+    to_open: &[u8],
+    peer_public_key_bytes: &[u8],
+    sig_bytes: &[u8],
+) -> Result<(), AppError> {
+    let peer_public_key = untrusted::Input::from(peer_public_key_bytes);
+    let msg = untrusted::Input::from(to_open);
+    let sig = untrusted::Input::from(sig_bytes);
+
+    signature::verify(algorithm.verification(), peer_public_key, msg, sig)
+        .map_err(|_| AppError::crypto("invalid signature"))
+}
+
+/// Path of the detached signature of the file: `pic.jpg.sig` of `pic.jpg`.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Sign the contents of the file with the key and write
+/// the detached signature to `signature_path(path)`, return its path.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///    let key_path = std::path::Path::new("pic.pk8");
+///    let signing_key = if key_path.exists() {
+///        SigningKey::load(key_path)?
+///    } else {
+///        SigningKey::generate_to_file(key_path)?
+///    };
+///
+///    // pic.jpg.sig
+///    sign_file(&signing_key, std::path::Path::new("pic.jpg"))?;
+///    verify_file(std::path::Path::new("pic.jpg"), signing_key.algorithm(), signing_key.public_key_bytes())?;
+///  Ok(())
+///  }
+/// ```
+pub fn sign_file(key: &SigningKey, path: &Path) -> Result<PathBuf, AppError> {
+    let content: Vec<u8> = fs::read(path)?;
+    let sig_path = signature_path(path);
+    fs::write(&sig_path, key.sign(&content)?)?;
+    Ok(sig_path)
+}
+
+/// Verification of the detached signature `signature_path(path)`
+/// of the contents of the file, written by `sign_file`.
+pub fn verify_file(
+    path: &Path,
+    algorithm: SignatureAlgorithm,
+    peer_public_key_bytes: &[u8],
+) -> Result<(), AppError> {
+    let content: Vec<u8> = fs::read(path)?;
+    let sig_bytes: Vec<u8> = fs::read(signature_path(path))?;
+    check_key_is_correct(algorithm, &content, peer_public_key_bytes, &sig_bytes)
+}
+
+/// Verification of the detached Ed25519 signature `signature_path(path)`
+/// of the contents of the file.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///    let content = std::fs::read("pic.jpg")?;
+///    let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&content,SignatureAlgorithm::Ed25519)?;
+///    std::fs::write("pic.jpg.sig", &sig_bytes)?;
+///
+///    verify_detached(std::path::Path::new("pic.jpg"), &peer_public_key_bytes)?;
+///  Ok(())
+///  }
+/// ```
+pub fn verify_detached(path: &Path, peer_public_key_bytes: &[u8]) -> Result<(), AppError> {
+    verify_file(path, SignatureAlgorithm::Ed25519, peer_public_key_bytes)
+}
+
+/// Return a new unique name for the file and hash of its contents.
+/// The contents of the file are created using crate `Blake2b`.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///
+///    let path = std::path::Path::new("pic.jpg");
+///
+///    let (uuid_name,hash_file) = get_file_name_and_hash(path)?;
+///
+///  Ok(())
+///  }
+/// ```
+pub fn get_file_name_and_hash(path: &std::path::Path) -> Result<(String, Vec<u8>), AppError> {
+    let uuid =
+        Uuid::new(uuid::UuidVersion::Random).ok_or_else(|| AppError::crypto("Error Uuid"))?;
+
+    let output = hash_file(path)?;
+
+    let uuid_name: String = format!("{:x}.{}", uuid.simple(), ENCRYPTED_EXTENSION);
+    //let hash_file:String  =  format!("{:x}" , output);
+
+    Ok((uuid_name, output))
+}
+
+/// Blake2b hash of the contents of the file.
+fn hash_file(path: &Path) -> Result<Vec<u8>, AppError> {
+    let mut file = fs::File::open(path)?;
+    Ok(Blake2b::digest_reader(&mut file)?.to_vec())
+}
+
+/// Check that the contents of the file have the hash of `get_file_name_and_hash`,
+/// the hashes are compared in constant time, the mismatch is `AppError::TamperDetected`.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///    let path = std::path::Path::new("pic.jpg");
+///    let (uuid_name,hash_file) = get_file_name_and_hash(path)?;
+///
+///    match verify_file_integrity(path,&hash_file) {
+///      Err(AppError::TamperDetected(what)) => println!("{} was tampered with", what),
+///      other => other?,
+///    }
+///  Ok(())
+///  }
+/// ```
+pub fn verify_file_integrity(path: &Path, expected_hash: &[u8]) -> Result<(), AppError> {
+    let actual_hash = hash_file(path)?;
+    constant_time::verify_slices_are_equal(&actual_hash, expected_hash)
+        .map_err(|_| AppError::TamperDetected(format!("the file {}", path.display())))
+}
+
+/// Decipher the received data with the key it was encrypted with,
+/// the algorithm and the nonce are read from its `EncryptedEnvelope`.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+///  extern crate common_errors;
+///  extern crate cryptography;
+///
+///  use common_errors::AppError;
+///  use cryptography::encrypt_file::*;
+///
+///  fn test()->Result<(),AppError>{
+///    let path = std::path::Path::new("pic.jpg");
+///    let (uuid_name,hash_file) = get_file_name_and_hash(path)?;
+///    let key = KeyMaterial::generate()?;
+///
+///    // создать шифрованную версия файла  
+///    let hash_file_encrypt:Vec<u8> = encrypt_file_content(path,&uuid_name,&key)?;
+///    // подписать хеш
+///    // let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&hash_file_encrypt).unwrap_or((vec![1u8;0],vec![1u8;0]));
+///
+///    let (peer_public_key_bytes,sig_bytes) = gen_fingerprint(&hash_file_encrypt,SignatureAlgorithm::Ed25519)?;
+///
+///    // проверить хеш
+///
+///    if check_key_is_correct(SignatureAlgorithm::Ed25519,&hash_file_encrypt,&peer_public_key_bytes,&sig_bytes).is_ok(){
+///
+///      println!("Можно расшифровывать в исходную картинку");
+///
+///      deciphering_file_content( std::path::Path::new(&uuid_name) ,std::path::Path::new("pic_deciphering.jpg"),&key)?;
+///    }   
+///  Ok(())
+///  }
+/// ```
+pub fn deciphering_file_content(
+    path_open: &std::path::Path,
+    path: &std::path::Path,
+    key: &KeyMaterial,
+) -> Result<(), AppError> {
+    let file: std::vec::Vec<u8> = std::fs::read(path_open)?;
+    let envelope = EncryptedEnvelope::from_bytes(&file)?;
+    std::fs::write(path, envelope.open(key)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_file_name_and_hash() {
+        let path = std::path::Path::new("test.txt");
+        assert!(fs::File::create(&path).is_ok());
+
+        assert!(get_file_name_and_hash(path).is_ok());
+
+        fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_encrypt_file_content() {
+        let path = std::path::Path::new("test2.txt");
+        assert!(fs::File::create(&path).is_ok());
+
+        if let Ok(uuid) = Uuid::new(uuid::UuidVersion::Random)
+            .ok_or_else(|| AppError::crypto("Error Uuid"))
+        {
+            let uuid_name: String = format!("{:x}.txt", uuid.simple());
+
+            let key = KeyMaterial::generate().unwrap();
+            assert!(encrypt_file_content(path, &uuid_name, &key).is_ok());
+
+            fs::remove_file(uuid_name);
+        } else {
+            assert!(false);
+        }
+        fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_check_key_is_correct() {
+        let path = std::path::Path::new("test_check.txt");
+        assert!(fs::File::create(&path).is_ok());
+        if let Ok(uuid) = Uuid::new(uuid::UuidVersion::Random)
+            .ok_or_else(|| AppError::crypto("Error Uuid"))
+        {
+            let uuid_name: String = format!("{:x}.txt", uuid.simple());
+
+            let key = KeyMaterial::generate().unwrap();
+            if let Ok(hash_file_encrypt) = encrypt_file_content(path, &uuid_name, &key) {
+                if let Ok((peer_public_key_bytes, sig_bytes)) =
+                    gen_fingerprint(&hash_file_encrypt, SignatureAlgorithm::Ed25519)
+                {
+                    assert!(
+                        check_key_is_correct(
+                            SignatureAlgorithm::Ed25519,
+                            &hash_file_encrypt,
+                            &peer_public_key_bytes,
+                            &sig_bytes
+                        ).is_ok()
+                    );
+                } else {
+                    assert!(false);
+                }
+            } else {
+                assert!(false);
+            }
+            fs::remove_file(uuid_name);
+        } else {
+            assert!(false);
+        }
+        fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_key_material() {
+        let path = Path::new("test_key_material.txt");
+        fs::write(path, b"page").unwrap();
+        let encrypted = "test_key_material.enc";
+        let decrypted = Path::new("test_key_material.dec");
+
+        let salt = KeyMaterial::generate_salt().unwrap();
+        let key = KeyMaterial::from_passphrase("correct horse", &salt);
+        encrypt_file_content(path, encrypted, &key).unwrap();
+        assert!(!fs::read(encrypted).unwrap().ends_with(b"page"));
+
+        // The same passphrase and salt give the same key.
+        let same_key = KeyMaterial::from_passphrase("correct horse", &salt);
+        deciphering_file_content(Path::new(encrypted), decrypted, &same_key).unwrap();
+        assert_eq!(b"page".to_vec(), fs::read(decrypted).unwrap());
+
+        let other_salt = KeyMaterial::generate_salt().unwrap();
+        for other_key in &[
+            KeyMaterial::from_passphrase("correct horse", &other_salt),
+            KeyMaterial::from_passphrase("battery staple", &salt),
+            KeyMaterial::generate().unwrap(),
+        ] {
+            assert!(deciphering_file_content(Path::new(encrypted), decrypted, other_key).is_err());
+        }
+
+        let key_path = Path::new("test_key_material.key");
+        key.store(key_path).unwrap();
+        let loaded = KeyMaterial::load(key_path).unwrap();
+        assert_eq!("KeyMaterial(..)", format!("{:?}", loaded));
+        deciphering_file_content(Path::new(encrypted), decrypted, &loaded).unwrap();
+        fs::write(key_path, b"short").unwrap();
+        assert!(KeyMaterial::load(key_path).is_err());
+
+        fs::write(encrypted, b"short").unwrap();
+        assert!(deciphering_file_content(Path::new(encrypted), decrypted, &key).is_err());
+
+        for file in &[path, Path::new(encrypted), decrypted, key_path] {
+            fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_encrypted_envelope() {
+        let path = Path::new("test_envelope.txt");
+        fs::write(path, b"page").unwrap();
+        let encrypted = "test_envelope.enc";
+        let decrypted = Path::new("test_envelope.dec");
+        let key = KeyMaterial::generate().unwrap();
+
+        for &algorithm in &[
+            AeadAlgorithm::ChaCha20Poly1305,
+            AeadAlgorithm::Aes256Gcm,
+            AeadAlgorithm::Aes128Gcm,
+        ] {
+            encrypt_file_content_with(path, encrypted, &key, algorithm).unwrap();
+            let first = EncryptedEnvelope::from_bytes(&fs::read(encrypted).unwrap()).unwrap();
+            assert_eq!(ENVELOPE_MAGIC, first.magic);
+            assert_eq!(algorithm, first.algorithm);
+            assert_eq!(
+                (12, 16, 4),
+                (first.nonce.len(), first.tag.len(), first.payload.len())
+            );
+            assert_eq!(None, first.signature);
+
+            // Every encryption has its own nonce.
+            encrypt_file_content_with(path, encrypted, &key, algorithm).unwrap();
+            let second = EncryptedEnvelope::from_bytes(&fs::read(encrypted).unwrap()).unwrap();
+            assert_ne!(first.nonce, second.nonce);
+
+            deciphering_file_content(Path::new(encrypted), decrypted, &key).unwrap();
+            assert_eq!(b"page".to_vec(), fs::read(decrypted).unwrap());
+        }
+
+        // Every algorithm has its own key.
+        let aes256 = AeadAlgorithm::Aes256Gcm.key(&key);
+        let aes128 = AeadAlgorithm::Aes128Gcm.key(&key);
+        assert_eq!(aes256, AeadAlgorithm::Aes256Gcm.key(&key));
+        assert_ne!(AeadAlgorithm::ChaCha20Poly1305.key(&key), aes256);
+        assert_eq!(16, aes128.len());
+        assert_ne!(&aes256[..16], &aes128[..]);
+
+        // The algorithm is authenticated.
+        let mut envelope =
+            EncryptedEnvelope::seal(AeadAlgorithm::Aes256Gcm, &key, b"page").unwrap();
+        for &other in &[AeadAlgorithm::ChaCha20Poly1305, AeadAlgorithm::Aes128Gcm] {
+            envelope.algorithm = other;
+            assert!(envelope.open(&key).is_err());
+        }
+
+        let mut bytes = envelope.to_bytes().unwrap();
+        bytes[ENVELOPE_MAGIC.len()] = ENVELOPE_VERSION + 1;
+        assert!(EncryptedEnvelope::from_bytes(&bytes).is_err());
+        bytes[0] = b'X';
+        assert!(EncryptedEnvelope::from_bytes(&bytes).is_err());
+        assert!(EncryptedEnvelope::from_bytes(&ENVELOPE_MAGIC).is_err());
+        assert!(EncryptedEnvelope::from_bytes(b"page").is_err());
+
+        for file in &[path, Path::new(encrypted), decrypted] {
+            fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_signed_envelope() {
+        let key = KeyMaterial::generate().unwrap();
+        let key_path = Path::new("test_envelope_key.pk8");
+        let signing_key = SigningKey::generate_to_file(key_path).unwrap();
+        let (ed25519, public_key) = (signing_key.algorithm(), signing_key.public_key_bytes());
+
+        let mut envelope =
+            EncryptedEnvelope::seal(AeadAlgorithm::default(), &key, b"page").unwrap();
+        assert!(envelope.verify(ed25519, public_key).is_err());
+        envelope.sign(&signing_key).unwrap();
+
+        let envelope = EncryptedEnvelope::from_bytes(&envelope.to_bytes().unwrap()).unwrap();
+        assert!(envelope.verify(ed25519, public_key).is_ok());
+        let rsa = SignatureAlgorithm::RsaPss2048;
+        assert!(envelope.verify(rsa, public_key).is_err());
+        assert_eq!(b"page".to_vec(), envelope.open(&key).unwrap());
+
+        let mut altered = envelope.clone();
+        altered.payload[0] ^= 1;
+        assert!(altered.verify(ed25519, public_key).is_err());
+        match altered.open(&key) {
+            Err(AppError::TamperDetected(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        fs::remove_file(key_path).unwrap();
+    }
+
+    #[test]
+    fn test_verify_file_integrity() {
+        let path = Path::new("test_integrity.txt");
+        fs::write(path, b"page").unwrap();
+        let (_, hash) = get_file_name_and_hash(path).unwrap();
+        assert!(verify_file_integrity(path, &hash).is_ok());
+
+        fs::write(path, b"altered page").unwrap();
+        let err = verify_file_integrity(path, &hash).unwrap_err();
+        assert_eq!(
+            "Tamper detected: the file test_integrity.txt",
+            err.to_string()
+        );
+        // A truncated hash doesn't match either.
+        match verify_file_integrity(path, &hash[..hash.len() - 1]) {
+            Err(AppError::TamperDetected(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+
+        fs::remove_file(path).unwrap();
+        // The missing file is not a tampered one.
+        match verify_file_integrity(path, &hash) {
+            Err(AppError::Io(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verify_detached() {
+        let path = Path::new("test_verify.txt");
+        fs::write(path, b"page").unwrap();
+        let (peer_public_key_bytes, sig_bytes) =
+            gen_fingerprint(b"page", SignatureAlgorithm::Ed25519).unwrap();
+        fs::write(signature_path(path), &sig_bytes).unwrap();
+
+        assert!(verify_detached(path, &peer_public_key_bytes).is_ok());
+        fs::write(path, b"altered page").unwrap();
+        assert!(verify_detached(path, &peer_public_key_bytes).is_err());
+
+        fs::remove_file(signature_path(path)).unwrap();
+        // No signature at all.
+        assert!(verify_detached(path, &peer_public_key_bytes).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_signing_key_file() {
+        let path = Path::new("test_sign_file.txt");
+        fs::write(path, b"page").unwrap();
+
+        for &algorithm in &[SignatureAlgorithm::Ed25519, SignatureAlgorithm::RsaPss2048] {
+            let key_path = Path::new("test_signing_key.pk8");
+            let generated = SigningKey::generate_to_file_with(key_path, algorithm).unwrap();
+            assert_private(key_path);
+            assert_eq!(signature_path(path), sign_file(&generated, path).unwrap());
+
+            // The key of another run verifies the signature of this one.
+            let loaded = SigningKey::load(key_path).unwrap();
+            assert_eq!(algorithm, loaded.algorithm());
+            assert_eq!(generated.public_key_bytes(), loaded.public_key_bytes());
+            assert!(verify_file(path, algorithm, loaded.public_key_bytes()).is_ok());
+
+            let other = SigningKey::generate_to_file_with(key_path, algorithm).unwrap();
+            assert!(verify_file(path, algorithm, other.public_key_bytes()).is_err());
+            fs::remove_file(key_path).unwrap();
+        }
+
+        let key_path = Path::new("test_signing_key.pk8");
+        let key = SigningKey::generate_to_file(key_path).unwrap();
+        assert_eq!("SigningKey(Ed25519)", format!("{:?}", key));
+        sign_file(&key, path).unwrap();
+        assert!(verify_detached(path, key.public_key_bytes()).is_ok());
+        fs::write(path, b"altered page").unwrap();
+        assert!(verify_file(path, key.algorithm(), key.public_key_bytes()).is_err());
+
+        fs::write(key_path, b"not a key").unwrap();
+        assert!(SigningKey::load(key_path).is_err());
+
+        for file in &[path, key_path, signature_path(path).as_path()] {
+            fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rsa_pss() {
+        let algorithm = SignatureAlgorithm::RsaPss2048;
+        let (peer_public_key_bytes, sig_bytes) = gen_fingerprint(b"hash", algorithm).unwrap();
+        assert_eq!(256, sig_bytes.len());
+        assert!(
+            check_key_is_correct(algorithm, b"hash", &peer_public_key_bytes, &sig_bytes).is_ok()
+        );
+        assert!(
+            check_key_is_correct(algorithm, b"other", &peer_public_key_bytes, &sig_bytes).is_err()
+        );
+        assert!(check_key_is_correct(
+            SignatureAlgorithm::Ed25519,
+            b"hash",
+            &peer_public_key_bytes,
+            &sig_bytes
+        )
+        .is_err());
+    }
+
+    /// On Unix only the owner can read the file.
+    #[cfg(unix)]
+    fn assert_private(path: &Path) {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+
+    #[cfg(not(unix))]
+    fn assert_private(_: &Path) {}
+
+    #[test]
+    fn test_pkcs8_store_load() {
+        let path = Path::new("test_rsa_key.pk8");
+        let algorithm = SignatureAlgorithm::RsaPss2048;
+        // The key written over a readable file is not readable either.
+        fs::write(path, b"old key").unwrap();
+        store_pkcs8(path, &generate_pkcs8(algorithm).unwrap()).unwrap();
+        assert_private(path);
+
+        let pkcs8_bytes = load_pkcs8(path, algorithm).unwrap();
+        let (peer_public_key_bytes, sig_bytes) =
+            sign_pkcs8(algorithm, &pkcs8_bytes, b"hash").unwrap();
+        assert!(
+            check_key_is_correct(algorithm, b"hash", &peer_public_key_bytes, &sig_bytes).is_ok()
+        );
+
+        // The key is neither of 4096 bits nor Ed25519.
+        assert!(load_pkcs8(path, SignatureAlgorithm::RsaPss4096).is_err());
+        assert!(load_pkcs8(path, SignatureAlgorithm::Ed25519).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
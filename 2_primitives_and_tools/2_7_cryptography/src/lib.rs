@@ -0,0 +1,14 @@
+//! Encryption and signing of the files, shared with the other steps:
+//! the downloader of the step 2.13 verifies the signatures of the downloads with it.
+extern crate bincode;
+extern crate blake2;
+extern crate common_errors;
+extern crate openssl;
+extern crate ring;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate untrusted;
+extern crate uuid;
+
+pub mod encrypt_file;
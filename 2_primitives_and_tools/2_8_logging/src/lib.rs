@@ -0,0 +1,11 @@
+//! Structured logging with `slog`: the level filters and the JSON loggers,
+//! shared with the other steps through `toolkit`,
+//! and the logging context of the thread attached to the records by `log_ctx`.
+extern crate chrono;
+#[macro_use]
+extern crate slog;
+extern crate slog_async;
+extern crate slog_json;
+
+pub mod log_ctx;
+pub mod structured_logging;
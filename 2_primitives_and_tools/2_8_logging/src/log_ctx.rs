@@ -0,0 +1,178 @@
+//! # Logging context of the thread (MDC).
+//!
+//! The values put by `insert()` are attached to every record logged
+//! by the thread until they are removed, so the functions deep in the call stack
+//! log the `order_id` of the request without getting a logger with it.
+//!
+//! The values are attached by `ContextDrain`: the drains of `structured_logging`
+//! are wrapped in it. It takes the values when the record is logged,
+//! because the `slog_async` drains serialize the logger values in their own thread.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! extern crate logging;
+//! #[macro_use]
+//! extern crate slog_scope;
+//!
+//! use logging::log_ctx;
+//! use logging::structured_logging::*;
+//!
+//! fn main() {
+//!     let _guard = slog_scope::set_global_logger(json_root(stdio_json_drain()));
+//!
+//!     log_ctx::insert("order_id", 42);
+//!     // {"msg":"paid","lvl":"INFO",...,"order_id":"42"}
+//!     info!("paid");
+//!     log_ctx::clear();
+//! }
+//! ```
+
+use slog::{Drain, Key, OwnedKV, OwnedKVList, Record, Serializer, KV};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+thread_local! {
+    static CONTEXT: RefCell<BTreeMap<Key, String>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Puts the value of `key` into the context of the thread, replacing the previous one.
+pub fn insert<V: Display>(key: Key, value: V) {
+    CONTEXT.with(|context| context.borrow_mut().insert(key, value.to_string()));
+}
+
+/// The value of `key` in the context of the thread.
+pub fn get(key: Key) -> Option<String> {
+    CONTEXT.with(|context| context.borrow().get(key).cloned())
+}
+
+/// Removes `key` from the context of the thread, returns its value.
+pub fn remove(key: Key) -> Option<String> {
+    CONTEXT.with(|context| context.borrow_mut().remove(key))
+}
+
+/// Removes all the values from the context of the thread.
+pub fn clear() {
+    CONTEXT.with(|context| context.borrow_mut().clear());
+}
+
+/// The values of the context of the thread at the moment.
+pub fn snapshot() -> Snapshot {
+    CONTEXT.with(|context| {
+        Snapshot(
+            context
+                .borrow()
+                .iter()
+                .map(|(key, value)| (*key, value.clone()))
+                .collect(),
+        )
+    })
+}
+
+/// Copy of the context of a thread, serialized as the key-value pairs of a record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot(pub Vec<(Key, String)>);
+
+impl KV for Snapshot {
+    fn serialize(&self, _record: &Record, serializer: &mut dyn Serializer) -> slog::Result {
+        for &(key, ref value) in &self.0 {
+            serializer.emit_str(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Drain attaching the context of the logging thread to the logger values of the records.
+pub struct ContextDrain<D: Drain>(pub D);
+
+/// Implement struct ContextDrain.
+impl<D: Drain> ContextDrain<D> {
+    /// Create ContextDrain.
+    pub fn new(drain: D) -> Self {
+        ContextDrain(drain)
+    }
+}
+
+/// Implement Drain trait for struct ContextDrain.
+impl<D: Drain> Drain for ContextDrain<D> {
+    type Ok = D::Ok;
+    type Err = D::Err;
+
+    fn log(&self, record: &Record, logger_values: &OwnedKVList) -> Result<D::Ok, D::Err> {
+        let context = snapshot();
+        if context.0.is_empty() {
+            return self.0.log(record, logger_values);
+        }
+        let values = OwnedKVList::from(OwnedKV((logger_values.clone(), context)));
+        self.0.log(record, &values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use slog::{Logger, Never};
+    use std::fmt::Arguments;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// Collects the keys and values of the logger values.
+    struct Collect(Vec<(Key, String)>);
+
+    impl Serializer for Collect {
+        fn emit_arguments(&mut self, key: Key, value: &Arguments) -> slog::Result {
+            self.0.push((key, value.to_string()));
+            Ok(())
+        }
+    }
+
+    /// Drain remembering the logger values of the last record.
+    #[derive(Clone)]
+    struct Last(Arc<Mutex<Vec<(Key, String)>>>);
+
+    impl Drain for Last {
+        type Ok = ();
+        type Err = Never;
+
+        fn log(&self, record: &Record, logger_values: &OwnedKVList) -> Result<(), Never> {
+            let mut collect = Collect(Vec::new());
+            logger_values.serialize(record, &mut collect).unwrap();
+            collect.0.sort();
+            *self.0.lock().unwrap() = collect.0;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_context() {
+        let last = Last(Arc::new(Mutex::new(Vec::new())));
+        let root = Logger::root(ContextDrain::new(last.clone()), o!("key" => "value"));
+
+        insert("order_id", 42);
+        insert("user", "Adam");
+        assert_eq!(Some("42".to_string()), get("order_id"));
+        info!(root, "paid");
+        assert_eq!(
+            vec![
+                ("key", "value".to_string()),
+                ("order_id", "42".to_string()),
+                ("user", "Adam".to_string()),
+            ],
+            *last.0.lock().unwrap()
+        );
+
+        // The context belongs to the thread.
+        let other = root.clone();
+        thread::spawn(move || info!(other, "other")).join().unwrap();
+        assert_eq!(vec![("key", "value".to_string())], *last.0.lock().unwrap());
+
+        assert_eq!(Some("Adam".to_string()), remove("user"));
+        clear();
+        assert!(snapshot().0.is_empty());
+        info!(root, "done");
+        assert_eq!(vec![("key", "value".to_string())], *last.0.lock().unwrap());
+    }
+}
@@ -0,0 +1,196 @@
+//! # Simple custom structured logging.
+//!
+//! ## Examples
+//!
+//! Basic usage:
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate slog;
+//! #[macro_use]
+//! extern crate slog_scope;
+//! extern crate logging;
+//!
+//! use logging::structured_logging::{json_root, stdio_json_drain};
+//!
+//! fn main() {
+//!     //  Global logging
+//!     let _guard = slog_scope::set_global_logger(json_root(stdio_json_drain()));
+//!
+//!     info!("Info message using the global logger");
+//!     debug!("debug");
+//!     error!("Error occurred");
+//!
+//!     slog_debug!(slog_scope::logger(), "slog_debug");
+//!     slog_error!(slog_scope::logger(), "Error occurred");
+//! }
+//! ```
+
+use chrono;
+use log_ctx::ContextDrain;
+use slog::{Drain, Duplicate, FnValue, Level, Logger, Never, OwnedKVList, PushFnValue, Record};
+use slog_async;
+use slog_json;
+use std::fs::OpenOptions;
+use std::io;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+/// Type of error level comparison.
+pub enum CmpLevel {
+    Less = 0,
+    Greater,
+}
+
+/// The implementation of struct slog :: LevelFilter.
+/// The logic of filtering the error levels in a partition priorities.
+///
+/// ## Examples
+///
+/// Basic usage:
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate slog;
+/// extern crate logging;
+/// extern crate slog_async;
+/// extern crate slog_json;
+///
+/// use logging::structured_logging::*;
+/// use slog::{Drain, Duplicate, Level, Logger};
+///
+/// fn main() {
+///     let d_stdout = slog_json::Json::default(std::io::stdout()).fuse();
+///     let d_stdout = slog_async::Async::new(d_stdout).build().fuse();
+///
+///     let d_stderr = slog_json::Json::default(std::io::stderr()).fuse();
+///     let d_stderr = slog_async::Async::new(d_stderr).build().fuse();
+///
+///     let drain_base = Duplicate::new(
+///         CustomLevelFilter::new(d_stderr, Level::Warning, CmpLevel::Less),
+///         CustomLevelFilter::new(d_stdout, Level::Info, CmpLevel::Greater),
+///     ).fuse();
+///
+///     let root = Logger::root(drain_base, o!());
+///
+///     info!(root, "{method} {path}", method = "POST", path = "/some"; );
+/// }
+/// ```
+pub struct CustomLevelFilter<D: Drain>(pub D, pub Level, pub CmpLevel);
+
+/// Implement struct CustomLevelFilter.
+impl<D: Drain> CustomLevelFilter<D> {
+    /// Create CustomLevelFilter.
+    pub fn new(drain: D, level: Level, cmp: CmpLevel) -> Self {
+        CustomLevelFilter(drain, level, cmp)
+    }
+}
+/// Implement Drain trait for struct CustomLevelFilter.
+/// Custom logic compare error slog::Level.
+impl<D: Drain> Drain for CustomLevelFilter<D> {
+    type Ok = ();
+    type Err = Never;
+    fn log(
+        &self,
+        record: &Record,
+        logger_values: &OwnedKVList,
+    ) -> std::result::Result<Self::Ok, Self::Err> {
+        match self.2 {
+            CmpLevel::Less => {
+                if record.level() <= self.1 {
+                    let _ = self.0.log(record, logger_values);
+                }
+            }
+            CmpLevel::Greater => {
+                if record.level() >= self.1 {
+                    let _ = self.0.log(record, logger_values);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Root logger of the JSON records with the `msg`, `time`, `file` and `lvl` keys.
+pub fn json_root<D>(drain: D) -> Logger
+where
+    D: Drain<Ok = (), Err = Never> + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+{
+    Logger::root(
+        drain,
+        o!(
+                "msg" => PushFnValue(move |record : &Record, ser| {
+                    ser.emit(record.msg())
+                }),
+                "time" => PushFnValue(move |_ : &Record, ser| {
+                    ser.emit(chrono::Utc::now().to_rfc3339())
+                }),
+                "file"=>"app.log",
+                "lvl" => FnValue(move |rinfo : &Record| {
+                    rinfo.level().as_str()
+                }),
+        ),
+    )
+}
+
+/// Drain of the JSON records: warnings and errors go to `stderr`, the others to `stdout`.
+/// The records get the values of `log_ctx`.
+pub fn stdio_json_drain(
+) -> impl Drain<Ok = (), Err = Never> + Send + Sync + UnwindSafe + RefUnwindSafe {
+    let drain = slog_json::Json::new(io::stdout())
+        .set_pretty(false)
+        .build()
+        .fuse();
+    let d_stdout = slog_async::Async::new(drain).build().fuse();
+
+    let drain = slog_json::Json::new(io::stderr())
+        .set_pretty(false)
+        .build()
+        .fuse();
+    let d_stderr = slog_async::Async::new(drain).build().fuse();
+
+    ContextDrain::new(
+        Duplicate::new(
+            CustomLevelFilter::new(d_stderr, Level::Warning, CmpLevel::Less),
+            CustomLevelFilter::new(d_stdout, Level::Info, CmpLevel::Greater),
+        ).fuse(),
+    )
+}
+
+/// Drain of the JSON records, one per line, into the truncated file `path`.
+/// The records get the values of `log_ctx`.
+pub fn file_json_drain(
+    path: &str,
+) -> io::Result<impl Drain<Ok = (), Err = Never> + Send + Sync + UnwindSafe + RefUnwindSafe> {
+    let file: std::fs::File = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+
+    let drain = slog_json::Json::new(file)
+        .set_pretty(false)
+        .set_newlines(true)
+        .build()
+        .fuse();
+
+    Ok(ContextDrain::new(
+        slog_async::Async::new(drain).build().fuse(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn test() {
+
+        let drain_base = Duplicate::new(
+            CustomLevelFilter::new(slog::Discard, Level::Warning, CmpLevel::Less),
+            CustomLevelFilter::new(slog::Discard, Level::Info, CmpLevel::Greater),
+        ).fuse();
+
+        let root: slog::Logger = Logger::root(drain_base, o!("key" => "value"));
+        let root_new: slog::Logger = root.new(o!("key_new" => "value_new"));
+        assert!(true);
+    }
+}
@@ -0,0 +1,39 @@
+//! # Authentication of the users with the `auth` section of the configuration.
+//!
+//! - `passwords`: the password hashes salted with `auth.user_password_salt`;
+//! - `tokens`: the signed session tokens renewable during `auth.renewal_duration`.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! extern crate auth;
+//! extern crate configuration;
+//!
+//! use auth::{Passwords, Tokens};
+//! use configuration::configuration::Auth;
+//!
+//! fn main() {
+//!     let config = Auth::default();
+//!     let passwords = Passwords::from_config(&config);
+//!     let hash = passwords.hash_password("qwerty").unwrap();
+//!     assert!(passwords.verify("qwerty", &hash).unwrap());
+//!     assert!(!passwords.verify("123456", &hash).unwrap());
+//!
+//!     let tokens = Tokens::from_config(&config, b"secret key").unwrap();
+//!     let token = tokens.issue("user:42");
+//!     assert_eq!("user:42", tokens.validate(&token).unwrap().subject);
+//!     // Too early: the token expires in an hour.
+//!     assert!(tokens.renew(&token).is_err());
+//! }
+//! ```
+extern crate base64;
+extern crate common_errors;
+extern crate configuration;
+extern crate humantime;
+extern crate ring;
+
+pub mod passwords;
+pub mod tokens;
+
+pub use passwords::Passwords;
+pub use tokens::{Claims, Clock, SystemClock, Tokens};
@@ -0,0 +1,155 @@
+//! Password hashes of the users.
+//!
+//! A hash is PBKDF2-HMAC-SHA256 of the password with `auth.user_password_salt`
+//! followed by a random salt of the password, stored as
+//! `pbkdf2-sha256$<iterations>$<salt>$<hash>` in base64 so the number of
+//! iterations can grow without invalidating the stored hashes.
+
+use base64;
+use common_errors::{AppError, Result};
+use configuration::configuration::Auth;
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fmt;
+use std::num::NonZeroU32;
+
+/// Iterations of the new hashes.
+pub const ITERATIONS: u32 = 100_000;
+
+const SCHEME: &str = "pbkdf2-sha256";
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+/// Hashes and verifies the passwords with the salt of the configuration.
+pub struct Passwords {
+    /// `auth.user_password_salt`, the prefix of the salt of every password.
+    salt: Vec<u8>,
+    iterations: NonZeroU32,
+    rng: SystemRandom,
+}
+
+impl Passwords {
+    pub fn new(salt: &str) -> Self {
+        Passwords {
+            salt: salt.as_bytes().to_vec(),
+            iterations: NonZeroU32::new(ITERATIONS).expect("ITERATIONS is positive"),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    pub fn from_config(config: &Auth) -> Self {
+        Passwords::new(&config.user_password_salt)
+    }
+
+    /// Iterations of the new hashes instead of `ITERATIONS`, at least one.
+    pub fn with_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = NonZeroU32::new(iterations.max(1)).expect("iterations are positive");
+        self
+    }
+
+    /// Hash of `plain` with a new random salt.
+    pub fn hash_password(&self, plain: &str) -> Result<String> {
+        let mut salt = [0u8; SALT_LEN];
+        self.rng
+            .fill(&mut salt)
+            .map_err(|_| AppError::crypto("failed to generate a salt"))?;
+        let mut hash = [0u8; HASH_LEN];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            self.iterations,
+            &self.salted(&salt),
+            plain.as_bytes(),
+            &mut hash,
+        );
+        Ok(format!(
+            "{}${}${}${}",
+            SCHEME,
+            self.iterations,
+            base64::encode_config(&salt, base64::STANDARD_NO_PAD),
+            base64::encode_config(&hash, base64::STANDARD_NO_PAD)
+        ))
+    }
+
+    /// `plain` is the password of `hash`, the comparison takes a constant time.
+    ///
+    /// A `hash` not produced by `hash_password` is an error.
+    pub fn verify(&self, plain: &str, hash: &str) -> Result<bool> {
+        let invalid = || AppError::parse("invalid password hash");
+        let parts: Vec<&str> = hash.split('$').collect();
+        if parts.len() != 4 || parts[0] != SCHEME {
+            return Err(invalid());
+        }
+        let iterations = parts[1]
+            .parse()
+            .ok()
+            .and_then(NonZeroU32::new)
+            .ok_or_else(invalid)?;
+        let salt =
+            base64::decode_config(parts[2], base64::STANDARD_NO_PAD).map_err(|_| invalid())?;
+        let hash =
+            base64::decode_config(parts[3], base64::STANDARD_NO_PAD).map_err(|_| invalid())?;
+        Ok(pbkdf2::verify(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &self.salted(&salt),
+            plain.as_bytes(),
+            &hash,
+        )
+        .is_ok())
+    }
+
+    fn salted(&self, salt: &[u8]) -> Vec<u8> {
+        let mut salted = self.salt.clone();
+        salted.extend_from_slice(salt);
+        salted
+    }
+}
+
+/// The salt is a secret.
+impl fmt::Debug for Passwords {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Passwords")
+            .field("iterations", &self.iterations)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash_password() {
+        let passwords = Passwords::new("pepper").with_iterations(1000);
+        let hash = passwords.hash_password("qwerty").unwrap();
+        assert!(hash.starts_with("pbkdf2-sha256$1000$"));
+        assert!(passwords.verify("qwerty", &hash).unwrap());
+        assert!(!passwords.verify("qwerty1", &hash).unwrap());
+
+        // Every hash has its own salt.
+        assert_ne!(hash, passwords.hash_password("qwerty").unwrap());
+
+        // The hashes are bound to the salt of the configuration.
+        let other = Passwords::from_config(&Auth::default());
+        assert!(!other.verify("qwerty", &hash).unwrap());
+
+        // The iterations of the hash are used.
+        let upgraded = Passwords::new("pepper").with_iterations(2000);
+        assert!(upgraded.verify("qwerty", &hash).unwrap());
+    }
+
+    #[test]
+    fn test_verify_invalid_hash() {
+        let passwords = Passwords::new("pepper");
+        for hash in &[
+            "",
+            "qwerty",
+            "md5$1000$c2FsdA$aGFzaA",
+            "pbkdf2-sha256$0$c2FsdA$aGFzaA",
+            "pbkdf2-sha256$1000$c2FsdA",
+            "pbkdf2-sha256$1000$!!!$aGFzaA",
+        ] {
+            assert!(passwords.verify("qwerty", hash).is_err(), "{}", hash);
+        }
+    }
+}
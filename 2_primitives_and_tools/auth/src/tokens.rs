@@ -0,0 +1,258 @@
+//! Session tokens of the users.
+//!
+//! A token is `<payload>.<signature>` in URL safe base64: the payload holds
+//! the expiration time and the subject, the signature is HMAC-SHA256 of it.
+//! A token can be renewed during the last `auth.renewal_duration` of its life,
+//! the clocks of the instances may differ by up to the skew.
+
+use base64;
+use common_errors::{AppError, Result};
+use configuration::configuration::Auth;
+use humantime;
+use ring::hmac;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Life of the new tokens by default.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Tolerated difference of the clocks by default.
+pub const DEFAULT_SKEW: Duration = Duration::from_secs(30);
+
+/// Source of the current time, replaced by a fake one in the tests.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The clock of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Content of a valid token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claims {
+    /// The user of the session.
+    pub subject: String,
+    pub expires: SystemTime,
+}
+
+/// Issues and checks the tokens signed by a secret key.
+pub struct Tokens {
+    key: hmac::Key,
+    ttl: Duration,
+    renewal: Duration,
+    skew: Duration,
+    clock: Arc<dyn Clock>,
+}
+
+impl Tokens {
+    /// Tokens signed by `secret`, renewable during the last `renewal` of their life.
+    pub fn new(secret: &[u8], renewal: Duration) -> Self {
+        Tokens {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+            ttl: DEFAULT_TTL,
+            renewal,
+            skew: DEFAULT_SKEW,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Tokens renewable during `auth.renewal_duration`, like `"5m"`.
+    pub fn from_config(config: &Auth, secret: &[u8]) -> Result<Self> {
+        let renewal = humantime::parse_duration(&config.renewal_duration)
+            .map_err(|err| AppError::config(format!("auth.renewal_duration: {}", err)))?;
+        Ok(Tokens::new(secret, renewal))
+    }
+
+    /// Life of the new tokens instead of `DEFAULT_TTL`.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Tolerated difference of the clocks instead of `DEFAULT_SKEW`.
+    pub fn with_skew(mut self, skew: Duration) -> Self {
+        self.skew = skew;
+        self
+    }
+
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// New token of `subject` expiring after the ttl.
+    pub fn issue(&self, subject: &str) -> String {
+        let expires = seconds(self.clock.now()) + self.ttl.as_secs();
+        let payload = format!("{}:{}", expires, subject);
+        let tag = hmac::sign(&self.key, payload.as_bytes());
+        format!("{}.{}", encode(payload.as_bytes()), encode(tag.as_ref()))
+    }
+
+    /// Claims of `token` if it is signed by the key and not expired.
+    pub fn validate(&self, token: &str) -> Result<Claims> {
+        let claims = self.decode(token)?;
+        if self.clock.now() > claims.expires + self.skew {
+            return Err(AppError::crypto("the token is expired"));
+        }
+        Ok(claims)
+    }
+
+    /// New token of the subject of `token`, if `token` is valid
+    /// and expires within the renewal duration.
+    pub fn renew(&self, token: &str) -> Result<String> {
+        let claims = self.validate(token)?;
+        if self.clock.now() + self.renewal + self.skew < claims.expires {
+            return Err(AppError::crypto("the token can't be renewed yet"));
+        }
+        Ok(self.issue(&claims.subject))
+    }
+
+    fn decode(&self, token: &str) -> Result<Claims> {
+        let invalid = || AppError::crypto("invalid token");
+        let mut parts = token.splitn(2, '.');
+        let payload = parts.next().and_then(decode).ok_or_else(invalid)?;
+        let tag = parts.next().and_then(decode).ok_or_else(invalid)?;
+        hmac::verify(&self.key, &payload, &tag).map_err(|_| invalid())?;
+
+        let payload = String::from_utf8(payload).map_err(|_| invalid())?;
+        let mut fields = payload.splitn(2, ':');
+        let expires = fields
+            .next()
+            .and_then(|expires| expires.parse().ok())
+            .ok_or_else(invalid)?;
+        let subject = fields.next().ok_or_else(invalid)?;
+        Ok(Claims {
+            subject: subject.to_string(),
+            expires: UNIX_EPOCH + Duration::from_secs(expires),
+        })
+    }
+}
+
+/// The key is a secret.
+impl fmt::Debug for Tokens {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Tokens")
+            .field("ttl", &self.ttl)
+            .field("renewal", &self.renewal)
+            .field("skew", &self.skew)
+            .finish()
+    }
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode(part: &str) -> Option<Vec<u8>> {
+    base64::decode_config(part, base64::URL_SAFE_NO_PAD).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Clock moved by the test, shared by its clones.
+    #[derive(Clone)]
+    struct FakeClock(Arc<Mutex<SystemTime>>);
+
+    impl FakeClock {
+        fn new() -> Self {
+            FakeClock(Arc::new(Mutex::new(
+                UNIX_EPOCH + Duration::from_secs(1_500_000_000),
+            )))
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> SystemTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn tokens(clock: &FakeClock) -> Tokens {
+        Tokens::from_config(&Auth::default(), b"secret")
+            .unwrap()
+            .with_ttl(Duration::from_secs(3600))
+            .with_skew(Duration::from_secs(30))
+            .with_clock(clock.clone())
+    }
+
+    #[test]
+    fn test_validate() {
+        let clock = FakeClock::new();
+        let tokens = tokens(&clock);
+        let token = tokens.issue("user:42");
+        let claims = tokens.validate(&token).unwrap();
+        assert_eq!("user:42", claims.subject);
+        assert_eq!(clock.now() + Duration::from_secs(3600), claims.expires);
+
+        // Expired, but within the skew of the clocks.
+        clock.advance(Duration::from_secs(3620));
+        assert!(tokens.validate(&token).is_ok());
+        clock.advance(Duration::from_secs(20));
+        assert!(tokens.validate(&token).is_err());
+
+        // Signed by another key or altered.
+        let other = Tokens::new(b"other", Duration::from_secs(300)).with_clock(clock.clone());
+        assert!(tokens.validate(&other.issue("user:42")).is_err());
+        let forged = format!(
+            "{}.{}",
+            encode(b"9999999999:admin"),
+            token.split('.').nth(1).unwrap()
+        );
+        assert!(tokens.validate(&forged).is_err());
+        assert!(tokens.validate("").is_err());
+        assert!(tokens.validate("not.base64!").is_err());
+    }
+
+    #[test]
+    fn test_renew() {
+        let clock = FakeClock::new();
+        let tokens = tokens(&clock);
+        let token = tokens.issue("user:42");
+
+        // The default renewal duration is 5m.
+        clock.advance(Duration::from_secs(3600 - 300 - 60));
+        assert!(tokens.renew(&token).is_err());
+        // The skew of the clocks is tolerated.
+        clock.advance(Duration::from_secs(40));
+        let renewed = tokens.renew(&token).unwrap();
+
+        let claims = tokens.validate(&renewed).unwrap();
+        assert_eq!("user:42", claims.subject);
+        assert_eq!(clock.now() + Duration::from_secs(3600), claims.expires);
+
+        clock.advance(Duration::from_secs(600));
+        assert!(tokens.renew(&token).is_err());
+        assert!(tokens.validate(&renewed).is_ok());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = Auth {
+            renewal_duration: "5 parsecs".into(),
+            ..Default::default()
+        };
+        let err = Tokens::from_config(&config, b"secret").unwrap_err();
+        assert!(err.to_string().contains("auth.renewal_duration"));
+    }
+}
@@ -0,0 +1,342 @@
+//! # Periodic background jobs.
+//!
+//! Every job of the `background` section of the configuration
+//! (`finalizer`, `recounter`, `watchdog`) runs in its own thread:
+//! each `period` (with a jitter) it processes at most `limit` entries,
+//! under a lock held for at most `lock_timeout` if the job has one.
+//! The runs are logged with the name of the job.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! #[macro_use]
+//! extern crate slog;
+//! extern crate background;
+//! extern crate common_errors;
+//!
+//! use background::{BackgroundJobs, Job, JobSettings, LocalLocker};
+//! use std::sync::Arc;
+//!
+//! struct Finalizer;
+//!
+//! impl Job for Finalizer {
+//!     fn name(&self) -> &str {
+//!         "finalizer"
+//!     }
+//!
+//!     fn run(&mut self, limit: usize) -> common_errors::Result<usize> {
+//!         // Finalize at most `limit` entries.
+//!         Ok(0)
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let log = slog::Logger::root(slog::Discard, o!());
+//!     let mut jobs = BackgroundJobs::new(log, Arc::new(LocalLocker::new()));
+//!     let settings = JobSettings::parse("finalizer", "10s", "50", None).unwrap();
+//!     jobs.spawn(Finalizer, settings).unwrap();
+//!     // The jobs are stopped by the drop.
+//! }
+//! ```
+extern crate common_errors;
+extern crate configuration;
+extern crate humantime;
+extern crate rand;
+#[macro_use]
+extern crate slog;
+
+mod lock;
+mod settings;
+
+pub use lock::{LocalLocker, Locker};
+pub use settings::{JobSettings, JITTER};
+
+use configuration::configuration::Background;
+use slog::Logger;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A periodic job.
+pub trait Job: Send + 'static {
+    /// Name of the job in the logs and of its lock.
+    fn name(&self) -> &str;
+
+    /// One run processing at most `limit` entries, returns the number of the processed ones.
+    fn run(&mut self, limit: usize) -> common_errors::Result<usize>;
+}
+
+/// The running jobs, stopped and joined by the drop.
+pub struct BackgroundJobs {
+    log: Logger,
+    locker: Arc<dyn Locker>,
+    stop: Arc<Stop>,
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+impl BackgroundJobs {
+    pub fn new(log: Logger, locker: Arc<dyn Locker>) -> Self {
+        BackgroundJobs {
+            log,
+            locker,
+            stop: Arc::new(Stop::default()),
+            threads: Vec::new(),
+        }
+    }
+
+    /// Run the `finalizer`, `recounter` and `watchdog` jobs with the settings of `config`.
+    pub fn start<F, R, W>(
+        config: &Background,
+        log: Logger,
+        locker: Arc<dyn Locker>,
+        finalizer: F,
+        recounter: R,
+        watchdog: W,
+    ) -> common_errors::Result<BackgroundJobs>
+    where
+        F: Job,
+        R: Job,
+        W: Job,
+    {
+        let finalizer_settings = JobSettings::finalizer(&config.finalizer)?;
+        let recounter_settings = JobSettings::recounter(&config.recounter)?;
+        let watchdog_settings = JobSettings::watchdog(&config.watchdog)?;
+
+        let mut jobs = BackgroundJobs::new(log, locker);
+        jobs.spawn(finalizer, finalizer_settings)?;
+        jobs.spawn(recounter, recounter_settings)?;
+        jobs.spawn(watchdog, watchdog_settings)?;
+        Ok(jobs)
+    }
+
+    /// Run `job` in a new thread, the first run is after a period.
+    pub fn spawn<J: Job>(&mut self, job: J, settings: JobSettings) -> io::Result<()> {
+        let log = self.log.new(o!("job" => job.name().to_string()));
+        let locker = Arc::clone(&self.locker);
+        let stop = Arc::clone(&self.stop);
+        let thread = thread::Builder::new()
+            .name(format!("background-{}", job.name()))
+            .spawn(move || {
+                let mut job = job;
+                info!(log, "started"; "period" => ?settings.period, "limit" => settings.limit);
+                while !stop.wait(settings.next_delay()) {
+                    run_once(&mut job, &settings, &*locker, &log);
+                }
+                info!(log, "stopped");
+            })?;
+        self.threads.push(thread);
+        Ok(())
+    }
+}
+
+/// The current runs are finished before the threads exit.
+impl Drop for BackgroundJobs {
+    fn drop(&mut self) {
+        self.stop.stop();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// One run of the job under its lock, if it has one.
+fn run_once<J: Job>(job: &mut J, settings: &JobSettings, locker: &dyn Locker, log: &Logger) {
+    let token = match settings.lock_timeout {
+        Some(ttl) => match locker.try_lock(job.name(), ttl) {
+            Ok(Some(token)) => Some(token),
+            Ok(None) => {
+                debug!(log, "skipped, the lock is held by another instance");
+                return;
+            }
+            Err(err) => {
+                warn!(log, "skipped, failed to take the lock"; "error" => %err);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let started = Instant::now();
+    let limit = settings.limit;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| job.run(limit)));
+    let elapsed = started.elapsed();
+    match result {
+        Ok(Ok(processed)) => info!(log, "run"; "processed" => processed, "elapsed" => ?elapsed),
+        Ok(Err(err)) => error!(log, "run failed"; "error" => %err, "elapsed" => ?elapsed),
+        Err(_) => crit!(log, "run panicked"; "elapsed" => ?elapsed),
+    }
+
+    if let (Some(ttl), Some(token)) = (settings.lock_timeout, token) {
+        if elapsed > ttl {
+            warn!(log, "the run outlived its lock"; "lock_timeout" => ?ttl);
+        }
+        // The lock taken by another instance after it expired is kept.
+        if let Err(err) = locker.unlock(job.name(), &token) {
+            warn!(log, "failed to release the lock"; "error" => %err);
+        }
+    }
+}
+
+/// Stop signal waking the sleeping jobs.
+#[derive(Default)]
+struct Stop {
+    stopped: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Stop {
+    fn stop(&self) {
+        *self.stopped.lock().unwrap() = true;
+        self.cond.notify_all();
+    }
+
+    /// Sleep for `timeout`, true if stopped meanwhile.
+    fn wait(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut stopped = self.stopped.lock().unwrap();
+        while !*stopped {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            stopped = self.cond.wait_timeout(stopped, deadline - now).unwrap().0;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use common_errors::AppError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts its runs, every second run fails.
+    struct Counting(&'static str, Arc<AtomicUsize>);
+
+    impl Job for Counting {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn run(&mut self, limit: usize) -> common_errors::Result<usize> {
+            if self.1.fetch_add(1, Ordering::SeqCst) % 2 == 1 {
+                return Err(AppError::db("connection lost"));
+            }
+            Ok(limit)
+        }
+    }
+
+    fn settings(lock_timeout: Option<Duration>) -> JobSettings {
+        JobSettings {
+            period: Duration::from_millis(5),
+            limit: 10,
+            lock_timeout,
+        }
+    }
+
+    #[test]
+    fn test_spawn() {
+        let log = Logger::root(slog::Discard, o!());
+        let locker = Arc::new(LocalLocker::new());
+        // The lock of "watchdog" is held by another instance.
+        locker
+            .try_lock("watchdog", Duration::from_secs(60))
+            .unwrap();
+
+        let finalizer = Arc::new(AtomicUsize::new(0));
+        let recounter = Arc::new(AtomicUsize::new(0));
+        let watchdog = Arc::new(AtomicUsize::new(0));
+        let mut jobs = BackgroundJobs::new(log, locker.clone());
+        jobs.spawn(
+            Counting("finalizer", Arc::clone(&finalizer)),
+            settings(None),
+        )
+        .unwrap();
+        jobs.spawn(
+            Counting("recounter", Arc::clone(&recounter)),
+            settings(Some(Duration::from_secs(1))),
+        )
+        .unwrap();
+        jobs.spawn(
+            Counting("watchdog", Arc::clone(&watchdog)),
+            settings(Some(Duration::from_secs(1))),
+        )
+        .unwrap();
+
+        thread::sleep(Duration::from_millis(100));
+        drop(jobs);
+
+        // A failed run doesn't stop the job.
+        assert!(finalizer.load(Ordering::SeqCst) > 2);
+        assert!(recounter.load(Ordering::SeqCst) > 2);
+        assert_eq!(0, watchdog.load(Ordering::SeqCst));
+        // The lock is released after the run.
+        assert!(locker
+            .try_lock("recounter", Duration::from_secs(1))
+            .unwrap()
+            .is_some());
+
+        // The jobs are stopped by the drop.
+        let runs = finalizer.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(runs, finalizer.load(Ordering::SeqCst));
+    }
+
+    /// Outlives its lock, meanwhile another instance takes the lock.
+    struct Outliving(Arc<LocalLocker>, Option<String>);
+
+    impl Job for Outliving {
+        fn name(&self) -> &str {
+            "watchdog"
+        }
+
+        fn run(&mut self, _limit: usize) -> common_errors::Result<usize> {
+            thread::sleep(Duration::from_millis(20));
+            self.1 = self.0.try_lock("watchdog", Duration::from_secs(60))?;
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_run_outlives_lock() {
+        let log = Logger::root(slog::Discard, o!());
+        let locker = Arc::new(LocalLocker::new());
+        let mut job = Outliving(Arc::clone(&locker), None);
+        run_once(
+            &mut job,
+            &settings(Some(Duration::from_millis(10))),
+            &*locker,
+            &log,
+        );
+
+        // The run doesn't release the lock of the other instance.
+        let other = job.1.expect("the expired lock is free");
+        assert_eq!(
+            None,
+            locker
+                .try_lock("watchdog", Duration::from_secs(60))
+                .unwrap()
+        );
+        locker.unlock("watchdog", &other).unwrap();
+        assert!(locker
+            .try_lock("watchdog", Duration::from_secs(60))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_stop_wakes_up() {
+        let stop = Arc::new(Stop::default());
+        let waiter = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || stop.wait(Duration::from_secs(60)))
+        };
+        thread::sleep(Duration::from_millis(10));
+        stop.stop();
+        assert!(waiter.join().unwrap());
+        assert!(!Stop::default().wait(Duration::from_millis(1)));
+    }
+}
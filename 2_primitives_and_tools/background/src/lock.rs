@@ -0,0 +1,113 @@
+//! Locks keeping a job from running on several instances at once.
+
+use common_errors::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Named locks expiring by themselves, so a crashed holder doesn't keep them forever.
+pub trait Locker: Send + Sync {
+    /// Take the lock `name` for at most `ttl`, returns the token of the holder
+    /// or `None` if it is held by somebody else.
+    fn try_lock(&self, name: &str, ttl: Duration) -> Result<Option<String>>;
+
+    /// Release the lock `name` if it is still held with `token` of `try_lock`:
+    /// after it expired, the lock taken by another holder is kept.
+    fn unlock(&self, name: &str, token: &str) -> Result<()>;
+}
+
+/// Locks of the current process, for a single instance and for the tests.
+#[derive(Debug, Default)]
+pub struct LocalLocker {
+    /// Token and expiration of the held locks.
+    locks: Mutex<HashMap<String, (String, Instant)>>,
+    /// Number of the taken locks, the token of the next one.
+    taken: AtomicUsize,
+}
+
+impl LocalLocker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Locker for LocalLocker {
+    fn try_lock(&self, name: &str, ttl: Duration) -> Result<Option<String>> {
+        let mut locks = self.locks.lock().unwrap();
+        let now = Instant::now();
+        match locks.get(name) {
+            Some(&(_, expires)) if expires > now => Ok(None),
+            _ => {
+                let token = self.taken.fetch_add(1, Ordering::SeqCst).to_string();
+                locks.insert(name.to_string(), (token.clone(), now + ttl));
+                Ok(Some(token))
+            }
+        }
+    }
+
+    fn unlock(&self, name: &str, token: &str) -> Result<()> {
+        let mut locks = self.locks.lock().unwrap();
+        let held = match locks.get(name) {
+            Some((held, _)) => held == token,
+            None => false,
+        };
+        if held {
+            locks.remove(name);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_local_locker() {
+        let locker = LocalLocker::new();
+        let token = locker
+            .try_lock("recounter", Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            None,
+            locker
+                .try_lock("recounter", Duration::from_secs(60))
+                .unwrap()
+        );
+        assert!(locker
+            .try_lock("watchdog", Duration::from_secs(60))
+            .unwrap()
+            .is_some());
+
+        locker.unlock("recounter", &token).unwrap();
+        let expired = locker
+            .try_lock("recounter", Duration::from_millis(10))
+            .unwrap()
+            .unwrap();
+
+        // An expired lock is free.
+        thread::sleep(Duration::from_millis(20));
+        let token = locker
+            .try_lock("recounter", Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        assert_ne!(expired, token);
+
+        // Only the holder releases the lock.
+        locker.unlock("recounter", &expired).unwrap();
+        assert_eq!(
+            None,
+            locker
+                .try_lock("recounter", Duration::from_secs(60))
+                .unwrap()
+        );
+        locker.unlock("recounter", &token).unwrap();
+        assert!(locker
+            .try_lock("recounter", Duration::from_secs(60))
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,123 @@
+//! Schedule of a job, parsed from the `background` section of the configuration.
+
+use common_errors::{AppError, Result};
+use configuration::configuration::{Finalizer, Recounter, Watchdog};
+use humantime;
+use rand::{self, Rng};
+use std::time::Duration;
+
+/// The delay between the runs is longer than the period by up to this share of it,
+/// so the instances started together don't run the job at the same moment.
+pub const JITTER: f64 = 0.1;
+
+/// Schedule of a job.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobSettings {
+    /// Delay between the runs, without the jitter.
+    pub period: Duration,
+    /// Maximum number of entries processed per run.
+    pub limit: usize,
+    /// The job runs under a lock held for at most this long, without a lock if `None`.
+    pub lock_timeout: Option<Duration>,
+}
+
+impl JobSettings {
+    /// Parse the settings of the job `name`: the durations are like `"5s"` or `"1m 30s"`.
+    pub fn parse(
+        name: &str,
+        period: &str,
+        limit: &str,
+        lock_timeout: Option<&str>,
+    ) -> Result<JobSettings> {
+        let duration = |field: &str, value: &str| {
+            humantime::parse_duration(value)
+                .map_err(|err| AppError::config(format!("background.{}.{}: {}", name, field, err)))
+        };
+        Ok(JobSettings {
+            period: duration("period", period)?,
+            limit: limit
+                .trim()
+                .parse()
+                .map_err(|err| AppError::config(format!("background.{}.limit: {}", name, err)))?,
+            lock_timeout: match lock_timeout {
+                Some(value) => Some(duration("lock_timeout", value)?),
+                None => None,
+            },
+        })
+    }
+
+    pub fn finalizer(config: &Finalizer) -> Result<JobSettings> {
+        JobSettings::parse("finalizer", &config.period, &config.limit, None)
+    }
+
+    pub fn recounter(config: &Recounter) -> Result<JobSettings> {
+        JobSettings::parse(
+            "recounter",
+            &config.period,
+            &config.limit,
+            Some(&config.lock_timeout),
+        )
+    }
+
+    pub fn watchdog(config: &Watchdog) -> Result<JobSettings> {
+        JobSettings::parse(
+            "watchdog",
+            &config.period,
+            &config.limit,
+            Some(&config.lock_timeout),
+        )
+    }
+
+    /// The period with a random jitter of up to `JITTER` of it.
+    pub fn next_delay(&self) -> Duration {
+        let period = millis(self.period);
+        let jitter = (period as f64 * JITTER) as u64;
+        if jitter == 0 {
+            return self.period;
+        }
+        Duration::from_millis(period + rand::thread_rng().gen_range(0, jitter + 1))
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let settings = JobSettings::recounter(&Recounter::default()).unwrap();
+        assert_eq!(
+            JobSettings {
+                period: Duration::from_secs(5),
+                limit: 50,
+                lock_timeout: Some(Duration::from_secs(4)),
+            },
+            settings
+        );
+        assert_eq!(
+            None,
+            JobSettings::finalizer(&Finalizer::default())
+                .unwrap()
+                .lock_timeout
+        );
+
+        let err = JobSettings::parse("watchdog", "5 parsecs", "10", None).unwrap_err();
+        assert!(err.to_string().contains("background.watchdog.period"));
+        let err = JobSettings::parse("watchdog", "5s", "ten", None).unwrap_err();
+        assert!(err.to_string().contains("background.watchdog.limit"));
+    }
+
+    #[test]
+    fn test_next_delay() {
+        let settings = JobSettings::parse("finalizer", "10s", "50", None).unwrap();
+        for _ in 0..100 {
+            let delay = settings.next_delay();
+            assert!(delay >= Duration::from_secs(10));
+            assert!(delay <= Duration::from_secs(11));
+        }
+    }
+}
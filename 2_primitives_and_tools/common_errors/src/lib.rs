@@ -0,0 +1,218 @@
+//! # Errors shared by the modules of the repository.
+//!
+//! Every module returns `AppError`, so the errors compose across the module
+//! boundaries with `?` instead of each module declaring its own enum and conversions.
+//!
+//! The conversions from the errors of the third party crates are behind the features
+//! named after the crates: `serde_json`, `serde_yaml`, `toml`, `bincode`, `ring`, `config`, `envy`.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use common_errors::{AppError, Result};
+//! use std::fs::File;
+//!
+//! fn open(path: &str) -> Result<File> {
+//!     Ok(File::open(path)?)
+//! }
+//!
+//! match open("not_found.txt") {
+//!     Err(AppError::Io(err)) => assert_eq!(std::io::ErrorKind::NotFound, err.kind()),
+//!     _ => unreachable!(),
+//! }
+//! ```
+#[cfg(feature = "bincode")]
+extern crate bincode;
+#[cfg(feature = "config")]
+extern crate config;
+#[cfg(feature = "envy")]
+extern crate envy;
+#[cfg(feature = "ring")]
+extern crate ring;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "serde_yaml")]
+extern crate serde_yaml;
+#[cfg(feature = "toml")]
+extern crate toml;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::result;
+
+/// The original error of `AppError`.
+pub type Cause = Box<dyn Error + Send + Sync + 'static>;
+
+/// Alias for result::Result with AppError.
+pub type Result<T> = result::Result<T, AppError>;
+
+/// The error of the application, by the kind of the failed operation.
+#[derive(Debug)]
+pub enum AppError {
+    Io(io::Error),
+    /// Configuration and environment variables.
+    Config(Cause),
+    /// Encryption, signatures and hashes.
+    Crypto(Cause),
+    Db(Cause),
+    Http(Cause),
+    /// Parsing and serialization of the data formats.
+    Parse(Cause),
+    /// The data doesn't match its hash or its authentication tag,
+    /// holds what was tampered with, e.g. `the file pic.jpg`.
+    TamperDetected(String),
+}
+
+impl AppError {
+    /// `Config` error from an error or a message.
+    pub fn config<E: Into<Cause>>(err: E) -> AppError {
+        AppError::Config(err.into())
+    }
+
+    /// `Crypto` error from an error or a message.
+    pub fn crypto<E: Into<Cause>>(err: E) -> AppError {
+        AppError::Crypto(err.into())
+    }
+
+    /// `Db` error from an error or a message.
+    pub fn db<E: Into<Cause>>(err: E) -> AppError {
+        AppError::Db(err.into())
+    }
+
+    /// `Http` error from an error or a message.
+    pub fn http<E: Into<Cause>>(err: E) -> AppError {
+        AppError::Http(err.into())
+    }
+
+    /// `Parse` error from an error or a message.
+    pub fn parse<E: Into<Cause>>(err: E) -> AppError {
+        AppError::Parse(err.into())
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AppError::Io(ref err) => write!(f, "IO error: {}", err),
+            AppError::Config(ref err) => write!(f, "Config error: {}", err),
+            AppError::Crypto(ref err) => write!(f, "Crypto error: {}", err),
+            AppError::Db(ref err) => write!(f, "DB error: {}", err),
+            AppError::Http(ref err) => write!(f, "HTTP error: {}", err),
+            AppError::Parse(ref err) => write!(f, "Parse error: {}", err),
+            AppError::TamperDetected(ref what) => write!(f, "Tamper detected: {}", what),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            AppError::Io(ref err) => Some(err),
+            AppError::Config(ref err)
+            | AppError::Crypto(ref err)
+            | AppError::Db(ref err)
+            | AppError::Http(ref err)
+            | AppError::Parse(ref err) => Some(&**err),
+            AppError::TamperDetected(_) => None,
+        }
+    }
+}
+
+/// Type conversion io::Error in AppError.
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> AppError {
+        AppError::Io(err)
+    }
+}
+
+/// Type conversion serde_json::Error in AppError.
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> AppError {
+        AppError::parse(err)
+    }
+}
+
+/// Type conversion serde_yaml::Error in AppError.
+#[cfg(feature = "serde_yaml")]
+impl From<serde_yaml::Error> for AppError {
+    fn from(err: serde_yaml::Error) -> AppError {
+        AppError::parse(err)
+    }
+}
+
+/// Type conversion toml::ser::Error in AppError.
+#[cfg(feature = "toml")]
+impl From<toml::ser::Error> for AppError {
+    fn from(err: toml::ser::Error) -> AppError {
+        AppError::parse(err)
+    }
+}
+
+/// Type conversion toml::de::Error in AppError.
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for AppError {
+    fn from(err: toml::de::Error) -> AppError {
+        AppError::parse(err)
+    }
+}
+
+/// Type conversion bincode::Error in AppError.
+#[cfg(feature = "bincode")]
+impl From<bincode::Error> for AppError {
+    fn from(err: bincode::Error) -> AppError {
+        AppError::parse(err)
+    }
+}
+
+/// Type conversion ring::error::Unspecified in AppError.
+#[cfg(feature = "ring")]
+impl From<ring::error::Unspecified> for AppError {
+    fn from(err: ring::error::Unspecified) -> AppError {
+        AppError::crypto(err)
+    }
+}
+
+/// Type conversion config::ConfigError in AppError.
+#[cfg(feature = "config")]
+impl From<config::ConfigError> for AppError {
+    fn from(err: config::ConfigError) -> AppError {
+        AppError::config(err)
+    }
+}
+
+/// Type conversion envy::Error in AppError.
+#[cfg(feature = "envy")]
+impl From<envy::Error> for AppError {
+    fn from(err: envy::Error) -> AppError {
+        AppError::config(err)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_app_error() {
+        let err: AppError = io::Error::new(io::ErrorKind::NotFound, "disk").into();
+        assert_eq!("IO error: disk", err.to_string());
+        assert_eq!("disk", err.source().unwrap().to_string());
+
+        let err = AppError::crypto("invalid signature");
+        assert_eq!("Crypto error: invalid signature", err.to_string());
+
+        let err = AppError::TamperDetected("the file pic.jpg".to_string());
+        assert_eq!("Tamper detected: the file pic.jpg", err.to_string());
+        assert!(err.source().is_none());
+
+        fn parse(s: &str) -> Result<i32> {
+            s.parse::<i32>().map_err(AppError::parse)
+        }
+        match parse("x") {
+            Err(AppError::Parse(_)) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
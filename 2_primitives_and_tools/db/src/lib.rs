@@ -0,0 +1,38 @@
+//! # Connections to the databases of the `db` section of the configuration.
+//!
+//! - `redis`: a client of `db.redis.addrs` failing over to the next address
+//!   when the current one is unreachable, also the `Locker` of the background jobs;
+//! - `mysql`: the connection pools of the `dating` and `social` databases of `db.mysql`.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! extern crate configuration;
+//! extern crate db;
+//!
+//! use configuration::configuration::{MySQL, Redis};
+//! use db::{MySqlPools, RedisClient};
+//! use std::time::Duration;
+//!
+//! fn main() {
+//!     let redis = RedisClient::from_config(&Redis::default()).unwrap();
+//!     redis.set("greeting", "hello", Some(Duration::from_secs(60))).unwrap();
+//!     assert_eq!(Some("hello".to_string()), redis.get("greeting").unwrap());
+//!
+//!     let mysql = MySqlPools::from_config(&MySQL::default()).unwrap();
+//!     let (dating, social) = mysql.versions().unwrap();
+//!     println!("dating: MySQL {}, social: MySQL {}", dating, social);
+//! }
+//! ```
+extern crate background;
+extern crate common_errors;
+extern crate configuration;
+extern crate mysql as mysql_client;
+extern crate rand;
+extern crate redis as redis_client;
+
+pub mod mysql;
+pub mod redis;
+
+pub use mysql::MySqlPools;
+pub use redis::RedisClient;
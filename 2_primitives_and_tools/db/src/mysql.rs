@@ -0,0 +1,131 @@
+//! Connection pools of the `dating` and `social` MySQL databases of `db.mysql`.
+//!
+//! Every database has its own pool limited by `connections`: at most `max_open`
+//! connections, of which `max_idle` are kept open while unused.
+
+use common_errors::{AppError, Result};
+use configuration::configuration::MySQL;
+use mysql_client::prelude::Queryable;
+use mysql_client::{self, Opts, OptsBuilder, Pool, PoolConstraints, PoolOpts, PooledConn};
+use std::time::Duration;
+
+/// Timeout of the connection to the server.
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pools of the databases, the clones share the connections.
+#[derive(Clone)]
+pub struct MySqlPools {
+    dating: Pool,
+    social: Pool,
+}
+
+impl MySqlPools {
+    /// Pools of `config`, the idle connections are opened right away.
+    pub fn from_config(config: &MySQL) -> Result<MySqlPools> {
+        let pool = |database: &str| Pool::new(opts(config, database)?).map_err(AppError::db);
+        Ok(MySqlPools {
+            dating: pool(&config.databases.dating)?,
+            social: pool(&config.databases.social)?,
+        })
+    }
+
+    /// Run `f` with a connection of the `dating` database, returned to the pool after it.
+    pub fn with_dating_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut PooledConn) -> mysql_client::Result<T>,
+    {
+        with_conn(&self.dating, f)
+    }
+
+    /// Run `f` with a connection of the `social` database, returned to the pool after it.
+    pub fn with_social_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut PooledConn) -> mysql_client::Result<T>,
+    {
+        with_conn(&self.social, f)
+    }
+
+    /// Version of the server of the `dating` and the `social` databases.
+    pub fn versions(&self) -> Result<(String, String)> {
+        let version = |conn: &mut PooledConn| {
+            conn.query_first("SELECT VERSION()")
+                .map(|version: Option<String>| version.unwrap_or_default())
+        };
+        Ok((
+            self.with_dating_conn(version)?,
+            self.with_social_conn(version)?,
+        ))
+    }
+}
+
+fn with_conn<T, F>(pool: &Pool, f: F) -> Result<T>
+where
+    F: FnOnce(&mut PooledConn) -> mysql_client::Result<T>,
+{
+    let mut conn = pool.get_conn().map_err(AppError::db)?;
+    f(&mut conn).map_err(AppError::db)
+}
+
+/// Options of the connections to `database`.
+///
+/// `connections.max_idle` greater than `connections.max_open` is reduced to it.
+pub fn opts(config: &MySQL, database: &str) -> Result<Opts> {
+    let max_open = usize::from(config.connections.max_open);
+    if max_open == 0 {
+        return Err(AppError::config(
+            "db.mysql.connections.max_open: must be positive",
+        ));
+    }
+    let max_idle = usize::from(config.connections.max_idle).min(max_open);
+    let constraints =
+        PoolConstraints::new(max_idle, max_open).expect("max_idle is at most max_open");
+    let pass = if config.pass.is_empty() {
+        None
+    } else {
+        Some(config.pass.to_string())
+    };
+    Ok(OptsBuilder::new()
+        .ip_or_hostname(Some(config.host.to_string()))
+        .tcp_port(config.port)
+        .user(Some(config.user.to_string()))
+        .pass(pass)
+        .db_name(Some(database))
+        .tcp_connect_timeout(Some(CONNECT_TIMEOUT))
+        .pool_opts(PoolOpts::new().with_constraints(constraints))
+        .into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_opts() {
+        let mut config = MySQL::default();
+        let social = opts(&config, &config.databases.social).unwrap();
+        assert_eq!("127.0.0.1", social.get_ip_or_hostname());
+        assert_eq!(3306, social.get_tcp_port());
+        assert_eq!(Some("root"), social.get_user());
+        assert_eq!(None, social.get_pass());
+        assert_eq!(Some("social"), social.get_db_name());
+        assert_eq!(
+            PoolConstraints::new(30, 30),
+            Some(social.get_pool_opts().constraints())
+        );
+
+        config.connections.max_idle = 50;
+        config.connections.max_open = 10;
+        config.pass = "secret".into();
+        let dating = opts(&config, &config.databases.dating).unwrap();
+        assert_eq!(Some("secret"), dating.get_pass());
+        assert_eq!(Some("dating"), dating.get_db_name());
+        // max_idle is reduced to max_open.
+        assert_eq!(
+            PoolConstraints::new(10, 10),
+            Some(dating.get_pool_opts().constraints())
+        );
+
+        config.connections.max_open = 0;
+        assert!(opts(&config, "dating").is_err());
+    }
+}
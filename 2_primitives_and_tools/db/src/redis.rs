@@ -0,0 +1,327 @@
+//! Redis client of `db.redis.addrs`.
+//!
+//! The addresses are replicas of the same data: the commands go to the current one,
+//! on a connection error the client drops its connection and retries the command
+//! on the next address. The connections are opened on the first use and reopened
+//! after a failure, so a recovered address is used again when its turn comes.
+
+use background::Locker;
+use common_errors::{AppError, Result};
+use configuration::configuration::{Addr, Redis};
+use rand::{self, Rng};
+use redis_client::{self, Client, Connection, ConnectionAddr, ConnectionInfo, RedisResult, Script};
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Timeout of the connection and of the commands, an address not answering
+/// in time is failed over.
+pub const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Prefix of the keys of the locks.
+const LOCK_PREFIX: &str = "lock:";
+
+/// Deletes the lock only if it is still held by the token of the caller,
+/// and not by another instance after it expired.
+const UNLOCK: &str = r#"
+if redis.call("get", KEYS[1]) == ARGV[1] then
+    return redis.call("del", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// The client, shared by the threads: the commands are serialized.
+pub struct RedisClient {
+    addrs: Vec<ConnectionInfo>,
+    state: Mutex<State>,
+    /// Value of the locks taken by this client.
+    token: String,
+    unlock: Script,
+}
+
+struct State {
+    /// Index of the address of the next command.
+    current: usize,
+    connections: Vec<Option<Connection>>,
+}
+
+impl RedisClient {
+    /// Client of `addrs`, tried in their order. Nothing is connected yet.
+    pub fn new(addrs: &[Addr]) -> Result<RedisClient> {
+        if addrs.is_empty() {
+            return Err(AppError::config("db.redis.addrs: no address"));
+        }
+        Ok(RedisClient {
+            addrs: addrs
+                .iter()
+                .map(|addr| ConnectionInfo {
+                    addr: Box::new(ConnectionAddr::Tcp(addr.host.to_string(), addr.port)),
+                    db: 0,
+                    passwd: None,
+                })
+                .collect(),
+            state: Mutex::new(State {
+                current: 0,
+                connections: addrs.iter().map(|_| None).collect(),
+            }),
+            token: random_token(),
+            unlock: Script::new(UNLOCK),
+        })
+    }
+
+    pub fn from_config(config: &Redis) -> Result<RedisClient> {
+        RedisClient::new(&config.addrs)
+    }
+
+    /// Value of `key`, `None` if it isn't set.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        self.execute(|conn| redis_client::cmd("GET").arg(key).query(conn))
+    }
+
+    /// Set `key` to `value`, expiring after `ttl` if any.
+    pub fn set(&self, key: &str, value: &str, ttl: Option<Duration>) -> Result<()> {
+        self.execute(|conn| {
+            let mut cmd = redis_client::cmd("SET");
+            cmd.arg(key).arg(value);
+            if let Some(ttl) = ttl {
+                cmd.arg("PX").arg(millis(ttl));
+            }
+            cmd.query(conn)
+        })
+    }
+
+    /// Take the lock `name` for `ttl` unless it is held, by this client too.
+    /// Returns the token of this client, the value of the lock.
+    pub fn lock(&self, name: &str, ttl: Duration) -> Result<Option<String>> {
+        let key = format!("{}{}", LOCK_PREFIX, name);
+        let reply: Option<String> = self.execute(|conn| {
+            redis_client::cmd("SET")
+                .arg(&key)
+                .arg(&self.token)
+                .arg("NX")
+                .arg("PX")
+                .arg(millis(ttl))
+                .query(conn)
+        })?;
+        Ok(reply.map(|_| self.token.clone()))
+    }
+
+    /// Release the lock `name` if it is still held with `token`.
+    pub fn unlock(&self, name: &str, token: &str) -> Result<()> {
+        let key = format!("{}{}", LOCK_PREFIX, name);
+        let _deleted: i64 = self.execute(|conn| self.unlock.key(&key).arg(token).invoke(conn))?;
+        Ok(())
+    }
+
+    /// Run `command` on the current address, then on the next ones
+    /// while the connection fails.
+    fn execute<T, F>(&self, command: F) -> Result<T>
+    where
+        F: Fn(&mut Connection) -> RedisResult<T>,
+    {
+        let mut state = self.state.lock().unwrap();
+        let mut errors = Vec::new();
+        for _ in 0..self.addrs.len() {
+            let index = state.current;
+            let result = match state.connections[index] {
+                Some(ref mut conn) => command(conn),
+                None => connect(&self.addrs[index]).and_then(|mut conn| {
+                    let result = command(&mut conn);
+                    state.connections[index] = Some(conn);
+                    result
+                }),
+            };
+            match result {
+                Err(ref err) if err.is_io_error() => {
+                    errors.push(format!("{}: {}", address(&self.addrs[index]), err));
+                    state.connections[index] = None;
+                    state.current = (index + 1) % self.addrs.len();
+                }
+                result => return result.map_err(AppError::db),
+            }
+        }
+        Err(AppError::db(format!(
+            "no Redis address is available: {}",
+            errors.join(", ")
+        )))
+    }
+}
+
+/// The locks of the background jobs, shared by the instances through Redis.
+impl Locker for RedisClient {
+    fn try_lock(&self, name: &str, ttl: Duration) -> Result<Option<String>> {
+        self.lock(name, ttl)
+    }
+
+    fn unlock(&self, name: &str, token: &str) -> Result<()> {
+        RedisClient::unlock(self, name, token)
+    }
+}
+
+fn connect(info: &ConnectionInfo) -> RedisResult<Connection> {
+    // The redis crate connects without a timeout, an unreachable host would block
+    // the command for minutes: the address is checked first.
+    if let ConnectionAddr::Tcp(ref host, port) = *info.addr {
+        let reachable = (host.as_str(), port)
+            .to_socket_addrs()?
+            .any(|addr| TcpStream::connect_timeout(&addr, TIMEOUT).is_ok());
+        if !reachable {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "unreachable").into());
+        }
+    }
+    let conn = Client::open(info.clone())?.get_connection()?;
+    conn.set_read_timeout(Some(TIMEOUT))?;
+    conn.set_write_timeout(Some(TIMEOUT))?;
+    Ok(conn)
+}
+
+fn address(info: &ConnectionInfo) -> String {
+    match *info.addr {
+        ConnectionAddr::Tcp(ref host, port) => format!("{}:{}", host, port),
+        ConnectionAddr::Unix(ref path) => path.display().to_string(),
+    }
+}
+
+/// Value of the locks of a client: 128 random bits in hex, unique among the instances
+/// unlike the process id, which is the same in every container.
+fn random_token() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn millis(duration: Duration) -> u64 {
+    // PX 0 is an error of Redis.
+    (duration.as_secs() * 1000 + u64::from(duration.subsec_millis())).max(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    type Store = Arc<Mutex<HashMap<String, String>>>;
+
+    /// Redis server knowing GET, SET, SCRIPT LOAD and EVALSHA of `UNLOCK`.
+    fn fake_redis(store: Store) -> Addr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let store = Arc::clone(&store);
+                thread::spawn(move || {
+                    let mut writer = stream.unwrap();
+                    let mut reader = BufReader::new(writer.try_clone().unwrap());
+                    while let Some(args) = read_command(&mut reader) {
+                        let reply = answer(&store, &args);
+                        writer.write_all(reply.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        Addr {
+            host: "127.0.0.1".into(),
+            port,
+        }
+    }
+
+    fn read_command<R: BufRead>(reader: &mut R) -> Option<Vec<String>> {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let count: usize = line.trim_end().trim_start_matches('*').parse().ok()?;
+        let mut args = Vec::new();
+        for _ in 0..count {
+            // `$len` and the value, the test values have no line breaks.
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+            args.push(line.trim_end().to_string());
+        }
+        Some(args)
+    }
+
+    fn answer(store: &Store, args: &[String]) -> String {
+        let mut store = store.lock().unwrap();
+        let bulk = |value: Option<&String>| match value {
+            Some(value) => format!("${}\r\n{}\r\n", value.len(), value),
+            None => "$-1\r\n".to_string(),
+        };
+        match args[0].as_str() {
+            "GET" => bulk(store.get(&args[1])),
+            "SET" if args.iter().any(|arg| arg == "NX") && store.contains_key(&args[1]) => {
+                bulk(None)
+            }
+            "SET" => {
+                store.insert(args[1].clone(), args[2].clone());
+                "+OK\r\n".to_string()
+            }
+            "SCRIPT" => bulk(Some(&args[2].clone())),
+            "EVALSHA" if store.get(&args[3]) == Some(&args[4]) => {
+                store.remove(&args[3]);
+                ":1\r\n".to_string()
+            }
+            "EVALSHA" => ":0\r\n".to_string(),
+            _ => "-ERR unknown command\r\n".to_string(),
+        }
+    }
+
+    /// Address refusing the connections.
+    fn closed_addr() -> Addr {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        Addr {
+            host: "127.0.0.1".into(),
+            port,
+        }
+    }
+
+    #[test]
+    fn test_failover() {
+        let store = Store::default();
+        let redis = RedisClient::new(&[closed_addr(), fake_redis(Arc::clone(&store))]).unwrap();
+
+        redis.set("greeting", "hello", None).unwrap();
+        assert_eq!(Some("hello".to_string()), redis.get("greeting").unwrap());
+        assert_eq!(None, redis.get("missing").unwrap());
+        assert_eq!(1, redis.state.lock().unwrap().current);
+
+        let redis = RedisClient::new(&[closed_addr(), closed_addr()]).unwrap();
+        let err = redis.get("greeting").unwrap_err().to_string();
+        assert!(err.contains("no Redis address is available"));
+
+        assert!(RedisClient::new(&[]).is_err());
+    }
+
+    #[test]
+    fn test_lock() {
+        let store = Store::default();
+        let addr = fake_redis(Arc::clone(&store));
+        let first = RedisClient::new(&[Addr {
+            host: addr.host.clone(),
+            port: addr.port,
+        }])
+        .unwrap();
+        let second = RedisClient::new(&[addr]).unwrap();
+        let ttl = Duration::from_secs(4);
+
+        let token = first.try_lock("recounter", ttl).unwrap().unwrap();
+        assert_eq!(None, first.try_lock("recounter", ttl).unwrap());
+        assert_eq!(None, second.try_lock("recounter", ttl).unwrap());
+
+        // Only the holder releases the lock.
+        Locker::unlock(&second, "recounter", &second.token).unwrap();
+        assert_eq!(None, second.try_lock("recounter", ttl).unwrap());
+        Locker::unlock(&first, "recounter", &token).unwrap();
+        assert!(second.try_lock("recounter", ttl).unwrap().is_some());
+        assert!(store.lock().unwrap().contains_key("lock:recounter"));
+    }
+}
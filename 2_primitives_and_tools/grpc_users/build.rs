@@ -0,0 +1,9 @@
+extern crate tower_grpc_build;
+
+fn main() {
+    tower_grpc_build::Config::new()
+        .enable_server(true)
+        .enable_client(true)
+        .build(&["proto/users.proto"], &["proto"])
+        .unwrap_or_else(|err| panic!("protobuf compilation failed: {}", err));
+}
@@ -0,0 +1,27 @@
+//! # gRPC server of the users repository.
+//!
+//! The `Users` service of `proto/users.proto` answers with the lookups of
+//! the `UsersRepository` trait (2_4_collections), the server listens
+//! on `server.grpc_port` of the configuration (2_11_configuration).
+extern crate bytes;
+extern crate collections;
+extern crate common_errors;
+extern crate config;
+extern crate futures;
+extern crate prost;
+#[macro_use]
+extern crate prost_derive;
+extern crate tokio;
+extern crate tower_grpc;
+extern crate tower_hyper;
+
+#[cfg(test)]
+extern crate im;
+
+/// Messages, client and server generated from `proto/users.proto`.
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/users.rs"));
+}
+
+pub mod server;
+pub mod service;
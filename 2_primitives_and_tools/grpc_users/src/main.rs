@@ -0,0 +1,42 @@
+extern crate collections;
+extern crate common_errors;
+extern crate config;
+extern crate configuration;
+extern crate grpc_users;
+extern crate im;
+extern crate tokio;
+
+use collections::users::{DBMemory, User, UserId};
+use common_errors::AppError;
+use config::Config;
+use configuration::configuration::AppConfig;
+use grpc_users::server::{grpc_addr, serve};
+use im::hashmap::HashMap;
+use std::borrow::Cow;
+use std::env;
+
+/// `$ grpc_users [config.toml]`, the config of 2_11_configuration by default.
+fn main() -> Result<(), AppError> {
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "../2_11_configuration/config.toml".to_string());
+    let config: Config = AppConfig::priority_config(&path)?;
+    let addr = grpc_addr(&config)?;
+
+    let mut map_users: HashMap<UserId, User> = HashMap::new();
+
+    let user = User::new(UserId(4), Cow::Borrowed("Sara Delafon"));
+    map_users.insert(user.get_id().clone(), user);
+
+    let user = User::new(UserId(2), Cow::Borrowed("Jacob Delafon"));
+    map_users.insert(user.get_id().clone(), user);
+
+    let user = User::new(UserId(5), Cow::Borrowed("Sara Daniel"));
+    map_users.insert(user.get_id().clone(), user);
+
+    let server = serve(&addr, DBMemory::new(map_users))?;
+    println!("Users gRPC service on {}", addr);
+    tokio::run(server);
+
+    Ok(())
+}
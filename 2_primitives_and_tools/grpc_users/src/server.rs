@@ -0,0 +1,59 @@
+//! HTTP/2 server of the `Users` service.
+
+use collections::users::UsersRepository;
+use common_errors::AppError;
+use config::Config;
+use futures::{Future, Stream};
+use proto::server::UsersServer;
+use service::UsersService;
+use std::io;
+use std::net::SocketAddr;
+use tokio;
+use tokio::net::TcpListener;
+use tower_hyper::server::{Http, Server};
+
+/// All interfaces on `server.grpc_port` of the configuration.
+pub fn grpc_addr(config: &Config) -> Result<SocketAddr, AppError> {
+    let port: u16 = config.get("server.grpc_port")?;
+    Ok(SocketAddr::from(([0, 0, 0, 0], port)))
+}
+
+/// Bind `addr`, the returned future accepts the connections
+/// and serves the `Users` service of `repository` on each of them.
+pub fn serve<R>(addr: &SocketAddr, repository: R) -> io::Result<impl Future<Item = (), Error = ()>>
+where
+    R: UsersRepository + Send + Sync + 'static,
+{
+    let mut server = Server::new(UsersServer::new(UsersService::new(repository)));
+    let http = Http::new().http2_only(true).clone();
+    let listener = TcpListener::bind(addr)?;
+
+    Ok(listener
+        .incoming()
+        .for_each(move |sock| {
+            sock.set_nodelay(true)?;
+            let serve = server.serve_with(sock, http.clone());
+            tokio::spawn(serve.map_err(|err| eprintln!("HTTP/2 error: {:?}", err)));
+            Ok(())
+        })
+        .map_err(|err| eprintln!("Accept error: {}", err)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_grpc_addr() {
+        let mut config = Config::new();
+        config.set("server.grpc_port", 8082).unwrap();
+
+        assert_eq!("0.0.0.0:8082", grpc_addr(&config).unwrap().to_string());
+
+        config.set("server.grpc_port", "port").unwrap();
+        match grpc_addr(&config) {
+            Err(AppError::Config(_)) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
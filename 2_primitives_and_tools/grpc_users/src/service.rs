@@ -0,0 +1,154 @@
+//! The `Users` service over any `UsersRepository`.
+
+use collections::users::{
+    get_ids_user_by_nickname, get_user_by_id, get_users_by_ids, User, UserId, UsersRepository,
+};
+use futures::future::{self, FutureResult};
+use proto;
+use proto::server::Users;
+use std::sync::Arc;
+use tower_grpc::{Code, Request, Response, Status};
+
+/// `Users` service answering from `repository`.
+///
+/// The server clones the service for every connection,
+/// the clones share the repository.
+pub struct UsersService<R> {
+    repository: Arc<R>,
+}
+
+impl<R> UsersService<R> {
+    pub fn new(repository: R) -> Self {
+        UsersService {
+            repository: Arc::new(repository),
+        }
+    }
+}
+
+impl<R> Clone for UsersService<R> {
+    fn clone(&self) -> Self {
+        UsersService {
+            repository: Arc::clone(&self.repository),
+        }
+    }
+}
+
+impl<R> Users for UsersService<R>
+where
+    R: UsersRepository + Send + Sync + 'static,
+{
+    type GetUserFuture = FutureResult<Response<proto::User>, Status>;
+    type GetUsersFuture = FutureResult<Response<proto::UserList>, Status>;
+    type FindUsersFuture = FutureResult<Response<proto::UserIds>, Status>;
+
+    fn get_user(&mut self, request: Request<proto::GetUserRequest>) -> Self::GetUserFuture {
+        let id = request.into_inner().id;
+        match get_user_by_id(&*self.repository, UserId(id as usize)) {
+            Some(user) => future::ok(Response::new(to_proto(&user))),
+            None => future::err(Status::new(
+                Code::NotFound,
+                format!("user {} not found", id),
+            )),
+        }
+    }
+
+    fn get_users(&mut self, request: Request<proto::GetUsersRequest>) -> Self::GetUsersFuture {
+        let ids = request
+            .into_inner()
+            .ids
+            .into_iter()
+            .map(|id| UserId(id as usize))
+            .collect();
+        let mut users: Vec<proto::User> = get_users_by_ids(&*self.repository, ids)
+            .iter()
+            .map(|(_, user)| to_proto(user))
+            .collect();
+        users.sort_by_key(|user| user.id);
+        future::ok(Response::new(proto::UserList { users }))
+    }
+
+    fn find_users(&mut self, request: Request<proto::FindUsersRequest>) -> Self::FindUsersFuture {
+        let nickname = request.into_inner().nickname;
+        let mut ids: Vec<u64> = get_ids_user_by_nickname(&*self.repository, &nickname)
+            .into_iter()
+            .map(|id| id.0 as u64)
+            .collect();
+        ids.sort();
+        future::ok(Response::new(proto::UserIds { ids }))
+    }
+}
+
+fn to_proto(user: &User) -> proto::User {
+    proto::User {
+        id: user.get_id().0 as u64,
+        nickname: user.get_nickname().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use collections::users::DBMemory;
+    use futures::Future;
+    use im::hashmap::HashMap;
+    use std::borrow::Cow;
+
+    fn service() -> UsersService<DBMemory> {
+        let mut map_users: HashMap<UserId, User> = HashMap::new();
+
+        let user = User::new(UserId(4), Cow::Borrowed("Sara Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let user = User::new(UserId(2), Cow::Borrowed("Jacob Delafon"));
+        map_users.insert(user.get_id().clone(), user);
+
+        let user = User::new(UserId(5), Cow::Borrowed("Sara Daniel"));
+        map_users.insert(user.get_id().clone(), user);
+
+        UsersService::new(DBMemory::new(map_users))
+    }
+
+    #[test]
+    fn test_get_user() {
+        let mut service = service();
+
+        let user = service
+            .get_user(Request::new(proto::GetUserRequest { id: 2 }))
+            .wait()
+            .unwrap()
+            .into_inner();
+        assert_eq!("Jacob Delafon", user.nickname);
+
+        let err = service
+            .get_user(Request::new(proto::GetUserRequest { id: 8 }))
+            .wait()
+            .unwrap_err();
+        assert_eq!(Code::NotFound, err.code());
+    }
+
+    #[test]
+    fn test_get_users_and_find_users() {
+        let mut service = service();
+
+        let users = service
+            .get_users(Request::new(proto::GetUsersRequest { ids: vec![4, 2, 8] }))
+            .wait()
+            .unwrap()
+            .into_inner()
+            .users;
+        assert_eq!(
+            vec![2, 4],
+            users.iter().map(|user| user.id).collect::<Vec<_>>()
+        );
+
+        let ids = service
+            .find_users(Request::new(proto::FindUsersRequest {
+                nickname: "delafon".to_string(),
+            }))
+            .wait()
+            .unwrap()
+            .into_inner()
+            .ids;
+        assert_eq!(vec![2, 4], ids);
+    }
+}
@@ -0,0 +1,201 @@
+//! # Health checks of the examples.
+//!
+//! The subsystems register their checks in the `HealthRegistry`,
+//! `spawn_server` evaluates them on every request:
+//!
+//! - `GET /healthz` runs the liveness checks, the process must be restarted if one fails;
+//! - `GET /readyz` runs the liveness and the readiness checks,
+//!   the process gets no traffic until all of them pass.
+//!
+//! The answer is `200 OK` or `503 Service Unavailable` with a line per check.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use healthz::{HealthRegistry, Probe};
+//!
+//! let health = HealthRegistry::new();
+//! health.readiness("db", || Err("no connection".to_string()));
+//!
+//! assert!(health.check(Probe::Liveness).is_ok());
+//! let report = health.check(Probe::Readiness);
+//! assert!(!report.is_ok());
+//! assert_eq!("db: no connection\n", report.to_string());
+//! ```
+#[cfg(feature = "config")]
+extern crate config;
+extern crate futures;
+extern crate hyper;
+
+mod server;
+
+pub use server::spawn_server;
+
+#[cfg(feature = "config")]
+use config::{Config, ConfigError};
+use std::fmt;
+#[cfg(feature = "config")]
+use std::net::SocketAddr;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+/// Default of `server.healthz_port` of the configuration.
+pub const DEFAULT_PORT: u16 = 10025;
+
+/// Result of a check, the error tells what is wrong.
+pub type CheckResult = Result<(), String>;
+
+type Check = Box<dyn Fn() -> CheckResult + Send + Sync + 'static>;
+
+/// Which endpoint runs a check.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Probe {
+    /// `/healthz` and `/readyz`.
+    Liveness,
+    /// `/readyz` only.
+    Readiness,
+}
+
+/// Checks of the subsystems, the clones share them.
+#[derive(Clone, Default)]
+pub struct HealthRegistry {
+    checks: Arc<RwLock<Vec<(Probe, String, Check)>>>,
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a check of `/healthz` and `/readyz`.
+    pub fn liveness<F>(&self, name: &str, check: F)
+    where
+        F: Fn() -> CheckResult + Send + Sync + 'static,
+    {
+        self.add(Probe::Liveness, name, Box::new(check));
+    }
+
+    /// Add a check of `/readyz`.
+    pub fn readiness<F>(&self, name: &str, check: F)
+    where
+        F: Fn() -> CheckResult + Send + Sync + 'static,
+    {
+        self.add(Probe::Readiness, name, Box::new(check));
+    }
+
+    fn add(&self, probe: Probe, name: &str, check: Check) {
+        self.checks
+            .write()
+            .unwrap()
+            .push((probe, name.to_string(), check));
+    }
+
+    /// Run the checks of `probe` in the order of registration,
+    /// a panicking check fails.
+    pub fn check(&self, probe: Probe) -> Report {
+        let checks = self.checks.read().unwrap();
+        let results = checks
+            .iter()
+            .filter(|(check_probe, _, _)| {
+                probe == Probe::Readiness || *check_probe == Probe::Liveness
+            })
+            .map(|(_, name, check)| {
+                let result = panic::catch_unwind(AssertUnwindSafe(check))
+                    .unwrap_or_else(|_| Err("check panicked".to_string()));
+                (name.clone(), result)
+            })
+            .collect();
+        Report { results }
+    }
+}
+
+impl fmt::Debug for HealthRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("HealthRegistry { .. }")
+    }
+}
+
+/// Results of the checks by their names.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub results: Vec<(String, CheckResult)>,
+}
+
+impl Report {
+    /// All the checks passed, true if there are none.
+    pub fn is_ok(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+}
+
+/// A line per check: `name: ok` or `name: error`.
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, result) in &self.results {
+            match result {
+                Ok(()) => writeln!(f, "{}: ok", name)?,
+                Err(err) => writeln!(f, "{}: {}", name, err)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// All interfaces on `server.healthz_port` of the configuration, `DEFAULT_PORT` if it isn't set.
+#[cfg(feature = "config")]
+pub fn healthz_addr(config: &Config) -> Result<SocketAddr, ConfigError> {
+    let port = match config.get::<u16>("server.healthz_port") {
+        Err(ConfigError::NotFound(_)) => DEFAULT_PORT,
+        port => port?,
+    };
+    Ok(SocketAddr::from(([0, 0, 0, 0], port)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_check() {
+        let health = HealthRegistry::new();
+        let ready = Arc::new(AtomicBool::new(false));
+        health.liveness("workers", || Ok(()));
+        {
+            let ready = Arc::clone(&ready);
+            health.readiness("db", move || {
+                if ready.load(Ordering::SeqCst) {
+                    Ok(())
+                } else {
+                    Err("no connection".to_string())
+                }
+            });
+        }
+        health.readiness("cache", || -> CheckResult { panic!("boom") });
+
+        let report = health.check(Probe::Liveness);
+        assert!(report.is_ok());
+        assert_eq!("workers: ok\n", report.to_string());
+
+        let report = health.check(Probe::Readiness);
+        assert!(!report.is_ok());
+        assert_eq!(
+            "workers: ok\ndb: no connection\ncache: check panicked\n",
+            report.to_string()
+        );
+
+        // The checks are evaluated on every call.
+        ready.store(true, Ordering::SeqCst);
+        assert_eq!(Ok(()), health.check(Probe::Readiness).results[1].1);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_healthz_addr() {
+        let mut config = Config::new();
+        assert_eq!("0.0.0.0:10025", healthz_addr(&config).unwrap().to_string());
+
+        config.set("server.healthz_port", 10026).unwrap();
+        assert_eq!("0.0.0.0:10026", healthz_addr(&config).unwrap().to_string());
+    }
+}
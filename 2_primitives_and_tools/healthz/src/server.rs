@@ -0,0 +1,96 @@
+//! HTTP server of `/healthz` and `/readyz`.
+
+use futures::Future;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::service_fn_ok;
+use hyper::{self, Body, Method, Request, Response, Server, StatusCode};
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+use {HealthRegistry, Probe};
+
+/// Serve the checks of `health` on `addr` in a background thread
+/// running until the end of the process.
+///
+/// Returns the bound address, the port is chosen by the OS if it is zero in `addr`.
+pub fn spawn_server(addr: &SocketAddr, health: HealthRegistry) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+
+    let server = Server::from_tcp(listener)
+        .map_err(io::Error::other)?
+        .serve(move || {
+            let health = health.clone();
+            service_fn_ok(move |req| respond(&health, &req))
+        })
+        .map_err(|err| eprintln!("Health check server error: {}", err));
+
+    thread::Builder::new()
+        .name("healthz".to_string())
+        .spawn(move || hyper::rt::run(server))?;
+
+    Ok(local_addr)
+}
+
+fn respond(health: &HealthRegistry, req: &Request<Body>) -> Response<Body> {
+    let probe = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/healthz") => Probe::Liveness,
+        (&Method::GET, "/readyz") => Probe::Readiness,
+        _ => {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())
+                .expect("the response is valid")
+        }
+    };
+    let report = health.check(probe);
+    let status = if report.is_ok() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(report.to_string()))
+        .expect("the response is valid")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn get(addr: &SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_spawn_server() {
+        let health = HealthRegistry::new();
+        health.liveness("workers", || Ok(()));
+        health.readiness("db", || Err("no connection".to_string()));
+
+        let addr = spawn_server(&"127.0.0.1:0".parse().unwrap(), health).unwrap();
+
+        let response = get(&addr, "/healthz");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("workers: ok\n"));
+
+        let response = get(&addr, "/readyz");
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable"));
+        assert!(response.ends_with("workers: ok\ndb: no connection\n"));
+
+        assert!(get(&addr, "/metrics").starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
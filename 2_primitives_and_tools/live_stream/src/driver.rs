@@ -0,0 +1,61 @@
+//! Drives the timeouts of a `LiveStream` with the tokio timer.
+
+use futures::{Future, Stream};
+use lifecycle::LiveStream;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::timer::Interval;
+
+/// Future ticking `stream` every `period` until it is finished.
+///
+/// The stream is shared with the handlers of the streamer activity and the chat:
+///
+/// ```rust,no_run
+/// # extern crate live_stream;
+/// # extern crate tokio;
+/// # use live_stream::{watch, LiveStream, Settings};
+/// # use std::sync::{Arc, Mutex};
+/// # use std::time::Instant;
+/// # fn main() {
+/// let settings = Settings::default();
+/// let period = settings.check_period();
+/// let stream = Arc::new(Mutex::new(LiveStream::new(42, settings, Instant::now())));
+/// tokio::run(watch(stream.clone(), period));
+/// # }
+/// ```
+pub fn watch(
+    stream: Arc<Mutex<LiveStream>>,
+    period: Duration,
+) -> impl Future<Item = (), Error = ()> {
+    Interval::new_interval(period)
+        .map_err(|err| eprintln!("live stream timer failed: {}", err))
+        .take_while(move |&now| {
+            let mut stream = stream.lock().unwrap();
+            stream.tick(now);
+            Ok(!stream.is_finished())
+        })
+        .for_each(|_| Ok(()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use settings::Settings;
+    use std::time::Instant;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn test_watch() {
+        let mut settings = Settings::default();
+        settings.setup.starting_timeout = Duration::from_millis(30);
+        let stream = Arc::new(Mutex::new(LiveStream::new(1, settings, Instant::now())));
+
+        let started = Instant::now();
+        let mut runtime = Runtime::new().unwrap();
+        runtime
+            .block_on(watch(stream.clone(), Duration::from_millis(10)))
+            .unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert!(stream.lock().unwrap().is_finished());
+    }
+}
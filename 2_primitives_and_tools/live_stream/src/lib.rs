@@ -0,0 +1,43 @@
+//! # Lifecycle of the live streams with the `app` section of the configuration.
+//!
+//! - `settings`: the timeouts of `app.setup_stream` and `app.live_stream`;
+//! - `lifecycle`: the typestate stream `Setup -> Starting -> Live <-> Idle -> Finished`
+//!   of `type_safety`, moved by the timeouts;
+//! - `driver`: the tokio timer checking the timeouts.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! extern crate configuration;
+//! extern crate live_stream;
+//!
+//! use configuration::configuration::App;
+//! use live_stream::{LiveStream, Settings};
+//! use std::time::{Duration, Instant};
+//!
+//! fn main() {
+//!     let settings = Settings::from_config(&App::default()).unwrap();
+//!     let now = Instant::now();
+//!     let mut stream = LiveStream::new(42, settings, now);
+//!     stream.start("model", now).unwrap();
+//!     stream.activity("media", now).unwrap();
+//!     stream.post_message("Hello!", now).unwrap();
+//!
+//!     assert!(stream.tick(now + Duration::from_secs(5)));
+//!     assert_eq!("Idle", stream.state());
+//! }
+//! ```
+extern crate common_errors;
+extern crate configuration;
+extern crate futures;
+extern crate humantime;
+extern crate tokio;
+extern crate type_safety;
+
+pub mod driver;
+pub mod lifecycle;
+pub mod settings;
+
+pub use driver::watch;
+pub use lifecycle::{AnyStream, Idle, LifecycleError, LiveStream, Starting};
+pub use settings::{Settings, SetupStreamSettings};
@@ -0,0 +1,396 @@
+//! Lifecycle `Setup -> Starting -> Live <-> Idle -> Finished` of a live stream.
+//!
+//! Extends the typestate `Stream` of `type_safety::stream` with the states
+//! `Starting` and `Idle`, and moves it by the timeouts of the `Settings`:
+//!
+//! - `Setup` is aborted if it isn't active within `setup.starting_timeout`,
+//!   or has no activity for `setup.idle_timeout` after that;
+//! - `Starting` is aborted if it doesn't go live within `live.starting_timeout`;
+//! - `Live` becomes `Idle` without activity for `live.idle_timeout`;
+//! - `Idle` is finished without activity for another `live.idle_timeout`.
+
+use settings::{Settings, SetupStreamSettings};
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+use type_safety::machine::{State, Transition, TransitionRecord};
+use type_safety::stream::{new_stream, Finished, Live, Setup, Stream, StreamContent};
+
+/// Actor of the transitions made by the timeouts.
+pub const TIMER: &str = "timer";
+
+/// The stream is started, the media isn't received yet.
+#[derive(Debug)]
+pub struct Starting;
+
+/// The live stream has no activity.
+#[derive(Debug)]
+pub struct Idle;
+
+impl State for Starting {
+    const NAME: &'static str = "Starting";
+}
+
+impl State for Idle {
+    const NAME: &'static str = "Idle";
+}
+
+impl Transition<Setup, Starting> for StreamContent {
+    const ACTION: &'static str = "start";
+}
+
+impl Transition<Starting, Live> for StreamContent {
+    const ACTION: &'static str = "go_live";
+}
+
+impl Transition<Starting, Finished> for StreamContent {
+    const ACTION: &'static str = "abort";
+}
+
+impl Transition<Live, Idle> for StreamContent {
+    const ACTION: &'static str = "idle";
+}
+
+impl Transition<Idle, Live> for StreamContent {
+    const ACTION: &'static str = "resume";
+}
+
+impl Transition<Idle, Finished> for StreamContent {
+    const ACTION: &'static str = "finish";
+}
+
+/// Stream in any state.
+#[derive(Debug)]
+pub enum AnyStream {
+    Setup(Stream<Setup>),
+    Starting(Stream<Starting>),
+    Live(Stream<Live>),
+    Idle(Stream<Idle>),
+    Finished(Stream<Finished>),
+}
+
+impl AnyStream {
+    pub fn state(&self) -> &'static str {
+        match self {
+            AnyStream::Setup(stream) => stream.state(),
+            AnyStream::Starting(stream) => stream.state(),
+            AnyStream::Live(stream) => stream.state(),
+            AnyStream::Idle(stream) => stream.state(),
+            AnyStream::Finished(stream) => stream.state(),
+        }
+    }
+
+    pub fn stream_id(&self) -> u64 {
+        match self {
+            AnyStream::Setup(stream) => stream.stream_id(),
+            AnyStream::Starting(stream) => stream.stream_id(),
+            AnyStream::Live(stream) => stream.stream_id(),
+            AnyStream::Idle(stream) => stream.stream_id(),
+            AnyStream::Finished(stream) => stream.stream_id(),
+        }
+    }
+
+    pub fn history(&self) -> &[TransitionRecord] {
+        match self {
+            AnyStream::Setup(stream) => stream.history(),
+            AnyStream::Starting(stream) => stream.history(),
+            AnyStream::Live(stream) => stream.history(),
+            AnyStream::Idle(stream) => stream.history(),
+            AnyStream::Finished(stream) => stream.history(),
+        }
+    }
+}
+
+/// Action not allowed in the current state of the stream.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LifecycleError {
+    WrongState {
+        action: &'static str,
+        state: &'static str,
+    },
+    MessageTooLong {
+        length: usize,
+        max: usize,
+    },
+}
+
+impl fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LifecycleError::WrongState { action, state } => {
+                write!(f, "can't {} the stream in the state {}", action, state)
+            }
+            LifecycleError::MessageTooLong { length, max } => write!(
+                f,
+                "chat message of {} characters is longer than {}",
+                length, max
+            ),
+        }
+    }
+}
+
+impl Error for LifecycleError {}
+
+/// Stream with the instants its timeouts are counted from.
+///
+/// The current instant is passed in, so the timeouts are driven by any timer.
+#[derive(Debug)]
+pub struct LiveStream {
+    stream: Option<AnyStream>,
+    setup: SetupStreamSettings,
+    /// When the current state was entered.
+    since: Instant,
+    /// Last activity in the current state.
+    active: Option<Instant>,
+}
+
+impl LiveStream {
+    /// New stream in the state `Setup`.
+    pub fn new(stream_id: u64, settings: Settings, now: Instant) -> Self {
+        LiveStream {
+            stream: Some(AnyStream::Setup(new_stream(stream_id, settings.live))),
+            setup: settings.setup,
+            since: now,
+            active: None,
+        }
+    }
+
+    pub fn stream(&self) -> &AnyStream {
+        self.stream.as_ref().expect("the stream is always set")
+    }
+
+    pub fn state(&self) -> &'static str {
+        self.stream().state()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.stream(), AnyStream::Finished(_))
+    }
+
+    /// `Setup -> Starting` by `actor`.
+    pub fn start(&mut self, actor: &str, now: Instant) -> Result<(), LifecycleError> {
+        match self.take() {
+            AnyStream::Setup(stream) => {
+                self.enter(AnyStream::Starting(stream.transition(actor)), now);
+                Ok(())
+            }
+            stream => Err(self.wrong_state(stream, "start")),
+        }
+    }
+
+    /// Activity of the streamer, like the received media or a heartbeat.
+    /// Takes `Starting` and `Idle` streams live.
+    pub fn activity(&mut self, actor: &str, now: Instant) -> Result<(), LifecycleError> {
+        let stream = match self.take() {
+            AnyStream::Starting(stream) => AnyStream::Live(stream.transition(actor)),
+            AnyStream::Idle(stream) => AnyStream::Live(stream.transition(actor)),
+            stream @ AnyStream::Finished(_) => return Err(self.wrong_state(stream, "activate")),
+            stream => {
+                self.stream = Some(stream);
+                self.active = Some(now);
+                return Ok(());
+            }
+        };
+        self.enter(stream, now);
+        self.active = Some(now);
+        Ok(())
+    }
+
+    /// Chat message, accepted by a `Live` stream if it fits `max_message_length`.
+    pub fn post_message(&mut self, message: &str, now: Instant) -> Result<(), LifecycleError> {
+        match self.stream() {
+            AnyStream::Live(stream) => {
+                if !stream.accepts_message(message) {
+                    return Err(LifecycleError::MessageTooLong {
+                        length: message.chars().count(),
+                        max: stream.settings().max_message_length,
+                    });
+                }
+            }
+            stream => {
+                return Err(LifecycleError::WrongState {
+                    action: "post to",
+                    state: stream.state(),
+                })
+            }
+        }
+        self.active = Some(now);
+        Ok(())
+    }
+
+    /// Finish the stream by `actor`: `Setup` and `Starting` ones are aborted.
+    pub fn finish(&mut self, actor: &str, now: Instant) -> Result<(), LifecycleError> {
+        let stream = match self.take() {
+            AnyStream::Setup(stream) => stream.transition(actor),
+            AnyStream::Starting(stream) => stream.transition(actor),
+            AnyStream::Live(stream) => stream.transition(actor),
+            AnyStream::Idle(stream) => stream.transition(actor),
+            stream @ AnyStream::Finished(_) => return Err(self.wrong_state(stream, "finish")),
+        };
+        self.enter(AnyStream::Finished(stream), now);
+        Ok(())
+    }
+
+    /// Apply the expired timeouts at `now`, returns whether the state is changed.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let timeout = match self.stream() {
+            AnyStream::Setup(_) => match self.active {
+                Some(_) => self.setup.idle_timeout,
+                None => self.setup.starting_timeout,
+            },
+            AnyStream::Starting(stream) => stream.settings().starting_timeout,
+            AnyStream::Live(stream) => stream.settings().idle_timeout,
+            AnyStream::Idle(stream) => stream.settings().idle_timeout,
+            AnyStream::Finished(_) => return false,
+        };
+        let from = self
+            .active
+            .map_or(self.since, |active| active.max(self.since));
+        if elapsed(from, now) < timeout {
+            return false;
+        }
+
+        let stream = match self.take() {
+            AnyStream::Live(stream) => AnyStream::Idle(stream.transition(TIMER)),
+            AnyStream::Setup(stream) => AnyStream::Finished(stream.transition(TIMER)),
+            AnyStream::Starting(stream) => AnyStream::Finished(stream.transition(TIMER)),
+            AnyStream::Idle(stream) => AnyStream::Finished(stream.transition(TIMER)),
+            AnyStream::Finished(_) => unreachable!("the finished streams have no timeouts"),
+        };
+        self.enter(stream, now);
+        true
+    }
+
+    fn take(&mut self) -> AnyStream {
+        self.stream.take().expect("the stream is always set")
+    }
+
+    fn enter(&mut self, stream: AnyStream, now: Instant) {
+        self.stream = Some(stream);
+        self.since = now;
+        self.active = None;
+    }
+
+    /// Put `stream` back and describe the refused `action`.
+    fn wrong_state(&mut self, stream: AnyStream, action: &'static str) -> LifecycleError {
+        let state = stream.state();
+        self.stream = Some(stream);
+        LifecycleError::WrongState { action, state }
+    }
+}
+
+fn elapsed(from: Instant, now: Instant) -> Duration {
+    if now > from {
+        now - from
+    } else {
+        Duration::from_secs(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn transitions(stream: &LiveStream) -> Vec<(&str, &str)> {
+        stream
+            .stream()
+            .history()
+            .iter()
+            .map(|r| (r.to.as_str(), r.actor.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let t0 = Instant::now();
+        let mut stream = LiveStream::new(7, Settings::default(), t0);
+        assert_eq!("Setup", stream.state());
+        assert!(stream.activity("model", t0 + secs(15)).is_ok());
+        // Active, so it is re-checked by the idle timeout from now on.
+        assert!(!stream.tick(t0 + secs(19)));
+        assert!(stream.start("model", t0 + secs(19)).is_ok());
+        assert!(stream.start("model", t0 + secs(19)).is_err());
+
+        assert!(!stream.tick(t0 + secs(38)));
+        assert!(stream.activity("media", t0 + secs(38)).is_ok());
+        assert_eq!("Live", stream.state());
+
+        assert!(stream.post_message("hi", t0 + secs(42)).is_ok());
+        assert!(!stream.tick(t0 + secs(46)));
+        assert!(stream.tick(t0 + secs(47)));
+        assert_eq!("Idle", stream.state());
+        assert!(stream.post_message("hi", t0 + secs(47)).is_err());
+
+        assert!(stream.activity("media", t0 + secs(50)).is_ok());
+        assert_eq!("Live", stream.state());
+        assert!(stream.tick(t0 + secs(55)));
+        assert!(stream.tick(t0 + secs(60)));
+        assert!(stream.is_finished());
+        assert!(!stream.tick(t0 + secs(100)));
+        assert!(stream.activity("media", t0 + secs(100)).is_err());
+        assert!(stream.finish("model", t0 + secs(100)).is_err());
+
+        assert_eq!(7, stream.stream().stream_id());
+        assert_eq!(
+            vec![
+                ("Starting", "model"),
+                ("Live", "media"),
+                ("Idle", TIMER),
+                ("Live", "media"),
+                ("Idle", TIMER),
+                ("Finished", TIMER),
+            ],
+            transitions(&stream)
+        );
+    }
+
+    #[test]
+    fn test_starting_timeouts() {
+        let t0 = Instant::now();
+        let mut stream = LiveStream::new(1, Settings::default(), t0);
+        assert!(!stream.tick(t0 + secs(19)));
+        assert!(stream.tick(t0 + secs(20)));
+        assert!(stream.is_finished());
+
+        let mut stream = LiveStream::new(2, Settings::default(), t0);
+        stream.start("model", t0).unwrap();
+        assert!(!stream.tick(t0 + secs(19)));
+        assert!(stream.tick(t0 + secs(20)));
+        assert_eq!(
+            vec![("Starting", "model"), ("Finished", TIMER)],
+            transitions(&stream)
+        );
+    }
+
+    #[test]
+    fn test_chat() {
+        let t0 = Instant::now();
+        let mut settings = Settings::default();
+        settings.live.max_message_length = 5;
+        let mut stream = LiveStream::new(1, settings, t0);
+        assert_eq!(
+            Err(LifecycleError::WrongState {
+                action: "post to",
+                state: "Setup",
+            }),
+            stream.post_message("hi", t0)
+        );
+
+        stream.start("model", t0).unwrap();
+        stream.activity("media", t0).unwrap();
+        assert!(stream.post_message("привет", t0).is_err());
+        assert_eq!(
+            Err(LifecycleError::MessageTooLong { length: 6, max: 5 }),
+            stream.post_message("hello!", t0)
+        );
+        assert!(stream.post_message("hello", t0 + secs(4)).is_ok());
+        // The chat keeps the stream active.
+        assert!(!stream.tick(t0 + secs(8)));
+        stream.finish("model", t0 + secs(8)).unwrap();
+        assert!(stream.post_message("hello", t0 + secs(8)).is_err());
+    }
+}
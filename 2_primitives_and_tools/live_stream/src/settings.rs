@@ -0,0 +1,80 @@
+//! Timeouts of the streams, parsed from the `app` section of the configuration.
+
+use common_errors::{AppError, Result};
+use configuration::configuration::App;
+use humantime;
+use std::time::Duration;
+use type_safety::stream::LiveStreamSettings;
+
+/// Settings of the setup stream, the same as `app.setup_stream` of the configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupStreamSettings {
+    /// The setup stream is finished without any activity for this long.
+    pub idle_timeout: Duration,
+    /// The setup stream is finished if it isn't active this long after it was created.
+    pub starting_timeout: Duration,
+}
+
+impl Default for SetupStreamSettings {
+    fn default() -> Self {
+        SetupStreamSettings {
+            idle_timeout: Duration::from_secs(5),
+            starting_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+/// All the timeouts of a stream from setup to finish.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub setup: SetupStreamSettings,
+    pub live: LiveStreamSettings,
+}
+
+impl Settings {
+    /// Parse `app.setup_stream` and `app.live_stream`: the durations are like `"5s"`.
+    pub fn from_config(config: &App) -> Result<Settings> {
+        let duration = |field: &str, value: &str| {
+            humantime::parse_duration(value)
+                .map_err(|err| AppError::config(format!("app.{}: {}", field, err)))
+        };
+        let live = &config.live_stream;
+        let setup = &config.setup_stream;
+        Ok(Settings {
+            setup: SetupStreamSettings {
+                idle_timeout: duration("setup_stream.idle_timeout", &setup.idle_timeout)?,
+                starting_timeout: duration(
+                    "setup_stream.starting_timeout",
+                    &setup.starting_timeout,
+                )?,
+            },
+            live: LiveStreamSettings {
+                max_message_length: usize::from(live.max_message_length),
+                idle_timeout: duration("live_stream.idle_timeout", &live.idle_timeout)?,
+                starting_timeout: duration("live_stream.starting_timeout", &live.starting_timeout)?,
+            },
+        })
+    }
+
+    /// How often the timeouts are checked: the shortest re-check timeout.
+    pub fn check_period(&self) -> Duration {
+        self.setup.idle_timeout.min(self.live.idle_timeout)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_config() {
+        let settings = Settings::from_config(&App::default()).unwrap();
+        assert_eq!(Settings::default(), settings);
+        assert_eq!(Duration::from_secs(5), settings.check_period());
+
+        let mut config = App::default();
+        config.live_stream.idle_timeout = "5 parsecs".into();
+        let err = Settings::from_config(&config).unwrap_err();
+        assert!(err.to_string().contains("app.live_stream.idle_timeout"));
+    }
+}
@@ -0,0 +1,44 @@
+//! Sum of a 1024×1024 matrix of `u8`.
+//!
+//! Compares `matrix::sum` over the dense rows with the previous consumers:
+//! `HashMap<(i32, i32), u8>` summed per element with rayon, and the plain
+//! per-element sum of the dense rows.
+//!
+//! ```bash
+//! $ cargo bench
+//! ```
+#[macro_use]
+extern crate criterion;
+extern crate matrix;
+extern crate rayon;
+
+use criterion::Criterion;
+use matrix::Matrix;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+const SIDE: usize = 1024;
+
+fn sum(c: &mut Criterion) {
+    let dense = Matrix::random(SIDE);
+    let mut map: HashMap<(i32, i32), u8> = HashMap::with_capacity(SIDE * SIDE);
+    for x in 0..SIDE {
+        for y in 0..SIDE {
+            map.insert((x as i32, y as i32), dense.get(x, y).unwrap());
+        }
+    }
+
+    c.bench_function("hashmap par_iter 1024x1024", move |b| {
+        b.iter(|| map.par_iter().map(|(_, &v)| u64::from(v)).sum::<u64>())
+    });
+    let m = dense.clone();
+    c.bench_function("dense per element 1024x1024", move |b| {
+        b.iter(|| m.as_slice().iter().map(|&v| u64::from(v)).sum::<u64>())
+    });
+    c.bench_function("matrix::sum 1024x1024", move |b| {
+        b.iter(|| matrix::sum(&dense))
+    });
+}
+
+criterion_group!(benches, sum);
+criterion_main!(benches);
@@ -0,0 +1,118 @@
+//! # Dense square matrices of the consumers.
+//!
+//! `Matrix` keeps its `u8` elements row by row in one `Vec`, so `sum` reads them
+//! sequentially in cache-friendly chunks instead of visiting every entry of
+//! a `HashMap<(i32, i32), u8>`. Shared by the consumers of the steps 2.12 and 2.14.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use matrix::Matrix;
+//!
+//! let m = Matrix::from_fn(3, |x, y| (x * 3 + y) as u8);
+//! assert_eq!(Some(5), m.get(1, 2));
+//! assert_eq!(36, matrix::sum(&m));
+//! ```
+extern crate rand;
+extern crate rayon;
+
+use rand::{thread_rng, Rng};
+use rayon::prelude::*;
+
+/// Side of the matrices generated by the producers: 4096 elements.
+pub const SIDE: usize = 64;
+
+/// Elements summed in `u32` before the widening into the `u64` total:
+/// `255 * LANE` fits `u32`, and the short loop over a lane is vectorized.
+const LANE: usize = 4096;
+
+/// Rows summed by one rayon task.
+const ROWS_PER_TASK: usize = 64;
+
+/// Square matrix of `u8`, stored row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    side: usize,
+    data: Vec<u8>,
+}
+
+impl Matrix {
+    /// Matrix with the element `f(x, y)` at the row `x` and the column `y`.
+    pub fn from_fn<F: FnMut(usize, usize) -> u8>(side: usize, mut f: F) -> Matrix {
+        let mut data = Vec::with_capacity(side * side);
+        for x in 0..side {
+            for y in 0..side {
+                data.push(f(x, y));
+            }
+        }
+        Matrix { side, data }
+    }
+
+    /// Matrix of random elements.
+    pub fn random(side: usize) -> Matrix {
+        let mut data = vec![0u8; side * side];
+        thread_rng().fill(&mut data[..]);
+        Matrix { side, data }
+    }
+
+    /// Number of the rows and of the columns.
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    /// Element at the row `x` and the column `y`.
+    pub fn get(&self, x: usize, y: usize) -> Option<u8> {
+        if x < self.side && y < self.side {
+            Some(self.data[x * self.side + y])
+        } else {
+            None
+        }
+    }
+
+    /// Elements row by row.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Sum of all the elements: the chunks of `ROWS_PER_TASK` rows are summed in parallel.
+pub fn sum(matrix: &Matrix) -> u64 {
+    let chunk = ROWS_PER_TASK * matrix.side.max(1);
+    matrix.data.par_chunks(chunk).map(sum_chunk).sum()
+}
+
+/// Sum of the elements of a chunk, lane by lane.
+fn sum_chunk(chunk: &[u8]) -> u64 {
+    chunk
+        .chunks(LANE)
+        .map(|lane| u64::from(lane.iter().map(|&v| u32::from(v)).sum::<u32>()))
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sum() {
+        let naive = |m: &Matrix| m.as_slice().iter().map(|&v| u64::from(v)).sum::<u64>();
+
+        let m = Matrix::from_fn(1024, |_, _| 255);
+        assert_eq!(1024 * 1024 * 255, sum(&m));
+
+        for &side in &[0, 1, 63, SIDE, 130] {
+            let m = Matrix::random(side);
+            assert_eq!(side, m.side());
+            assert_eq!(naive(&m), sum(&m));
+        }
+    }
+
+    #[test]
+    fn test_get() {
+        let m = Matrix::from_fn(2, |x, y| (10 * x + y) as u8);
+        assert_eq!(&[0, 1, 10, 11], m.as_slice());
+        assert_eq!(Some(10), m.get(1, 0));
+        assert_eq!(None, m.get(2, 0));
+        assert_eq!(None, m.get(0, 2));
+    }
+}
@@ -0,0 +1,86 @@
+//! HTTP server of the metrics for the prometheus scraper.
+
+use futures::Future;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::service_fn_ok;
+use hyper::{self, Body, Method, Request, Response, Server, StatusCode};
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+use Metrics;
+
+/// Serve `metrics` on `GET /metrics` of `addr` in a background thread
+/// running until the end of the process.
+///
+/// Returns the bound address, the port is chosen by the OS if it is zero in `addr`.
+pub fn spawn_exporter(addr: &SocketAddr, metrics: Metrics) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local_addr = listener.local_addr()?;
+
+    let server = Server::from_tcp(listener)
+        .map_err(io::Error::other)?
+        .serve(move || {
+            let metrics = metrics.clone();
+            service_fn_ok(move |req| respond(&metrics, &req))
+        })
+        .map_err(|err| eprintln!("Metrics exporter error: {}", err));
+
+    thread::Builder::new()
+        .name("metrics-exporter".to_string())
+        .spawn(move || hyper::rt::run(server))?;
+
+    Ok(local_addr)
+}
+
+fn respond(metrics: &Metrics, req: &Request<Body>) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            let (text, content_type) = metrics.encode();
+            Response::builder()
+                .header(CONTENT_TYPE, content_type)
+                .body(Body::from(text))
+                .expect("the response is valid")
+        }
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .expect("the response is valid"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    fn get(addr: &SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_spawn_exporter() {
+        let metrics = Metrics::new();
+        metrics
+            .counter("exported_total", "Exported.")
+            .unwrap()
+            .inc_by(5.0);
+
+        let addr = spawn_exporter(&"127.0.0.1:0".parse().unwrap(), metrics).unwrap();
+
+        let response = get(&addr, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("exported_total 5"));
+
+        assert!(get(&addr, "/").starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
@@ -0,0 +1,196 @@
+//! # Prometheus metrics of the examples.
+//!
+//! `Metrics` is the registry shared by the instrumented code: the thread pool,
+//! the downloader and the actors register their counters, gauges and histograms
+//! in it, and `spawn_exporter` serves all of them on `GET /metrics`.
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use metrics::Metrics;
+//!
+//! let metrics = Metrics::new();
+//! let downloads = metrics.counter("downloads_total", "Downloaded links.").unwrap();
+//! downloads.inc();
+//!
+//! let (text, _content_type) = metrics.encode();
+//! assert!(String::from_utf8(text).unwrap().contains("downloads_total 1"));
+//! ```
+#[cfg(feature = "config")]
+extern crate config;
+extern crate futures;
+extern crate hyper;
+extern crate prometheus;
+
+mod exporter;
+
+pub use exporter::spawn_exporter;
+pub use prometheus::{Counter, Gauge, Histogram, Result};
+
+#[cfg(feature = "config")]
+use config::{Config, ConfigError};
+use prometheus::{Encoder, HistogramOpts, Opts, Registry, TextEncoder};
+use std::fmt;
+#[cfg(feature = "config")]
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Default of `server.metrics_port` of the configuration.
+pub const DEFAULT_PORT: u16 = 9199;
+
+/// Buckets of the durations in seconds, from 5ms to 10s.
+pub const SECONDS_BUCKETS: &[f64] = &[0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+type Hook = Box<dyn Fn() + Send + Sync + 'static>;
+
+/// Registry of the metrics, the clones share it.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    /// Run before every `encode`.
+    hooks: Arc<Mutex<Vec<Hook>>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics {
+            registry: Registry::new(),
+            hooks: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Register a counter, fails if the name is invalid or already registered.
+    pub fn counter(&self, name: &str, help: &str) -> Result<Counter> {
+        let counter = Counter::with_opts(Opts::new(name, help))?;
+        self.registry.register(Box::new(counter.clone()))?;
+        Ok(counter)
+    }
+
+    /// Register a gauge, fails if the name is invalid or already registered.
+    pub fn gauge(&self, name: &str, help: &str) -> Result<Gauge> {
+        let gauge = Gauge::with_opts(Opts::new(name, help))?;
+        self.registry.register(Box::new(gauge.clone()))?;
+        Ok(gauge)
+    }
+
+    /// Register a histogram with the upper bounds `buckets`,
+    /// fails if the name is invalid or already registered.
+    pub fn histogram(&self, name: &str, help: &str, buckets: &[f64]) -> Result<Histogram> {
+        let histogram =
+            Histogram::with_opts(HistogramOpts::new(name, help).buckets(buckets.to_vec()))?;
+        self.registry.register(Box::new(histogram.clone()))?;
+        Ok(histogram)
+    }
+
+    /// Run `hook` before every `encode`, to set the metrics
+    /// copied from the state of the instrumented code.
+    pub fn on_collect<F>(&self, hook: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.hooks.lock().unwrap().push(Box::new(hook));
+    }
+
+    /// All the metrics in the prometheus text format and its content type.
+    pub fn encode(&self) -> (Vec<u8>, String) {
+        for hook in self.hooks.lock().unwrap().iter() {
+            hook();
+        }
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+        encoder
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics are encoded into memory");
+        (buffer, encoder.format_type().to_string())
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+impl fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Metrics { .. }")
+    }
+}
+
+/// Set the counter counting elsewhere to `value`, counters only go up.
+pub fn set_counter(counter: &Counter, value: f64) {
+    let delta = value - counter.get();
+    if delta > 0.0 {
+        counter.inc_by(delta);
+    }
+}
+
+/// All interfaces on `server.metrics_port` of the configuration, `DEFAULT_PORT` if it isn't set.
+#[cfg(feature = "config")]
+pub fn metrics_addr(config: &Config) -> ::std::result::Result<SocketAddr, ConfigError> {
+    let port = match config.get::<u16>("server.metrics_port") {
+        Err(ConfigError::NotFound(_)) => DEFAULT_PORT,
+        port => port?,
+    };
+    Ok(SocketAddr::from(([0, 0, 0, 0], port)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn text(metrics: &Metrics) -> String {
+        String::from_utf8(metrics.encode().0).unwrap()
+    }
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Metrics::new();
+        let counter = metrics.counter("jobs_total", "Jobs.").unwrap();
+        let gauge = metrics.gauge("jobs_queued", "Queued jobs.").unwrap();
+        let histogram = metrics
+            .histogram("job_seconds", "Job time.", SECONDS_BUCKETS)
+            .unwrap();
+        assert!(metrics.counter("jobs_total", "Jobs.").is_err());
+
+        counter.inc();
+        gauge.set(3.0);
+        histogram.observe(0.2);
+
+        let text = text(&metrics);
+        assert!(text.contains("jobs_total 1"));
+        assert!(text.contains("jobs_queued 3"));
+        assert!(text.contains("job_seconds_bucket{le=\"0.25\"} 1"));
+        assert!(text.contains("job_seconds_count 1"));
+    }
+
+    #[test]
+    fn test_on_collect() {
+        let metrics = Metrics::new();
+        let counter = metrics.counter("collected_total", "Collects.").unwrap();
+        let collects = Arc::new(AtomicUsize::new(0));
+        {
+            let collects = Arc::clone(&collects);
+            let counter = counter.clone();
+            metrics.on_collect(move || {
+                let value = collects.fetch_add(1, Ordering::SeqCst) + 1;
+                set_counter(&counter, value as f64);
+            });
+        }
+
+        text(&metrics);
+        assert!(text(&metrics).contains("collected_total 2"));
+        assert_eq!(2.0, counter.get());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_metrics_addr() {
+        let mut config = Config::new();
+        assert_eq!("0.0.0.0:9199", metrics_addr(&config).unwrap().to_string());
+
+        config.set("server.metrics_port", 9100).unwrap();
+        assert_eq!("0.0.0.0:9100", metrics_addr(&config).unwrap().to_string());
+    }
+}
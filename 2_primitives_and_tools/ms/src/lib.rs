@@ -0,0 +1,35 @@
+//! # Clients of the media servers of the `ms` section of the configuration.
+//!
+//! - `openvidu`: the sessions of OpenVidu Server at `ms.openvidu`.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! extern crate configuration;
+//! extern crate ms;
+//!
+//! use configuration::configuration::Openvidu;
+//! use ms::OpenviduClient;
+//!
+//! fn main() {
+//!     let openvidu = OpenviduClient::from_config(&Openvidu::default(), "MY_SECRET").unwrap();
+//!     let session = openvidu.create_session(Some("stream_42")).unwrap();
+//!     assert!(openvidu.sessions().unwrap().contains(&session));
+//!     assert!(openvidu.destroy_session(&session.id).unwrap());
+//! }
+//! ```
+extern crate base64;
+extern crate common_errors;
+extern crate configuration;
+extern crate futures;
+extern crate hyper;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate tokio;
+
+pub mod openvidu;
+
+pub use openvidu::{OpenviduClient, Session};
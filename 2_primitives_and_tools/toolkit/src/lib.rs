@@ -0,0 +1,51 @@
+//! # One API surface over the modules of the steps.
+//!
+//! The types and helpers of the other steps are re-exported here,
+//! each step behind its own feature, so a binary depends on `toolkit`
+//! instead of copying the glue code between the steps:
+//!
+//! - `users`: the `UsersRepository` trait with `DBMemory` (2_4_collections);
+//! - `email`: `User` with the validated email (2_2_regular_expressions), default;
+//! - `random`: tokens, passwords, passphrases and uuids (2_6_randomness), default;
+//! - `log`: the JSON loggers and `CustomLevelFilter` (2_8_logging);
+//! - `config_log`: `init_logging` with the levels of the `log` section
+//!   of the configuration (2_11_configuration).
+//!
+//! ## Examples
+//!
+//! ```rust
+//! use toolkit::email::User;
+//! use toolkit::random::generate_password;
+//!
+//! let user = User::validate_and_set_email("mail@mail.ru").unwrap();
+//! assert_eq!(Some("mail.ru"), user.email_domain());
+//!
+//! assert_eq!(10, generate_password(10).len());
+//! ```
+#[cfg(feature = "users")]
+extern crate collections;
+#[cfg(feature = "config_log")]
+extern crate configuration;
+#[cfg(feature = "log")]
+extern crate logging;
+#[cfg(feature = "random")]
+extern crate randomness;
+#[cfg(feature = "email")]
+extern crate regular_expressions;
+#[cfg(feature = "config_log")]
+extern crate slog;
+
+#[cfg(feature = "users")]
+pub use collections::users;
+
+#[cfg(feature = "email")]
+pub use regular_expressions::user as email;
+
+#[cfg(feature = "random")]
+pub use randomness::rand_mod as random;
+
+#[cfg(feature = "log")]
+pub use logging::structured_logging as log;
+
+#[cfg(feature = "config_log")]
+pub mod log_config;
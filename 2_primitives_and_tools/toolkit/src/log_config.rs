@@ -0,0 +1,81 @@
+//! Loggers configured by the `log` section of the configuration (2_11_configuration).
+//!
+//! `log.app`, `log.access` and `log.user` have their own drains,
+//! each one dropping the records below the level set in the config:
+//! changing `log.app.level` in `config.toml` changes the verbosity of the app logger.
+//!
+//! ## Examples
+//!
+//! ```rust,no_run
+//! #[macro_use]
+//! extern crate slog;
+//! extern crate configuration;
+//! extern crate toolkit;
+//!
+//! use configuration::configuration::{AppConfig, Log};
+//! use toolkit::log_config::init_logging;
+//!
+//! fn main() {
+//!     let config = AppConfig::priority_config("config.toml").unwrap();
+//!     let log: Log = config.get("log").unwrap();
+//!     let loggers = init_logging(&log).unwrap();
+//!
+//!     info!(loggers.app, "started");
+//!     debug!(loggers.access, "GET /"; "status" => 200);
+//! }
+//! ```
+
+use configuration::configuration::{ErrorLevel, Log};
+use logging::structured_logging::{file_json_drain, json_root, stdio_json_drain};
+use slog::{self, Drain, LevelFilter, Logger};
+use std::io;
+use std::panic::{RefUnwindSafe, UnwindSafe};
+
+/// Access log of `init_logging`.
+pub const ACCESS_LOG: &str = "access.log";
+/// User log of `init_logging`.
+pub const USER_LOG: &str = "user.log";
+
+/// The loggers of the three log streams.
+pub struct Loggers {
+    /// JSON to `stdout`, warnings and errors to `stderr`.
+    pub app: Logger,
+    /// JSON lines in `ACCESS_LOG`.
+    pub access: Logger,
+    /// JSON lines in `USER_LOG`.
+    pub user: Logger,
+}
+
+/// Build the loggers of `log`, the log files are truncated.
+pub fn init_logging(log: &Log) -> io::Result<Loggers> {
+    Ok(Loggers {
+        app: filtered(stdio_json_drain(), &log.app.level),
+        access: filtered(file_json_drain(ACCESS_LOG)?, &log.access.level),
+        user: filtered(file_json_drain(USER_LOG)?, &log.user.level),
+    })
+}
+
+/// Root logger of `drain` passing the records at `level` and above.
+fn filtered<D>(drain: D, level: &ErrorLevel) -> Logger
+where
+    D: Drain<Ok = (), Err = slog::Never> + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+{
+    match level.as_slog_level() {
+        Some(level) => json_root(LevelFilter::new(drain, level).fuse()),
+        None => json_root(slog::Discard),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use slog::Level;
+
+    #[test]
+    fn test_slog_level() {
+        // A record passes the filter if it is at least as severe as the configured level.
+        let level = ErrorLevel::WARN.as_slog_level().unwrap();
+        assert!(Level::Error.is_at_least(level));
+        assert!(!Level::Info.is_at_least(level));
+    }
+}